@@ -0,0 +1,25 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use freedesktop_file_parser::{parse, DesktopFile, DesktopFileShared};
+
+const BROWSER: &str = include_str!("data/browser.desktop");
+
+fn bench_clone(c: &mut Criterion) {
+    let file = parse(BROWSER).unwrap();
+    let files = vec![file; 2000];
+    let shared = files
+        .iter()
+        .cloned()
+        .map(DesktopFileShared::from)
+        .collect::<Vec<_>>();
+
+    c.bench_function("clone_2000_files", |b| b.iter(|| black_box(&files).clone()));
+    c.bench_function("clone_2000_shared_files", |b| {
+        b.iter(|| black_box(&shared).clone())
+    });
+    c.bench_function("shared_to_file", |b| {
+        b.iter(|| DesktopFile::from(black_box(&shared[0]).clone()))
+    });
+}
+
+criterion_group!(benches, bench_clone);
+criterion_main!(benches);
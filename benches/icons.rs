@@ -0,0 +1,49 @@
+use std::path::{Path, PathBuf};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use freedesktop_file_parser::icons::{IconCache, IconOptions};
+
+/// Stats the candidate files of a theme layout like a real lookup would
+fn lookup(name: &str, options: &IconOptions) -> Option<PathBuf> {
+    let base = Path::new("/usr/share/icons").join(&options.theme);
+    let sized = format!("{0}x{0}", options.size);
+    for dir in [sized.as_str(), "scalable"] {
+        for context in ["apps", "mimetypes", "places", "devices"] {
+            for ext in ["png", "svg", "xpm"] {
+                let path = base.join(dir).join(context).join(format!("{name}.{ext}"));
+                if path.is_file() {
+                    return Some(path);
+                }
+            }
+        }
+    }
+    None
+}
+
+fn bench_icons(c: &mut Criterion) {
+    let names = (0..200).map(|i| format!("app-{i}")).collect::<Vec<_>>();
+    let options = IconOptions::default();
+
+    c.bench_function("icons_uncached", |b| {
+        b.iter(|| {
+            for name in &names {
+                black_box(lookup(name, &options));
+            }
+        })
+    });
+
+    let cache = IconCache::new();
+    for name in &names {
+        cache.lookup_with(name, &options, lookup);
+    }
+    c.bench_function("icons_cached", |b| {
+        b.iter(|| {
+            for name in &names {
+                black_box(cache.lookup_with(name, &options, lookup));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_icons);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use freedesktop_file_parser::{
+    parse, parse_summary, parse_unchecked, parse_with_options, ParseOptions,
+};
+
+const BROWSER: &str = include_str!("data/browser.desktop");
+
+/// A file with 50 actions, each with a Name in 50 locales
+fn many_actions() -> String {
+    let ids = (0..50).map(|i| format!("action{i}")).collect::<Vec<_>>();
+    let mut file = format!(
+        "[Desktop Entry]\nType=Application\nName=App\nExec=app\nActions={};\n",
+        ids.join(";")
+    );
+    for id in &ids {
+        file.push_str(&format!(
+            "\n[Desktop Action {id}]\nName=Run {id}\nExec=app --{id}\n"
+        ));
+        for locale in 0..50 {
+            file.push_str(&format!("Name[l{locale}]=Run {id} in l{locale}\n"));
+        }
+    }
+    file
+}
+
+fn bench_parse(c: &mut Criterion) {
+    c.bench_function("parse", |b| b.iter(|| parse(black_box(BROWSER)).unwrap()));
+    let options = ParseOptions {
+        parse_actions: false,
+        ..Default::default()
+    };
+    c.bench_function("parse_without_actions", |b| {
+        b.iter(|| parse_with_options(black_box(BROWSER), &options).unwrap())
+    });
+    let many_actions = many_actions();
+    c.bench_function("parse_many_actions", |b| {
+        b.iter(|| parse(black_box(&many_actions)).unwrap())
+    });
+    c.bench_function("parse_many_actions_skipped", |b| {
+        b.iter(|| parse_with_options(black_box(&many_actions), &options).unwrap())
+    });
+    c.bench_function("parse_unchecked", |b| {
+        b.iter(|| parse_unchecked(black_box(BROWSER)))
+    });
+    c.bench_function("parse_summary", |b| {
+        b.iter(|| parse_summary(black_box(BROWSER), &["de_DE.UTF-8"]).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
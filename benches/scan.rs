@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use freedesktop_file_parser::{events::Events, parser::parse_list};
+
+const BROWSER: &str = include_str!("data/browser.desktop");
+
+fn bench_scan(c: &mut Criterion) {
+    // the fixture repeated, as a stand-in for a whole applications directory
+    let corpus = BROWSER.repeat(500);
+    let mut group = c.benchmark_group("scan");
+    group.throughput(Throughput::Bytes(corpus.len() as u64));
+    group.bench_function("events", |b| {
+        b.iter(|| {
+            Events::new(black_box(&corpus))
+                .filter(|e| e.is_ok())
+                .count()
+        })
+    });
+    group.bench_function("parse_list", |b| {
+        b.iter(|| {
+            Events::new(black_box(&corpus))
+                .filter_map(|e| match e {
+                    Ok(freedesktop_file_parser::events::Event::KeyValue { value, .. }) => {
+                        Some(parse_list(value).len())
+                    }
+                    _ => None,
+                })
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_scan);
+criterion_main!(benches);
@@ -0,0 +1,104 @@
+//! Measures the memory the parsed entries of applications directories take, to compare the
+//! layouts of `EntryType::Application`:
+//!
+//! ```sh
+//! cargo run --release --example entry_memory -- /usr/share/applications
+//! cargo run --release --example entry_memory --features boxed-fields -- /usr/share/applications
+//! ```
+//!
+//! Without arguments `/usr/share/applications` is scanned. The memory of an entry is its size
+//! in a `Vec` plus everything it allocates, counted by a global allocator.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fs,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use freedesktop_file_parser::{DesktopFile, EntryType};
+
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn collect(dir: &Path, contents: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect(&path, contents);
+        } else if path.extension().is_some_and(|e| e == "desktop") {
+            if let Ok(content) = fs::read_to_string(&path) {
+                contents.push(content);
+            }
+        }
+    }
+}
+
+fn main() {
+    let mut dirs = std::env::args().skip(1).collect::<Vec<_>>();
+    if dirs.is_empty() {
+        dirs.push("/usr/share/applications".into());
+    }
+    let mut contents = vec![];
+    for dir in &dirs {
+        collect(Path::new(dir), &mut contents);
+    }
+
+    let before = LIVE.load(Ordering::Relaxed);
+    let mut files = Vec::<DesktopFile>::with_capacity(contents.len());
+    files.extend(
+        contents
+            .iter()
+            .filter_map(|c| freedesktop_file_parser::parse(c).ok()),
+    );
+    files.shrink_to_fit();
+    let total = LIVE.load(Ordering::Relaxed) - before;
+
+    let applications = files
+        .iter()
+        .filter(|f| matches!(f.entry.entry_type, EntryType::Application(_)))
+        .count();
+    println!(
+        "layout: {}",
+        if cfg!(feature = "boxed-fields") {
+            "boxed"
+        } else {
+            "inline"
+        }
+    );
+    println!(
+        "files: {} read, {} parsed, {applications} applications",
+        contents.len(),
+        files.len()
+    );
+    println!(
+        "size_of: EntryType {} B, DesktopFile {} B",
+        size_of::<EntryType>(),
+        size_of::<DesktopFile>()
+    );
+    if !files.is_empty() {
+        println!(
+            "memory: {total} B in total, {} B per entry",
+            total / files.len()
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Editing the actions of an application while keeping the Actions key and the action groups
+//! in agreement.
+
+use thiserror::Error;
+
+use crate::{ApplicationFields, DesktopAction, DesktopFile, EntryType, IconIdentifier};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ActionError {
+    #[error("Action Error: only applications can have actions")]
+    NotApplication,
+    #[error("Action Error: {0:?} is not a valid action identifier, only A-Za-z0-9- are allowed")]
+    InvalidId(String),
+    #[error("Action Error: the action {0:?} already exists")]
+    Duplicate(String),
+    #[error("Action Error: the action {0:?} doesn't exist")]
+    NotFound(String),
+}
+
+/// An action ready to be shown in a menu
+#[derive(Debug)]
+pub struct ActionDisplay {
+    pub id: String,
+    /// The name resolved for the locales
+    pub name: String,
+    pub icon: Option<IconIdentifier>,
+}
+
+/// Options of [`DesktopFile::actions_for_display_with`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ActionDisplayOptions {
+    /// Keeps the actions declared in the Actions key without a group or with an empty name,
+    /// named after their id, instead of skipping them
+    pub keep_unnamed: bool,
+}
+
+fn check_id(id: &str) -> Result<(), ActionError> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(ActionError::InvalidId(id.to_string()));
+    }
+    Ok(())
+}
+
+impl DesktopFile {
+    fn application_fields(&mut self) -> Result<&mut ApplicationFields, ActionError> {
+        match self.entry.entry_type {
+            EntryType::Application(ref mut fields) => Ok(fields),
+            _ => Err(ActionError::NotApplication),
+        }
+    }
+
+    /// Adds an action and appends its id to the Actions key, creating the key if needed. An
+    /// id of [`DesktopFile::unlisted_actions`] counts as taken.
+    pub fn add_action(&mut self, id: &str, action: DesktopAction) -> Result<(), ActionError> {
+        check_id(id)?;
+        if self.actions.contains_key(id) || self.unlisted_actions.contains_key(id) {
+            return Err(ActionError::Duplicate(id.to_string()));
+        }
+
+        let declared = self
+            .application_fields()?
+            .actions
+            .get_or_insert_with(Vec::new);
+        if !declared.iter().any(|d| d == id) {
+            declared.push(id.to_string());
+        }
+        self.actions.insert(id.to_string(), action);
+        Ok(())
+    }
+
+    /// Removes an action and its id from the Actions key, returning the action
+    pub fn remove_action(&mut self, id: &str) -> Result<DesktopAction, ActionError> {
+        let fields = self.application_fields()?;
+        if let Some(ref mut declared) = fields.actions {
+            declared.retain(|d| d != id);
+        }
+
+        self.actions
+            .remove(id)
+            .ok_or_else(|| ActionError::NotFound(id.to_string()))
+    }
+
+    /// Returns the actions in the order of the Actions key with their names resolved for the
+    /// locales, skipping the ones without a name
+    pub fn actions_for_display(&self, locales: &[&str]) -> Vec<ActionDisplay> {
+        self.actions_for_display_with(locales, &ActionDisplayOptions::default())
+    }
+
+    /// Returns the actions like [`DesktopFile::actions_for_display`], with options
+    pub fn actions_for_display_with(
+        &self,
+        locales: &[&str],
+        options: &ActionDisplayOptions,
+    ) -> Vec<ActionDisplay> {
+        let EntryType::Application(ref fields) = self.entry.entry_type else {
+            return vec![];
+        };
+
+        let mut seen = vec![];
+        let mut displays = vec![];
+        for id in fields.actions.iter().flatten() {
+            if id.is_empty() || seen.contains(&id) {
+                continue;
+            }
+            seen.push(id);
+
+            let action = self.actions.get(id);
+            let name = action.map_or("", |a| a.name.resolve(locales).0);
+            if name.is_empty() && !options.keep_unnamed {
+                continue;
+            }
+
+            displays.push(ActionDisplay {
+                id: id.clone(),
+                name: match name {
+                    "" => id.clone(),
+                    name => name.to_string(),
+                },
+                icon: action
+                    .and_then(|a| a.icon.clone())
+                    .map(|icon| icon.to_identifier()),
+            });
+        }
+        displays
+    }
+
+    /// Renames an action, keeping its position in the Actions key. An id of
+    /// [`DesktopFile::unlisted_actions`] counts as taken.
+    pub fn rename_action(&mut self, old: &str, new: &str) -> Result<(), ActionError> {
+        check_id(new)?;
+        self.application_fields()?;
+        if !self.actions.contains_key(old) {
+            return Err(ActionError::NotFound(old.to_string()));
+        }
+        if old != new && (self.actions.contains_key(new) || self.unlisted_actions.contains_key(new))
+        {
+            return Err(ActionError::Duplicate(new.to_string()));
+        }
+
+        let action = self.actions.remove(old).unwrap_or_default();
+        self.actions.insert(new.to_string(), action);
+
+        let fields = self.application_fields()?;
+        if let Some(ref mut declared) = fields.actions {
+            for d in declared.iter_mut().filter(|d| *d == old) {
+                *d = new.to_string();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LocaleString;
+
+    fn action(name: &str) -> DesktopAction {
+        DesktopAction {
+            name: LocaleString {
+                default: name.to_string(),
+                ..Default::default()
+            },
+            exec: Some(format!("notes --{name}")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_actions() {
+        let mut file = DesktopFile::new_application("Notes", "notes").unwrap();
+        file.add_action("new", action("new")).unwrap();
+        file.add_action("open", action("open")).unwrap();
+        assert_eq!(
+            file.add_action("new", action("new")),
+            Err(ActionError::Duplicate("new".into()))
+        );
+        assert_eq!(
+            file.add_action("a b", action("x")),
+            Err(ActionError::InvalidId("a b".into()))
+        );
+
+        file.rename_action("new", "create").unwrap();
+        file.remove_action("open").unwrap();
+        assert!(file.remove_action("open").is_err());
+
+        let parsed = crate::parse(&file.to_desktop_string()).unwrap();
+        let EntryType::Application(ref fields) = parsed.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(fields.actions, Some(vec!["create".to_string()]));
+        assert_eq!(parsed.actions["create"].name.default, "new");
+
+        let mut file = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Notes\nExec=notes\nActions=new;\n\n[Desktop Action new]\nName=New\n\n[Desktop Action a]\nName=A\n",
+        )
+        .unwrap();
+        assert_eq!(
+            file.add_action("a", action("a")),
+            Err(ActionError::Duplicate("a".into()))
+        );
+        assert_eq!(
+            file.rename_action("new", "a"),
+            Err(ActionError::Duplicate("a".into()))
+        );
+        file.add_action("open", action("open")).unwrap();
+        let parsed = crate::parse(&file.to_desktop_string()).unwrap();
+        assert_eq!(parsed.actions.len(), 2);
+        assert_eq!(parsed.unlisted_actions["a"].name.default, "A");
+
+        let mut link = DesktopFile::new_link("Docs", "https://example.com");
+        assert_eq!(
+            link.add_action("new", action("new")),
+            Err(ActionError::NotApplication)
+        );
+    }
+
+    #[test]
+    fn test_actions_for_display() {
+        let file = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Browser\nExec=browser\nActions=private;new;missing;\n\n[Desktop Action new]\nName=New Window\nName[de]=Neues Fenster\nIcon=window-new\n\n[Desktop Action private]\nName=Private Window\n",
+        )
+        .unwrap();
+
+        let displays = file.actions_for_display(&["de_DE"]);
+        assert_eq!(
+            displays
+                .iter()
+                .map(|d| (d.id.as_str(), d.name.as_str()))
+                .collect::<Vec<_>>(),
+            [("private", "Private Window"), ("new", "Neues Fenster")]
+        );
+        assert!(displays[0].icon.is_none());
+        assert!(matches!(
+            displays[1].icon,
+            Some(IconIdentifier::Name(ref name)) if name == "window-new"
+        ));
+
+        let options = ActionDisplayOptions { keep_unnamed: true };
+        let displays = file.actions_for_display_with(&[], &options);
+        assert_eq!(displays.len(), 3);
+        assert_eq!(displays[2].name, "missing");
+    }
+}
@@ -0,0 +1,121 @@
+//! Structural statistics of a desktop file, for tooling auditing large numbers of files.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    keys::Key,
+    locale::normalize_locale,
+    parser::{tokens, Token},
+    DesktopFile, ParseError,
+};
+
+/// Keys of older versions of the specification that were removed from it
+const DEPRECATED_KEYS: &[&str] = &[
+    "Encoding",
+    "MiniIcon",
+    "TerminalOptions",
+    "Protocols",
+    "Extensions",
+    "BinaryPattern",
+    "MapNotify",
+    "SwallowTitle",
+    "SwallowExec",
+    "SortOrder",
+    "FilePattern",
+];
+
+/// Structural statistics of a desktop file, as written in the source
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParseReport {
+    /// The number of groups, including the Desktop Entry and the actions
+    pub groups: usize,
+    /// The number of key value pairs, counting each locale variant
+    pub keys: usize,
+    /// Every locale a value is localized for, normalized with [`normalize_locale`]
+    pub locales: BTreeSet<String>,
+    /// The number of keys that are neither standard nor extensions
+    pub unknown_keys: usize,
+    /// The number of extension keys, starting with `X-`
+    pub extension_keys: usize,
+    /// The deprecated keys present, such as `Encoding` or `MiniIcon`
+    pub deprecated_keys: BTreeSet<String>,
+    /// The size of the source in bytes
+    pub bytes: usize,
+    /// The length in bytes of the longest line, without its line break
+    pub longest_line: usize,
+}
+
+impl ParseReport {
+    /// Whether a deprecated key was seen
+    pub fn has_deprecated_keys(&self) -> bool {
+        !self.deprecated_keys.is_empty()
+    }
+
+    /// Gathers the statistics of a source without interpreting its values
+    fn of(input: &str) -> Result<Self, ParseError> {
+        let mut report = Self {
+            bytes: input.len(),
+            longest_line: input
+                .lines()
+                .map(|line| line.trim_end_matches('\r').len())
+                .max()
+                .unwrap_or(0),
+            ..Default::default()
+        };
+
+        for token in tokens(input) {
+            let parts = match token? {
+                Token::Group { .. } => {
+                    report.groups += 1;
+                    continue;
+                }
+                Token::Pair(parts) => parts,
+            };
+
+            report.keys += 1;
+            if let Some(ref locale) = parts.locale {
+                report.locales.insert(normalize_locale(locale));
+            }
+            match parts.key {
+                Key::Extension(_) => report.extension_keys += 1,
+                Key::Unknown(ref name) => {
+                    report.unknown_keys += 1;
+                    if DEPRECATED_KEYS.contains(&name.as_str()) {
+                        report.deprecated_keys.insert(name.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Parses a desktop file like [`crate::parse`] and gathers the structural statistics of its
+/// source along the way
+pub fn analyze(input: &str) -> Result<(DesktopFile, ParseReport), ParseError> {
+    let file = crate::parse(input)?;
+    Ok((file, ParseReport::of(input)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze() {
+        let input = "# comment line that is the longest one\n[Desktop Entry]\r\nType=Application\nName=App\nName[de_DE.UTF-8]=Anwendung\nName[fr]=Application\nExec=app\nEncoding=UTF-8\nX-Vendor=1\n\n[X-Group]\nFoo=bar\n";
+        let (_, report) = analyze(input).unwrap();
+
+        assert_eq!(report.groups, 2);
+        assert_eq!(report.keys, 8);
+        assert_eq!(report.locales.iter().collect::<Vec<_>>(), ["de_DE", "fr"]);
+        assert_eq!(report.unknown_keys, 2);
+        assert_eq!(report.extension_keys, 1);
+        assert!(report.has_deprecated_keys());
+        assert_eq!(report.longest_line, 38);
+        assert_eq!(report.bytes, input.len());
+    }
+}
@@ -0,0 +1,717 @@
+//! Reading the desktop entry embedded in type 2 AppImages.
+//!
+//! A type 2 AppImage is an ELF runtime followed by a squashfs image holding the application.
+//! This module implements just enough of squashfs 4.0 to list the top level directory and read
+//! files from it, with gzip, xz and zstd compression.
+
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use thiserror::Error;
+
+use crate::{
+    exec::{quote_arg, ExecCommand},
+    DesktopFile, EntryType, IconString, ParseError,
+};
+
+#[derive(Debug, Error)]
+pub enum AppImageError {
+    #[error("AppImage Error: {0}")]
+    Io(#[from] io::Error),
+    #[error("AppImage Error: the file is not an ELF executable")]
+    NotElf,
+    #[error("AppImage Error: no squashfs image follows the runtime")]
+    NoSquashfs,
+    #[error("AppImage Error: unsupported squashfs compressor {0}")]
+    UnsupportedCompressor(u16),
+    #[error("AppImage Error: corrupt squashfs image: {0}")]
+    Corrupt(String),
+    #[error("AppImage Error: {0:?} not found in the image")]
+    NotFound(String),
+    #[error("AppImage Error: no desktop file at the top level of the image")]
+    NoDesktopFile,
+    #[error("AppImage Error: {0}")]
+    Parse(#[from] ParseError),
+}
+
+const SQUASHFS_MAGIC: u32 = 0x7371_7368;
+const NO_FRAGMENT: u32 = 0xFFFF_FFFF;
+const BLOCK_UNCOMPRESSED: u32 = 0x0100_0000;
+const METADATA_UNCOMPRESSED: u16 = 0x8000;
+const MAX_SYMLINK_DEPTH: usize = 8;
+
+const COMPRESSOR_GZIP: u16 = 1;
+const COMPRESSOR_XZ: u16 = 4;
+const COMPRESSOR_ZSTD: u16 = 6;
+
+fn u16_at(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
+
+fn u32_at(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap())
+}
+
+fn u64_at(data: &[u8], pos: usize) -> u64 {
+    u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap())
+}
+
+/// The parts of the squashfs superblock needed for reading
+#[derive(Debug, Clone)]
+struct Superblock {
+    block_size: u32,
+    compressor: u16,
+    root_inode: u64,
+    inode_table: u64,
+    directory_table: u64,
+    fragment_table: u64,
+}
+
+#[derive(Debug, Clone)]
+enum Inode {
+    Dir {
+        block_index: u32,
+        block_offset: u16,
+        size: u32,
+    },
+    File {
+        blocks_start: u64,
+        fragment: u32,
+        fragment_offset: u32,
+        size: u64,
+        block_sizes: Vec<u32>,
+    },
+    Symlink(String),
+    Other,
+}
+
+/// Reads consecutive bytes from a metadata table, loading blocks as they are needed
+struct MetadataCursor {
+    next_block: u64,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+/// A squashfs image starting at an offset of a file
+struct Squashfs {
+    file: File,
+    offset: u64,
+    superblock: Superblock,
+}
+
+impl Squashfs {
+    fn open(mut file: File, offset: u64) -> Result<Self, AppImageError> {
+        let mut data = [0u8; 96];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut data)
+            .map_err(|_| AppImageError::NoSquashfs)?;
+
+        if u32_at(&data, 0) != SQUASHFS_MAGIC {
+            return Err(AppImageError::NoSquashfs);
+        }
+
+        let superblock = Superblock {
+            block_size: u32_at(&data, 12),
+            compressor: u16_at(&data, 20),
+            root_inode: u64_at(&data, 32),
+            inode_table: u64_at(&data, 64),
+            directory_table: u64_at(&data, 72),
+            fragment_table: u64_at(&data, 80),
+        };
+
+        if !matches!(
+            superblock.compressor,
+            COMPRESSOR_GZIP | COMPRESSOR_XZ | COMPRESSOR_ZSTD
+        ) {
+            return Err(AppImageError::UnsupportedCompressor(superblock.compressor));
+        }
+
+        Ok(Self {
+            file,
+            offset,
+            superblock,
+        })
+    }
+
+    fn read_at(&mut self, pos: u64, len: usize) -> Result<Vec<u8>, AppImageError> {
+        let mut data = vec![0u8; len];
+        self.file.seek(SeekFrom::Start(self.offset + pos))?;
+        self.file.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, AppImageError> {
+        let corrupt = |e: String| AppImageError::Corrupt(format!("decompression failed: {e}"));
+
+        match self.superblock.compressor {
+            COMPRESSOR_GZIP => miniz_oxide::inflate::decompress_to_vec_zlib(data)
+                .map_err(|e| corrupt(format!("{e:?}"))),
+            COMPRESSOR_XZ => {
+                let mut output = vec![];
+                lzma_rs::xz_decompress(&mut io::BufReader::new(data), &mut output)
+                    .map_err(|e| corrupt(e.to_string()))?;
+                Ok(output)
+            }
+            COMPRESSOR_ZSTD => {
+                let mut output = vec![];
+                ruzstd::decoding::StreamingDecoder::new(data)
+                    .map_err(|e| corrupt(e.to_string()))?
+                    .read_to_end(&mut output)?;
+                Ok(output)
+            }
+            other => Err(AppImageError::UnsupportedCompressor(other)),
+        }
+    }
+
+    fn cursor(&self, table: u64, block: u64, offset: usize) -> MetadataCursor {
+        MetadataCursor {
+            next_block: table + block,
+            buf: vec![],
+            pos: offset,
+        }
+    }
+
+    fn read_metadata(
+        &mut self,
+        cursor: &mut MetadataCursor,
+        len: usize,
+    ) -> Result<Vec<u8>, AppImageError> {
+        while cursor.buf.len() < cursor.pos + len {
+            let header = u16_at(&self.read_at(cursor.next_block, 2)?, 0);
+            let size = (header & !METADATA_UNCOMPRESSED) as usize;
+            let data = self.read_at(cursor.next_block + 2, size)?;
+
+            if header & METADATA_UNCOMPRESSED != 0 {
+                cursor.buf.extend(data);
+            } else {
+                cursor.buf.extend(self.decompress(&data)?);
+            }
+            cursor.next_block += 2 + size as u64;
+        }
+
+        let data = cursor.buf[cursor.pos..cursor.pos + len].to_vec();
+        cursor.pos += len;
+        Ok(data)
+    }
+
+    fn read_inode(&mut self, reference: u64) -> Result<Inode, AppImageError> {
+        let mut cursor = self.cursor(
+            self.superblock.inode_table,
+            reference >> 16,
+            (reference & 0xFFFF) as usize,
+        );
+        let header = self.read_metadata(&mut cursor, 16)?;
+
+        let inode = match u16_at(&header, 0) {
+            // basic directory
+            1 => {
+                let data = self.read_metadata(&mut cursor, 16)?;
+                Inode::Dir {
+                    block_index: u32_at(&data, 0),
+                    size: u16_at(&data, 8) as u32,
+                    block_offset: u16_at(&data, 10),
+                }
+            }
+            // extended directory
+            8 => {
+                let data = self.read_metadata(&mut cursor, 24)?;
+                Inode::Dir {
+                    size: u32_at(&data, 4),
+                    block_index: u32_at(&data, 8),
+                    block_offset: u16_at(&data, 18),
+                }
+            }
+            // basic and extended file
+            kind @ (2 | 9) => {
+                let (blocks_start, fragment, fragment_offset, size) = if kind == 2 {
+                    let data = self.read_metadata(&mut cursor, 16)?;
+                    (
+                        u32_at(&data, 0) as u64,
+                        u32_at(&data, 4),
+                        u32_at(&data, 8),
+                        u32_at(&data, 12) as u64,
+                    )
+                } else {
+                    let data = self.read_metadata(&mut cursor, 40)?;
+                    (
+                        u64_at(&data, 0),
+                        u32_at(&data, 28),
+                        u32_at(&data, 32),
+                        u64_at(&data, 8),
+                    )
+                };
+
+                let block_size = self.superblock.block_size as u64;
+                let block_count = if fragment == NO_FRAGMENT {
+                    size.div_ceil(block_size)
+                } else {
+                    size / block_size
+                };
+
+                let data = self.read_metadata(&mut cursor, block_count as usize * 4)?;
+                Inode::File {
+                    blocks_start,
+                    fragment,
+                    fragment_offset,
+                    size,
+                    block_sizes: (0..block_count as usize)
+                        .map(|i| u32_at(&data, i * 4))
+                        .collect(),
+                }
+            }
+            // basic and extended symlink
+            3 | 10 => {
+                let data = self.read_metadata(&mut cursor, 8)?;
+                let target = self.read_metadata(&mut cursor, u32_at(&data, 4) as usize)?;
+                Inode::Symlink(String::from_utf8_lossy(&target).into_owned())
+            }
+            _ => Inode::Other,
+        };
+
+        Ok(inode)
+    }
+
+    /// Returns the names and inode references of the entries of a directory
+    fn list_dir(&mut self, inode: &Inode) -> Result<Vec<(String, u64)>, AppImageError> {
+        let Inode::Dir {
+            block_index,
+            block_offset,
+            size,
+        } = *inode
+        else {
+            return Err(AppImageError::Corrupt("expected a directory".into()));
+        };
+
+        let mut entries = vec![];
+        // the size includes the implicit "." and ".." entries
+        let total = (size as usize).saturating_sub(3);
+        let mut cursor = self.cursor(
+            self.superblock.directory_table,
+            block_index as u64,
+            block_offset as usize,
+        );
+        let start = cursor.pos;
+
+        while cursor.pos - start < total {
+            let header = self.read_metadata(&mut cursor, 12)?;
+            let count = u32_at(&header, 0) as usize + 1;
+            let inode_block = u32_at(&header, 4) as u64;
+
+            for _ in 0..count {
+                let entry = self.read_metadata(&mut cursor, 8)?;
+                let name_size = u16_at(&entry, 6) as usize + 1;
+                let name = self.read_metadata(&mut cursor, name_size)?;
+
+                entries.push((
+                    String::from_utf8_lossy(&name).into_owned(),
+                    (inode_block << 16) | u16_at(&entry, 0) as u64,
+                ));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn read_block(&mut self, pos: u64, block_size: u32) -> Result<Vec<u8>, AppImageError> {
+        let data = self.read_at(pos, (block_size & !BLOCK_UNCOMPRESSED) as usize)?;
+        if block_size & BLOCK_UNCOMPRESSED != 0 {
+            Ok(data)
+        } else {
+            self.decompress(&data)
+        }
+    }
+
+    fn read_file(&mut self, inode: &Inode) -> Result<Vec<u8>, AppImageError> {
+        let Inode::File {
+            blocks_start,
+            fragment,
+            fragment_offset,
+            size,
+            ref block_sizes,
+        } = *inode
+        else {
+            return Err(AppImageError::Corrupt("expected a regular file".into()));
+        };
+
+        let block_size = self.superblock.block_size as usize;
+        let mut data = Vec::with_capacity(size as usize);
+        let mut pos = blocks_start;
+
+        for &stored in block_sizes {
+            let remaining = size as usize - data.len();
+            if stored & !BLOCK_UNCOMPRESSED == 0 {
+                // sparse block
+                data.resize(data.len() + remaining.min(block_size), 0);
+                continue;
+            }
+
+            data.extend(self.read_block(pos, stored)?);
+            pos += (stored & !BLOCK_UNCOMPRESSED) as u64;
+        }
+
+        if fragment != NO_FRAGMENT {
+            let index = fragment as usize;
+            let table =
+                self.read_at(self.superblock.fragment_table + (index / 512) as u64 * 8, 8)?;
+            let mut cursor = self.cursor(0, u64_at(&table, 0), (index % 512) * 16);
+            let entry = self.read_metadata(&mut cursor, 16)?;
+
+            let block = self.read_block(u64_at(&entry, 0), u32_at(&entry, 8))?;
+            let start = fragment_offset as usize;
+            let end = start + size as usize - data.len();
+            data.extend(
+                block
+                    .get(start..end)
+                    .ok_or_else(|| AppImageError::Corrupt("fragment out of range".into()))?,
+            );
+        }
+
+        data.truncate(size as usize);
+        Ok(data)
+    }
+
+    /// Looks up a path relative to the root, following symlinks
+    fn lookup(&mut self, path: &str, depth: usize) -> Result<Inode, AppImageError> {
+        if depth > MAX_SYMLINK_DEPTH {
+            return Err(AppImageError::Corrupt(format!(
+                "too many symlinks at {path:?}"
+            )));
+        }
+
+        let components = path
+            .split('/')
+            .filter(|c| !c.is_empty() && *c != ".")
+            .collect::<Vec<_>>();
+        let mut inode = self.read_inode(self.superblock.root_inode)?;
+
+        for (i, component) in components.iter().enumerate() {
+            let reference = self
+                .list_dir(&inode)?
+                .into_iter()
+                .find(|(name, _)| name == component)
+                .map(|(_, reference)| reference)
+                .ok_or_else(|| AppImageError::NotFound(path.to_string()))?;
+
+            inode = self.read_inode(reference)?;
+
+            if let Inode::Symlink(ref target) = inode {
+                let mut resolved = if target.starts_with('/') {
+                    vec![]
+                } else {
+                    components[..i].to_vec()
+                };
+                resolved.push(target);
+                resolved.extend(&components[i + 1..]);
+                return self.lookup(&resolved.join("/"), depth + 1);
+            }
+        }
+
+        Ok(inode)
+    }
+}
+
+/// Finds where the squashfs image starts, which is right after the section header table that
+/// ends the ELF runtime
+fn runtime_size(file: &mut File) -> Result<u64, AppImageError> {
+    let mut header = [0u8; 64];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut header)
+        .map_err(|_| AppImageError::NotElf)?;
+
+    if &header[..4] != b"\x7fELF" {
+        return Err(AppImageError::NotElf);
+    }
+
+    let little_endian = header[5] == 1;
+    let u16_at = |pos: usize| {
+        let bytes = [header[pos], header[pos + 1]];
+        if little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    };
+    let uint_at = |pos: usize, len: usize| {
+        let mut bytes = [0u8; 8];
+        if little_endian {
+            bytes[..len].copy_from_slice(&header[pos..pos + len]);
+            u64::from_le_bytes(bytes)
+        } else {
+            bytes[8 - len..].copy_from_slice(&header[pos..pos + len]);
+            u64::from_be_bytes(bytes)
+        }
+    };
+
+    let (shoff, shentsize, shnum) = match header[4] {
+        1 => (uint_at(0x20, 4), u16_at(0x2E), u16_at(0x30)),
+        2 => (uint_at(0x28, 8), u16_at(0x3A), u16_at(0x3C)),
+        _ => return Err(AppImageError::NotElf),
+    };
+
+    Ok(shoff + shentsize as u64 * shnum as u64)
+}
+
+/// An opened type 2 AppImage
+pub struct AppImage {
+    fs: Squashfs,
+}
+
+impl AppImage {
+    /// Opens an AppImage and locates the squashfs image inside it
+    pub fn open(path: &Path) -> Result<Self, AppImageError> {
+        let mut file = File::open(path)?;
+        let offset = runtime_size(&mut file)?;
+
+        Ok(Self {
+            fs: Squashfs::open(file, offset)?,
+        })
+    }
+
+    /// Returns the names of the entries of the top level directory of the image
+    pub fn top_level_entries(&mut self) -> Result<Vec<String>, AppImageError> {
+        let root = self.fs.read_inode(self.fs.superblock.root_inode)?;
+        Ok(self
+            .fs
+            .list_dir(&root)?
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect())
+    }
+
+    /// Reads a file of the image, following symlinks, such as `usr/share/icons/foo.png`
+    pub fn read_file(&mut self, path: &str) -> Result<Vec<u8>, AppImageError> {
+        let inode = self.fs.lookup(path, 0)?;
+        self.fs.read_file(&inode)
+    }
+
+    /// Returns the name of the desktop file at the top level of the image
+    pub fn desktop_file_name(&mut self) -> Result<String, AppImageError> {
+        self.top_level_entries()?
+            .into_iter()
+            .find(|name| name.ends_with(".desktop"))
+            .ok_or(AppImageError::NoDesktopFile)
+    }
+
+    /// Reads and parses the desktop file at the top level of the image
+    pub fn desktop_file(&mut self) -> Result<DesktopFile, AppImageError> {
+        let name = self.desktop_file_name()?;
+        let content = self.read_file(&name)?;
+        Ok(crate::parse(&String::from_utf8_lossy(&content))?)
+    }
+
+    /// Returns the path inside the image of the icon named by the desktop file, falling back
+    /// to `.DirIcon`. The icon can be read with [`AppImage::read_file`].
+    pub fn icon_path(&mut self) -> Result<Option<String>, AppImageError> {
+        let file = self.desktop_file()?;
+        let entries = self.top_level_entries()?;
+
+        if let Some(IconString { content: icon }) = file.entry.icon {
+            let found = ["png", "svg", "svgz", "xpm"]
+                .iter()
+                .map(|ext| format!("{icon}.{ext}"))
+                .chain(std::iter::once(icon.clone()))
+                .find(|name| entries.contains(name));
+
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+
+        Ok(entries.into_iter().find(|name| name == ".DirIcon"))
+    }
+}
+
+/// Reads and parses the desktop file embedded in a type 2 AppImage
+pub fn parse_appimage(path: &Path) -> Result<DesktopFile, AppImageError> {
+    AppImage::open(path)?.desktop_file()
+}
+
+/// Replaces the program of an Exec value with the AppImage, keeping the arguments
+fn rewrite_exec(exec: &str, appimage: &str) -> String {
+    match ExecCommand::parse(exec) {
+        Ok(mut command) => {
+            command.args[0].value = appimage.to_string();
+            command.to_exec_string()
+        }
+        Err(_) => quote_arg(appimage, false),
+    }
+}
+
+/// Rewrites an entry read from an AppImage so that it can be installed into an applications
+/// directory: Exec and TryExec of the entry and its actions run the AppImage instead of the
+/// relative `AppRun`, and Icon points at the extracted icon if one is given.
+pub fn rewrite_for_appimage(file: &mut DesktopFile, appimage: &Path, icon: Option<&Path>) {
+    let appimage = appimage.to_string_lossy();
+
+    if let EntryType::Application(ref mut fields) = file.entry.entry_type {
+        if let Some(ref mut exec) = fields.exec {
+            *exec = rewrite_exec(exec, &appimage);
+        }
+        if fields.try_exec.is_some() {
+            fields.try_exec = Some(appimage.to_string());
+        }
+        file.entry.raw_values.exec = None;
+        file.entry.raw_values.try_exec = None;
+    }
+
+    for action in file.actions.values_mut() {
+        if let Some(ref mut exec) = action.exec {
+            *exec = rewrite_exec(exec, &appimage);
+        }
+    }
+
+    if let Some(icon) = icon {
+        file.entry.icon = Some(IconString {
+            content: icon.to_string_lossy().into_owned(),
+        });
+        file.entry.raw_values.icon = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a minimal AppImage with an uncompressed squashfs image holding regular files and
+    /// symlinks in the root directory
+    fn build_appimage(files: &[(&str, &[u8])], symlinks: &[(&str, &str)]) -> Vec<u8> {
+        let mut elf = vec![0u8; 64];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[4] = 2;
+        elf[5] = 1;
+        // an empty section header table right after the ELF header
+        elf[0x28..0x30].copy_from_slice(&64u64.to_le_bytes());
+
+        let mut data = vec![];
+        let mut inodes = vec![];
+        let mut dir_entries = vec![];
+        let mut names = vec![];
+        let mut inode_number = 1u32;
+
+        for (name, content) in files {
+            let blocks_start = 96 + data.len() as u32;
+            data.extend_from_slice(content);
+
+            dir_entries.push((name.to_string(), inodes.len() as u16, inode_number, 2u16));
+            inodes.extend(2u16.to_le_bytes());
+            inodes.extend([0u8; 10]);
+            inodes.extend(inode_number.to_le_bytes());
+            inodes.extend(blocks_start.to_le_bytes());
+            inodes.extend(NO_FRAGMENT.to_le_bytes());
+            inodes.extend(0u32.to_le_bytes());
+            inodes.extend((content.len() as u32).to_le_bytes());
+            if !content.is_empty() {
+                inodes.extend((content.len() as u32 | BLOCK_UNCOMPRESSED).to_le_bytes());
+            }
+            inode_number += 1;
+        }
+
+        for (name, target) in symlinks {
+            dir_entries.push((name.to_string(), inodes.len() as u16, inode_number, 3u16));
+            inodes.extend(3u16.to_le_bytes());
+            inodes.extend([0u8; 10]);
+            inodes.extend(inode_number.to_le_bytes());
+            inodes.extend(1u32.to_le_bytes());
+            inodes.extend((target.len() as u32).to_le_bytes());
+            inodes.extend(target.as_bytes());
+            inode_number += 1;
+        }
+
+        dir_entries.sort();
+        let mut listing = vec![];
+        listing.extend((dir_entries.len() as u32 - 1).to_le_bytes());
+        listing.extend(0u32.to_le_bytes());
+        listing.extend(1u32.to_le_bytes());
+        for (name, offset, number, kind) in &dir_entries {
+            names.push(name.clone());
+            listing.extend(offset.to_le_bytes());
+            listing.extend((*number as i16 - 1).to_le_bytes());
+            listing.extend(kind.to_le_bytes());
+            listing.extend((name.len() as u16 - 1).to_le_bytes());
+            listing.extend(name.as_bytes());
+        }
+
+        let root_offset = inodes.len() as u64;
+        inodes.extend(1u16.to_le_bytes());
+        inodes.extend([0u8; 10]);
+        inodes.extend(inode_number.to_le_bytes());
+        inodes.extend(0u32.to_le_bytes());
+        inodes.extend(2u32.to_le_bytes());
+        inodes.extend((listing.len() as u16 + 3).to_le_bytes());
+        inodes.extend(0u16.to_le_bytes());
+        inodes.extend(0u32.to_le_bytes());
+
+        let inode_table = 96 + data.len() as u64;
+        let directory_table = inode_table + 2 + inodes.len() as u64;
+
+        let mut superblock = vec![0u8; 96];
+        superblock[0..4].copy_from_slice(&SQUASHFS_MAGIC.to_le_bytes());
+        superblock[12..16].copy_from_slice(&131072u32.to_le_bytes());
+        superblock[20..22].copy_from_slice(&COMPRESSOR_GZIP.to_le_bytes());
+        superblock[32..40].copy_from_slice(&root_offset.to_le_bytes());
+        superblock[64..72].copy_from_slice(&inode_table.to_le_bytes());
+        superblock[72..80].copy_from_slice(&directory_table.to_le_bytes());
+        superblock[80..88].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        let mut image = elf;
+        image.extend(superblock);
+        image.extend(data);
+        image.extend((inodes.len() as u16 | METADATA_UNCOMPRESSED).to_le_bytes());
+        image.extend(inodes);
+        image.extend((listing.len() as u16 | METADATA_UNCOMPRESSED).to_le_bytes());
+        image.extend(listing);
+        image
+    }
+
+    #[test]
+    fn test_parse_appimage() {
+        let desktop = b"[Desktop Entry]\nType=Application\nName=Foo\nIcon=foo\nExec=AppRun --flag %U\nTryExec=AppRun\n";
+        let image = build_appimage(
+            &[
+                ("AppRun", b"#!/bin/sh\n"),
+                ("foo.desktop", desktop),
+                ("foo.png", b"png"),
+            ],
+            &[(".DirIcon", "foo.png")],
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "desktop-file-parser-{}.AppImage",
+            std::process::id()
+        ));
+        std::fs::write(&path, image).unwrap();
+
+        let mut appimage = AppImage::open(&path).unwrap();
+        assert_eq!(appimage.desktop_file_name().unwrap(), "foo.desktop");
+        assert_eq!(appimage.icon_path().unwrap().as_deref(), Some("foo.png"));
+        assert_eq!(appimage.read_file(".DirIcon").unwrap(), b"png");
+
+        let mut file = parse_appimage(&path).unwrap();
+        rewrite_for_appimage(
+            &mut file,
+            Path::new("/opt/apps/Foo App.AppImage"),
+            Some(Path::new("/home/user/.local/share/icons/foo.png")),
+        );
+
+        let EntryType::Application(ref fields) = file.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(
+            fields.exec.as_deref(),
+            Some("\"/opt/apps/Foo App.AppImage\" --flag %U")
+        );
+        assert_eq!(
+            fields.try_exec.as_deref(),
+            Some("/opt/apps/Foo App.AppImage")
+        );
+        assert_eq!(
+            file.entry.icon.unwrap().content,
+            "/home/user/.local/share/icons/foo.png"
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,246 @@
+//! Autostart entries as described in the
+//! [Desktop Application Autostart Specification](https://specifications.freedesktop.org/autostart-spec/latest/),
+//! and minimal user overrides of them.
+//!
+//! A file in the user autostart directory takes the place of the file with the same name in
+//! the system directories. The overrides written here only hold the changed keys plus the keys
+//! identifying the entry, and [`AutostartDirs::effective_entries`] lays them over the system
+//! file, so the override keeps following updates of the system file.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    keyfile::{self, KeyFile},
+    util::{config_dirs, config_home, write_atomic},
+    DesktopFile,
+};
+
+/// The keys copied from the system file into every override, so that the override is still a
+/// valid entry for sessions that don't merge it with the system file
+pub const IDENTITY_KEYS: &[&str] = &["Type", "Name", "Exec", "TryExec"];
+
+const ENTRY_GROUP: &str = "Desktop Entry";
+
+/// The directories autostart entries are read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutostartDirs {
+    /// The user directory overrides are written to, usually `~/.config/autostart`
+    pub user: PathBuf,
+    /// The system directories in order of importance, usually `/etc/xdg/autostart`
+    pub system: Vec<PathBuf>,
+}
+
+fn to_io_error(e: crate::ParseError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn read_keyfile(path: &Path) -> io::Result<KeyFile> {
+    keyfile::parse(&std::fs::read_to_string(path)?).map_err(to_io_error)
+}
+
+fn file_name(id: &str) -> String {
+    if id.ends_with(".desktop") {
+        id.to_string()
+    } else {
+        format!("{id}.desktop")
+    }
+}
+
+impl AutostartDirs {
+    /// Uses `$XDG_CONFIG_HOME/autostart` and the `autostart` directories of `$XDG_CONFIG_DIRS`
+    pub fn from_env() -> io::Result<Self> {
+        let user = config_home()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no user config directory"))?;
+
+        Ok(Self {
+            user: user.join("autostart"),
+            system: config_dirs()
+                .into_iter()
+                .map(|d| d.join("autostart"))
+                .collect(),
+        })
+    }
+
+    /// Returns the most important system file of an entry
+    pub fn system_file(&self, id: &str) -> Option<PathBuf> {
+        let name = file_name(id);
+        self.system
+            .iter()
+            .map(|d| d.join(&name))
+            .find(|p| p.is_file())
+    }
+
+    /// Returns the path of the user override of an entry, whether it exists or not
+    pub fn override_path(&self, id: &str) -> PathBuf {
+        self.user.join(file_name(id))
+    }
+
+    /// Writes a user override of an entry containing the identity keys of the system file and
+    /// the given changes, such as `("Hidden", "true")` to disable the entry. Changes already
+    /// present in an existing override are kept. Values are written as they are.
+    pub fn override_entry(&self, id: &str, changes: &[(&str, &str)]) -> io::Result<PathBuf> {
+        let system_path = self.system_file(id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no system autostart file for {id:?}"),
+            )
+        })?;
+        let system = read_keyfile(&system_path)?;
+        let path = self.override_path(id);
+
+        let mut result = if path.is_file() {
+            read_keyfile(&path)?
+        } else {
+            KeyFile::default()
+        };
+
+        let group = result.group_mut(ENTRY_GROUP);
+        if let Some(system_group) = system.group(ENTRY_GROUP) {
+            for key in IDENTITY_KEYS {
+                if let Some(value) = system_group.get_raw(key) {
+                    group.set_raw(key, None, value);
+                }
+            }
+        }
+
+        for (key, value) in changes {
+            let (key, locale) = match key.split_once('[') {
+                Some((key, locale)) => (key, Some(locale.trim_end_matches(']'))),
+                None => (*key, None),
+            };
+            group.set_raw(key, locale, value);
+        }
+
+        std::fs::create_dir_all(&self.user)?;
+        write_atomic(&path, result.to_string().as_bytes())?;
+        Ok(path)
+    }
+
+    /// Removes the user override of an entry, returning whether there was one
+    pub fn remove_override(&self, id: &str) -> io::Result<bool> {
+        match std::fs::remove_file(self.override_path(id)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Resolves every autostart entry, laying user overrides over the system files. Entries
+    /// with Hidden=true are left out since the specification treats them as deleted, and
+    /// files that fail to parse are skipped. The result is sorted by id.
+    pub fn effective_entries(&self) -> io::Result<Vec<(String, DesktopFile)>> {
+        let mut files: BTreeMap<String, KeyFile> = BTreeMap::new();
+
+        // least important first, so more important files are laid over them
+        let dirs = self.system.iter().rev().chain(std::iter::once(&self.user));
+        for (i, dir) in dirs.enumerate() {
+            let Ok(read_dir) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            let is_user = i == self.system.len();
+
+            for dir_entry in read_dir.flatten() {
+                let path = dir_entry.path();
+                let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                if !name.ends_with(".desktop") {
+                    continue;
+                }
+                let Ok(file) = read_keyfile(&path) else {
+                    continue;
+                };
+
+                match files.get_mut(name) {
+                    Some(base) if is_user => base.overlay(&file),
+                    _ => {
+                        files.insert(name.to_string(), file);
+                    }
+                }
+            }
+        }
+
+        Ok(files
+            .into_iter()
+            .filter_map(|(id, file)| {
+                let file = crate::parse(&file.to_string()).ok()?;
+                (file.entry.hidden != Some(true)).then_some((id, file))
+            })
+            .collect())
+    }
+}
+
+/// Writes a user override of an entry with the directories of the environment. See
+/// [`AutostartDirs::override_entry`].
+pub fn override_entry(id: &str, changes: &[(&str, &str)]) -> io::Result<PathBuf> {
+    AutostartDirs::from_env()?.override_entry(id, changes)
+}
+
+/// Removes the user override of an entry with the directories of the environment
+pub fn remove_override(id: &str) -> io::Result<bool> {
+    AutostartDirs::from_env()?.remove_override(id)
+}
+
+/// Resolves every autostart entry with the directories of the environment. See
+/// [`AutostartDirs::effective_entries`].
+pub fn effective_autostart_entries() -> io::Result<Vec<(String, DesktopFile)>> {
+    AutostartDirs::from_env()?.effective_entries()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_chain() {
+        let base = std::env::temp_dir().join(format!(
+            "desktop-file-parser-autostart-{}",
+            std::process::id()
+        ));
+        let dirs = AutostartDirs {
+            user: base.join("user"),
+            system: vec![base.join("system")],
+        };
+        std::fs::create_dir_all(&dirs.system[0]).unwrap();
+        std::fs::write(
+            dirs.system[0].join("tracker.desktop"),
+            "[Desktop Entry]\nType=Application\nName=Tracker\nComment=Indexes files\nExec=tracker daemon\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dirs.system[0].join("updater.desktop"),
+            "[Desktop Entry]\nType=Application\nName=Updater\nExec=updater\n",
+        )
+        .unwrap();
+
+        let path = dirs
+            .override_entry("tracker", &[("X-GNOME-Autostart-Delay", "10")])
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[Desktop Entry]\nType=Application\nName=Tracker\nExec=tracker daemon\nX-GNOME-Autostart-Delay=10\n"
+        );
+        dirs.override_entry("updater.desktop", &[("Hidden", "true")])
+            .unwrap();
+
+        let entries = dirs.effective_entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        let (id, file) = &entries[0];
+        assert_eq!(id, "tracker.desktop");
+        assert_eq!(
+            file.entry.comment.as_ref().unwrap().default,
+            "Indexes files"
+        );
+        assert_eq!(file.entry.extras["X-GNOME-Autostart-Delay"], "10");
+
+        assert!(dirs.remove_override("updater").unwrap());
+        assert!(!dirs.remove_override("updater").unwrap());
+        assert_eq!(dirs.effective_entries().unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,276 @@
+//! Byte-accurate values for the keys naming files. Paths on Linux are not guaranteed to be
+//! UTF-8, so the Exec, TryExec, Path and Icon values of a file read with
+//! [`parse_bytes_with_options`](crate::parse_bytes_with_options) can keep their bytes next to
+//! the lossy strings of the typed fields.
+
+use std::{ffi::OsString, path::PathBuf};
+
+use crate::{
+    exec::ExecCommand, exec::ExecError, keys::Key, parser::unescape_char, DesktopEntry, EntryType,
+};
+
+/// The bytes of the values of the Desktop Entry group that are not valid UTF-8, with their
+/// escape sequences resolved. Values that are valid UTF-8 are exactly the typed fields and are
+/// not repeated here.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawValues {
+    pub exec: Option<Vec<u8>>,
+    pub try_exec: Option<Vec<u8>>,
+    pub path: Option<Vec<u8>>,
+    pub icon: Option<Vec<u8>>,
+}
+
+impl RawValues {
+    pub fn is_empty(&self) -> bool {
+        self.exec.is_none() && self.try_exec.is_none() && self.path.is_none() && self.icon.is_none()
+    }
+
+    /// Drops the bytes of a key whose typed field was changed, so they don't override the new
+    /// value
+    pub(crate) fn forget(&mut self, key: &Key) {
+        match key {
+            Key::Exec => self.exec = None,
+            Key::TryExec => self.try_exec = None,
+            Key::Path => self.path = None,
+            Key::Icon => self.icon = None,
+            _ => {}
+        }
+    }
+}
+
+/// Resolves the escape sequences of a value like [`crate::parser::unescape_string`]
+fn unescape_bytes(value: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(value.len());
+    let mut bytes = value.iter();
+
+    while let Some(&byte) = bytes.next() {
+        if byte != b'\\' {
+            res.push(byte);
+            continue;
+        }
+
+        match bytes.next() {
+            Some(&escaped) => match unescape_char(escaped as char) {
+                Some(unescaped) => res.push(unescaped as u8),
+                None => res.extend([b'\\', escaped]),
+            },
+            None => res.push(b'\\'),
+        }
+    }
+
+    res
+}
+
+/// Collects the values of the Desktop Entry group that are not valid UTF-8. The unlocalized
+/// value of the first Desktop Entry group is taken.
+pub(crate) fn raw_values(input: &[u8]) -> RawValues {
+    let mut values = RawValues::default();
+    let mut in_entry = false;
+    let mut seen_entry = false;
+
+    // a byte order mark is skipped like the parser does
+    let input = input.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(input);
+    for line in input.split(|b| *b == b'\n') {
+        let line = line.trim_ascii();
+        if let Some(name) = line.strip_prefix(b"[").and_then(|l| l.strip_suffix(b"]")) {
+            in_entry = !seen_entry && name.trim_ascii() == b"Desktop Entry";
+            seen_entry |= in_entry;
+            continue;
+        }
+        if !in_entry || line.starts_with(b"#") {
+            continue;
+        }
+
+        let Some(eq) = line.iter().position(|b| *b == b'=') else {
+            continue;
+        };
+        let value = line[eq + 1..].trim_ascii_start();
+        if std::str::from_utf8(value).is_ok() {
+            continue;
+        }
+
+        let target = match line[..eq].trim_ascii() {
+            b"Exec" => &mut values.exec,
+            b"TryExec" => &mut values.try_exec,
+            b"Path" => &mut values.path,
+            b"Icon" => &mut values.icon,
+            _ => continue,
+        };
+        target.get_or_insert_with(|| unescape_bytes(value));
+    }
+
+    values
+}
+
+/// Turns the bytes of a value into an OsString on Unix, falling back to the lossy string
+fn os_value(raw: Option<&Vec<u8>>, lossy: Option<&String>) -> Option<OsString> {
+    #[cfg(unix)]
+    if let Some(bytes) = raw {
+        return Some(std::os::unix::ffi::OsStringExt::from_vec(bytes.clone()));
+    }
+    #[cfg(not(unix))]
+    let _ = raw;
+
+    lossy.map(OsString::from)
+}
+
+impl DesktopEntry {
+    fn application_value(
+        &self,
+        value: impl Fn(&crate::ApplicationFields) -> Option<&String>,
+    ) -> Option<&String> {
+        match self.entry_type {
+            EntryType::Application(ref fields) => value(fields),
+            _ => None,
+        }
+    }
+
+    /// Returns the Exec value with its original bytes when it was read with
+    /// [`ParseOptions::keep_raw_bytes`](crate::ParseOptions::keep_raw_bytes), and the typed
+    /// field otherwise
+    pub fn exec_os(&self) -> Option<OsString> {
+        os_value(
+            self.raw_values.exec.as_ref(),
+            self.application_value(|f| f.exec.as_ref()),
+        )
+    }
+
+    /// Returns the TryExec value with its original bytes, like [`DesktopEntry::exec_os`]
+    pub fn try_exec_os(&self) -> Option<OsString> {
+        os_value(
+            self.raw_values.try_exec.as_ref(),
+            self.application_value(|f| f.try_exec.as_ref()),
+        )
+    }
+
+    /// Returns the working directory with its original bytes, like [`DesktopEntry::exec_os`]
+    pub fn path_os(&self) -> Option<PathBuf> {
+        os_value(
+            self.raw_values.path.as_ref(),
+            self.application_value(|f| f.path.as_ref()),
+        )
+        .map(PathBuf::from)
+    }
+
+    /// Splits the Exec value into its arguments like [`ExecCommand::parse`], keeping the
+    /// original bytes of each argument when they were read with
+    /// [`ParseOptions::keep_raw_bytes`](crate::ParseOptions::keep_raw_bytes). Field codes are
+    /// kept as written. This is the command line to expand and spawn for an application
+    /// installed under a path that is not UTF-8.
+    pub fn exec_args_os(&self) -> Option<Result<Vec<OsString>, ExecError>> {
+        let Some(ref raw) = self.raw_values.exec else {
+            let exec = self.application_value(|f| f.exec.as_ref())?;
+            return Some(ExecCommand::parse(exec).map(|command| {
+                command
+                    .args
+                    .into_iter()
+                    .map(|arg| OsString::from(arg.value))
+                    .collect()
+            }));
+        };
+
+        // every byte stands for the char of the same value, so the quoting rules, which only
+        // involve ASCII characters, apply unchanged and the bytes come back as they were
+        let latin1 = raw.iter().map(|b| *b as char).collect::<String>();
+        Some(ExecCommand::parse(&latin1).map(|command| {
+            command
+                .args
+                .into_iter()
+                .map(|arg| {
+                    let bytes = arg.value.chars().map(|c| c as u8).collect::<Vec<_>>();
+                    os_value(Some(&bytes), None).unwrap_or_else(|| {
+                        OsString::from(String::from_utf8_lossy(&bytes).into_owned())
+                    })
+                })
+                .collect()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_values() {
+        let input = b"[Desktop Entry]\nType=Application\nName=App\nExec=\"/opt/Caf\xe9 App/run\" --flag %U\nPath=/opt/Caf\xe9\\sApp\nTryExec=app\n\n[Desktop Action new]\nExec=/opt/\xff\n";
+        let options = crate::ParseOptions {
+            keep_raw_bytes: true,
+            ..Default::default()
+        };
+
+        let entry = crate::parse_bytes(input).unwrap().entry;
+        assert!(entry.raw_values.is_empty());
+        assert_eq!(
+            entry.path_os().unwrap().to_string_lossy(),
+            "/opt/Caf\u{fffd} App"
+        );
+
+        let entry = crate::parse_bytes_with_options(input, &options)
+            .unwrap()
+            .entry;
+        assert_eq!(
+            entry.raw_values,
+            RawValues {
+                exec: Some(b"\"/opt/Caf\xe9 App/run\" --flag %U".to_vec()),
+                path: Some(b"/opt/Caf\xe9 App".to_vec()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(entry.try_exec_os(), Some("app".into()));
+        let with_bom = [&b"\xEF\xBB\xBF"[..], input].concat();
+        assert_eq!(
+            crate::parse_bytes_with_options(&with_bom, &options)
+                .unwrap()
+                .entry
+                .raw_values,
+            entry.raw_values
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+
+            assert_eq!(
+                entry.path_os().unwrap().as_os_str().as_bytes(),
+                b"/opt/Caf\xe9 App"
+            );
+            let args = entry.exec_args_os().unwrap().unwrap();
+            let args = args.iter().map(|a| a.as_bytes()).collect::<Vec<_>>();
+            assert_eq!(args, [&b"/opt/Caf\xe9 App/run"[..], b"--flag", b"%U"]);
+        }
+
+        // editing a key only drops the bytes of that key
+        let mut edited = entry.clone();
+        edited.set_key("NoDisplay", "true").unwrap();
+        assert_eq!(edited.raw_values, entry.raw_values);
+        edited.set_key("Exec", "other %U").unwrap();
+        assert_eq!(edited.exec_os(), Some("other %U".into()));
+        assert!(edited.raw_values.path.is_some());
+        edited.remove_key("Path").unwrap();
+        assert!(edited.raw_values.is_empty());
+
+        let mut edited = entry.clone();
+        edited
+            .set(Key::Exec, crate::value::Value::String("run".into()))
+            .unwrap();
+        assert_eq!(edited.exec_os(), Some("run".into()));
+
+        let mut entry = crate::parse_bytes_with_options(
+            b"[Desktop Entry]\nType=Application\nName=App\nExec=$HOME/caf\xe9/tool %U\n",
+            &options,
+        )
+        .unwrap()
+        .entry;
+        let env = |name: &str| (name == "HOME").then(|| "/home/me".to_string());
+        let changed = entry
+            .expand_vars(&env, &crate::expand::ExpandOptions::default())
+            .unwrap();
+        assert_eq!(changed, [Key::Exec]);
+        assert_eq!(entry.exec_os(), Some("/home/me/caf\u{fffd}/tool %U".into()));
+        assert_eq!(
+            entry.exec_args_os().unwrap().unwrap()[0],
+            "/home/me/caf\u{fffd}/tool"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! A normal form of desktop files, so files that only differ in formatting compare equal.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{locale::normalize_locale, DesktopFile, EntryType};
+
+/// Normalizes the locales of the variants, keeping the variant written without an encoding
+/// when two variants only differ by it
+fn normalize_variants<T>(variants: &mut HashMap<String, T>) {
+    let mut locales = variants.keys().cloned().collect::<Vec<_>>();
+    // shorter locales first, so `de_DE` wins over `de_DE.UTF-8`
+    locales.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+
+    let mut normalized = HashMap::with_capacity(variants.len());
+    for locale in locales {
+        let value = variants.remove(&locale).unwrap();
+        normalized.entry(normalize_locale(&locale)).or_insert(value);
+    }
+    *variants = normalized;
+}
+
+/// Removes the repeated items of a list, keeping the first occurrence of each
+fn dedup(list: &mut Vec<String>) {
+    let mut seen = HashSet::new();
+    list.retain(|item| seen.insert(item.clone()));
+}
+
+impl DesktopFile {
+    /// Brings the file to its canonical form, without changing what it means:
+    /// - the locales of the variants are normalized, dropping their encoding which the
+    ///   specification says to ignore
+    /// - repeated Categories, MimeType, OnlyShowIn and NotShowIn items are removed
+    /// - comments are dropped
+    ///
+    /// Serializing then decodes and re-encodes the escapes, terminates lists with semicolons,
+    /// sorts the variants and writes the keys in the order of
+    /// [`KeyOrder::SpecConventional`](crate::serializer::KeyOrder::SpecConventional), which
+    /// [`DesktopFile::to_canonical_string`] does. Values of other groups are kept as written.
+    pub fn canonicalize(&mut self) {
+        let entry = &mut self.entry;
+        normalize_variants(&mut entry.name.variants);
+        for string in [entry.generic_name.as_mut(), entry.comment.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            normalize_variants(&mut string.variants);
+        }
+        for list in [entry.only_show_in.as_mut(), entry.not_show_in.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            dedup(list);
+        }
+
+        if let EntryType::Application(ref mut fields) = entry.entry_type {
+            if let Some(ref mut keywords) = fields.keywords {
+                normalize_variants(&mut keywords.variants);
+            }
+            for list in [fields.categories.as_mut(), fields.mime_type.as_mut()]
+                .into_iter()
+                .flatten()
+            {
+                dedup(list);
+            }
+        }
+
+        for action in self.actions.values_mut() {
+            normalize_variants(&mut action.name.variants);
+        }
+
+        self.comments = Default::default();
+    }
+
+    /// Serializes the canonical form of the file, so two files with the same meaning give the
+    /// same string
+    pub fn to_canonical_string(&self) -> String {
+        let mut file = self.clone();
+        file.canonicalize();
+        file.to_desktop_string()
+    }
+
+    /// Hashes the canonical form of the file, to tell whether it meaningfully changed without
+    /// keeping a copy of it.
+    ///
+    /// The hash is the 64-bit FNV-1a of the UTF-8 bytes of
+    /// [`DesktopFile::to_canonical_string`], so files equal after the rules of
+    /// [`DesktopFile::canonicalize`] hash equal: comments, key order, locale encodings and
+    /// repeated list items don't matter, while any value or locale variant does. The scheme
+    /// doesn't depend on the platform or the Rust version, but changes to the canonical form
+    /// change the hashes.
+    pub fn content_hash(&self) -> u64 {
+        fnv1a(self.to_canonical_string().as_bytes())
+    }
+}
+
+/// The 64-bit FNV-1a hash of some bytes
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, parse_with_options, ParseOptions};
+
+    #[test]
+    fn test_canonical_string() {
+        let a = parse_with_options(
+            "# comment\n[Desktop Entry]\nName[de_DE.UTF-8]=Editor\nType=Application\nName=Editor\nExec=editor\nCategories=Utility;TextEditor;Utility\n",
+            &ParseOptions {
+                keep_comments: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let b = parse(
+            "[Desktop Entry]\nType=Application\nExec=editor\nName=Editor\nName[de_DE]=Editor\nCategories=Utility;TextEditor;\n",
+        )
+        .unwrap();
+
+        assert_eq!(a.to_canonical_string(), b.to_canonical_string());
+        assert_eq!(
+            a.to_canonical_string(),
+            "[Desktop Entry]\nType=Application\nName=Editor\nName[de_DE]=Editor\nExec=editor\nCategories=Utility;TextEditor;\n"
+        );
+    }
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(super::fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(super::fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+
+        let a = parse_with_options(
+            "[Desktop Entry]\n# comment\nName=Editor\nType=Application\nName[de]=Editor\nExec=editor\n",
+            &ParseOptions {
+                keep_comments: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let b =
+            parse("[Desktop Entry]\nType=Application\nExec=editor\nName[de]=Editor\nName=Editor\n")
+                .unwrap();
+        let c = parse(
+            "[Desktop Entry]\nType=Application\nExec=editor\nName[de]=Bearbeiter\nName=Editor\n",
+        )
+        .unwrap();
+
+        assert_eq!(a.content_hash(), b.content_hash());
+        assert_ne!(a.content_hash(), c.content_hash());
+        assert_eq!(a.content_hash(), 0x4298_8081_540d_7ffc);
+        assert_eq!(c.content_hash(), 0xe860_6be4_42c3_0d3c);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_canonical_semantics(
+            name in "[^\\s\\[\\]=]([^\\n]*[^\\s])?",
+            locales in proptest::collection::vec("[a-z]{2}(_[A-Z]{2})?(@[a-z]{3})?", 0..4),
+            categories in proptest::collection::vec("[A-Za-z]{1,8}", 0..6),
+        ) {
+            let mut file = crate::DesktopFile::new_application(name.clone(), "app").unwrap();
+            for (i, locale) in locales.iter().enumerate() {
+                file.entry.name.variants.insert(locale.clone(), format!("{name} {i}"));
+            }
+            if let crate::EntryType::Application(ref mut fields) = file.entry.entry_type {
+                fields.categories = Some(categories.clone());
+            }
+
+            let canonical = file.to_canonical_string();
+            let parsed = parse(&canonical).unwrap();
+            proptest::prop_assert_eq!(&parsed.to_canonical_string(), &canonical);
+
+            // every locale resolves to the value it had before
+            for locale in &locales {
+                proptest::prop_assert_eq!(
+                    parsed.entry.name.resolve(&[locale]).0,
+                    file.entry.name.resolve(&[locale]).0
+                );
+            }
+            let crate::EntryType::Application(ref fields) = parsed.entry.entry_type else {
+                panic!("expected an application");
+            };
+            let parsed_categories = fields.categories.clone().unwrap_or_default();
+            for category in &categories {
+                proptest::prop_assert!(parsed_categories.contains(category));
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! A set of desktop files keyed by desktop file ID, with indices for the usual lookups.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{DesktopFile, EntryType};
+
+/// The ids of the files having each value of a key
+type Index = HashMap<String, BTreeSet<String>>;
+
+/// Desktop files by id, indexed by category, MIME type, implemented interface and
+/// StartupWMClass. The indices only store ids, and they are kept up to date by
+/// [`DesktopFileCollection::insert`] and [`DesktopFileCollection::remove`].
+#[derive(Debug, Clone, Default)]
+pub struct DesktopFileCollection {
+    files: HashMap<String, DesktopFile>,
+    categories: Index,
+    mime_types: Index,
+    implements: Index,
+    wm_classes: Index,
+}
+
+impl DesktopFileCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or updates the indices of a file
+    fn index(&mut self, id: &str, file: &DesktopFile, add: bool) {
+        fn update(index: &mut Index, key: String, id: &str, add: bool) {
+            if add {
+                index.entry(key).or_default().insert(id.to_string());
+            } else if let Some(ids) = index.get_mut(&key) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    index.remove(&key);
+                }
+            }
+        }
+
+        let EntryType::Application(ref fields) = file.entry.entry_type else {
+            return;
+        };
+
+        for category in fields.categories.iter().flatten() {
+            update(&mut self.categories, category.clone(), id, add);
+        }
+        for mime_type in fields.mime_type.iter().flatten() {
+            update(&mut self.mime_types, mime_type.to_lowercase(), id, add);
+        }
+        for interface in fields.implements.iter().flatten() {
+            update(&mut self.implements, interface.clone(), id, add);
+        }
+        if let Some(ref class) = fields.startup_wm_class {
+            update(&mut self.wm_classes, class.clone(), id, add);
+        }
+    }
+
+    /// Adds a file, returning the file it replaced
+    pub fn insert(&mut self, id: impl Into<String>, file: DesktopFile) -> Option<DesktopFile> {
+        let id = id.into();
+        let old = self.remove(&id);
+        self.index(&id, &file, true);
+        self.files.insert(id, file);
+        old
+    }
+
+    /// Removes a file, returning it
+    pub fn remove(&mut self, id: &str) -> Option<DesktopFile> {
+        let file = self.files.remove(id)?;
+        self.index(id, &file, false);
+        Some(file)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&DesktopFile> {
+        self.files.get(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Iterates over the files and their ids, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DesktopFile)> {
+        self.files.iter().map(|(id, file)| (id.as_str(), file))
+    }
+
+    fn lookup<'a>(
+        &'a self,
+        index: &'a Index,
+        key: &str,
+    ) -> impl Iterator<Item = (&'a str, &'a DesktopFile)> {
+        index
+            .get(key)
+            .into_iter()
+            .flatten()
+            .map(|id| (id.as_str(), &self.files[id]))
+    }
+
+    /// Returns the files in a category, sorted by id
+    pub fn by_category(&self, category: &str) -> impl Iterator<Item = (&str, &DesktopFile)> {
+        self.lookup(&self.categories, category)
+    }
+
+    /// Returns the files supporting a MIME type, compared case-insensitively, sorted by id
+    pub fn by_mime_type(&self, mime_type: &str) -> impl Iterator<Item = (&str, &DesktopFile)> {
+        self.lookup(&self.mime_types, &mime_type.to_lowercase())
+    }
+
+    /// Returns the files implementing an interface, sorted by id
+    pub fn by_implements(&self, interface: &str) -> impl Iterator<Item = (&str, &DesktopFile)> {
+        self.lookup(&self.implements, interface)
+    }
+
+    /// Returns the files with a StartupWMClass, sorted by id
+    pub fn by_wm_class(&self, class: &str) -> impl Iterator<Item = (&str, &DesktopFile)> {
+        self.lookup(&self.wm_classes, class)
+    }
+}
+
+impl<S: Into<String>> Extend<(S, DesktopFile)> for DesktopFileCollection {
+    fn extend<T: IntoIterator<Item = (S, DesktopFile)>>(&mut self, iter: T) {
+        for (id, file) in iter {
+            self.insert(id, file);
+        }
+    }
+}
+
+impl<S: Into<String>> FromIterator<(S, DesktopFile)> for DesktopFileCollection {
+    fn from_iter<T: IntoIterator<Item = (S, DesktopFile)>>(iter: T) -> Self {
+        let mut res = Self::new();
+        res.extend(iter);
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(categories: &str, mime_types: &str) -> DesktopFile {
+        crate::parse(&format!(
+            "[Desktop Entry]\nType=Application\nName=App\nExec=app\nCategories={categories}\nMimeType={mime_types}\nStartupWMClass=app\n"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_collection() {
+        let mut collection = [
+            ("editor.desktop", app("Utility;TextEditor;", "text/plain;")),
+            ("viewer.desktop", app("Utility;", "Text/Plain;image/png;")),
+        ]
+        .into_iter()
+        .collect::<DesktopFileCollection>();
+
+        let ids = |iter: &mut dyn Iterator<Item = (&str, &DesktopFile)>| {
+            iter.map(|(id, _)| id.to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(&mut collection.by_category("Utility")),
+            ["editor.desktop", "viewer.desktop"]
+        );
+        assert_eq!(ids(&mut collection.by_mime_type("text/PLAIN")).len(), 2);
+        assert_eq!(ids(&mut collection.by_wm_class("app")).len(), 2);
+        assert!(collection.by_implements("org.example.Foo").next().is_none());
+
+        // replacing a file drops its old index entries
+        collection.insert("editor.desktop", app("Development;", ""));
+        assert_eq!(
+            ids(&mut collection.by_category("Utility")),
+            ["viewer.desktop"]
+        );
+        assert!(collection.by_category("TextEditor").next().is_none());
+        assert!(!collection.categories.contains_key("TextEditor"));
+
+        collection.remove("viewer.desktop").unwrap();
+        collection.remove("editor.desktop").unwrap();
+        assert!(collection.is_empty());
+        assert!(collection.categories.is_empty());
+        assert!(collection.mime_types.is_empty());
+        assert!(collection.wm_classes.is_empty());
+        assert!(collection.remove("editor.desktop").is_none());
+    }
+}
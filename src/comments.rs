@@ -0,0 +1,104 @@
+//! Comments of a file, kept when parsing with [`ParseOptions::keep_comments`] and written
+//! back in place by the serializer.
+//!
+//! [`ParseOptions::keep_comments`]: crate::ParseOptions::keep_comments
+
+use std::collections::HashMap;
+
+use crate::DesktopFile;
+
+/// The comment blocks of a file, each attached to the group header or the key right after it.
+/// Comment lines are stored without their `#`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comments {
+    pub(crate) groups: HashMap<String, Vec<String>>,
+    pub(crate) keys: HashMap<(String, String), Vec<String>>,
+    pub(crate) trailing: Vec<String>,
+}
+
+impl Comments {
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty() && self.keys.is_empty() && self.trailing.is_empty()
+    }
+
+    /// Attaches a comment block to the header of a group, replacing its previous comments
+    pub fn set_group(&mut self, group: &str, lines: Vec<String>) {
+        self.groups.insert(group.to_string(), lines);
+    }
+
+    /// Attaches a comment block to a key like `Name[de]` of a group, replacing its previous
+    /// comments
+    pub fn set_key(&mut self, group: &str, key: &str, lines: Vec<String>) {
+        self.keys
+            .insert((group.to_string(), key.to_string()), lines);
+    }
+}
+
+impl DesktopFile {
+    /// Returns the comment lines above the header of a group
+    pub fn comments_for(&self, group: &str) -> &[String] {
+        self.comments
+            .groups
+            .get(group)
+            .map_or(&[], |c| c.as_slice())
+    }
+
+    /// Returns the comment lines above a key of a group, written like `Name[de]`
+    pub fn comments_for_key(&self, group: &str, key: &str) -> &[String] {
+        self.comments
+            .keys
+            .get(&(group.to_string(), key.to_string()))
+            .map_or(&[], |c| c.as_slice())
+    }
+
+    /// Returns the comment lines after the last key of the file
+    pub fn trailing_comments(&self) -> &[String] {
+        &self.comments.trailing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, parse_with_options, ParseOptions};
+
+    const INPUT: &str = "# generated file
+[Desktop Entry]
+Type=Application
+# TRANSLATORS: keep the brand name
+Name=Acme
+Name[de]=Acme
+Exec=acme
+Actions=new;
+
+# the new window action
+[Desktop Action new]
+Name=New
+# end
+";
+
+    #[test]
+    fn test_comments() {
+        let options = ParseOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        let file = parse_with_options(INPUT, &options).unwrap();
+
+        assert_eq!(file.comments_for("Desktop Entry"), [" generated file"]);
+        assert_eq!(
+            file.comments_for_key("Desktop Entry", "Name"),
+            [" TRANSLATORS: keep the brand name"]
+        );
+        assert!(file
+            .comments_for_key("Desktop Entry", "Name[de]")
+            .is_empty());
+        assert_eq!(
+            file.comments_for("Desktop Action new"),
+            [" the new window action"]
+        );
+        assert_eq!(file.trailing_comments(), [" end"]);
+        assert_eq!(file.to_desktop_string(), INPUT);
+
+        assert!(parse(INPUT).unwrap().comments.is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+//! Reports of which localizable keys of a file lack a translation for a set of locales.
+
+use std::fmt;
+
+use crate::{
+    locale::{normalize_locale, LocalizedKey},
+    DesktopFile,
+};
+
+/// The translation state of a file for one locale
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LocaleCoverage {
+    pub locale: String,
+    /// The localizable keys present in the file without a variant for the locale
+    pub missing: Vec<LocalizedKey>,
+    /// The number of localizable keys present in the file
+    pub total: usize,
+}
+
+impl LocaleCoverage {
+    /// The number of keys with a variant for the locale
+    pub fn translated(&self) -> usize {
+        self.total - self.missing.len()
+    }
+}
+
+/// The translation state of a file for a set of required locales
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CoverageReport {
+    /// One entry per required locale, in the order they were given
+    pub locales: Vec<LocaleCoverage>,
+    /// The percentage of translated keys over every required locale, 100 if there is nothing
+    /// to translate
+    pub percentage: f64,
+}
+
+/// Checks which localizable keys present in the file (Name, GenericName, Comment, Keywords and
+/// the action names) lack a variant for each of the required locales. Locales are compared
+/// after [`normalize_locale`], and a variant only counts for its exact locale.
+pub fn translation_coverage(file: &DesktopFile, required_locales: &[&str]) -> CoverageReport {
+    let keys = file.localized_keys();
+
+    let locales = required_locales
+        .iter()
+        .map(|required| {
+            let required = normalize_locale(required);
+            let missing = keys
+                .iter()
+                .filter(|(_, locales)| !locales.iter().any(|l| normalize_locale(l) == required))
+                .map(|(key, _)| key.clone())
+                .collect();
+
+            LocaleCoverage {
+                locale: required,
+                missing,
+                total: keys.len(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let total = locales.iter().map(|l| l.total).sum::<usize>();
+    let translated = locales.iter().map(|l| l.translated()).sum::<usize>();
+    let percentage = if total == 0 {
+        100.0
+    } else {
+        translated as f64 * 100.0 / total as f64
+    };
+
+    CoverageReport {
+        locales,
+        percentage,
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for locale in &self.locales {
+            write!(
+                f,
+                "{}: {}/{} translated",
+                locale.locale,
+                locale.translated(),
+                locale.total
+            )?;
+            for (i, key) in locale.missing.iter().enumerate() {
+                let sep = if i == 0 { ", missing " } else { ", " };
+                write!(f, "{sep}{key}")?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "overall: {:.1}%", self.percentage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coverage() {
+        let file = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Editor\nName[de]=Bearbeiter\nName[fr]=Éditeur\nComment=Edit\nComment[de]=Bearbeiten\nActions=new;\n\n[Desktop Action new]\nName=New\nName[de.UTF-8]=Neu\n",
+        )
+        .unwrap();
+
+        let report = translation_coverage(&file, &["de", "fr"]);
+        assert_eq!(report.locales[0].missing, []);
+        assert_eq!(report.locales[1].translated(), 1);
+        assert_eq!(report.percentage, 4.0 * 100.0 / 6.0);
+        assert_eq!(
+            report.to_string(),
+            "de: 3/3 translated\nfr: 1/3 translated, missing Comment, Name of action new\noverall: 66.7%"
+        );
+
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            serde_json::to_value(&report).unwrap()["locales"][1]["missing"][0],
+            serde_json::json!({ "Entry": "Comment" })
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Rendering of parse errors and lint findings as [`miette`] diagnostics, with the offending
+//! part of the source labeled.
+
+use std::fmt;
+
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
+
+use crate::{
+    keys::suggest_key,
+    validate::{Finding, Severity, UNKNOWN_KEY},
+    ParseError,
+};
+
+/// A parse error or a finding together with the source it is about
+#[derive(Debug)]
+pub struct SourceDiagnostic {
+    message: String,
+    code: Option<&'static str>,
+    severity: miette::Severity,
+    help: Option<String>,
+    label: Option<LabeledSpan>,
+    source: NamedSource<String>,
+}
+
+/// Returns the byte range of the content of a line, from the given column on
+fn line_span(source: &str, line: usize, col: usize) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    for (i, content) in source.split('\n').enumerate() {
+        if i == line {
+            let content = content.trim_end();
+            let start = col.min(content.len());
+            return Some((offset + start, (content.len() - start).max(1)));
+        }
+        offset += content.len() + 1;
+    }
+    None
+}
+
+impl fmt::Display for SourceDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for SourceDiagnostic {}
+
+impl Diagnostic for SourceDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.code
+            .map(|code| Box::new(code) as Box<dyn fmt::Display>)
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(self.severity)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        self.label
+            .clone()
+            .map(|label| Box::new(std::iter::once(label)) as Box<dyn Iterator<Item = _>>)
+    }
+}
+
+impl ParseError {
+    /// Turns the error into a diagnostic labeling its position in the source it was parsed
+    /// from. `name` is usually the path of the file.
+    pub fn to_diagnostic(&self, name: &str, source: &str) -> SourceDiagnostic {
+        let help = match self {
+            Self::Syntax { .. } => {
+                Some("keys may only contain A-Za-z0-9- and an optional [locale]")
+            }
+            Self::RepetitiveEntry { .. } => Some("a file has a single [Desktop Entry] group"),
+            Self::FormatError { .. } => {
+                Some("only comments may come before the [Desktop Entry] group")
+            }
+            Self::RepetitiveKey { .. } => Some("remove one of the declarations"),
+            _ => None,
+        };
+        let label = self
+            .position()
+            .and_then(|(row, col)| line_span(source, row, col))
+            .map(|span| LabeledSpan::at(span, self.code_name()));
+
+        SourceDiagnostic {
+            message: self.to_string(),
+            code: Some(self.code()),
+            severity: miette::Severity::Error,
+            help: help.map(|h| h.to_string()),
+            label,
+            source: NamedSource::new(name, source.to_string()),
+        }
+    }
+}
+
+impl Finding {
+    /// Turns the finding into a diagnostic labeling its line in the source it was validated
+    /// from. Unknown keys get a suggestion of the standard key they are likely a typo of.
+    pub fn to_diagnostic(&self, name: &str, source: &str) -> SourceDiagnostic {
+        let help = match self.key {
+            Some(ref key) if self.rule == UNKNOWN_KEY => {
+                suggest_key(key).map(|suggestion| format!("did you mean {suggestion:?}?"))
+            }
+            _ => None,
+        };
+        let label = self
+            .line
+            .and_then(|line| line_span(source, line, 0))
+            .map(|span| LabeledSpan::at(span, self.rule));
+
+        SourceDiagnostic {
+            message: self.message.clone(),
+            code: self.code(),
+            severity: match self.severity {
+                Severity::Hint => miette::Severity::Advice,
+                Severity::Warning => miette::Severity::Warning,
+                Severity::Error => miette::Severity::Error,
+            },
+            help,
+            label,
+            source: NamedSource::new(name, source.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::Validator;
+
+    #[test]
+    fn test_diagnostics() {
+        let source = "[Desktop Entry]\nType=Application\nName=Foo\nbad key=1\n";
+        let error = crate::parse(source).unwrap_err();
+        let diagnostic = error.to_diagnostic("foo.desktop", source);
+        assert_eq!(diagnostic.code().unwrap().to_string(), "E002");
+        let label = diagnostic.labels().unwrap().next().unwrap();
+        assert_eq!(
+            &source[label.offset()..label.offset() + label.len()],
+            "key=1"
+        );
+
+        let source = "[Desktop Entry]\nType=Application\nName=Foo\nCatgories=Utility;\n";
+        let report = Validator::new().validate_source(source).unwrap();
+        let diagnostic = report.findings[0].to_diagnostic("foo.desktop", source);
+        assert_eq!(diagnostic.severity(), Some(miette::Severity::Warning));
+        assert_eq!(
+            diagnostic.help().unwrap().to_string(),
+            "did you mean \"Categories\"?"
+        );
+        let label = diagnostic.labels().unwrap().next().unwrap();
+        assert_eq!(label.offset(), source.find("Catgories").unwrap());
+    }
+}
@@ -0,0 +1,495 @@
+//! A pull parser yielding the groups, key value pairs and comments of a file lazily, without
+//! building a [`crate::DesktopFile`]. It is the tokenizer behind [`crate::parse`] and
+//! [`crate::keyfile::parse`].
+
+use crate::{
+    internal_structs::{group_name, Header},
+    keys::Key,
+    ParseError,
+};
+
+/// The position of a line in the input, as byte offsets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// The line number, starting at 0
+    pub line: usize,
+    /// The offset of the first byte of the line content, after leading indentation
+    pub start: usize,
+    /// The offset after the last byte of the line content, before trailing whitespace unless
+    /// it is kept as part of a value
+    pub end: usize,
+}
+
+/// A meaningful piece of the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// A group header such as `[Desktop Entry]`, with the name inside the brackets
+    GroupStart(&'a str),
+    /// A key value pair. The value is as written, with escape sequences intact, and starts
+    /// after the whitespace following `=`.
+    KeyValue {
+        key: Key,
+        locale: Option<&'a str>,
+        value: &'a str,
+        span: Span,
+        /// Whether trailing whitespace was cut off the value, see
+        /// [`Events::keep_trailing_whitespace`]
+        trimmed: bool,
+    },
+    /// A comment line, with the text after `#`
+    Comment(&'a str),
+}
+
+/// Iterates over the events of the input. Errors are yielded in place of the event of the
+/// line they occur on, and iteration can go on after them.
+pub struct Events<'a> {
+    input: &'a str,
+    next_line: usize,
+    next_offset: usize,
+    span: Span,
+    keep_trailing_whitespace: bool,
+    reject_indentation: bool,
+    template_keys: bool,
+    /// Whether the key of the last event was written with a leading underscore
+    translatable: bool,
+    /// Whether the lines up to the next group header are skipped
+    skipping: bool,
+}
+
+impl<'a> Events<'a> {
+    /// Starts at the beginning of the input, after a byte order mark that some editors write.
+    /// Offsets stay relative to the input including the mark.
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            next_line: 0,
+            next_offset: if input.starts_with('\u{FEFF}') {
+                '\u{FEFF}'.len_utf8()
+            } else {
+                0
+            },
+            span: Span {
+                line: 0,
+                start: 0,
+                end: 0,
+            },
+            keep_trailing_whitespace: false,
+            reject_indentation: false,
+            template_keys: false,
+            translatable: false,
+            skipping: false,
+        }
+    }
+
+    /// Keeps the trailing whitespace of values instead of cutting it off, which is what happens
+    /// by default. A `\r` ending the line is dropped either way.
+    pub fn keep_trailing_whitespace(mut self, keep: bool) -> Self {
+        self.keep_trailing_whitespace = keep;
+        self
+    }
+
+    /// Fails on group headers and key value lines indented with spaces or tabs, which the
+    /// specification doesn't allow, instead of ignoring the indentation. Comments may still be
+    /// indented.
+    pub fn reject_indentation(mut self, reject: bool) -> Self {
+        self.reject_indentation = reject;
+        self
+    }
+
+    /// Accepts the keys of `.desktop.in` templates marked for translation with a leading
+    /// underscore, like `_Name`. They are yielded without the underscore, and
+    /// [`Events::is_translatable`] tells them apart.
+    pub fn template_keys(mut self, template_keys: bool) -> Self {
+        self.template_keys = template_keys;
+        self
+    }
+
+    /// Returns whether the key of the last event was marked for translation, see
+    /// [`Events::template_keys`]
+    pub fn is_translatable(&self) -> bool {
+        self.translatable
+    }
+
+    /// Returns the span of the line of the last event
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Skips the rest of the current group. The lines up to the next group header are passed
+    /// over without being tokenized, so they yield neither events nor errors.
+    pub fn skip_group(&mut self) {
+        self.skipping = true;
+    }
+
+    fn group(&self, content: &'a str, col: usize) -> Result<Event<'a>, ParseError> {
+        group_name(content, self.span.line, col).map(Event::GroupStart)
+    }
+
+    /// Returns the next line, scanning for its end byte-wise
+    fn next_raw(&self) -> Option<&'a str> {
+        let rest = self.input.get(self.next_offset..)?;
+        let end = memchr::memchr(b'\n', rest.as_bytes()).unwrap_or(rest.len());
+        Some(&rest[..end])
+    }
+
+    /// Parses a well formed key value line by scanning its bytes for the separators, which is
+    /// safe since keys are ASCII. Returns None if the line needs the careful parse, which also
+    /// reports the errors.
+    fn fast_key_value(&self, content: &'a str, trailing: usize) -> Option<Event<'a>> {
+        let bytes = content.as_bytes();
+        let sep = memchr::memchr2(b'=', b'[', bytes)?;
+        let key = content[..sep].trim_end_matches(' ');
+        if !key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+            return None;
+        }
+
+        let (locale, value) = if bytes[sep] == b'[' {
+            let close = sep + 1 + memchr::memchr(b']', &bytes[sep + 1..])?;
+            if bytes.get(close + 1) != Some(&b'=') {
+                return None;
+            }
+            (Some(&content[sep + 1..close]), &content[close + 2..])
+        } else {
+            (None, &content[sep + 1..])
+        };
+
+        let value = value.trim_start();
+        Some(Event::KeyValue {
+            key: Key::from(key),
+            locale,
+            value,
+            span: self.span,
+            trimmed: trailing > 0 && !value.is_empty(),
+        })
+    }
+
+    /// Parses a key value line. `trailing` is the whitespace cut off the end of `content`.
+    fn key_value(
+        &self,
+        content: &'a str,
+        trailing: usize,
+        col: usize,
+    ) -> Result<Event<'a>, ParseError> {
+        match self.fast_key_value(content, trailing) {
+            Some(event) => Ok(event),
+            None => self.careful_key_value(content, trailing, col),
+        }
+    }
+
+    /// Parses a key value line character by character, reporting where it is malformed
+    fn careful_key_value(
+        &self,
+        content: &'a str,
+        trailing: usize,
+        col: usize,
+    ) -> Result<Event<'a>, ParseError> {
+        enum State {
+            /// the initial key parser
+            Key,
+            /// the locale parser
+            KeyLocale(usize),
+            /// the character that ends the locale spec
+            LocaleToValue,
+        }
+
+        let mut state = State::Key;
+        let mut key_end = None;
+        let mut locale = None;
+        let mut value = "";
+
+        for (i, ch) in content.char_indices() {
+            match state {
+                State::Key => match ch {
+                    '[' => {
+                        key_end.get_or_insert(i);
+                        state = State::KeyLocale(i + 1);
+                    }
+                    '=' => {
+                        key_end.get_or_insert(i);
+                        value = &content[i + 1..];
+                        break;
+                    }
+                    ' ' => {
+                        key_end.get_or_insert(i);
+                    }
+                    'A'..='Z' | 'a'..='z' | '0'..='9' | '-' if key_end.is_none() => {}
+                    _ => {
+                        return Err(ParseError::Syntax {
+                            msg: "Keys shouldn't have characters other than A-Za-z0-9-".into(),
+                            row: self.span.line,
+                            col: col + i,
+                            group: None,
+                        })
+                    }
+                },
+                State::KeyLocale(start) => {
+                    if ch == ']' {
+                        locale = Some(&content[start..i]);
+                        state = State::LocaleToValue;
+                    }
+                }
+                State::LocaleToValue => match ch {
+                    '=' => {
+                        value = &content[i + 1..];
+                        break;
+                    }
+                    _ => {
+                        return Err(ParseError::Syntax {
+                            msg: "Expect \"=\" after \"=\"".into(),
+                            row: self.span.line,
+                            col: col + i,
+                            group: None,
+                        });
+                    }
+                },
+            }
+        }
+
+        // a locale that is never closed takes the rest of the line
+        if let State::KeyLocale(start) = state {
+            locale = Some(&content[start..]);
+        }
+
+        let value = value.trim_start();
+        Ok(Event::KeyValue {
+            key: Key::from(&content[..key_end.unwrap_or(content.len())]),
+            locale,
+            value,
+            span: self.span,
+            trimmed: trailing > 0 && !value.is_empty(),
+        })
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Result<Event<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let raw = self.next_raw()?;
+            let line = self.next_line;
+            let offset = self.next_offset;
+            self.next_line += 1;
+            self.next_offset += raw.len() + 1;
+
+            if raw.is_empty() {
+                continue;
+            }
+
+            if self.skipping {
+                if !raw.trim_start_matches([' ', '\t']).starts_with('[') {
+                    continue;
+                }
+                self.skipping = false;
+            }
+
+            let trimmed = raw.trim_start();
+            if let Some(comment) = trimmed.strip_prefix('#') {
+                let start = offset + raw.len() - trimmed.len();
+                self.span = Span {
+                    line,
+                    start,
+                    end: start + trimmed.trim_end().len(),
+                };
+                return Some(Ok(Event::Comment(comment.trim_end())));
+            }
+
+            // indentation with spaces and tabs is ignored unless rejected, errors still point at
+            // the columns of the raw line
+            let col = raw.len() - raw.trim_start_matches([' ', '\t']).len();
+            let content = raw[col..].trim_end();
+            if content.is_empty() {
+                continue;
+            }
+
+            let is_group = content.starts_with('[');
+            if self.reject_indentation && col > 0 {
+                self.span = Span {
+                    line,
+                    start: offset,
+                    end: offset + col + content.len(),
+                };
+                // an indented header is reported in the group it opens, a key is left to the
+                // caller, which knows the group it is in
+                let group = is_group
+                    .then(|| group_name(content, line, col).ok())
+                    .flatten()
+                    .map(|name| Header::from_name(name.to_string()).name());
+                return Some(Err(ParseError::Syntax {
+                    msg: "Lines shouldn't be indented".into(),
+                    row: line,
+                    col: 0,
+                    group,
+                }));
+            }
+
+            // the whitespace after a value is only kept up to the line ending
+            let untrimmed = raw[col..].strip_suffix('\r').unwrap_or(&raw[col..]);
+            let line_content = if self.keep_trailing_whitespace && !is_group {
+                untrimmed
+            } else {
+                content
+            };
+
+            self.span = Span {
+                line,
+                start: offset + col,
+                end: offset + col + line_content.len(),
+            };
+
+            if is_group {
+                return Some(self.group(content, col));
+            }
+
+            let trailing = untrimmed.len() - line_content.len();
+            self.translatable = false;
+            if let (true, Some(key_value)) = (self.template_keys, line_content.strip_prefix('_')) {
+                self.translatable = true;
+                return Some(self.key_value(key_value, trailing, col + 1));
+            }
+            return Some(self.key_value(line_content, trailing, col));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let input = "# comment\n[Desktop Entry]\nName[de]= Hallo \n\n Type=Application\n  #indented\nbad key=1\n";
+        let events = Events::new(input).collect::<Vec<_>>();
+
+        assert_eq!(events[0], Ok(Event::Comment(" comment")));
+        assert_eq!(events[1], Ok(Event::GroupStart("Desktop Entry")));
+        assert_eq!(
+            events[2],
+            Ok(Event::KeyValue {
+                key: Key::Name,
+                locale: Some("de"),
+                value: "Hallo",
+                span: Span {
+                    line: 2,
+                    start: 26,
+                    end: 41,
+                },
+                trimmed: true,
+            })
+        );
+        assert!(matches!(
+            events[3],
+            Ok(Event::KeyValue {
+                key: Key::Type,
+                value: "Application",
+                ..
+            })
+        ));
+        assert_eq!(events[4], Ok(Event::Comment("indented")));
+        assert!(matches!(
+            events[5],
+            Err(ParseError::Syntax { row: 6, col: 4, .. })
+        ));
+        assert_eq!(events.len(), 6);
+
+        let events = Events::new("Name=Foo \t\r\nIcon=foo\r\nExec= \n")
+            .keep_trailing_whitespace(true)
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            events[0],
+            Ok(Event::KeyValue {
+                value: "Foo \t",
+                trimmed: false,
+                span: Span { end: 10, .. },
+                ..
+            })
+        ));
+        assert!(matches!(
+            events[1],
+            Ok(Event::KeyValue { value: "foo", .. })
+        ));
+        assert!(matches!(
+            events[2],
+            Ok(Event::KeyValue {
+                value: "",
+                trimmed: false,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_indentation() {
+        let input = "\t[Desktop Entry]\n  \tName=Foo\n\t# comment\n\tbad key=1\n";
+        let events = Events::new(input).collect::<Vec<_>>();
+
+        assert_eq!(events[0], Ok(Event::GroupStart("Desktop Entry")));
+        assert!(matches!(
+            events[1],
+            Ok(Event::KeyValue {
+                key: Key::Name,
+                value: "Foo",
+                span: Span { start: 20, .. },
+                ..
+            })
+        ));
+        assert_eq!(events[2], Ok(Event::Comment(" comment")));
+        assert!(matches!(
+            events[3],
+            Err(ParseError::Syntax { row: 3, col: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn test_reject_indentation() {
+        let input = "[A]\n\tName=Foo\n  [B]\n \t Type=Application\n  # comment\nIcon=foo\n";
+        let events = Events::new(input)
+            .reject_indentation(true)
+            .collect::<Vec<_>>();
+
+        assert_eq!(events[0], Ok(Event::GroupStart("A")));
+        for (event, row, group) in [
+            (&events[1], 1, None),
+            (&events[2], 2, Some("B")),
+            (&events[3], 3, None),
+        ] {
+            assert_eq!(
+                *event,
+                Err(ParseError::Syntax {
+                    msg: "Lines shouldn't be indented".into(),
+                    row,
+                    col: 0,
+                    group: group.map(String::from),
+                })
+            );
+        }
+        assert_eq!(events[4], Ok(Event::Comment(" comment")));
+        assert!(matches!(
+            events[5],
+            Ok(Event::KeyValue { key: Key::Icon, .. })
+        ));
+    }
+
+    #[test]
+    fn test_skip_group() {
+        let mut events = Events::new("[A]\nbad key=1\n  [B]\nName=Foo\n");
+        assert_eq!(events.next(), Some(Ok(Event::GroupStart("A"))));
+        events.skip_group();
+        assert_eq!(events.next(), Some(Ok(Event::GroupStart("B"))));
+        assert_eq!(events.span().line, 2);
+        assert!(matches!(
+            events.next(),
+            Some(Ok(Event::KeyValue { key: Key::Name, .. }))
+        ));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_fast_key_value(content in "[A-Za-z0-9 \\[\\]=;é-]{0,12}", trailing in 0..2usize) {
+            let events = Events::new("");
+            if let Some(event) = events.fast_key_value(&content, trailing) {
+                proptest::prop_assert_eq!(Ok(event), events.careful_key_value(&content, trailing, 0));
+            }
+        }
+    }
+}
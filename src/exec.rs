@@ -0,0 +1,488 @@
+//! This module implements the quoting rules of the `Exec` key as described in the
+//! [Exec key](https://specifications.freedesktop.org/desktop-entry-spec/latest/exec-variables.html)
+//! section of the specification.
+
+use thiserror::Error;
+
+use crate::{DesktopEntry, DesktopFile, EntryType};
+
+/// Characters that make an argument require quoting.
+const RESERVED: &[char] = &[
+    ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')',
+    '`',
+];
+
+/// Characters that need to be escaped with a backslash inside a quoted argument.
+const ESCAPED_IN_QUOTES: &[char] = &['"', '`', '$', '\\'];
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ExecError {
+    #[error("Exec Error: the command line is empty")]
+    Empty,
+    #[error("Exec Error: unterminated quoted argument starting at column {col:?}")]
+    UnterminatedQuote { col: usize },
+    #[error("Exec Error: {ch:?} must not be escaped inside a quoted argument, at column {col:?}")]
+    InvalidEscape { ch: char, col: usize },
+    #[error("Exec Error: unexpected {ch:?} inside an unquoted argument at column {col:?}")]
+    ReservedCharacter { ch: char, col: usize },
+    #[error("Exec Error: invalid field code {code:?} at column {col:?}")]
+    InvalidFieldCode { code: String, col: usize },
+    #[error("Exec Error: field code {code:?} used inside a quoted argument at column {col:?}")]
+    FieldCodeInQuotes { code: String, col: usize },
+}
+
+/// A single argument of an Exec command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecArg {
+    /// The argument with quoting and escaping removed. Field codes are kept as written.
+    pub value: String,
+    /// Whether the argument was written as a quoted argument
+    pub quoted: bool,
+    /// The column of the first character of the argument in the Exec value
+    pub col: usize,
+}
+
+/// An Exec value split into its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecCommand {
+    /// The arguments of the command line, the first one being the program
+    pub args: Vec<ExecArg>,
+}
+
+impl ExecCommand {
+    /// Splits an Exec value into arguments, following the quoting rules of the specification.
+    /// The value is expected to already have the general string escapes (`\s`, `\\`, ...) decoded.
+    pub fn parse(exec: &str) -> Result<Self, ExecError> {
+        enum State {
+            /// between two arguments
+            Idle,
+            /// inside an unquoted argument
+            Unquoted,
+            /// inside a quoted argument
+            Quoted,
+        }
+
+        let mut args: Vec<ExecArg> = vec![];
+        let mut state = State::Idle;
+        let mut current = ExecArg {
+            value: String::new(),
+            quoted: false,
+            col: 0,
+        };
+
+        let mut chars = exec.char_indices();
+        while let Some((col, ch)) = chars.next() {
+            match state {
+                State::Idle => match ch {
+                    ' ' | '\t' => {}
+                    '"' => {
+                        state = State::Quoted;
+                        current = ExecArg {
+                            value: String::new(),
+                            quoted: true,
+                            col,
+                        };
+                    }
+                    _ => {
+                        state = State::Unquoted;
+                        current = ExecArg {
+                            value: ch.to_string(),
+                            quoted: false,
+                            col,
+                        };
+                    }
+                },
+                State::Unquoted => match ch {
+                    ' ' | '\t' => {
+                        args.push(std::mem::replace(
+                            &mut current,
+                            ExecArg {
+                                value: String::new(),
+                                quoted: false,
+                                col: 0,
+                            },
+                        ));
+                        state = State::Idle;
+                    }
+                    // reserved characters are tolerated in unquoted arguments since real world
+                    // files frequently use them, but a quote can't start in the middle
+                    '"' => return Err(ExecError::ReservedCharacter { ch, col }),
+                    _ => current.value.push(ch),
+                },
+                State::Quoted => match ch {
+                    '"' => {
+                        args.push(std::mem::replace(
+                            &mut current,
+                            ExecArg {
+                                value: String::new(),
+                                quoted: false,
+                                col: 0,
+                            },
+                        ));
+                        state = State::Idle;
+                    }
+                    '\\' => match chars.next() {
+                        Some((_, escaped)) if ESCAPED_IN_QUOTES.contains(&escaped) => {
+                            current.value.push(escaped)
+                        }
+                        Some((col, escaped)) => {
+                            return Err(ExecError::InvalidEscape { ch: escaped, col })
+                        }
+                        None => return Err(ExecError::UnterminatedQuote { col: current.col }),
+                    },
+                    _ => current.value.push(ch),
+                },
+            }
+        }
+
+        match state {
+            State::Quoted => return Err(ExecError::UnterminatedQuote { col: current.col }),
+            State::Unquoted => args.push(current),
+            State::Idle => {}
+        }
+
+        if args.is_empty() {
+            return Err(ExecError::Empty);
+        }
+
+        Ok(Self { args })
+    }
+
+    /// Joins the arguments back into an Exec value, quoting the arguments that need it
+    pub fn to_exec_string(&self) -> String {
+        self.args
+            .iter()
+            .map(|arg| quote_arg(&arg.value, arg.quoted))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Returns the program, which is the first argument
+    pub fn program(&self) -> &str {
+        &self.args[0].value
+    }
+
+    /// Returns the index of the program that is actually run, skipping a leading `env`
+    /// together with its options and `VAR=value` assignments.
+    pub fn effective_program_index(&self) -> Option<usize> {
+        let is_env = |arg: &ExecArg| arg.value == "env" || arg.value == "/usr/bin/env";
+
+        if !is_env(&self.args[0]) {
+            return Some(0);
+        }
+
+        let mut index = 1;
+        while let Some(arg) = self.args.get(index) {
+            match arg.value.as_str() {
+                "-u" | "--unset" | "-C" | "--chdir" | "-S" | "--split-string" => index += 2,
+                v if v.starts_with('-') => index += 1,
+                v if v.contains('=') => index += 1,
+                _ => return Some(index),
+            }
+        }
+
+        None
+    }
+
+    /// Returns the program that is actually run, skipping a leading `env` together with its
+    /// options and `VAR=value` assignments. Returns None if `env` is not followed by a program.
+    pub fn effective_program(&self) -> Option<&str> {
+        self.effective_program_index()
+            .map(|index| self.args[index].value.as_str())
+    }
+
+    /// Sets the columns of the arguments to where [`ExecCommand::to_exec_string`] writes them
+    fn update_cols(&mut self) {
+        let mut col = 0;
+        for arg in &mut self.args {
+            arg.col = col;
+            col += quote_arg(&arg.value, arg.quoted).len() + 1;
+        }
+    }
+
+    /// Puts a wrapper like `firejail --private` or `env FOO=bar` in front of the command line.
+    /// The arguments are taken literally, so a `%` in them is written as `%%` instead of
+    /// starting a field code, and the field codes of the command keep their arguments.
+    pub fn prepend_wrapper(&mut self, argv: &[&str]) {
+        self.args
+            .splice(0..0, argv.iter().map(|arg| literal_arg(arg)));
+        self.update_cols();
+    }
+
+    /// Adds literal arguments at the end of the command line, after its field codes
+    pub fn append_args(&mut self, argv: &[&str]) {
+        self.args.extend(argv.iter().map(|arg| literal_arg(arg)));
+        self.update_cols();
+    }
+
+    /// Replaces the program that is actually run, see [`ExecCommand::effective_program`],
+    /// keeping a leading `env` with its assignments. When `env` isn't followed by a program,
+    /// the program is added after it.
+    pub fn set_program(&mut self, path: &str) {
+        match self.effective_program_index() {
+            Some(index) => self.args[index] = literal_arg(path),
+            None => self.args.push(literal_arg(path)),
+        }
+        self.update_cols();
+    }
+
+    /// Checks that every field code is valid and that no field code is used inside a quoted
+    /// argument.
+    pub fn validate_field_codes(&self) -> Result<(), ExecError> {
+        for arg in &self.args {
+            let mut chars = arg.value.char_indices();
+            while let Some((offset, ch)) = chars.next() {
+                if ch != '%' {
+                    continue;
+                }
+
+                let code = match chars.next() {
+                    Some((_, code)) => code,
+                    None => {
+                        return Err(ExecError::InvalidFieldCode {
+                            code: "%".into(),
+                            col: arg.col + offset,
+                        })
+                    }
+                };
+
+                match code {
+                    '%' => {}
+                    'f' | 'F' | 'u' | 'U' | 'i' | 'c' | 'k' | 'd' | 'D' | 'n' | 'N' | 'v' | 'm' => {
+                        if arg.quoted {
+                            return Err(ExecError::FieldCodeInQuotes {
+                                code: format!("%{code}"),
+                                col: arg.col + offset,
+                            });
+                        }
+                    }
+                    _ => {
+                        return Err(ExecError::InvalidFieldCode {
+                            code: format!("%{code}"),
+                            col: arg.col + offset,
+                        })
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An argument whose value is taken literally, with `%` escaped
+fn literal_arg(value: &str) -> ExecArg {
+    ExecArg {
+        value: value.replace('%', "%%"),
+        quoted: value.is_empty() || value.contains(RESERVED),
+        col: 0,
+    }
+}
+
+/// Wraps an Exec value, checking that it is a valid command line
+fn wrap(exec: &str, wrapper: &[&str]) -> Result<String, ExecError> {
+    let mut command = ExecCommand::parse(exec)?;
+    command.prepend_wrapper(wrapper);
+    Ok(command.to_exec_string())
+}
+
+impl DesktopEntry {
+    /// Puts a wrapper in front of the Exec value of an application, see
+    /// [`ExecCommand::prepend_wrapper`]. Returns whether there was an Exec value to wrap, and
+    /// leaves the entry unchanged if it isn't a valid command line.
+    pub fn wrap_exec(&mut self, wrapper: &[&str]) -> Result<bool, ExecError> {
+        let EntryType::Application(ref mut fields) = self.entry_type else {
+            return Ok(false);
+        };
+        let Some(ref mut exec) = fields.exec else {
+            return Ok(false);
+        };
+
+        let wrapped = wrap(exec, wrapper)?;
+        // the original bytes are wrapped the same way, with every byte standing for the char of
+        // the same value as in exec_args_os, so the wrapper is written as its UTF-8 bytes
+        if let Some(ref mut raw) = self.raw_values.exec {
+            let latin1 = |bytes: &[u8]| bytes.iter().map(|b| *b as char).collect::<String>();
+            let wrapper = wrapper
+                .iter()
+                .map(|arg| latin1(arg.as_bytes()))
+                .collect::<Vec<_>>();
+            let wrapper = wrapper.iter().map(String::as_str).collect::<Vec<_>>();
+            *raw = wrap(&latin1(raw), &wrapper)?
+                .chars()
+                .map(|c| c as u8)
+                .collect();
+        }
+        *exec = wrapped;
+        Ok(true)
+    }
+}
+
+impl DesktopFile {
+    /// Puts a wrapper in front of the Exec value of the entry, and of the actions when
+    /// `actions` is set, like [`DesktopEntry::wrap_exec`]. Returns the number of Exec values
+    /// wrapped. Nothing changes if one of them isn't a valid command line.
+    pub fn wrap_exec(&mut self, wrapper: &[&str], actions: bool) -> Result<usize, ExecError> {
+        let mut wrapped_actions = vec![];
+        if actions {
+            for (id, action) in &self.actions {
+                if let Some(ref exec) = action.exec {
+                    wrapped_actions.push((id.clone(), wrap(exec, wrapper)?));
+                }
+            }
+        }
+
+        let mut count = self.entry.wrap_exec(wrapper)? as usize;
+        count += wrapped_actions.len();
+        for (id, exec) in wrapped_actions {
+            self.actions.get_mut(&id).unwrap().exec = Some(exec);
+        }
+        Ok(count)
+    }
+}
+
+/// Quotes an argument if it is empty, contains reserved characters or `force` is set
+pub fn quote_arg(arg: &str, force: bool) -> String {
+    if !force && !arg.is_empty() && !arg.contains(RESERVED) {
+        return arg.to_string();
+    }
+
+    let mut result = String::with_capacity(arg.len() + 2);
+    result.push('"');
+    for ch in arg.chars() {
+        if ESCAPED_IN_QUOTES.contains(&ch) {
+            result.push('\\');
+        }
+        result.push(ch);
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(cmd: &ExecCommand) -> Vec<&str> {
+        cmd.args.iter().map(|a| a.value.as_str()).collect()
+    }
+
+    #[test]
+    fn test_quoting() {
+        let cmd =
+            ExecCommand::parse(r#"env FOO=bar "/opt/My App/run" --name "a \"b\" \$c" %U"#).unwrap();
+        assert_eq!(
+            values(&cmd),
+            vec![
+                "env",
+                "FOO=bar",
+                "/opt/My App/run",
+                "--name",
+                "a \"b\" $c",
+                "%U"
+            ]
+        );
+        assert_eq!(cmd.effective_program(), Some("/opt/My App/run"));
+        assert!(cmd.validate_field_codes().is_ok());
+        assert_eq!(
+            values(&ExecCommand::parse(&cmd.to_exec_string()).unwrap()),
+            values(&cmd)
+        );
+
+        assert!(matches!(
+            ExecCommand::parse(r#"foo "bar"#),
+            Err(ExecError::UnterminatedQuote { col: 4 })
+        ));
+        assert!(matches!(
+            ExecCommand::parse(r#"foo a"b""#),
+            Err(ExecError::ReservedCharacter { ch: '"', col: 5 })
+        ));
+    }
+
+    #[test]
+    fn test_field_codes() {
+        assert!(matches!(
+            ExecCommand::parse("foo %x").unwrap().validate_field_codes(),
+            Err(ExecError::InvalidFieldCode { .. })
+        ));
+        assert!(matches!(
+            ExecCommand::parse(r#"foo "%f""#)
+                .unwrap()
+                .validate_field_codes(),
+            Err(ExecError::FieldCodeInQuotes { .. })
+        ));
+        assert!(ExecCommand::parse("foo 100%%")
+            .unwrap()
+            .validate_field_codes()
+            .is_ok());
+    }
+
+    /// Expands an Exec value with the given files the way a launcher does
+    fn expand(exec: &str, files: &[&str]) -> Vec<String> {
+        let cmd = ExecCommand::parse(exec).unwrap();
+        cmd.validate_field_codes().unwrap();
+        let mut argv = vec![];
+        for arg in cmd.args {
+            match arg.value.as_str() {
+                "%F" | "%U" => argv.extend(files.iter().map(|f| f.to_string())),
+                "%f" | "%u" => argv.extend(files.first().map(|f| f.to_string())),
+                value => argv.push(value.replace("%%", "%")),
+            }
+        }
+        argv
+    }
+
+    #[test]
+    fn test_rewriting() {
+        let mut cmd = ExecCommand::parse(r#""/opt/My App/run" --name "a b" %U"#).unwrap();
+        cmd.prepend_wrapper(&["env", "FOO=1 2", "firejail", "--profile=100%"]);
+        cmd.append_args(&["--verbose", "$HOME"]);
+        assert_eq!(
+            cmd.to_exec_string(),
+            r#"env "FOO=1 2" firejail --profile=100%% "/opt/My App/run" --name "a b" %U --verbose "\$HOME""#
+        );
+        assert_eq!(ExecCommand::parse(&cmd.to_exec_string()).unwrap(), cmd);
+        assert_eq!(
+            expand(&cmd.to_exec_string(), &["/tmp/a.txt", "/tmp/b c.txt"]),
+            [
+                "env",
+                "FOO=1 2",
+                "firejail",
+                "--profile=100%",
+                "/opt/My App/run",
+                "--name",
+                "a b",
+                "/tmp/a.txt",
+                "/tmp/b c.txt",
+                "--verbose",
+                "$HOME"
+            ]
+        );
+
+        let mut cmd = ExecCommand::parse("env FOO=bar app %f").unwrap();
+        cmd.set_program("/usr/bin/other app");
+        assert_eq!(
+            cmd.to_exec_string(),
+            r#"env FOO=bar "/usr/bin/other app" %f"#
+        );
+
+        let mut file = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=App\nExec=app %u\nActions=new;\n\n[Desktop Action new]\nName=New\nExec=app --new\n",
+        )
+        .unwrap();
+        assert_eq!(file.wrap_exec(&["flatpak", "run"], false), Ok(1));
+        assert_eq!(file.actions["new"].exec.as_deref(), Some("app --new"));
+        assert_eq!(file.wrap_exec(&["prime-run"], true), Ok(2));
+        assert_eq!(
+            expand(
+                file.entry.as_application().unwrap().exec.as_ref().unwrap(),
+                &["https://example.com"]
+            ),
+            ["prime-run", "flatpak", "run", "app", "https://example.com"]
+        );
+        assert_eq!(
+            file.actions["new"].exec.as_deref(),
+            Some("prime-run app --new")
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Opt-in expansion of `~` and environment variables in the values users write by hand,
+//! like `Icon=~/Pictures/icon.png` or `Exec=$HOME/bin/tool %U`. The specification allows
+//! neither, so nothing in the crate expands them unless asked to.
+
+use thiserror::Error;
+
+use crate::{keys::Key, DesktopEntry, EntryType, IconString};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ExpandError {
+    #[error("Expand Error: the variable {name:?} used in {key} is not set")]
+    UnsetVariable { key: Key, name: String },
+}
+
+/// How to expand variables
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExpandOptions {
+    /// Fail on variables that are not set instead of leaving them as written
+    pub fail_on_unset: bool,
+}
+
+/// Expands `~` at the start of a path and `$VAR` or `${VAR}` anywhere, returning the name of
+/// the first variable that is not set when failing on them. In Exec values, single-quoted
+/// text and escaped characters are left alone, values expanded inside double quotes are
+/// escaped for them, and `~` is expanded at the start of every argument.
+fn expand<F>(value: &str, env: &F, is_exec: bool, fail_on_unset: bool) -> Result<String, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut res = String::with_capacity(value.len());
+    let mut chars = value.char_indices().peekable();
+    let (mut in_single, mut in_double) = (false, false);
+    let mut word_start = true;
+
+    let lookup = |name: &str, written: &str, res: &mut String, quoted: bool| match env(name) {
+        Some(v) if quoted => {
+            for c in v.chars() {
+                if matches!(c, '"' | '`' | '$' | '\\') {
+                    res.push('\\');
+                }
+                res.push(c);
+            }
+            Ok(())
+        }
+        Some(v) => {
+            res.push_str(&v);
+            Ok(())
+        }
+        None if fail_on_unset => Err(name.to_string()),
+        None => {
+            res.push_str(written);
+            Ok(())
+        }
+    };
+
+    while let Some((i, ch)) = chars.next() {
+        let at_word_start = word_start;
+        word_start = false;
+
+        match ch {
+            '\'' if is_exec && !in_double => {
+                in_single = !in_single;
+                res.push(ch);
+            }
+            '"' if is_exec && !in_single => {
+                in_double = !in_double;
+                res.push(ch);
+            }
+            '\\' if is_exec => {
+                res.push(ch);
+                if let Some((_, next)) = chars.next() {
+                    res.push(next);
+                }
+            }
+            ' ' | '\t' if is_exec && !in_single && !in_double => {
+                word_start = true;
+                res.push(ch);
+            }
+            '~' if at_word_start
+                && !in_single
+                && !in_double
+                && chars
+                    .peek()
+                    .is_none_or(|(_, c)| *c == '/' || (is_exec && (*c == ' ' || *c == '\t'))) =>
+            {
+                lookup("HOME", "~", &mut res, false)?;
+            }
+            '$' if !in_single => {
+                let rest = &value[i + 1..];
+                let (name, written) = match rest.strip_prefix('{') {
+                    Some(braced) => match braced.find('}') {
+                        Some(end) => (&braced[..end], &value[i..i + end + 3]),
+                        None => ("", "$"),
+                    },
+                    None => {
+                        let end = rest
+                            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                            .unwrap_or(rest.len());
+                        (&rest[..end], &value[i..i + end + 1])
+                    }
+                };
+
+                let is_valid = name
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+                if !is_valid {
+                    res.push(ch);
+                    continue;
+                }
+
+                lookup(name, written, &mut res, in_double)?;
+                for _ in 1..written.chars().count() {
+                    chars.next();
+                }
+            }
+            _ => res.push(ch),
+        }
+    }
+
+    Ok(res)
+}
+
+impl DesktopEntry {
+    /// Expands `~` and environment variables in Exec, TryExec, Path, Icon and URL, looking
+    /// variables up with `env`, such as `|name| std::env::var(name).ok()`. `~` is expanded
+    /// from `HOME` at the start of a value, and in Exec at the start of every argument outside
+    /// quotes. `$VAR` and `${VAR}` are expanded anywhere except in Exec inside single quotes
+    /// and after a backslash. Inside double-quoted Exec arguments the expanded value is
+    /// escaped as the quoting rules require.
+    ///
+    /// Returns the keys whose value changed. When failing on unset variables the entry is left
+    /// unchanged on error.
+    pub fn expand_vars<F>(
+        &mut self,
+        env: &F,
+        options: &ExpandOptions,
+    ) -> Result<Vec<Key>, ExpandError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let expand = |key: Key, value: &str| {
+            expand(value, env, key == Key::Exec, options.fail_on_unset)
+                .map_err(|name| ExpandError::UnsetVariable {
+                    key: key.clone(),
+                    name,
+                })
+                .map(|expanded| (key, expanded))
+        };
+
+        let mut values = vec![];
+        if let Some(ref icon) = self.icon {
+            values.push(expand(Key::Icon, &icon.content)?);
+        }
+        match self.entry_type {
+            EntryType::Application(ref fields) => {
+                for (key, value) in [
+                    (Key::Exec, &fields.exec),
+                    (Key::TryExec, &fields.try_exec),
+                    (Key::Path, &fields.path),
+                ] {
+                    if let Some(value) = value {
+                        values.push(expand(key, value)?);
+                    }
+                }
+            }
+            EntryType::Link(ref fields) => values.push(expand(Key::Url, &fields.url)?),
+            _ => {}
+        }
+
+        let mut changed = vec![];
+        for (key, expanded) in values {
+            let target = match (&key, &mut self.entry_type) {
+                (Key::Icon, _) => {
+                    let icon = self.icon.get_or_insert_with(IconString::default);
+                    &mut icon.content
+                }
+                (Key::Url, EntryType::Link(fields)) => &mut fields.url,
+                (Key::Exec, EntryType::Application(fields)) => fields.exec.get_or_insert_default(),
+                (Key::TryExec, EntryType::Application(fields)) => {
+                    fields.try_exec.get_or_insert_default()
+                }
+                (_, EntryType::Application(fields)) => fields.path.get_or_insert_default(),
+                _ => continue,
+            };
+
+            if *target != expanded {
+                *target = expanded;
+                self.raw_values.forget(&key);
+                changed.push(key);
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_vars() {
+        let env = |name: &str| match name {
+            "HOME" => Some("/home/me".to_string()),
+            "TOOLS" => Some("/opt/tools".to_string()),
+            "QUOTE" => Some("a\"b".to_string()),
+            _ => None,
+        };
+        let mut entry = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Tool\nExec=${TOOLS}/tool ~/file '$HOME' \"\\\\$HOME\" \"$HOME/bin\" \"$QUOTE\" $UNSET %U\nIcon=~/Pictures/icon.png\nPath=$HOME/projects\nTryExec=tool\n",
+        )
+        .unwrap()
+        .entry;
+        let original = entry.clone();
+
+        let changed = entry.expand_vars(&env, &ExpandOptions::default()).unwrap();
+        assert_eq!(changed, [Key::Icon, Key::Exec, Key::Path]);
+        assert_eq!(entry.icon.unwrap().content, "/home/me/Pictures/icon.png");
+        let EntryType::Application(ref fields) = entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(
+            fields.exec.as_deref(),
+            Some("/opt/tools/tool /home/me/file '$HOME' \"\\$HOME\" \"/home/me/bin\" \"a\\\"b\" $UNSET %U")
+        );
+        assert_eq!(fields.path.as_deref(), Some("/home/me/projects"));
+
+        let mut entry = original.clone();
+        let options = ExpandOptions {
+            fail_on_unset: true,
+        };
+        assert_eq!(
+            entry.expand_vars(&env, &options),
+            Err(ExpandError::UnsetVariable {
+                key: Key::Exec,
+                name: "UNSET".into(),
+            })
+        );
+        assert_eq!(entry.icon.unwrap().content, "~/Pictures/icon.png");
+    }
+}
@@ -0,0 +1,103 @@
+//! Typed access to the keys Flatpak adds to the desktop files it exports.
+
+use crate::{exec::ExecCommand, parser::parse_list, DesktopEntry, EntryType};
+
+/// The Flatpak specific keys of a desktop entry
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlatpakFields {
+    /// The application id, from `X-Flatpak`
+    pub app_id: String,
+    /// Previous ids of the application, from `X-Flatpak-RenamedFrom`, such as `org.foo.Old.desktop`
+    pub renamed_from: Vec<String>,
+    /// Tags of the application, from `X-Flatpak-Tags`
+    pub tags: Vec<String>,
+}
+
+impl DesktopEntry {
+    /// Returns the Flatpak keys of the entry, or None if it has no `X-Flatpak` key
+    pub fn flatpak(&self) -> Option<FlatpakFields> {
+        let app_id = self.extras.get("X-Flatpak")?;
+
+        let list = |key: &str| {
+            self.extras
+                .get(key)
+                .map(|v| parse_list(v))
+                .unwrap_or_default()
+        };
+
+        Some(FlatpakFields {
+            app_id: app_id.clone(),
+            renamed_from: list("X-Flatpak-RenamedFrom"),
+            tags: list("X-Flatpak-Tags"),
+        })
+    }
+
+    /// Whether the entry launches a Flatpak application, either because it has an `X-Flatpak`
+    /// key or because its Exec runs `flatpak run`
+    pub fn is_flatpak(&self) -> bool {
+        if self.extras.contains_key("X-Flatpak") {
+            return true;
+        }
+
+        let EntryType::Application(ref fields) = self.entry_type else {
+            return false;
+        };
+
+        let Some(Ok(command)) = fields.exec.as_deref().map(ExecCommand::parse) else {
+            return false;
+        };
+
+        let Some(index) = command.effective_program_index() else {
+            return false;
+        };
+
+        let program = command.args[index].value.as_str();
+        let program = program.rsplit('/').next().unwrap_or(program);
+
+        program == "flatpak"
+            && command
+                .args
+                .get(index + 1)
+                .is_some_and(|arg| arg.value == "run")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    #[test]
+    fn test_flatpak_fields() {
+        let file = parse(
+            r#"[Desktop Entry]
+Type=Application
+Name=Foo
+Exec=/usr/bin/flatpak run --branch=stable --command=foo org.foo.Foo
+X-Flatpak=org.foo.Foo
+X-Flatpak-RenamedFrom=org.foo.Old.desktop;foo\;legacy.desktop;
+X-Flatpak-Tags=proprietary;"#,
+        )
+        .unwrap();
+
+        let fields = file.entry.flatpak().unwrap();
+        assert_eq!(fields.app_id, "org.foo.Foo");
+        assert_eq!(
+            fields.renamed_from,
+            vec!["org.foo.Old.desktop", "foo;legacy.desktop"]
+        );
+        assert_eq!(fields.tags, vec!["proprietary"]);
+        assert!(file.entry.is_flatpak());
+
+        let file = parse(
+            "[Desktop Entry]\nType=Application\nName=Foo\nExec=env FOO=1 flatpak run org.foo.Foo",
+        )
+        .unwrap();
+        assert!(file.entry.flatpak().is_none());
+        assert!(file.entry.is_flatpak());
+
+        let file =
+            parse("[Desktop Entry]\nType=Application\nName=Foo\nExec=flatpak-spawn --host foo")
+                .unwrap();
+        assert!(!file.entry.is_flatpak());
+    }
+}
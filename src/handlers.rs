@@ -0,0 +1,257 @@
+//! Custom handlers for extension keys like `X-MyOrg-Color`, registered in
+//! [`ParseOptions::key_handlers`](crate::ParseOptions::key_handlers) and called by the parser
+//! with the position of each key, before the key would land in the extras of its group.
+
+use std::{collections::HashSet, fmt, sync::Arc};
+
+use crate::{parser::LinePart, ParseError, ParseWarning};
+
+/// The keys a handler is called for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyPattern {
+    /// A key with exactly this name, in any locale
+    Exact(String),
+    /// Every key whose name starts with this prefix
+    Prefix(String),
+}
+
+impl KeyPattern {
+    fn matches(&self, key: &str) -> bool {
+        match self {
+            Self::Exact(name) => key == name,
+            Self::Prefix(prefix) => key.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+/// A key value pair given to a handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CustomKey<'a> {
+    /// The group the key is in, such as `Desktop Entry` or `Desktop Action new`
+    pub group: &'a str,
+    pub key: &'a str,
+    pub locale: Option<&'a str>,
+    /// The value as written, with escape sequences intact
+    pub value: &'a str,
+    pub row: usize,
+}
+
+/// What happens to a key after its handler ran
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyDisposition {
+    /// The handler took the key, so it is not added to the extras
+    Claim,
+    /// The handler only looked at the key, which is added to the extras as usual
+    Observe,
+}
+
+/// Lets a handler report problems with a key as warnings of the parse
+pub struct KeyHandlerContext<'a> {
+    key: &'a CustomKey<'a>,
+    warnings: &'a mut Vec<ParseWarning>,
+}
+
+impl KeyHandlerContext<'_> {
+    /// Adds a [`ParseWarning::CustomKey`] about the key to the warnings of the parse
+    pub fn warn(&mut self, message: impl Into<String>) {
+        self.warnings.push(ParseWarning::CustomKey {
+            key: self.key.key.to_string(),
+            message: message.into(),
+            row: self.key.row,
+            group: self.key.group.to_string(),
+        });
+    }
+}
+
+/// A handler, returning what to do with the key or the message of the error failing the
+/// parse
+pub type KeyHandler = Arc<
+    dyn Fn(&CustomKey<'_>, &mut KeyHandlerContext<'_>) -> Result<KeyDisposition, String>
+        + Send
+        + Sync,
+>;
+
+/// The handlers of a parse, called in the order they were added. A key is claimed when one of
+/// its handlers claims it.
+#[derive(Clone, Default)]
+pub struct KeyHandlers {
+    handlers: Vec<(KeyPattern, KeyHandler)>,
+}
+
+impl fmt::Debug for KeyHandlers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.handlers.iter().map(|(pattern, _)| pattern))
+            .finish()
+    }
+}
+
+impl PartialEq for KeyHandlers {
+    /// Handlers are equal when they are the same closures for the same patterns
+    fn eq(&self, other: &Self) -> bool {
+        self.handlers.len() == other.handlers.len()
+            && self
+                .handlers
+                .iter()
+                .zip(&other.handlers)
+                .all(|(a, b)| a.0 == b.0 && Arc::ptr_eq(&a.1, &b.1))
+    }
+}
+
+impl Eq for KeyHandlers {}
+
+impl KeyHandlers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a handler for the keys matching the pattern
+    pub fn with<F>(mut self, pattern: KeyPattern, handler: F) -> Self
+    where
+        F: Fn(&CustomKey<'_>, &mut KeyHandlerContext<'_>) -> Result<KeyDisposition, String>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.handlers.push((pattern, Arc::new(handler)));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+/// The state of the handlers during a parse
+pub(crate) struct KeyHooks<'a> {
+    pub(crate) handlers: &'a KeyHandlers,
+    pub(crate) group: &'a str,
+    pub(crate) warnings: &'a mut Vec<ParseWarning>,
+    /// The claimed keys by group, written like `X-Foo[de]`, to report repeated ones
+    pub(crate) claimed: &'a mut HashSet<(String, String)>,
+}
+
+impl KeyHooks<'_> {
+    /// Runs the handlers matching a key, returning whether one of them claimed it
+    pub(crate) fn run(&mut self, parts: &LinePart) -> Result<bool, ParseError> {
+        let name = parts.key.as_str();
+        let key = CustomKey {
+            group: self.group,
+            key: name,
+            locale: parts.locale.as_deref(),
+            value: &parts.value,
+            row: parts.line_number,
+        };
+
+        let mut claimed = false;
+        for (pattern, handler) in &self.handlers.handlers {
+            if !pattern.matches(name) {
+                continue;
+            }
+
+            let mut ctx = KeyHandlerContext {
+                key: &key,
+                warnings: self.warnings,
+            };
+            match handler(&key, &mut ctx) {
+                Ok(KeyDisposition::Claim) => claimed = true,
+                Ok(KeyDisposition::Observe) => {}
+                Err(msg) => {
+                    return Err(ParseError::Syntax {
+                        msg,
+                        row: parts.line_number,
+                        col: 0,
+                        group: None,
+                    })
+                }
+            }
+        }
+
+        let full_key = match parts.locale {
+            Some(ref locale) => format!("{name}[{locale}]"),
+            None => name.to_string(),
+        };
+        if claimed
+            && !self
+                .claimed
+                .insert((self.group.to_string(), full_key.clone()))
+        {
+            return Err(ParseError::RepetitiveKey {
+                key: full_key,
+                row: parts.line_number,
+                col: 0,
+                group: None,
+            });
+        }
+        Ok(claimed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{parse_with_warnings, ParseOptions};
+
+    #[test]
+    fn test_key_handlers() {
+        let colors = Arc::new(Mutex::new(vec![]));
+        let seen = colors.clone();
+        let options = ParseOptions {
+            key_handlers: KeyHandlers::new()
+                .with(
+                    KeyPattern::Exact("X-MyOrg-Color".into()),
+                    move |key, ctx| {
+                        if !key.value.starts_with('#') {
+                            ctx.warn("the color should start with #");
+                        }
+                        seen.lock().unwrap().push((
+                            key.group.to_string(),
+                            key.value.to_string(),
+                            key.row,
+                        ));
+                        Ok(KeyDisposition::Claim)
+                    },
+                )
+                .with(KeyPattern::Prefix("X-MyOrg-".into()), |key, _| {
+                    match key.value {
+                        "invalid" => Err(format!("{} is invalid", key.key)),
+                        _ => Ok(KeyDisposition::Observe),
+                    }
+                }),
+            ..Default::default()
+        };
+
+        let (file, warnings) = parse_with_warnings(
+            "[Desktop Entry]\nType=Application\nName=App\nExec=app\nX-MyOrg-Color=red\nX-MyOrg-Size=2\nActions=new;\n\n[Desktop Action new]\nName=New\nX-MyOrg-Color=#fff\n",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            *colors.lock().unwrap(),
+            [
+                ("Desktop Entry".to_string(), "red".to_string(), 4),
+                ("Desktop Action new".to_string(), "#fff".to_string(), 10),
+            ]
+        );
+        assert!(!file.entry.extras.contains_key("X-MyOrg-Color"));
+        assert_eq!(file.entry.extras["X-MyOrg-Size"], "2");
+        assert!(file.actions["new"].extras.is_empty());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code(), "W006");
+        assert_eq!(warnings[0].position(), Some((4, 0)));
+
+        let parse = |input: &str| parse_with_warnings(input, &options).map(|_| ());
+        assert!(parse(
+            "[Desktop Entry]\nType=Application\nName=App\nX-MyOrg-Color=#000\nX-MyOrg-Color=#111\n"
+        )
+        .is_err());
+        assert!(matches!(
+            parse("[Desktop Entry]\nType=Application\nName=App\nX-MyOrg-Size=invalid\n"),
+            Err(ParseError::Syntax { row: 3, .. })
+        ));
+        assert_ne!(options, ParseOptions::default());
+        assert_eq!(options.clone(), options);
+    }
+}
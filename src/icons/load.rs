@@ -0,0 +1,265 @@
+//! Decoding of icon files into pixels, which needs the `image` feature. SVG icons also need
+//! the `svg` feature.
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use image::{imageops::FilterType, ImageFormat, Rgba, RgbaImage};
+use thiserror::Error;
+
+use super::ResolvedIcon;
+
+#[derive(Debug, Error)]
+pub enum IconError {
+    #[error("Icon Error: {0:?} does not exist")]
+    Missing(PathBuf),
+    #[error("Icon Error: failed to read {path:?}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("Icon Error: failed to decode {path:?}: {msg}")]
+    Decode { path: PathBuf, msg: String },
+    #[error("Icon Error: {path:?} can only be loaded with the {feature} feature")]
+    NeedsFeature {
+        path: PathBuf,
+        feature: &'static str,
+    },
+    #[error("Icon Error: {0:?} is not in a supported format")]
+    Unsupported(PathBuf),
+}
+
+impl ResolvedIcon {
+    /// Decodes the icon and scales it to fit in a square of `target_size` pixels, keeping its
+    /// aspect ratio. PNG and XPM icons are supported, SVG icons need the `svg` feature.
+    pub fn load_rgba(&self, target_size: u32) -> Result<RgbaImage, IconError> {
+        load_rgba(&self.path, target_size)
+    }
+}
+
+fn load_rgba(path: &Path, target_size: u32) -> Result<RgbaImage, IconError> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    let decode_error = |msg: String| IconError::Decode {
+        path: path.to_path_buf(),
+        msg,
+    };
+
+    let bytes = std::fs::read(path).map_err(|source| match source.kind() {
+        io::ErrorKind::NotFound => IconError::Missing(path.to_path_buf()),
+        _ => IconError::Io {
+            path: path.to_path_buf(),
+            source,
+        },
+    })?;
+
+    let image = match extension.as_deref() {
+        Some("png") => image::load_from_memory_with_format(&bytes, ImageFormat::Png)
+            .map_err(|e| decode_error(e.to_string()))?
+            .to_rgba8(),
+        Some("xpm") => {
+            let text = String::from_utf8_lossy(&bytes);
+            decode_xpm(&text).map_err(decode_error)?
+        }
+        #[cfg(feature = "svg")]
+        Some("svg" | "svgz") => return render_svg(&bytes, target_size).map_err(decode_error),
+        #[cfg(not(feature = "svg"))]
+        Some("svg" | "svgz") => {
+            return Err(IconError::NeedsFeature {
+                path: path.to_path_buf(),
+                feature: "svg",
+            })
+        }
+        _ => return Err(IconError::Unsupported(path.to_path_buf())),
+    };
+
+    Ok(fit(image, target_size))
+}
+
+/// Returns the size of an image of `width` by `height` scaled to fit in a square of `target`
+fn fitted_size(width: f64, height: f64, target: u32) -> (u32, u32) {
+    let scale = target as f64 / width.max(height);
+    let scaled = |v: f64| ((v * scale).round() as u32).max(1);
+    (scaled(width), scaled(height))
+}
+
+/// Scales an image to fit in a square of `target` pixels
+fn fit(image: RgbaImage, target: u32) -> RgbaImage {
+    let (width, height) = fitted_size(image.width() as f64, image.height() as f64, target);
+    if (width, height) == image.dimensions() {
+        return image;
+    }
+    image::imageops::resize(&image, width, height, FilterType::Triangle)
+}
+
+#[cfg(feature = "svg")]
+fn render_svg(data: &[u8], target: u32) -> Result<RgbaImage, String> {
+    use resvg::{tiny_skia, usvg};
+
+    let tree = usvg::Tree::from_data(data, &usvg::Options::default()).map_err(|e| e.to_string())?;
+    let size = tree.size();
+    let (width, height) = fitted_size(size.width() as f64, size.height() as f64, target);
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or("the icon is empty")?;
+    let transform = tiny_skia::Transform::from_scale(
+        width as f32 / size.width(),
+        height as f32 / size.height(),
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    let mut image = RgbaImage::new(width, height);
+    for (pixel, color) in image.pixels_mut().zip(pixmap.pixels()) {
+        let color = color.demultiply();
+        *pixel = Rgba([color.red(), color.green(), color.blue(), color.alpha()]);
+    }
+    Ok(image)
+}
+
+/// Returns the strings of the C array an XPM file consists of, skipping comments
+fn xpm_strings(text: &str) -> Vec<&str> {
+    let mut strings = vec![];
+    let mut rest = text;
+
+    loop {
+        let comment = rest.find("/*");
+        let quote = rest.find('"');
+        match (comment, quote) {
+            (Some(c), Some(q)) if c < q => match rest[c..].find("*/") {
+                Some(end) => rest = &rest[c + end + 2..],
+                None => break,
+            },
+            (_, Some(q)) => match rest[q + 1..].find('"') {
+                Some(end) => {
+                    strings.push(&rest[q + 1..q + 1 + end]);
+                    rest = &rest[q + end + 2..];
+                }
+                None => break,
+            },
+            (_, None) => break,
+        }
+    }
+
+    strings
+}
+
+/// Parses a color of an XPM file, `#RGB` style or one of the few names icons use
+fn xpm_color(value: &str) -> Option<Rgba<u8>> {
+    match value.to_ascii_lowercase().as_str() {
+        "none" => return Some(Rgba([0, 0, 0, 0])),
+        "black" => return Some(Rgba([0, 0, 0, 255])),
+        "white" => return Some(Rgba([255, 255, 255, 255])),
+        _ => {}
+    }
+
+    let hex = value.strip_prefix('#')?;
+    if hex.len() % 3 != 0 || hex.is_empty() || !hex.is_ascii() {
+        return None;
+    }
+    let digits = hex.len() / 3;
+    let channel = |i: usize| {
+        let v = u32::from_str_radix(&hex[i * digits..(i + 1) * digits], 16).ok()?;
+        let max = (1u64 << (4 * digits)) - 1;
+        Some((v as u64 * 255 / max) as u8)
+    };
+    Some(Rgba([channel(0)?, channel(1)?, channel(2)?, 255]))
+}
+
+/// Decodes an XPM (version 3) image
+fn decode_xpm(text: &str) -> Result<RgbaImage, String> {
+    let strings = xpm_strings(text);
+    let (header, rest) = strings.split_first().ok_or("missing header")?;
+
+    let values = header
+        .split_whitespace()
+        .map(|v| v.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid header {header:?}"))?;
+    let [width, height, colors, cpp, ..] = values[..] else {
+        return Err(format!("invalid header {header:?}"));
+    };
+    if rest.len() < colors + height || cpp == 0 {
+        return Err("the image is truncated".into());
+    }
+
+    let mut palette = HashMap::new();
+    for line in &rest[..colors] {
+        let code = line.get(..cpp).ok_or("invalid color")?;
+        let mut tokens = line[cpp..].split_whitespace();
+        let color = std::iter::from_fn(|| Some((tokens.next()?, tokens.next()?)))
+            .find(|(key, _)| *key == "c")
+            .and_then(|(_, value)| xpm_color(value))
+            .ok_or_else(|| format!("unsupported color {line:?}"))?;
+        palette.insert(code, color);
+    }
+
+    let mut image = RgbaImage::new(width as u32, height as u32);
+    for (y, row) in rest[colors..colors + height].iter().enumerate() {
+        for x in 0..width {
+            let code = row
+                .get(x * cpp..(x + 1) * cpp)
+                .ok_or_else(|| format!("row {y} is too short"))?;
+            let color = palette
+                .get(code)
+                .ok_or_else(|| format!("unknown color {code:?}"))?;
+            image.put_pixel(x as u32, y as u32, *color);
+        }
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_rgba() {
+        let dir =
+            std::env::temp_dir().join(format!("desktop-file-parser-load-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let resolved = |name: &str| ResolvedIcon {
+            path: dir.join(name),
+            candidate: 0,
+        };
+
+        RgbaImage::from_pixel(16, 8, Rgba([255, 0, 0, 255]))
+            .save(dir.join("wide.png"))
+            .unwrap();
+        let image = resolved("wide.png").load_rgba(8).unwrap();
+        assert_eq!(image.dimensions(), (8, 4));
+        assert_eq!(image.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+
+        let xpm = "/* XPM */\nstatic char *icon[] = {\n\"2 2 2 1\",\n\"  c None\",\n\". c #ff0000\",\n\" .\",\n\". \"};\n";
+        std::fs::write(dir.join("icon.xpm"), xpm).unwrap();
+        let image = resolved("icon.xpm").load_rgba(2).unwrap();
+        assert_eq!(image.get_pixel(0, 0), &Rgba([0, 0, 0, 0]));
+        assert_eq!(image.get_pixel(1, 0), &Rgba([255, 0, 0, 255]));
+
+        std::fs::write(dir.join("broken.png"), b"not a png").unwrap();
+        assert!(matches!(
+            resolved("broken.png").load_rgba(8),
+            Err(IconError::Decode { .. })
+        ));
+        assert!(matches!(
+            resolved("missing.png").load_rgba(8),
+            Err(IconError::Missing(_))
+        ));
+
+        std::fs::write(
+            dir.join("icon.svg"),
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="20"><rect width="10" height="20" fill="blue"/></svg>"#,
+        )
+        .unwrap();
+        let svg = resolved("icon.svg").load_rgba(40);
+        #[cfg(feature = "svg")]
+        assert_eq!(svg.unwrap().dimensions(), (20, 40));
+        #[cfg(not(feature = "svg"))]
+        assert!(matches!(
+            svg,
+            Err(IconError::NeedsFeature { feature: "svg", .. })
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
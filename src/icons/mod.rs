@@ -0,0 +1,270 @@
+//! Icon lookups, and a cache of their results that can be shared across threads.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{PoisonError, RwLock},
+};
+
+use crate::IconString;
+
+#[cfg(feature = "image")]
+mod load;
+#[cfg(feature = "image")]
+pub use load::IconError;
+
+/// What to look an icon name up with
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IconOptions {
+    /// The icon theme to search before falling back to hicolor
+    pub theme: String,
+    /// The nominal icon size
+    pub size: u16,
+    pub scale: u16,
+}
+
+impl Default for IconOptions {
+    fn default() -> Self {
+        Self {
+            theme: "hicolor".into(),
+            size: 48,
+            scale: 1,
+        }
+    }
+}
+
+/// Looks an icon name up in the system icon themes
+#[cfg(feature = "resolve-icons")]
+pub fn lookup_icon(name: &str, options: &IconOptions) -> Option<PathBuf> {
+    freedesktop_icons::lookup(name)
+        .with_theme(&options.theme)
+        .with_size(options.size)
+        .with_scale(options.scale)
+        .find()
+}
+
+/// An icon found on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedIcon {
+    pub path: PathBuf,
+    /// The index of the candidate the icon was found for
+    pub candidate: usize,
+}
+
+/// Resolves the first candidate that can be found, such as the Icon of an action, then the
+/// Icon of the entry, then a generic icon like `application-x-executable`. Absolute paths
+/// are used when they point to a file, names are looked up with `lookup`.
+pub fn resolve_first_with<F>(
+    candidates: &[&IconString],
+    options: &IconOptions,
+    mut lookup: F,
+) -> Option<ResolvedIcon>
+where
+    F: FnMut(&str, &IconOptions) -> Option<PathBuf>,
+{
+    candidates.iter().enumerate().find_map(|(i, icon)| {
+        let path = Path::new(&icon.content);
+        let path = if icon.content.is_empty() {
+            None
+        } else if path.is_absolute() {
+            path.is_file().then(|| path.to_path_buf())
+        } else {
+            lookup(&icon.content, options)
+        };
+
+        path.map(|path| ResolvedIcon { path, candidate: i })
+    })
+}
+
+/// Resolves the first candidate that can be found in the system icon themes, see
+/// [`resolve_first_with`]. The themes are only indexed once for all the candidates.
+#[cfg(feature = "resolve-icons")]
+pub fn resolve_first(candidates: &[&IconString], options: &IconOptions) -> Option<ResolvedIcon> {
+    resolve_first_with(candidates, options, lookup_icon)
+}
+
+/// Remembers the results of icon lookups, including the names that were not found, keyed by
+/// the name and the [`IconOptions`]. The cache can be shared across threads.
+#[derive(Debug, Default)]
+pub struct IconCache {
+    entries: RwLock<HashMap<(String, IconOptions), Option<PathBuf>>>,
+}
+
+impl IconCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached result of the lookup, calling `lookup` and remembering its result
+    /// when there is none
+    pub fn lookup_with<F>(&self, name: &str, options: &IconOptions, lookup: F) -> Option<PathBuf>
+    where
+        F: FnOnce(&str, &IconOptions) -> Option<PathBuf>,
+    {
+        let key = (name.to_string(), options.clone());
+        let entries = self.entries.read().unwrap_or_else(PoisonError::into_inner);
+        if let Some(path) = entries.get(&key) {
+            return path.clone();
+        }
+        drop(entries);
+
+        // the lock isn't held while looking up, so two threads may both look the name up
+        let path = lookup(name, options);
+        self.entries
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key, path.clone());
+        path
+    }
+
+    /// Looks an icon name up in the system icon themes, using the cached result if any
+    #[cfg(feature = "resolve-icons")]
+    pub fn lookup(&self, name: &str, options: &IconOptions) -> Option<PathBuf> {
+        self.lookup_with(name, options, lookup_icon)
+    }
+
+    /// Resolves the first candidate that can be found like [`resolve_first_with`], using
+    /// the cached results of the names
+    pub fn resolve_first_with<F>(
+        &self,
+        candidates: &[&IconString],
+        options: &IconOptions,
+        mut lookup: F,
+    ) -> Option<ResolvedIcon>
+    where
+        F: FnMut(&str, &IconOptions) -> Option<PathBuf>,
+    {
+        resolve_first_with(candidates, options, |name, options| {
+            self.lookup_with(name, options, &mut lookup)
+        })
+    }
+
+    /// Resolves the first candidate that can be found in the system icon themes, using the
+    /// cached results of the names
+    #[cfg(feature = "resolve-icons")]
+    pub fn resolve_first(
+        &self,
+        candidates: &[&IconString],
+        options: &IconOptions,
+    ) -> Option<ResolvedIcon> {
+        self.resolve_first_with(candidates, options, lookup_icon)
+    }
+
+    /// Forgets every result
+    pub fn invalidate(&self) {
+        self.entries
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Forgets the results of the lookups in a theme, such as after its directory changed.
+    /// Lookups in other themes may have fallen back to it too, so hicolor invalidates
+    /// everything.
+    pub fn invalidate_theme(&self, theme: &str) {
+        let mut entries = self.entries.write().unwrap_or_else(PoisonError::into_inner);
+        if theme == "hicolor" {
+            entries.clear();
+        } else {
+            entries.retain(|(_, options), _| options.theme != theme);
+        }
+    }
+
+    /// Returns the number of cached results
+    pub fn len(&self) -> usize {
+        self.entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_icon_cache() {
+        let cache = IconCache::new();
+        let calls = AtomicUsize::new(0);
+        let lookup = |name: &str, _: &IconOptions| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            (name == "firefox").then(|| PathBuf::from("/icons/firefox.png"))
+        };
+
+        let breeze = IconOptions {
+            theme: "breeze".into(),
+            ..Default::default()
+        };
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for name in ["firefox", "missing"] {
+                        cache.lookup_with(name, &IconOptions::default(), lookup);
+                    }
+                });
+            }
+        });
+        assert_eq!(cache.len(), 2);
+        let after_threads = calls.load(Ordering::SeqCst);
+        assert!(after_threads <= 8);
+
+        assert_eq!(
+            cache.lookup_with("firefox", &IconOptions::default(), lookup),
+            Some(PathBuf::from("/icons/firefox.png"))
+        );
+        assert_eq!(
+            cache.lookup_with("missing", &IconOptions::default(), lookup),
+            None
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), after_threads);
+
+        cache.lookup_with("firefox", &breeze, lookup);
+        assert_eq!(cache.len(), 3);
+        cache.invalidate_theme("breeze");
+        assert_eq!(cache.len(), 2);
+        cache.invalidate();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_first() {
+        let icon = |content: &str| IconString {
+            content: content.into(),
+        };
+        let (action, entry, generic) = (
+            icon("/nonexistent/action.png"),
+            icon("firefox"),
+            icon("application-x-executable"),
+        );
+        let cache = IconCache::new();
+        let lookup = |name: &str, _: &IconOptions| {
+            (name != "missing").then(|| PathBuf::from(format!("/icons/{name}.png")))
+        };
+
+        let resolved = cache
+            .resolve_first_with(
+                &[&action, &entry, &generic],
+                &IconOptions::default(),
+                lookup,
+            )
+            .unwrap();
+        assert_eq!(resolved.candidate, 1);
+        assert_eq!(resolved.path, PathBuf::from("/icons/firefox.png"));
+        assert_eq!(cache.len(), 1);
+
+        let missing = icon("missing");
+        let resolved = cache.resolve_first_with(
+            &[&icon(""), &missing, &generic],
+            &IconOptions::default(),
+            lookup,
+        );
+        assert_eq!(resolved.unwrap().candidate, 2);
+        assert!(resolve_first_with(&[&missing], &IconOptions::default(), lookup).is_none());
+    }
+}
@@ -0,0 +1,205 @@
+//! Installation of desktop files into an applications directory, in the style of
+//! `desktop-file-install`. Every step is exposed on its own so build systems can compose them.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::{
+    mimeinfo::write_mimeinfo_cache,
+    util::write_atomic,
+    validate::{ValidationReport, Validator},
+    DesktopFile, EntryType, ParseError,
+};
+
+#[derive(Debug, Error)]
+pub enum InstallError {
+    #[error("Install Error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Install Error: {0}")]
+    Parse(#[from] ParseError),
+    #[error("Install Error: the file failed validation with {} finding(s)", .0.findings.len())]
+    Invalid(ValidationReport),
+}
+
+/// What to do when installing a desktop file.
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    /// Prefix added to the file name, separated by a dash, unless it is already there
+    pub vendor_prefix: Option<String>,
+    /// Keys to set, such as `("Name[de]", "Editor")`, applied after `remove_keys`
+    pub set_keys: Vec<(String, String)>,
+    /// Keys to remove, such as `X-Foo` or `Name[de]`
+    pub remove_keys: Vec<String>,
+    /// Desktop environments to add to OnlyShowIn
+    pub add_only_show_in: Vec<String>,
+    /// The applications directory to install into
+    pub target_dir: PathBuf,
+    /// Whether to regenerate `mimeinfo.cache` in the target directory afterwards
+    pub rebuild_mime_cache: bool,
+}
+
+/// Applies the key edits of the options to a parsed file
+pub fn apply_edits(file: &mut DesktopFile, options: &InstallOptions) -> Result<(), ParseError> {
+    for key in &options.remove_keys {
+        file.entry.remove_key(key)?;
+    }
+
+    for (key, value) in &options.set_keys {
+        file.entry.set_key(key, value)?;
+    }
+
+    if !options.add_only_show_in.is_empty() {
+        let only_show_in = file.entry.only_show_in.get_or_insert_with(Vec::new);
+        for desktop in &options.add_only_show_in {
+            if !only_show_in.contains(desktop) {
+                only_show_in.push(desktop.clone());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates a file with the default rules, failing if any finding is an error
+pub fn validate_for_install(file: &DesktopFile) -> Result<ValidationReport, InstallError> {
+    let report = Validator::new().validate_source(&file.to_desktop_string())?;
+
+    if report.is_valid() {
+        Ok(report)
+    } else {
+        Err(InstallError::Invalid(report))
+    }
+}
+
+/// Returns the file name the source is installed as, with the vendor prefix applied
+pub fn installed_file_name(source: &Path, vendor_prefix: Option<&str>) -> io::Result<String> {
+    let name = source.file_name().and_then(|n| n.to_str()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{source:?} has no valid file name"),
+        )
+    })?;
+
+    Ok(match vendor_prefix {
+        Some(prefix) if !name.starts_with(&format!("{prefix}-")) => format!("{prefix}-{name}"),
+        _ => name.to_string(),
+    })
+}
+
+/// Writes a file into the target directory atomically, returning the written path
+pub fn write_installed(file: &DesktopFile, target_dir: &Path, name: &str) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(target_dir)?;
+    let path = target_dir.join(name);
+    write_atomic(&path, file.to_desktop_string().as_bytes())?;
+    Ok(path)
+}
+
+/// Collects the desktop files below an applications directory with their desktop file ids,
+/// skipping files that can't be read or parsed
+fn collect_applications(dir: &Path, prefix: &str, result: &mut Vec<(String, DesktopFile)>) {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut paths = read_dir
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    for path in paths {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+
+        if path.is_dir() {
+            collect_applications(&path, &format!("{prefix}{name}-"), result);
+        } else if name.ends_with(".desktop") {
+            if let Ok(file) = std::fs::read_to_string(&path)
+                .map_err(|_| ())
+                .and_then(|c| crate::parse(&c).map_err(|_| ()))
+            {
+                result.push((format!("{prefix}{name}"), file));
+            }
+        }
+    }
+}
+
+/// Regenerates `mimeinfo.cache` from the desktop files below an applications directory
+pub fn rebuild_mime_cache(applications_dir: &Path) -> io::Result<PathBuf> {
+    let mut applications = vec![];
+    collect_applications(applications_dir, "", &mut applications);
+
+    write_mimeinfo_cache(
+        applications_dir,
+        applications.iter().map(|(id, file)| (id.as_str(), file)),
+    )
+}
+
+/// Parses, edits, validates and writes a desktop file into the target directory, returning
+/// the installed path.
+pub fn install(source: &Path, options: &InstallOptions) -> Result<PathBuf, InstallError> {
+    let content = std::fs::read_to_string(source)?;
+    let mut file = crate::parse(&content)?;
+
+    apply_edits(&mut file, options)?;
+    validate_for_install(&file)?;
+
+    let name = installed_file_name(source, options.vendor_prefix.as_deref())?;
+    let path = write_installed(&file, &options.target_dir, &name)?;
+
+    let has_mime_types = matches!(
+        file.entry.entry_type,
+        EntryType::Application(ref fields) if fields.mime_type.is_some()
+    );
+    if options.rebuild_mime_cache && has_mime_types {
+        rebuild_mime_cache(&options.target_dir)?;
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install() {
+        let dir = std::env::temp_dir().join(format!(
+            "desktop-file-parser-install-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("editor.desktop");
+        std::fs::write(
+            &source,
+            "[Desktop Entry]\nType=Application\nName=Editor\nExec=editor %F\nMimeType=text/plain;\nX-Old=1\n",
+        )
+        .unwrap();
+
+        let options = InstallOptions {
+            vendor_prefix: Some("acme".into()),
+            set_keys: vec![("Name[de]".into(), "Bearbeiter".into())],
+            remove_keys: vec!["X-Old".into()],
+            add_only_show_in: vec!["GNOME".into()],
+            target_dir: dir.join("applications"),
+            rebuild_mime_cache: true,
+        };
+
+        let path = install(&source, &options).unwrap();
+        assert_eq!(path, dir.join("applications").join("acme-editor.desktop"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[Desktop Entry]\nType=Application\nName=Editor\nName[de]=Bearbeiter\nOnlyShowIn=GNOME;\nExec=editor %F\nMimeType=text/plain;\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("applications").join("mimeinfo.cache")).unwrap(),
+            "[MIME Cache]\ntext/plain=acme-editor.desktop;\n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
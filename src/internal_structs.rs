@@ -1,18 +1,131 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display};
 
 use crate::{
     ApplicationFields, DesktopAction, DesktopEntry, EntryType, IconString, LinkFields,
     LocaleString, LocaleStringList, ParseError,
 };
 
-#[derive(Debug, Clone)]
-#[doc(hidden)]
+/// The kind of a group header such as `[Desktop Action new]`
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Header {
     DesktopEntry,
     DesktopAction { name: String },
     Other { name: String },
 }
 
+impl Header {
+    /// Classifies a group by the name written between the brackets. Whitespace around the
+    /// name and before the id of an action is dropped, so `[ Desktop Action  new ]` is the
+    /// action `new`.
+    pub(crate) fn from_name(name: String) -> Self {
+        let trimmed = name.trim();
+        if trimmed == "Desktop Entry" {
+            Self::DesktopEntry
+        } else if let Some(remain) = trimmed.strip_prefix("Desktop Action ") {
+            Self::DesktopAction {
+                name: remain.trim_start().to_string(),
+            }
+        } else if trimmed.len() == name.len() {
+            Self::Other { name }
+        } else {
+            Self::Other {
+                name: trimmed.to_string(),
+            }
+        }
+    }
+
+    /// Returns the name of the group as written between the brackets
+    pub fn name(&self) -> String {
+        match self {
+            Self::DesktopEntry => "Desktop Entry".into(),
+            Self::DesktopAction { name } => format!("Desktop Action {name}"),
+            Self::Other { name } => name.clone(),
+        }
+    }
+
+    /// Parses a group header line like `[Desktop Entry]`, failing on the characters group
+    /// names can't contain like the parser does. Unlike the parser, which reads up to the end
+    /// of the line, the closing bracket is required. Displaying a parsed header gives a line
+    /// that parses back to it, as the whitespace around the name is dropped.
+    pub fn parse(line: &str) -> Result<Self, ParseError> {
+        let content = line.trim();
+        if !content.starts_with('[') {
+            return Err(ParseError::Syntax {
+                msg: "a group header starts with \"[\"".into(),
+                row: 0,
+                col: 0,
+                group: None,
+            });
+        }
+
+        let col = line.len() - line.trim_start().len();
+        let name = group_name(content, 0, col)?;
+        if !content.ends_with(']') {
+            return Err(ParseError::Syntax {
+                msg: "a group header ends with \"]\"".into(),
+                row: 0,
+                col: col + content.len(),
+                group: None,
+            });
+        }
+        Ok(Self::from_name(name.to_string()))
+    }
+}
+
+impl Display for Header {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}]", self.name())
+    }
+}
+
+/// Returns the name of a header line starting with `[`, which is at the given row and column
+/// for errors. The closing bracket is optional but nothing may follow it, and the name can't
+/// be empty or only whitespace.
+pub(crate) fn group_name(content: &str, row: usize, col: usize) -> Result<&str, ParseError> {
+    let name = &content[1..];
+    if name.strip_suffix(']').unwrap_or(name).trim().is_empty() {
+        return Err(ParseError::Syntax {
+            msg: "the group name is empty".to_string(),
+            row,
+            col: col + 1,
+            group: None,
+        });
+    }
+
+    for (i, ch) in name.char_indices() {
+        let col = col + 1 + i;
+        match ch {
+            ']' if i != name.len() - 1 => {
+                return Err(ParseError::Syntax {
+                    msg: "nothing is expected after \"]\"".to_string(),
+                    row,
+                    col,
+                    group: None,
+                });
+            }
+            '[' => {
+                return Err(ParseError::UnacceptableCharacter {
+                    ch: ch.to_string(),
+                    row,
+                    col,
+                    msg: format!("\"{ch}\" is not accepted in header"),
+                });
+            }
+            _ if ch.is_control() => {
+                return Err(ParseError::UnacceptableCharacter {
+                    ch: ch.to_string(),
+                    row,
+                    col,
+                    msg: "none".to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(name.strip_suffix(']').unwrap_or(name))
+}
+
 #[derive(Debug, Clone, Default)]
 #[doc(hidden)]
 pub enum EntryTypeInternal {
@@ -109,6 +222,8 @@ pub struct DesktopEntryInternal {
     pub prefers_non_default_gpu: Option<bool>,
     /// If true, the application has a single main window, and does not support having an additional one opened. This key is used to signal to the implementation to avoid offering a UI to launch another window of the app. This key is only a hint and support might not be present depending on the implementation.
     pub single_main_window: Option<bool>,
+    /// Keys that are not part of the specification
+    pub extras: HashMap<String, String>,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -118,80 +233,108 @@ pub struct DesktopActionInternal {
     pub name: Option<LocaleStringInternal>, // required
     pub exec: Option<String>,
     pub icon: Option<IconString>,
+    pub extras: HashMap<String, String>,
 }
 
-impl TryInto<LocaleString> for LocaleStringInternal {
-    type Error = ParseError;
+impl DesktopActionInternal {
+    /// Removes a locale variant of a key, returning whether it was present
+    pub fn remove_variant(&mut self, key: &str, locale: &str) -> bool {
+        match key {
+            "Name" => self
+                .name
+                .as_mut()
+                .is_some_and(|name| name.variants.remove(locale).is_some()),
+            _ => self.extras.remove(&format!("{key}[{locale}]")).is_some(),
+        }
+    }
 
-    fn try_into(self) -> Result<LocaleString, Self::Error> {
-        Ok(LocaleString {
-            default: match self.default {
-                Some(d) => d,
-                None => {
-                    return Err(ParseError::KeyError {
-                        msg: "The default value of locale string must be specified".into(),
-                    })
-                }
+    /// Converts the action like [`vec_to_map`] does, with an empty name if it has none
+    pub fn into_unchecked(mut self) -> (String, DesktopAction) {
+        let mut name = self.name.take().unwrap_or_default();
+        let action = DesktopAction {
+            name: LocaleString {
+                default: name.default.take().unwrap_or_default(),
+                variants: name.variants,
             },
-            variants: self.variants,
-        })
+            exec: self.exec,
+            icon: self.icon,
+            extras: self.extras,
+        };
+        (self.ref_name, action)
     }
 }
 
-impl TryInto<LocaleStringList> for LocaleStringListInternal {
-    type Error = ParseError;
+/// The error for a localized key written only with locale variants, naming them
+pub(crate) fn missing_default<T>(
+    key: &str,
+    group: &str,
+    variants: &HashMap<String, T>,
+) -> ParseError {
+    let mut locales = variants
+        .keys()
+        .map(|l| format!("{key}[{l}]"))
+        .collect::<Vec<_>>();
+    locales.sort();
+    ParseError::KeyError {
+        msg: format!(
+            "{key} has no default value in group [{group}], only {}; add a {key}= line",
+            locales.join(", ")
+        ),
+    }
+}
 
-    fn try_into(self) -> Result<LocaleStringList, Self::Error> {
-        Ok(LocaleStringList {
-            default: match self.default {
-                Some(d) => d,
-                None => {
-                    return Err(ParseError::KeyError {
-                        msg: "The default value of locale string must be specified".into(),
-                    })
-                }
-            },
-            variants: self.variants,
-        })
+impl LocaleStringInternal {
+    /// Converts the value of a key, failing with an error naming the key and the group when it
+    /// has no default value
+    pub fn into_locale_string(self, key: &str, group: &str) -> Result<LocaleString, ParseError> {
+        match self.default {
+            Some(default) => Ok(LocaleString {
+                default,
+                variants: self.variants,
+            }),
+            None => Err(missing_default(key, group, &self.variants)),
+        }
+    }
+}
+
+/// The specification doesn't require localized keys to have a default value, so a list written
+/// only with locale variants, like `Keywords[de]=...`, gets an empty default list
+impl From<LocaleStringListInternal> for LocaleStringList {
+    fn from(value: LocaleStringListInternal) -> Self {
+        LocaleStringList {
+            default: value.default.unwrap_or_default(),
+            variants: value.variants,
+        }
     }
 }
 
+/// Converts the declared actions into the actions of the file in one pass, in the order of the
+/// file. The ids are unique, as the parser rejects repeated action groups.
 pub fn vec_to_map(
     vec: Vec<DesktopActionInternal>,
-    list: &mut [String],
 ) -> Result<HashMap<String, DesktopAction>, ParseError> {
-    let mut result = HashMap::new();
-    list.sort();
-
-    for action in vec.into_iter() {
-        if list.binary_search(&action.ref_name).is_ok() {
-            if result.contains_key(&action.ref_name) {
-                return Err(ParseError::KeyError {
-                    msg: format!(
-                        "There are two actions with the same name: {}",
-                        &action.ref_name
-                    ),
-                });
-            }
+    let mut result = HashMap::with_capacity(vec.len());
 
-            let name = action.ref_name.clone();
-
-            result.insert(
-                action.ref_name,
-                DesktopAction {
-                    name: match action.name {
-                        Some(n) => n.try_into()?,
-                        None => {
-                            return Err(ParseError::KeyError {
-                                msg: format!("The name of the action {} must be specified", name),
-                            })
-                        }
-                    },
-                    exec: action.exec,
-                    icon: action.icon,
-                },
-            );
-        }
+    for action in vec {
+        let Some(name) = action.name else {
+            return Err(ParseError::KeyError {
+                msg: format!(
+                    "The name of the action {} must be specified",
+                    action.ref_name
+                ),
+            });
+        };
+        let name =
+            name.into_locale_string("Name", &format!("Desktop Action {}", action.ref_name))?;
+        result.insert(
+            action.ref_name,
+            DesktopAction {
+                name,
+                exec: action.exec,
+                icon: action.icon,
+                extras: action.extras,
+            },
+        );
     }
 
     Ok(result)
@@ -212,26 +355,19 @@ impl TryInto<DesktopEntry> for DesktopEntryInternal {
                     mime_type: self.mime_type,
                     categories: self.categories,
                     implements: self.implements,
-                    keywords: match self.keywords {
-                        Some(l) => Some(l.try_into()?),
-                        None => None,
-                    },
+                    keywords: self.keywords.map(Into::into),
                     startup_notify: self.startup_notify,
                     startup_wm_class: self.startup_wm_class,
                     prefers_non_default_gpu: self.prefers_non_default_gpu,
                     single_main_window: self.single_main_window,
                 };
-                EntryType::Application(fields)
+                EntryType::new_application(fields)
             }
             Some(EntryTypeInternal::Link) => {
                 let fields = LinkFields {
                     url: match self.url {
-                        Some(url) => url,
-                        None => {
-                            return Err(ParseError::KeyError {
-                                msg: "URL must be specified for the entry type Link".into(),
-                            })
-                        }
+                        Some(url) if !url.is_empty() => url,
+                        _ => return Err(ParseError::MissingUrl { row: None }),
                     },
                 };
                 EntryType::Link(fields)
@@ -242,14 +378,14 @@ impl TryInto<DesktopEntry> for DesktopEntryInternal {
                     msg: "Entry Type must be specified".into(),
                 })
             }
-            _ => EntryType::Unknown,
+            Some(EntryTypeInternal::Unknown(name)) => EntryType::Unknown(name),
         };
 
         Ok(DesktopEntry {
             entry_type,
             version: self.version,
             name: match self.name {
-                Some(n) => n.try_into()?,
+                Some(n) => n.into_locale_string("Name", "Desktop Entry")?,
                 None => {
                     return Err(ParseError::KeyError {
                         msg: "Entry name must be specified".into(),
@@ -257,12 +393,12 @@ impl TryInto<DesktopEntry> for DesktopEntryInternal {
                 }
             },
             generic_name: match self.generic_name {
-                Some(l) => Some(l.try_into()?),
+                Some(l) => Some(l.into_locale_string("GenericName", "Desktop Entry")?),
                 None => None,
             },
             no_display: self.no_display,
             comment: match self.comment {
-                Some(l) => Some(l.try_into()?),
+                Some(l) => Some(l.into_locale_string("Comment", "Desktop Entry")?),
                 None => None,
             },
             icon: self.icon,
@@ -270,6 +406,309 @@ impl TryInto<DesktopEntry> for DesktopEntryInternal {
             only_show_in: self.only_show_in,
             not_show_in: self.not_show_in,
             dbus_activatable: self.dbus_activatable,
+            extras: self.extras,
+            raw_values: Default::default(),
         })
     }
 }
+
+impl DesktopEntryInternal {
+    /// Converts the entry like `try_into`, filling the missing required keys with empty
+    /// values instead of failing. An entry without a type gets the unknown type.
+    pub fn into_unchecked(mut self) -> DesktopEntry {
+        let entry_type = self
+            .entry_type
+            .get_or_insert(EntryTypeInternal::Unknown(String::new()));
+        if let EntryTypeInternal::Link = entry_type {
+            self.url.get_or_insert_with(String::new);
+        }
+        for string in [&mut self.generic_name, &mut self.comment]
+            .into_iter()
+            .flatten()
+        {
+            string.default.get_or_insert_with(String::new);
+        }
+        self.name
+            .get_or_insert_with(Default::default)
+            .default
+            .get_or_insert_with(String::new);
+
+        match self.try_into() {
+            Ok(entry) => entry,
+            Err(_) => unreachable!("every required key is filled in"),
+        }
+    }
+}
+
+impl From<LocaleString> for LocaleStringInternal {
+    fn from(value: LocaleString) -> Self {
+        Self {
+            default: Some(value.default),
+            variants: value.variants,
+        }
+    }
+}
+
+impl From<LocaleStringList> for LocaleStringListInternal {
+    fn from(value: LocaleStringList) -> Self {
+        Self {
+            default: Some(value.default),
+            variants: value.variants,
+        }
+    }
+}
+
+impl From<DesktopEntry> for DesktopEntryInternal {
+    fn from(value: DesktopEntry) -> Self {
+        let mut result = Self {
+            version: value.version,
+            name: Some(value.name.into()),
+            generic_name: value.generic_name.map(|l| l.into()),
+            no_display: value.no_display,
+            comment: value.comment.map(|l| l.into()),
+            icon: value.icon,
+            hidden: value.hidden,
+            only_show_in: value.only_show_in,
+            not_show_in: value.not_show_in,
+            dbus_activatable: value.dbus_activatable,
+            extras: value.extras,
+            ..Default::default()
+        };
+
+        result.entry_type = Some(match value.entry_type {
+            EntryType::Application(fields) => {
+                result.try_exec = fields.try_exec;
+                result.exec = fields.exec;
+                result.path = fields.path;
+                result.terminal = fields.terminal;
+                result.actions = fields.actions;
+                result.mime_type = fields.mime_type;
+                result.categories = fields.categories;
+                result.implements = fields.implements;
+                result.keywords = fields.keywords.map(|l| l.into());
+                result.startup_notify = fields.startup_notify;
+                result.startup_wm_class = fields.startup_wm_class;
+                result.prefers_non_default_gpu = fields.prefers_non_default_gpu;
+                result.single_main_window = fields.single_main_window;
+                EntryTypeInternal::Application
+            }
+            EntryType::Link(fields) => {
+                result.url = Some(fields.url);
+                EntryTypeInternal::Link
+            }
+            EntryType::Directory => EntryTypeInternal::Directory,
+            EntryType::Unknown(name) => EntryTypeInternal::Unknown(name),
+        });
+
+        result
+    }
+}
+
+impl DesktopEntryInternal {
+    /// Removes a key, or only one of its variants if a locale is given.
+    /// Returns whether something was removed.
+    pub fn remove_key(&mut self, key: &str, locale: Option<&str>) -> bool {
+        fn take<T>(opt: &mut Option<T>) -> bool {
+            opt.take().is_some()
+        }
+
+        fn take_locale_str(opt: &mut Option<LocaleStringInternal>, locale: Option<&str>) -> bool {
+            match (opt.as_mut(), locale) {
+                (Some(str), Some(locale)) => str.variants.remove(locale).is_some(),
+                (Some(str), None) => {
+                    // variants without a default can't exist in the public types
+                    str.default.take();
+                    opt.take();
+                    true
+                }
+                (None, _) => false,
+            }
+        }
+
+        match (key, locale) {
+            ("Name", _) => take_locale_str(&mut self.name, locale),
+            ("GenericName", _) => take_locale_str(&mut self.generic_name, locale),
+            ("Comment", _) => take_locale_str(&mut self.comment, locale),
+            ("Keywords", Some(locale)) => self
+                .keywords
+                .as_mut()
+                .is_some_and(|k| k.variants.remove(locale).is_some()),
+            ("Keywords", None) => take(&mut self.keywords),
+            ("Type", None) => take(&mut self.entry_type),
+            ("Version", None) => take(&mut self.version),
+            ("NoDisplay", None) => take(&mut self.no_display),
+            ("Icon", None) => take(&mut self.icon),
+            ("Hidden", None) => take(&mut self.hidden),
+            ("OnlyShowIn", None) => take(&mut self.only_show_in),
+            ("NotShowIn", None) => take(&mut self.not_show_in),
+            ("DBusActivatable", None) => take(&mut self.dbus_activatable),
+            ("TryExec", None) => take(&mut self.try_exec),
+            ("Exec", None) => take(&mut self.exec),
+            ("Path", None) => take(&mut self.path),
+            ("Terminal", None) => take(&mut self.terminal),
+            ("Actions", None) => take(&mut self.actions),
+            ("MimeType", None) => take(&mut self.mime_type),
+            ("Categories", None) => take(&mut self.categories),
+            ("Implements", None) => take(&mut self.implements),
+            ("StartupNotify", None) => take(&mut self.startup_notify),
+            ("StartupWMClass", None) => take(&mut self.startup_wm_class),
+            ("URL", None) => take(&mut self.url),
+            ("PrefersNonDefaultGPU", None) => take(&mut self.prefers_non_default_gpu),
+            ("SingleMainWindow", None) => take(&mut self.single_main_window),
+            (key, Some(locale)) => self.extras.remove(&format!("{key}[{locale}]")).is_some(),
+            (key, None) => self.extras.remove(key).is_some(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_parse() {
+        assert_eq!(
+            Header::parse("[Desktop Entry]").unwrap(),
+            Header::DesktopEntry
+        );
+        assert_eq!(
+            Header::parse("  [Desktop Action new-window]\r\n").unwrap(),
+            Header::DesktopAction {
+                name: "new-window".into()
+            }
+        );
+        assert!(matches!(
+            Header::parse("[X-Foo] bar"),
+            Err(ParseError::Syntax { col: 6, .. })
+        ));
+        assert!(matches!(
+            Header::parse(" [X[Foo]"),
+            Err(ParseError::UnacceptableCharacter { col: 3, .. })
+        ));
+        assert!(Header::parse("Desktop Entry").is_err());
+        assert_eq!(
+            Header::parse("[ Desktop Action  new ]").unwrap(),
+            Header::DesktopAction { name: "new".into() }
+        );
+        assert_eq!(Header::parse("[ X-Foo ]").unwrap().to_string(), "[X-Foo]");
+        assert!(Header::parse("[ ]").is_err());
+        for line in ["[Desktop Entry", "[Desktop Action foo", " [X-Foo \n"] {
+            assert!(matches!(
+                Header::parse(line),
+                Err(ParseError::Syntax { msg, .. }) if msg == "a group header ends with \"]\""
+            ));
+        }
+        // the parser itself still reads an unclosed header to the end of the line
+        assert!(crate::parse("[Desktop Entry\nType=Application\nName=App\n").is_ok());
+    }
+
+    #[test]
+    fn test_repeated_actions() {
+        let input = "[Desktop Entry]\nType=Application\nName=App\nActions=new;\n\n[Desktop Action new]\nName=New\n\n[Desktop Action other]\nName=Other\n\n[Desktop Action new]\nName=New\n";
+        let err = ParseError::RepetitiveEntry {
+            msg: "the action group \"new\" repeats the one at line 5".into(),
+            row: 11,
+            col: 0,
+        };
+        assert_eq!(crate::parse(input).unwrap_err(), err);
+
+        // with other keys, and whether or not the action is declared
+        let input = input.replace(
+            "Name=New\n\n[Desktop Action other]",
+            "Exec=old\n\n[Desktop Action other]",
+        );
+        assert_eq!(crate::parse(&input).unwrap_err(), err);
+        let input = input.replace("Actions=new;", "Actions=other;");
+        assert_eq!(crate::parse(&input).unwrap_err(), err);
+    }
+
+    #[test]
+    fn test_action_without_name() {
+        for keys in ["", "Icon=window-new\n"] {
+            let input = format!("[Desktop Entry]\nType=Application\nName=App\nActions=new-window;\n\n[Desktop Action new-window]\n{keys}");
+            assert_eq!(
+                crate::parse(&input).unwrap_err(),
+                ParseError::KeyError {
+                    msg: "The name of the action new-window must be specified".into()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_list_without_default() {
+        let file = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=App\nKeywords[de]=Netz;Web;\nKeywords[fr]=Toile;\nActions=new;\n\n[Desktop Action new]\nName=New\nKeywords[de]=Neu;\n",
+        )
+        .unwrap();
+        let keywords = file
+            .entry
+            .as_application()
+            .unwrap()
+            .keywords
+            .as_ref()
+            .unwrap();
+        assert!(keywords.default.is_empty());
+        assert_eq!(keywords.resolve(&["de_DE"]).0, ["Netz", "Web"]);
+        assert_eq!(file.actions["new"].extras["Keywords[de]"], "Neu;");
+
+        let written = file.to_desktop_string();
+        assert!(!written.contains("Keywords="));
+        assert!(written.contains("Keywords[fr]=Toile;"));
+
+        // Name stays required for actions, localized or not
+        assert!(matches!(
+            crate::parse("[Desktop Entry]\nType=Application\nName=App\nActions=new;\n\n[Desktop Action new]\nName[de]=Neu\n"),
+            Err(ParseError::KeyError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_string_without_default() {
+        let entry = "[Desktop Entry]\nType=Application\nName=App\n";
+        for (input, msg) in [
+            (
+                "[Desktop Entry]\nType=Application\nName[en_GB]=Editor\nName[de]=Bearbeiter\n".to_string(),
+                "Name has no default value in group [Desktop Entry], only Name[de], Name[en_GB]; add a Name= line",
+            ),
+            (
+                format!("{entry}GenericName[de]=Editor\n"),
+                "GenericName has no default value in group [Desktop Entry], only GenericName[de]; add a GenericName= line",
+            ),
+            (
+                format!("{entry}Comment[fr]=Éditer\n"),
+                "Comment has no default value in group [Desktop Entry], only Comment[fr]; add a Comment= line",
+            ),
+            (
+                format!("{entry}Actions=new;\n\n[Desktop Action new]\nName[de]=Neu\n"),
+                "Name has no default value in group [Desktop Action new], only Name[de]; add a Name= line",
+            ),
+        ] {
+            assert_eq!(
+                crate::parse(&input).unwrap_err(),
+                ParseError::KeyError { msg: msg.into() }
+            );
+        }
+
+        // the same conversion is used when setting keys
+        let mut entry = crate::parse(entry).unwrap().entry;
+        assert_eq!(
+            entry.set_key("Comment[de]", "Bearbeiten").unwrap_err(),
+            ParseError::KeyError {
+                msg: "Comment has no default value in group [Desktop Entry], only Comment[de]; add a Comment= line".into()
+            }
+        );
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_header_round_trip(name in "[^\\[\\]\\s\\p{Cc}]([^\\[\\]\\p{Cc}]*[^\\[\\]\\s\\p{Cc}])?") {
+            for header in [
+                Header::from_name(name.clone()),
+                Header::DesktopAction { name: name.clone() },
+            ] {
+                proptest::prop_assert_eq!(Header::parse(&header.to_string()).unwrap(), header);
+            }
+        }
+    }
+}
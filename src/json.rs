@@ -0,0 +1,193 @@
+//! Machine-readable output of errors and findings.
+//!
+//! Everything is serialized as a [`Record`], one JSON object per problem:
+//!
+//! ```json
+//! {"code":"L001","severity":"warning","message":"...","file":"foo.desktop","line":3,"column":null,"suggestion":"Categories"}
+//! ```
+//!
+//! `code` is the stable code of the error or rule (see [`ParseError`] and
+//! [`crate::validate::RuleInfo`]), null for custom rules. `severity` is one of `hint`,
+//! `warning` and `error`. `line` and `column` start at 0 and are null when unknown. `file` is
+//! null unless the record was tagged with [`Record::with_file`]. [`write_json_lines`] writes
+//! one record per line.
+
+use std::io;
+
+use serde::{Serialize, Serializer};
+
+use crate::{
+    install::InstallError,
+    keys::suggest_key,
+    validate::{Finding, Severity, UNKNOWN_KEY},
+    ParseError, ParseWarning,
+};
+
+/// A problem in the shape shared by every machine-readable output of the crate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Record {
+    pub code: Option<&'static str>,
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub suggestion: Option<String>,
+}
+
+impl Record {
+    /// Sets the file the record is about
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    fn error(code: &'static str, message: String) -> Self {
+        Self {
+            code: Some(code),
+            severity: Severity::Error,
+            message,
+            file: None,
+            line: None,
+            column: None,
+            suggestion: None,
+        }
+    }
+}
+
+impl From<&ParseError> for Record {
+    fn from(e: &ParseError) -> Self {
+        let position = e.position();
+        Self {
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, col)| col),
+            ..Self::error(e.code(), e.to_string())
+        }
+    }
+}
+
+impl From<&ParseWarning> for Record {
+    fn from(w: &ParseWarning) -> Self {
+        let position = w.position();
+        Self {
+            code: Some(w.code()),
+            severity: Severity::Warning,
+            message: w.to_string(),
+            file: None,
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, col)| col),
+            suggestion: None,
+        }
+    }
+}
+
+impl From<&Finding> for Record {
+    fn from(f: &Finding) -> Self {
+        let suggestion = match f.key {
+            Some(ref key) if f.rule == UNKNOWN_KEY => suggest_key(key).map(|s| s.to_string()),
+            _ => f.suggestion.clone(),
+        };
+
+        Self {
+            code: f.code(),
+            severity: f.severity,
+            message: f.to_string(),
+            file: None,
+            line: f.line,
+            column: None,
+            suggestion,
+        }
+    }
+}
+
+impl InstallError {
+    /// Returns the records of the error. A failed validation gives one record per finding,
+    /// and I/O errors, which have no code of their own, use the code `io-error`.
+    pub fn records(&self) -> Vec<Record> {
+        match self {
+            Self::Io(e) => vec![Record::error("io-error", e.to_string())],
+            Self::Parse(e) => vec![e.into()],
+            Self::Invalid(report) => report.findings.iter().map(Record::from).collect(),
+        }
+    }
+}
+
+impl Serialize for ParseError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Record::from(self).serialize(serializer)
+    }
+}
+
+impl Serialize for ParseWarning {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Record::from(self).serialize(serializer)
+    }
+}
+
+impl Serialize for Finding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Record::from(self).serialize(serializer)
+    }
+}
+
+impl Serialize for InstallError {
+    /// Serializes the error as a list of records, see [`InstallError::records`]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.records().serialize(serializer)
+    }
+}
+
+/// Writes records as JSON Lines, one record per line
+pub fn write_json_lines<W, I>(mut writer: W, records: I) -> io::Result<()>
+where
+    W: io::Write,
+    I: IntoIterator<Item = Record>,
+{
+    for record in records {
+        serde_json::to_writer(&mut writer, &record)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::Validator;
+
+    #[test]
+    fn test_json_lines() {
+        let source = "[Desktop Entry]\nType=Application\nName=Foo\nCatgories=Utility;\n";
+        let report = Validator::new().validate_source(source).unwrap();
+        let error = crate::parse("[Desktop Entry]\nbad key=1").unwrap_err();
+
+        let mut output = vec![];
+        let records = report
+            .findings
+            .iter()
+            .map(Record::from)
+            .chain(std::iter::once(Record::from(&error)))
+            .map(|r| r.with_file("foo.desktop"));
+        write_json_lines(&mut output, records).unwrap();
+
+        let lines = String::from_utf8(output).unwrap();
+        let lines = lines
+            .lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["code"], "L001");
+        assert_eq!(lines[0]["severity"], "warning");
+        assert_eq!(lines[0]["line"], 3);
+        assert_eq!(lines[0]["suggestion"], "Categories");
+        assert_eq!(lines[1]["code"], "E002");
+        assert_eq!(lines[1]["column"], 4);
+        assert_eq!(lines[1]["file"], "foo.desktop");
+
+        let io_error = InstallError::Io(io::Error::new(io::ErrorKind::NotFound, "gone"));
+        assert_eq!(
+            serde_json::to_value(&io_error).unwrap()[0]["code"],
+            "io-error"
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! Generic parsing of the key file format that desktop entries share with other freedesktop
+//! formats, such as `.thumbnailer` files, portal `.portal` files and `index.theme`.
+//!
+//! Values are kept as written and interpreted on demand, with the same escaping rules the
+//! desktop entry parser uses.
+
+use std::collections::HashSet;
+
+use crate::{
+    parser::{parse_list, tokens, unescape_string, Token},
+    LocaleString, LocaleStringList, ParseError,
+};
+
+/// A key value pair of a group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyValue {
+    pub key: String,
+    /// The locale of a localized key, such as `de` for `Name[de]`
+    pub locale: Option<String>,
+    /// The value as written in the file, with escape sequences intact
+    pub value: String,
+    pub line_number: usize,
+}
+
+/// A group of a key file, such as `[Thumbnailer Entry]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    pub line_number: usize,
+    /// The key value pairs in the order of the file
+    pub entries: Vec<KeyValue>,
+}
+
+/// A parsed key file, with its groups in the order of the file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyFile {
+    pub groups: Vec<Group>,
+}
+
+impl KeyFile {
+    /// Returns the group with the given name
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+
+    /// Returns the group with the given name, appending an empty group if there is none
+    pub fn group_mut(&mut self, name: &str) -> &mut Group {
+        let index = match self.groups.iter().position(|g| g.name == name) {
+            Some(index) => index,
+            None => {
+                self.groups.push(Group {
+                    name: name.to_string(),
+                    line_number: 0,
+                    entries: vec![],
+                });
+                self.groups.len() - 1
+            }
+        };
+
+        &mut self.groups[index]
+    }
+
+    /// Lays the keys of another file over this one, replacing keys present in both
+    pub fn overlay(&mut self, other: &KeyFile) {
+        for group in &other.groups {
+            let target = self.group_mut(&group.name);
+            for entry in &group.entries {
+                target.set_raw(&entry.key, entry.locale.as_deref(), &entry.value);
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for KeyFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, group) in self.groups.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{}]", group.name)?;
+            for entry in &group.entries {
+                match entry.locale {
+                    Some(ref locale) => writeln!(f, "{}[{}]={}", entry.key, locale, entry.value)?,
+                    None => writeln!(f, "{}={}", entry.key, entry.value)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Group {
+    /// Returns the value of a key as written in the file, without a locale
+    pub fn get_raw(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|e| e.key == key && e.locale.is_none())
+            .map(|e| e.value.as_str())
+    }
+
+    /// Sets the value of a key as it is written in the file, replacing its previous value
+    pub fn set_raw(&mut self, key: &str, locale: Option<&str>, value: &str) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.key == key && e.locale.as_deref() == locale)
+        {
+            Some(entry) => entry.value = value.to_string(),
+            None => self.entries.push(KeyValue {
+                key: key.to_string(),
+                locale: locale.map(|l| l.to_string()),
+                value: value.to_string(),
+                line_number: 0,
+            }),
+        }
+    }
+
+    /// Returns the value of a string key with its escape sequences resolved
+    pub fn get_string(&self, key: &str) -> Option<String> {
+        self.get_raw(key).map(unescape_string)
+    }
+
+    /// Returns the value of a boolean key, failing if it is neither `true` nor `false`
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>, ParseError> {
+        let Some(entry) = self
+            .entries
+            .iter()
+            .find(|e| e.key == key && e.locale.is_none())
+        else {
+            return Ok(None);
+        };
+
+        entry
+            .value
+            .parse::<bool>()
+            .map(Some)
+            .map_err(|_| ParseError::Syntax {
+                msg: "Property's value needs to be bool".into(),
+                row: entry.line_number,
+                col: 0,
+                group: Some(self.name.clone()),
+            })
+    }
+
+    /// Returns the items of a list key
+    pub fn get_string_list(&self, key: &str) -> Option<Vec<String>> {
+        self.get_raw(key).map(parse_list)
+    }
+
+    /// Returns a localized string key with all of its variants. None if the key has no
+    /// unlocalized value.
+    pub fn get_locale_string(&self, key: &str) -> Option<LocaleString> {
+        Some(LocaleString {
+            default: self.get_string(key)?,
+            variants: self
+                .localized(key)
+                .map(|(locale, value)| (locale, unescape_string(value)))
+                .collect(),
+        })
+    }
+
+    /// Returns a localized list key with all of its variants. None if the key has no
+    /// unlocalized value.
+    pub fn get_locale_string_list(&self, key: &str) -> Option<LocaleStringList> {
+        Some(LocaleStringList {
+            default: self.get_string_list(key)?,
+            variants: self
+                .localized(key)
+                .map(|(locale, value)| (locale, parse_list(value)))
+                .collect(),
+        })
+    }
+
+    fn localized<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (String, &'a str)> + 'a {
+        self.entries
+            .iter()
+            .filter(move |e| e.key == key)
+            .filter_map(|e| {
+                e.locale
+                    .as_ref()
+                    .map(|locale| (locale.clone(), e.value.as_str()))
+            })
+    }
+}
+
+/// Parses a key file into its groups. Key value pairs must be inside a group, and neither a
+/// group nor a key inside a group may be declared twice.
+pub fn parse(input: &str) -> Result<KeyFile, ParseError> {
+    let mut groups: Vec<Group> = vec![];
+    let mut seen_keys = HashSet::new();
+
+    for token in tokens(input) {
+        match token? {
+            Token::Group { name, line_number } => {
+                if groups.iter().any(|g| g.name == name) {
+                    return Err(ParseError::Syntax {
+                        msg: format!("Group {name:?} is declared more than once"),
+                        row: line_number,
+                        col: 0,
+                        group: None,
+                    });
+                }
+
+                seen_keys.clear();
+                groups.push(Group {
+                    name,
+                    line_number,
+                    entries: vec![],
+                });
+            }
+            Token::Pair(parts) => {
+                let Some(group) = groups.last_mut() else {
+                    return Err(ParseError::Syntax {
+                        msg: "Key value pairs must be inside a group".into(),
+                        row: parts.line_number,
+                        col: 0,
+                        group: None,
+                    });
+                };
+
+                if !seen_keys.insert((parts.key.clone(), parts.locale.clone())) {
+                    return Err(ParseError::RepetitiveKey {
+                        key: parts.key.to_string(),
+                        row: parts.line_number,
+                        col: 0,
+                        group: Some(group.name.clone()),
+                    });
+                }
+
+                group.entries.push(KeyValue {
+                    key: parts.key.to_string(),
+                    locale: parts.locale,
+                    value: parts.value,
+                    line_number: parts.line_number,
+                });
+            }
+        }
+    }
+
+    Ok(KeyFile { groups })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumbnailer() {
+        let file = parse(
+            r#"# a thumbnailer
+[Thumbnailer Entry]
+TryExec=evince-thumbnailer
+Exec=evince-thumbnailer -s %s %u %o
+MimeType=application/pdf;application/x-bzpdf;
+Comment=PDF\sthumbnails
+Comment[de]=PDF-Vorschau
+Hidden=false"#,
+        )
+        .unwrap();
+
+        let group = file.group("Thumbnailer Entry").unwrap();
+        assert_eq!(
+            group.get_string("Exec").as_deref(),
+            Some("evince-thumbnailer -s %s %u %o")
+        );
+        assert_eq!(
+            group.get_string_list("MimeType").unwrap(),
+            vec!["application/pdf", "application/x-bzpdf"]
+        );
+        let comment = group.get_locale_string("Comment").unwrap();
+        assert_eq!(comment.default, "PDF thumbnails");
+        assert_eq!(comment.get_variant("de"), "PDF-Vorschau");
+        assert_eq!(group.get_bool("Hidden").unwrap(), Some(false));
+        assert_eq!(group.get_bool("NoDisplay").unwrap(), None);
+
+        assert!(parse("Key=value\n[Group]").is_err());
+        assert!(parse("[Group]\nKey=a\nKey=b").is_err());
+        assert!(parse("[Group]\n[Group]").is_err());
+    }
+}
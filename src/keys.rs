@@ -0,0 +1,261 @@
+//! The keys of the specification and what the specification says about them, in one table
+//! that the parser, the serializer and the validator all read from.
+
+use std::{convert::Infallible, fmt, str::FromStr};
+
+/// The type of the value of a key, as listed in the
+/// [Recognized desktop entry keys](https://specifications.freedesktop.org/desktop-entry-spec/latest/recognized-keys.html)
+/// section of the specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    String,
+    LocaleString,
+    IconString,
+    Boolean,
+    /// A list of strings separated by semicolons
+    StringList,
+    /// A list of localized strings separated by semicolons
+    LocaleStringList,
+}
+
+/// A version of the Desktop Entry Specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SpecVersion {
+    V1_0,
+    V1_1,
+    V1_2,
+    V1_3,
+    V1_4,
+    V1_5,
+}
+
+impl SpecVersion {
+    /// The version as written in the Version key, like `1.5`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V1_0 => "1.0",
+            Self::V1_1 => "1.1",
+            Self::V1_2 => "1.2",
+            Self::V1_3 => "1.3",
+            Self::V1_4 => "1.4",
+            Self::V1_5 => "1.5",
+        }
+    }
+}
+
+impl fmt::Display for SpecVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Where a key may appear
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyScope {
+    /// In the Desktop Entry group of every type of entry
+    Entry,
+    /// In the Desktop Entry group of applications
+    Application,
+    /// In the Desktop Entry group of links
+    Link,
+    /// In the Desktop Entry group and in action groups
+    EntryAndAction,
+}
+
+/// The metadata of a standard key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyInfo {
+    pub name: &'static str,
+    pub value_type: ValueType,
+    pub scope: KeyScope,
+    /// The version of the specification that introduced the key
+    pub since: SpecVersion,
+}
+
+impl KeyInfo {
+    /// Whether the key may appear in action groups
+    pub fn in_action(&self) -> bool {
+        self.scope == KeyScope::EntryAndAction
+    }
+
+    /// Whether the value of the key is a list
+    pub fn is_list(&self) -> bool {
+        matches!(
+            self.value_type,
+            ValueType::StringList | ValueType::LocaleStringList
+        )
+    }
+
+    /// Whether the key may have localized variants
+    pub fn is_localized(&self) -> bool {
+        matches!(
+            self.value_type,
+            ValueType::LocaleString | ValueType::IconString | ValueType::LocaleStringList
+        )
+    }
+}
+
+macro_rules! keys {
+    ($($variant:ident => $name:literal, $value_type:ident, $scope:ident, $since:ident;)*) => {
+        /// A key of a desktop entry. Standard keys are ordered as in the specification.
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum Key {
+            $($variant,)*
+            /// A key starting with `X-`, reserved for extensions
+            Extension(String),
+            /// Any other key
+            Unknown(String),
+        }
+
+        /// Every standard key, in the order of the specification
+        pub const KEYS: &[KeyInfo] = &[
+            $(KeyInfo {
+                name: $name,
+                value_type: ValueType::$value_type,
+                scope: KeyScope::$scope,
+                since: SpecVersion::$since,
+            },)*
+        ];
+
+        impl Key {
+            /// Returns the key as it is written in a file
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $name,)*
+                    Self::Extension(name) | Self::Unknown(name) => name,
+                }
+            }
+
+            /// Returns the metadata of a standard key
+            pub fn info(&self) -> Option<&'static KeyInfo> {
+                match self {
+                    Self::Extension(_) | Self::Unknown(_) => None,
+                    _ => KEYS.iter().find(|info| info.name == self.as_str()),
+                }
+            }
+        }
+
+        impl FromStr for Key {
+            type Err = Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(match s {
+                    $($name => Self::$variant,)*
+                    _ if s.starts_with("X-") => Self::Extension(s.to_string()),
+                    _ => Self::Unknown(s.to_string()),
+                })
+            }
+        }
+    };
+}
+
+keys! {
+    Type => "Type", String, Entry, V1_0;
+    Version => "Version", String, Entry, V1_0;
+    Name => "Name", LocaleString, EntryAndAction, V1_0;
+    GenericName => "GenericName", LocaleString, Entry, V1_0;
+    NoDisplay => "NoDisplay", Boolean, Entry, V1_0;
+    Comment => "Comment", LocaleString, Entry, V1_0;
+    Icon => "Icon", IconString, EntryAndAction, V1_0;
+    Hidden => "Hidden", Boolean, Entry, V1_0;
+    OnlyShowIn => "OnlyShowIn", StringList, Entry, V1_0;
+    NotShowIn => "NotShowIn", StringList, Entry, V1_0;
+    DBusActivatable => "DBusActivatable", Boolean, Entry, V1_1;
+    TryExec => "TryExec", String, Application, V1_0;
+    Exec => "Exec", String, EntryAndAction, V1_0;
+    Path => "Path", String, Application, V1_0;
+    Terminal => "Terminal", Boolean, Application, V1_0;
+    Actions => "Actions", StringList, Application, V1_1;
+    MimeType => "MimeType", StringList, Application, V1_0;
+    Categories => "Categories", StringList, Application, V1_0;
+    Implements => "Implements", StringList, Entry, V1_1;
+    Keywords => "Keywords", LocaleStringList, Application, V1_1;
+    StartupNotify => "StartupNotify", Boolean, Application, V1_0;
+    StartupWMClass => "StartupWMClass", String, Application, V1_0;
+    Url => "URL", String, Link, V1_0;
+    PrefersNonDefaultGPU => "PrefersNonDefaultGPU", Boolean, Application, V1_4;
+    SingleMainWindow => "SingleMainWindow", Boolean, Application, V1_5;
+}
+
+impl Key {
+    /// Returns the type of the value of a standard key
+    pub fn value_type(&self) -> Option<ValueType> {
+        self.info().map(|info| info.value_type)
+    }
+
+    /// Whether the key is one of the keys of the specification
+    pub fn is_standard(&self) -> bool {
+        self.info().is_some()
+    }
+}
+
+/// Returns the standard key closest to a misspelled one, ignoring case, if it is close enough
+/// to be a likely typo
+pub fn suggest_key(name: &str) -> Option<&'static str> {
+    fn distance(a: &str, b: &str) -> usize {
+        let b = b.chars().collect::<Vec<_>>();
+        let mut prev = (0..=b.len()).collect::<Vec<_>>();
+        for (i, ca) in a.chars().enumerate() {
+            let mut cur = vec![i + 1];
+            for (j, cb) in b.iter().enumerate() {
+                let cost = usize::from(!ca.eq_ignore_ascii_case(cb));
+                cur.push((prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1));
+            }
+            prev = cur;
+        }
+        prev[b.len()]
+    }
+
+    KEYS.iter()
+        .map(|info| (distance(name, info.name), info.name))
+        .filter(|(d, _)| *d <= (name.len() / 4).max(1))
+        .min()
+        .map(|(_, name)| name)
+}
+
+impl From<&str> for Key {
+    fn from(s: &str) -> Self {
+        let Ok(key) = s.parse();
+        key
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Key {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys() {
+        for info in KEYS {
+            let key = Key::from(info.name);
+            assert_eq!(key.as_str(), info.name);
+            assert_eq!(key.info(), Some(info));
+        }
+
+        assert_eq!(Key::from("URL"), Key::Url);
+        assert_eq!(
+            Key::from("Keywords").value_type(),
+            Some(ValueType::LocaleStringList)
+        );
+        assert_eq!(Key::from("X-Foo"), Key::Extension("X-Foo".into()));
+        assert_eq!(Key::from("Foo"), Key::Unknown("Foo".into()));
+        assert!(Key::from("X-Foo").info().is_none());
+
+        assert_eq!(suggest_key("Catgories"), Some("Categories"));
+        assert_eq!(suggest_key("name"), Some("Name"));
+        assert_eq!(suggest_key("Foo"), None);
+    }
+}
@@ -0,0 +1,378 @@
+//! Exchange of translations with gettext PO files, so translators can work with their usual
+//! tools. Every message carries a `msgctxt` naming its group and key, like
+//! `Desktop Entry/Name` or `Desktop Action new/Name`, which keeps identical source strings of
+//! different keys apart.
+
+use crate::{
+    locale::canonicalize_locale, parser::parse_list, serializer::escape_list_item, DesktopFile,
+    EntryType, ParseError,
+};
+
+/// A translatable key of a desktop file
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field {
+    Name,
+    GenericName,
+    Comment,
+    Keywords,
+    /// The Name of the action with the given id
+    ActionName(String),
+}
+
+impl Field {
+    fn context(&self) -> String {
+        match self {
+            Self::Name => "Desktop Entry/Name".into(),
+            Self::GenericName => "Desktop Entry/GenericName".into(),
+            Self::Comment => "Desktop Entry/Comment".into(),
+            Self::Keywords => "Desktop Entry/Keywords".into(),
+            Self::ActionName(id) => format!("Desktop Action {id}/Name"),
+        }
+    }
+
+    /// Stores a translation in the variant of the locale, if the file has the key
+    fn set(&self, file: &mut DesktopFile, locale: &str, translation: &str) {
+        let entry = &mut file.entry;
+        let string = match self {
+            Self::Name => Some(&mut entry.name),
+            Self::GenericName => entry.generic_name.as_mut(),
+            Self::Comment => entry.comment.as_mut(),
+            Self::ActionName(id) => file.actions.get_mut(id).map(|a| &mut a.name),
+            Self::Keywords => {
+                if let EntryType::Application(ref mut fields) = entry.entry_type {
+                    if let Some(keywords) = fields.keywords.as_mut() {
+                        keywords
+                            .variants
+                            .insert(locale.to_string(), parse_list(translation));
+                    }
+                }
+                return;
+            }
+        };
+
+        if let Some(string) = string {
+            string
+                .variants
+                .insert(locale.to_string(), translation.to_string());
+        }
+    }
+}
+
+/// A translatable value with its current translation
+struct Message {
+    field: Field,
+    source: String,
+    translation: Option<String>,
+}
+
+/// Joins a list the way it is written in a desktop file, like `text;plain;`
+fn join_list(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|item| escape_list_item(item) + ";")
+        .collect()
+}
+
+/// Collects the translatable values of a file, with their translation to a canonical locale.
+/// Actions come sorted by id.
+fn messages(file: &DesktopFile, locale: &str) -> Vec<Message> {
+    let entry = &file.entry;
+    let mut messages = vec![];
+
+    let strings = [
+        (Field::Name, Some(&entry.name)),
+        (Field::GenericName, entry.generic_name.as_ref()),
+        (Field::Comment, entry.comment.as_ref()),
+    ];
+    for (field, string) in strings {
+        if let Some(string) = string {
+            messages.push(Message {
+                field,
+                source: string.default.clone(),
+                translation: string.variants.get(locale).cloned(),
+            });
+        }
+    }
+
+    if let EntryType::Application(ref fields) = entry.entry_type {
+        if let Some(ref keywords) = fields.keywords {
+            messages.push(Message {
+                field: Field::Keywords,
+                source: join_list(&keywords.default),
+                translation: keywords.variants.get(locale).map(|v| join_list(v)),
+            });
+        }
+    }
+
+    let mut ids = file.actions.keys().collect::<Vec<_>>();
+    ids.sort();
+    for id in ids {
+        let name = &file.actions[id].name;
+        messages.push(Message {
+            field: Field::ActionName(id.clone()),
+            source: name.default.clone(),
+            translation: name.variants.get(locale).cloned(),
+        });
+    }
+
+    messages
+}
+
+/// Quotes a string as a PO string literal
+fn quote(value: &str) -> String {
+    let mut res = String::with_capacity(value.len() + 2);
+    res.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\t' => res.push_str("\\t"),
+            '\r' => res.push_str("\\r"),
+            _ => res.push(ch),
+        }
+    }
+    res.push('"');
+    res
+}
+
+/// Writes the translatable values of a file as a PO file for a locale: Name, GenericName,
+/// Comment, Keywords as a `;` separated list, and the names of the actions. The msgstrs hold
+/// the translations the file already has for the locale, and are empty otherwise.
+pub fn extract_po(file: &DesktopFile, locale: &str) -> String {
+    let locale = canonicalize_locale(locale);
+    let mut output = format!(
+        "msgid \"\"\nmsgstr \"\"\n\"Language: {locale}\\n\"\n\"MIME-Version: 1.0\\n\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n\"Content-Transfer-Encoding: 8bit\\n\"\n"
+    );
+
+    for message in messages(file, &locale) {
+        output.push_str(&format!(
+            "\nmsgctxt {}\nmsgid {}\nmsgstr {}\n",
+            quote(&message.field.context()),
+            quote(&message.source),
+            quote(message.translation.as_deref().unwrap_or_default())
+        ));
+    }
+
+    output
+}
+
+/// A message of a PO file
+#[derive(Debug, Default)]
+struct PoEntry {
+    context: Option<String>,
+    id: String,
+    string: String,
+    fuzzy: bool,
+    plural: bool,
+}
+
+/// The part of a PO entry a string literal belongs to
+#[derive(Clone, Copy)]
+enum PoField {
+    Context,
+    Id,
+    Plural,
+    Str,
+}
+
+/// Resolves a PO string literal like `"a \"quoted\" word"`
+fn unquote(literal: &str, row: usize) -> Result<String, ParseError> {
+    let error = || ParseError::Syntax {
+        msg: format!("{literal:?} is not a valid PO string"),
+        row,
+        col: 0,
+        group: None,
+    };
+    let inner = literal
+        .strip_prefix('"')
+        .and_then(|l| l.strip_suffix('"'))
+        .filter(|_| literal.len() >= 2)
+        .ok_or_else(error)?;
+
+    let mut res = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            res.push(ch);
+            continue;
+        }
+        match chars.next().ok_or_else(error)? {
+            'n' => res.push('\n'),
+            't' => res.push('\t'),
+            'r' => res.push('\r'),
+            escaped => res.push(escaped),
+        }
+    }
+
+    Ok(res)
+}
+
+/// Parses the messages of a PO file. Obsolete `#~` messages are skipped, and so are the
+/// comments but for the `fuzzy` flag.
+fn parse_po(input: &str) -> Result<Vec<PoEntry>, ParseError> {
+    let mut entries = vec![];
+    let mut current = PoEntry::default();
+    let mut field = None;
+
+    for (row, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("#~") {
+            continue;
+        }
+
+        // a new message starts with its comments or its msgctxt or msgid
+        let starts_message =
+            line.starts_with('#') || line.starts_with("msgctxt ") || line.starts_with("msgid ");
+        if starts_message && matches!(field, Some(PoField::Str)) {
+            entries.push(std::mem::take(&mut current));
+            field = None;
+        }
+
+        if let Some(flags) = line.strip_prefix("#,") {
+            current.fuzzy |= flags.split(',').any(|f| f.trim() == "fuzzy");
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (next, literal) = if let Some(rest) = line.strip_prefix("msgctxt ") {
+            (PoField::Context, rest)
+        } else if let Some(rest) = line.strip_prefix("msgid_plural ") {
+            current.plural = true;
+            (PoField::Plural, rest)
+        } else if let Some(rest) = line.strip_prefix("msgid ") {
+            (PoField::Id, rest)
+        } else if let Some(rest) = line.strip_prefix("msgstr") {
+            // `msgstr[n]` of plural messages, which are never used by desktop files
+            let rest = match rest.strip_prefix('[') {
+                Some(rest) => rest.split_once(']').map_or(rest, |(_, r)| r),
+                None => rest,
+            };
+            (PoField::Str, rest.trim_start())
+        } else if line.starts_with('"') {
+            let Some(field) = field else {
+                return Err(ParseError::Syntax {
+                    msg: "A PO string must follow a keyword".into(),
+                    row,
+                    col: 0,
+                    group: None,
+                });
+            };
+            (field, line)
+        } else {
+            return Err(ParseError::Syntax {
+                msg: format!("{line:?} is not a valid PO line"),
+                row,
+                col: 0,
+                group: None,
+            });
+        };
+
+        let value = unquote(literal.trim(), row)?;
+        match next {
+            PoField::Context => current.context.get_or_insert_default().push_str(&value),
+            PoField::Id => current.id.push_str(&value),
+            PoField::Plural => {}
+            PoField::Str => current.string.push_str(&value),
+        }
+        field = Some(next);
+    }
+
+    if field.is_some() {
+        entries.push(current);
+    }
+    Ok(entries)
+}
+
+/// Writes the translations of a PO file into the variants of a locale. A message applies to
+/// the key its msgctxt names, or to every key with its msgid when it has none, and only while
+/// the msgid still matches the value of the key. Fuzzy and untranslated messages are skipped,
+/// so are the keys the file doesn't have, and the variants of other locales are kept.
+///
+/// Returns the number of values translated.
+pub fn apply_po(file: &mut DesktopFile, locale: &str, po: &str) -> Result<usize, ParseError> {
+    let locale = canonicalize_locale(locale);
+    let messages = messages(file, &locale);
+    let mut applied = 0;
+
+    for entry in parse_po(po)? {
+        if entry.fuzzy || entry.plural || entry.id.is_empty() || entry.string.is_empty() {
+            continue;
+        }
+
+        for message in &messages {
+            let context_matches = entry
+                .context
+                .as_ref()
+                .is_none_or(|c| *c == message.field.context());
+            if context_matches && message.source == entry.id {
+                message.field.set(file, &locale, &entry.string);
+                applied += 1;
+            }
+        }
+    }
+
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_po_round_trip() {
+        let mut file = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Editor\nName[fr]=Éditeur\nGenericName=Editor\nComment=Edit \"text\" files\nKeywords=text;plain;\nKeywords[de]=Text;\nExec=editor\nActions=new;\n\n[Desktop Action new]\nName=New Window\n",
+        )
+        .unwrap();
+
+        let po = extract_po(&file, "de");
+        assert!(po.contains("\"Language: de\\n\"\n"));
+        assert!(po.contains(
+            "msgctxt \"Desktop Entry/Comment\"\nmsgid \"Edit \\\"text\\\" files\"\nmsgstr \"\"\n"
+        ));
+        assert!(po.contains(
+            "msgctxt \"Desktop Entry/Keywords\"\nmsgid \"text;plain;\"\nmsgstr \"Text;\"\n"
+        ));
+        assert!(po.contains("msgctxt \"Desktop Action new/Name\"\nmsgid \"New Window\"\n"));
+
+        let translated =
+            po.replace(
+                "msgctxt \"Desktop Entry/Name\"\nmsgid \"Editor\"\nmsgstr \"\"",
+                "msgctxt \"Desktop Entry/Name\"\nmsgid \"Editor\"\nmsgstr \"Text\"\n\"editor\"",
+            )
+            .replace("msgstr \"Text;\"", "msgstr \"Text;einfach;\"")
+            .replace(
+                "msgctxt \"Desktop Entry/Comment\"",
+                "#, fuzzy\nmsgctxt \"Desktop Entry/Comment\"",
+            )
+            .replace(
+                "msgid \"Edit \\\"text\\\" files\"\nmsgstr \"\"",
+                "msgid \"Edit \\\"text\\\" files\"\nmsgstr \"Textdateien bearbeiten\"",
+            ) + "\nmsgid \"New Window\"\nmsgstr \"Neues Fenster\"\n";
+
+        assert_eq!(apply_po(&mut file, "de", &translated).unwrap(), 3);
+        assert_eq!(file.entry.name.variants["de"], "Texteditor");
+        assert_eq!(file.entry.name.variants["fr"], "Éditeur");
+        assert!(!file
+            .entry
+            .generic_name
+            .as_ref()
+            .unwrap()
+            .variants
+            .contains_key("de"));
+        assert!(file.entry.comment.as_ref().unwrap().variants.is_empty());
+        let EntryType::Application(ref fields) = file.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(
+            fields.keywords.as_ref().unwrap().variants["de"],
+            ["Text", "einfach"]
+        );
+        assert_eq!(file.actions["new"].name.variants["de"], "Neues Fenster");
+
+        assert!(apply_po(&mut file, "de", "msgid \"a\"\nbogus").is_err());
+    }
+}
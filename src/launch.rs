@@ -0,0 +1,100 @@
+//! Startup notification for launching entries, through both paths a launch can take: the
+//! environment of a spawned command and the platform data of the `Activate` and `Open` calls
+//! of D-Bus activatable applications.
+//!
+//! The library doesn't talk to the compositor or the X server; the caller obtains the
+//! xdg-activation token or the startup id and passes it in [`LaunchOptions`].
+
+use crate::DesktopEntry;
+
+/// The variable carrying the xdg-activation token on Wayland
+pub const ACTIVATION_TOKEN_VAR: &str = "XDG_ACTIVATION_TOKEN";
+/// The variable carrying the startup id of the X11 startup notification protocol
+pub const STARTUP_ID_VAR: &str = "DESKTOP_STARTUP_ID";
+
+/// Options of a launch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchOptions {
+    /// The xdg-activation token the compositor handed out for the launch, which Wayland
+    /// compositors need to let the new window take focus
+    pub activation_token: Option<String>,
+    /// The startup id of the X11 startup notification protocol
+    pub startup_id: Option<String>,
+}
+
+impl LaunchOptions {
+    /// Whether the X11 startup id is passed on. The specification says not to set it for
+    /// entries known not to support startup notification (StartupNotify=false), while the
+    /// activation token only lets the window take focus and is always passed on.
+    fn passes_startup_id(entry: &DesktopEntry) -> bool {
+        match entry.entry_type {
+            crate::EntryType::Application(ref fields) => fields.startup_notify != Some(false),
+            _ => true,
+        }
+    }
+
+    /// Returns the variables to add to the environment of the spawned command, the
+    /// activation token first since it is the mechanism to prefer when both are present
+    pub fn env(&self, entry: &DesktopEntry) -> Vec<(String, String)> {
+        let mut env = vec![];
+        if let Some(ref token) = self.activation_token {
+            env.push((ACTIVATION_TOKEN_VAR.to_string(), token.clone()));
+        }
+        if let (Some(id), true) = (&self.startup_id, Self::passes_startup_id(entry)) {
+            env.push((STARTUP_ID_VAR.to_string(), id.clone()));
+        }
+        env
+    }
+
+    /// Returns the entries of the `platform-data` dictionary of the `Activate`, `Open` and
+    /// `ActivateAction` calls of the `org.freedesktop.Application` interface, as string
+    /// values
+    pub fn platform_data(&self, entry: &DesktopEntry) -> Vec<(&'static str, String)> {
+        let mut data = vec![];
+        if let Some(ref token) = self.activation_token {
+            data.push(("activation-token", token.clone()));
+        }
+        if let (Some(id), true) = (&self.startup_id, Self::passes_startup_id(entry)) {
+            data.push(("desktop-startup-id", id.clone()));
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_startup_notification() {
+        let options = LaunchOptions {
+            activation_token: Some("token".into()),
+            startup_id: Some("id".into()),
+        };
+        let entry = crate::parse("[Desktop Entry]\nType=Application\nName=App\nExec=app\n")
+            .unwrap()
+            .entry;
+        assert_eq!(
+            options.env(&entry),
+            [
+                ("XDG_ACTIVATION_TOKEN".to_string(), "token".to_string()),
+                ("DESKTOP_STARTUP_ID".to_string(), "id".to_string()),
+            ]
+        );
+        assert_eq!(
+            options.platform_data(&entry),
+            [
+                ("activation-token", "token".to_string()),
+                ("desktop-startup-id", "id".to_string()),
+            ]
+        );
+
+        let entry = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=App\nExec=app\nStartupNotify=false\n",
+        )
+        .unwrap()
+        .entry;
+        assert_eq!(options.env(&entry).len(), 1);
+        assert_eq!(options.platform_data(&entry).len(), 1);
+    }
+}
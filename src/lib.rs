@@ -1,9 +1,70 @@
+pub mod actions;
+pub mod analyze;
+#[cfg(feature = "appimage")]
+pub mod appimage;
+pub mod autostart;
+pub mod bytes;
+pub mod canonical;
+pub mod collection;
+pub mod comments;
+pub mod coverage;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+pub mod events;
+pub mod exec;
+pub mod expand;
+#[cfg(feature = "flatpak")]
+pub mod flatpak;
+pub mod handlers;
+pub mod icons;
+pub mod install;
 pub mod internal_structs;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod keyfile;
+pub mod keys;
+#[cfg(feature = "l10n")]
+pub mod l10n;
+pub mod launch;
+pub mod locale;
+#[cfg(feature = "menu")]
+pub mod menu;
+pub mod merge;
+#[cfg(feature = "mime")]
+pub mod mime;
+pub mod mimeinfo;
+#[cfg(feature = "origin")]
+pub mod origin;
+pub mod overview;
 pub mod parser;
+pub mod raw;
+pub mod render;
+pub mod sanitize;
+pub mod scan;
+pub mod serializer;
+pub mod shared;
+#[cfg(feature = "snap")]
+pub mod snap;
 pub mod structs;
+pub mod summary;
+pub mod systemd;
+pub mod template;
+#[cfg(all(unix, feature = "trust"))]
+pub mod trust;
+pub mod upgrade;
+mod util;
+pub mod validate;
+pub mod value;
+#[cfg(feature = "vendor")]
+pub mod vendor;
 
-pub use parser::parse;
+pub use parser::{
+    parse, parse_bytes, parse_bytes_with_options, parse_unchecked, parse_with_options,
+    parse_with_warnings, ParseOptions,
+};
+pub use shared::DesktopFileShared;
 pub use structs::*;
+pub use summary::{parse_summary, EntrySummary};
 
 #[cfg(test)]
 mod tests {
@@ -159,6 +220,74 @@ Exec=firefox --private-window
     }
 
     #[test]
+    fn test_crlf_line_endings() {
+        let lf = "[Desktop Entry]\nName=Text Editor\nName[es]=Editor de texto\nName[fr]=Éditeur de texte\nGenericName=Text Editor\nGenericName[es]=Editor\nExec=gedit %F\nType=Application\nCategories=Utility;TextEditor;\nKeywords[de]=Text;Notizen;\nActions=new-window;\n\n[Desktop Action new-window]\nName=New Window\nName[es]=Nueva ventana\nExec=gedit --new-window\n\n[X-Extra]\nKey=value\n";
+        let crlf = lf.replace('\n', "\r\n");
+
+        let (expected, expected_warnings) =
+            parse_with_warnings(lf, &ParseOptions::default()).unwrap();
+        let (f, warnings) = parse_with_warnings(&crlf, &ParseOptions::default()).unwrap();
+        assert_eq!(f.to_desktop_string(), expected.to_desktop_string());
+        assert_eq!(warnings, expected_warnings);
+        assert!(f.entry.extras.is_empty());
+        assert_eq!(f.actions["new-window"].name.variants["es"], "Nueva ventana");
+        assert_eq!(f.other_groups[0].name, "X-Extra");
+        assert_eq!(f.other_groups[0].entries[0].value, "value");
+
+        let bytes = parse_bytes(crlf.as_bytes()).unwrap();
+        assert_eq!(bytes.to_desktop_string(), expected.to_desktop_string());
+        assert_eq!(
+            parse_unchecked(&crlf).to_desktop_string(),
+            parse_unchecked(lf).to_desktop_string()
+        );
+        let summary = parse_summary(&crlf, &["es"]).unwrap();
+        assert_eq!(summary.name.default, "Text Editor");
+        assert_eq!(summary.name.variants["es"], "Editor de texto");
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        for content in [
+            "\u{FEFF}# comment\n[Desktop Entry]\nType=Application\nName=App\n",
+            "\u{FEFF}[Desktop Entry]\nType=Application\nName=App\n",
+            "\u{FEFF}\n\n[Desktop Entry]\nType=Application\nName=App\n",
+        ] {
+            let f = parse(content).unwrap();
+            assert_eq!(f.entry.name.default, "App");
+            assert_eq!(parse_unchecked(content).entry.name.default, "App");
+            assert_eq!(parse_summary(content, &[]).unwrap().name.default, "App");
+        }
+
+        assert_eq!(parse("\u{FEFF}").unwrap_err(), ParseError::EmptyInput);
+        assert_eq!(
+            parse_summary("\u{FEFF}\n", &[]).unwrap_err(),
+            ParseError::EmptyInput
+        );
+
+        let mut events = events::Events::new("\u{FEFF}Name=App\n");
+        let Some(Ok(events::Event::KeyValue { span, .. })) = events.next() else {
+            panic!("expected a key value pair");
+        };
+        assert_eq!((span.start, span.end), (3, 11));
+
+        // only a mark starting the input is skipped
+        assert!(matches!(
+            parse("[Desktop Entry]\n\u{FEFF}Type=Application\nName=App\n"),
+            Err(ParseError::Syntax { row: 1, .. })
+        ));
+        assert!(parse("\u{FEFF}\u{FEFF}[Desktop Entry]\nType=Application\nName=App\n").is_err());
+        assert_eq!(
+            parse("[Desktop Entry]\nType=Application\nName=App\u{FEFF}\n")
+                .unwrap()
+                .entry
+                .name
+                .default,
+            "App\u{FEFF}"
+        );
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_boolean_values() {
         let content = r#"
 [Desktop Entry]
@@ -256,7 +385,7 @@ Implements=org.freedesktop.Application;
         assert!(matches!(app_entry.entry_type, EntryType::Application(_)));
         assert!(matches!(link_entry.entry_type, EntryType::Link(_)));
         assert!(matches!(dir_entry.entry_type, EntryType::Directory));
-        assert!(matches!(unknown_entry.entry_type, EntryType::Unknown));
+        assert!(matches!(unknown_entry.entry_type, EntryType::Unknown(ref t) if t == "CustomType"));
     }
 
     #[test]
@@ -275,6 +404,598 @@ Icon=test-icon
         // Note: We can't effectively test get_icon_path() here without mocking the filesystem
     }
 
+    #[test]
+    fn test_from_str() {
+        let content = "[Desktop Entry]\nType=Directory\nName=Test";
+        let f: DesktopFile = content.parse().unwrap();
+        assert_eq!(f.entry.name.default, "Test");
+        assert!(DesktopFile::try_from(content).is_ok());
+
+        let err = "Name=Test".parse::<DesktopFile>().unwrap_err();
+        assert_eq!(Err(err), parse("Name=Test").map(|_| ()));
+    }
+
+    #[test]
+    fn test_constructors() {
+        let app = DesktopFile::new_application("Notes", "notes %F")
+            .unwrap()
+            .with_icon("notes")
+            .with_categories(["Office"])
+            .with_comment("Take notes")
+            .with_key("Comment[de]", "Notizen")
+            .unwrap();
+        assert_eq!(
+            app.to_desktop_string(),
+            "[Desktop Entry]\nType=Application\nName=Notes\nComment=Take notes\nComment[de]=Notizen\nIcon=notes\nExec=notes %F\nCategories=Office;\n"
+        );
+        assert!(DesktopFile::new_application("Notes", "notes %z").is_err());
+
+        let link = DesktopFile::new_link("Docs", "https://example.com");
+        let parsed = parse(&link.to_desktop_string()).unwrap();
+        assert!(
+            matches!(parsed.entry.entry_type, EntryType::Link(ref f) if f.url == "https://example.com")
+        );
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let errors = [
+            ParseError::UnacceptableCharacter {
+                ch: "[".into(),
+                row: 0,
+                col: 0,
+                msg: String::new(),
+            },
+            ParseError::Syntax {
+                msg: String::new(),
+                row: 0,
+                col: 0,
+                group: None,
+            },
+            ParseError::RepetitiveEntry {
+                msg: String::new(),
+                row: 0,
+                col: 0,
+            },
+            ParseError::FormatError {
+                msg: String::new(),
+                row: 0,
+                col: 0,
+            },
+            ParseError::InternalError {
+                msg: String::new(),
+                row: 0,
+                col: 0,
+            },
+            ParseError::RepetitiveKey {
+                key: String::new(),
+                row: 0,
+                col: 0,
+                group: None,
+            },
+            ParseError::KeyError { msg: String::new() },
+            ParseError::EmptyInput,
+            ParseError::MissingDesktopEntryGroup { row: None },
+            ParseError::MissingUrl { row: None },
+        ];
+        let codes = errors
+            .iter()
+            .map(|e| (e.code(), e.code_name()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            [
+                ("E001", "unacceptable-character"),
+                ("E002", "syntax-error"),
+                ("E003", "repeated-entry"),
+                ("E004", "misplaced-content"),
+                ("E005", "internal-error"),
+                ("E006", "repeated-key"),
+                ("E007", "key-error"),
+                ("E008", "empty-input"),
+                ("E009", "missing-entry-group"),
+                ("E010", "missing-url"),
+            ]
+        );
+        for e in errors {
+            assert!(e
+                .to_string()
+                .starts_with(&format!("Parse Error [{}]", e.code())));
+        }
+    }
+
+    #[test]
+    fn test_missing_entry_group() {
+        assert_eq!(parse("").unwrap_err(), ParseError::EmptyInput);
+        assert_eq!(parse(" \n\n").unwrap_err(), ParseError::EmptyInput);
+        assert_eq!(
+            parse("# just a comment\n").unwrap_err(),
+            ParseError::MissingDesktopEntryGroup { row: None }
+        );
+        assert_eq!(
+            parse("# keys without a group\nType=Application\nName=Foo\n").unwrap_err(),
+            ParseError::MissingDesktopEntryGroup { row: Some(1) }
+        );
+        assert_eq!(
+            parse_summary("\nName=Foo\n", &[]).unwrap_err(),
+            ParseError::MissingDesktopEntryGroup { row: Some(1) }
+        );
+    }
+
+    #[test]
+    fn test_keys_before_header() {
+        for (line, key) in [
+            ("Name=Foo", "Name"),
+            ("X-Vendor-Id=42", "X-Vendor-Id"),
+            ("Name[de]=Foo", "Name"),
+        ] {
+            let input = format!(
+                "# generated\n{line}\nComment=Bar\n[Desktop Entry]\nType=Application\nName=A\n"
+            );
+            for err in [
+                parse(&input).unwrap_err(),
+                parse_summary(&input, &[]).unwrap_err(),
+            ] {
+                assert_eq!(
+                    err,
+                    ParseError::FormatError {
+                        msg: format!("the key {key:?} appears before any group header"),
+                        row: 1,
+                        col: 0,
+                    }
+                );
+            }
+        }
+
+        let err = parse("Name=Foo\n[Desktop Entry]\nType=Application\nName=A\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse Error [E004]: Format error at line 0 column 0, message: \"the key \\\"Name\\\" appears before any group header\""
+        );
+        assert_eq!(err.code_name(), "misplaced-content");
+    }
+
+    #[test]
+    fn test_missing_url() {
+        for url in ["", "URL=\n", "URL=  \n"] {
+            let err = parse(&format!(
+                "# exported\n[Desktop Entry]\nType=Link\nName=Docs\n{url}"
+            ))
+            .unwrap_err();
+            assert_eq!(err, ParseError::MissingUrl { row: Some(1) });
+            assert_eq!(err.position(), Some((1, 0)));
+        }
+        assert!(parse("[Desktop Entry]\nType=Link\nName=Docs\nURL=https://example.com\n").is_ok());
+
+        let mut entry = parse("[Desktop Entry]\nType=Link\nName=Docs\nURL=https://example.com\n")
+            .unwrap()
+            .entry;
+        assert_eq!(
+            entry.set_key("URL", "").unwrap_err(),
+            ParseError::MissingUrl { row: None }
+        );
+    }
+
+    #[test]
+    fn test_leading_groups() {
+        let content = "# generated\n[X-Generator]\nTool=foo\n\n[Desktop Entry]\nType=Application\nName=Test\nExec=test\n";
+
+        assert!(matches!(
+            parse(content),
+            Err(ParseError::FormatError { row: 1, .. })
+        ));
+
+        let options = ParseOptions {
+            skip_leading_groups: true,
+            ..Default::default()
+        };
+        let f = parse_with_options(content, &options).unwrap();
+        assert_eq!(f.entry.name.default, "Test");
+        assert_eq!(f.other_groups[0].name, "X-Generator");
+        assert_eq!(f.other_groups[0].get_raw("Tool"), Some("foo"));
+        assert!(f.entry.extras.is_empty());
+
+        assert!(matches!(
+            parse("[Desktop Action new]\nName=New\n[Desktop Entry]\nType=Directory\nName=Test"),
+            Err(ParseError::FormatError { row: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_locale_case() {
+        let content = "[Desktop Entry]\nType=Directory\nName=Test\nName[pt_br]=Teste\nName[de]=Test\nName[DE]=Prüfung\n";
+
+        assert!(matches!(
+            parse(content),
+            Err(ParseError::RepetitiveKey { row: 5, .. })
+        ));
+
+        let options = ParseOptions {
+            duplicate_locales_last_wins: true,
+            ..Default::default()
+        };
+        let (f, warnings) = parse_with_warnings(content, &options).unwrap();
+        assert_eq!(f.entry.name.variants["de"], "Prüfung");
+        assert_eq!(f.entry.name.get_variant("pt_BR"), "Teste");
+        assert_eq!(
+            warnings,
+            [ParseWarning::DuplicateLocale {
+                key: "Name".into(),
+                locale: "de".into(),
+                row: 5,
+                group: "Desktop Entry".into(),
+            }]
+        );
+        assert_eq!(warnings[0].code(), "W001");
+        assert!(f.to_desktop_string().contains("Name[pt_BR]=Teste\n"));
+    }
+
+    #[test]
+    fn test_promote_name_variant() {
+        let content = "[Desktop Entry]\nType=Application\nName[fr]=Éditeur\nName[de]=Editor\nExec=editor\nActions=new;\n\n[Desktop Action new]\nName[C]=New\nName[de]=Neu\n";
+
+        let err = parse(content).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse Error [E007]: Key Error, message: \"Name has no default value in group [Desktop Entry], only Name[de], Name[fr]; add a Name= line\""
+        );
+
+        let options = ParseOptions {
+            promote_name_variant: true,
+            ..Default::default()
+        };
+        let (f, warnings) = parse_with_warnings(content, &options).unwrap();
+        assert_eq!(f.entry.name.default, "Editor");
+        assert_eq!(f.actions["new"].name.default, "New");
+        assert_eq!(
+            warnings,
+            [
+                ParseWarning::PromotedName {
+                    locale: "de".into(),
+                    group: "Desktop Entry".into(),
+                },
+                ParseWarning::PromotedName {
+                    locale: "c".into(),
+                    group: "Desktop Action new".into(),
+                },
+            ]
+        );
+
+        let f = parse_with_options(
+            "[Desktop Entry]\nType=Directory\nName[fr]=Jeux\nName[en]=Games\n",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(f.entry.name.default, "Games");
+    }
+
+    #[test]
+    fn test_padded_group_headers() {
+        let content = "[ Desktop Entry ]\nType=Application\nName=Foo\nExec=foo\nActions=new;\n\n[Desktop Action  new]\nName=New\n";
+        let (f, warnings) = parse_with_warnings(content, &ParseOptions::default()).unwrap();
+
+        assert_eq!(f.actions["new"].name.default, "New");
+        assert_eq!(
+            warnings,
+            [
+                ParseWarning::PaddedGroupHeader {
+                    written: " Desktop Entry ".into(),
+                    row: 0,
+                    group: "Desktop Entry".into(),
+                },
+                ParseWarning::PaddedGroupHeader {
+                    written: "Desktop Action  new".into(),
+                    row: 6,
+                    group: "Desktop Action new".into(),
+                },
+            ]
+        );
+        assert!(matches!(
+            parse("[ ]\nType=Application\n"),
+            Err(ParseError::Syntax { row: 0, col: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_indented_lines() {
+        let content = "  [Desktop Entry]\n\tType=Application\n Name=App\n \t Exec=app\n\t  Name[de]=Anwendung\nActions=new;\n\n\t[Desktop Action new]\n\tName=New\n";
+
+        let (f, warnings) = parse_with_warnings(content, &ParseOptions::default()).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(f.entry.name.default, "App");
+        assert_eq!(f.entry.name.variants["de"], "Anwendung");
+        assert_eq!(
+            f.entry.as_application().unwrap().exec.as_deref(),
+            Some("app")
+        );
+        assert_eq!(f.actions["new"].name.default, "New");
+
+        let strict = ParseOptions {
+            reject_indentation: true,
+            ..Default::default()
+        };
+        let error = |row, group: &str| ParseError::Syntax {
+            msg: "Lines shouldn't be indented".into(),
+            row,
+            col: 0,
+            group: Some(group.to_string()),
+        };
+        assert_eq!(
+            parse_with_options(content, &strict).unwrap_err(),
+            error(0, "Desktop Entry")
+        );
+        assert_eq!(
+            parse_with_options("[Desktop Entry]\n\tType=Application\nName=App\n", &strict)
+                .unwrap_err(),
+            error(1, "Desktop Entry")
+        );
+        assert_eq!(
+            parse_with_options(
+                "[Desktop Entry]\nType=Application\nName=App\nActions=new;\n\n[Desktop Action new]\n \tName=New\n",
+                &strict
+            )
+            .unwrap_err(),
+            error(6, "Desktop Action new")
+        );
+        assert!(parse_with_options(
+            "[Desktop Entry]\nType=Application\n\t# comment\nName=App\n",
+            &strict
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_merge_duplicate_entry_groups() {
+        // an exporter appending the translations in a second entry group after the actions
+        let content = "[Desktop Entry]\nType=Application\nName=Files\nComment=Browse files\nExec=files %U\nActions=new-window;\n\n[Desktop Action new-window]\nName=New Window\nExec=files --new-window\n\n[Desktop Entry]\nName[de]=Dateien\nComment[de]=Dateien durchsuchen\n";
+
+        assert!(matches!(
+            parse(content),
+            Err(ParseError::RepetitiveEntry { row: 11, .. })
+        ));
+
+        let options = ParseOptions {
+            merge_duplicate_entry_groups: true,
+            ..Default::default()
+        };
+        let (f, warnings) = parse_with_warnings(content, &options).unwrap();
+        assert_eq!(f.entry.name.get_variant("de"), "Dateien");
+        assert_eq!(
+            f.entry.comment.unwrap().get_variant("de"),
+            "Dateien durchsuchen"
+        );
+        assert_eq!(f.actions["new-window"].name.default, "New Window");
+        assert!(f.actions["new-window"].name.variants.is_empty());
+        assert_eq!(
+            warnings,
+            [ParseWarning::DuplicateEntryGroup {
+                first_row: 0,
+                row: 11
+            }]
+        );
+
+        let repeated = format!("{content}Name=Other\n");
+        assert!(matches!(
+            parse_with_options(&repeated, &options),
+            Err(ParseError::RepetitiveKey { row: 14, .. })
+        ));
+    }
+
+    #[test]
+    fn test_entry_type_accessors() {
+        let mut file = parse(
+            "[Desktop Entry]\nType=Application\nName=Editor\nExec=editor\nCategories=Utility;\n",
+        )
+        .unwrap();
+        let entry = &mut file.entry;
+
+        entry
+            .as_application_mut()
+            .unwrap()
+            .categories
+            .get_or_insert_with(Vec::new)
+            .push("TextEditor".into());
+
+        // borrowing the type only leaves the other keys of the entry readable
+        let fields = entry.entry_type.application_mut().unwrap();
+        fields.startup_wm_class = Some(entry.name.default.to_lowercase());
+
+        let name = entry.name.default.clone();
+        if let Ok(fields) = entry.expect_application_mut() {
+            fields.try_exec = Some(name.to_lowercase());
+        }
+
+        let fields = entry.expect_application().unwrap();
+        assert_eq!(
+            fields.categories.as_deref(),
+            Some(&["Utility".to_string(), "TextEditor".to_string()][..])
+        );
+        assert_eq!(fields.startup_wm_class.as_deref(), Some("editor"));
+        assert_eq!(fields.try_exec.as_deref(), Some("editor"));
+        assert!(entry.as_link().is_none());
+        assert!(!entry.is_directory() && !entry.is_unknown());
+        assert_eq!(
+            entry.expect_link().unwrap_err().to_string(),
+            "Entry Type Error: expected an entry of type Link, found Application"
+        );
+
+        let mut entry = DesktopEntry::new("Site", EntryType::Link(LinkFields::new("a")));
+        entry.as_link_mut().unwrap().url = "https://example.org".into();
+        assert_eq!(entry.expect_link().unwrap().url, "https://example.org");
+        assert!(entry.expect_application().is_err());
+    }
+
+    #[test]
+    fn test_entry_type_size() {
+        // with boxed application fields every entry type stays small
+        #[cfg(feature = "boxed-fields")]
+        assert!(std::mem::size_of::<EntryType>() <= 32);
+        let fields = ApplicationFields {
+            terminal: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(
+            EntryType::new_application(fields)
+                .application()
+                .unwrap()
+                .terminal,
+            Some(false)
+        );
+
+        let mut entry = parse("[Desktop Entry]\nType=Application\nName=App\nExec=app\n")
+            .unwrap()
+            .entry;
+        entry.entry_type.application_mut().unwrap().terminal = Some(true);
+        assert_eq!(entry.entry_type.application().unwrap().terminal, Some(true));
+        assert!(EntryType::Directory.application().is_none());
+    }
+
+    #[test]
+    fn test_error_group() {
+        let content = "[Desktop Entry]\nType=Application\nName=App\nActions=new;\n\n[Desktop Action new]\nName=New\nName=Other\n";
+        let err = parse(content).unwrap_err();
+        assert_eq!(err.group(), Some("Desktop Action new"));
+        assert!(err
+            .to_string()
+            .contains("at line 7 column 0 in group [Desktop Action new]"));
+
+        let err = parse("[Desktop Entry]\nType=Application\nName=App\nTerminal=yes\n").unwrap_err();
+        assert_eq!(err.group(), Some("Desktop Entry"));
+    }
+
+    #[test]
+    fn test_trailing_whitespace() {
+        let content =
+            "[Desktop Entry]\r\nType=Application \r\nName=Test\r\nExec=  run --prefix= \t\r\n";
+
+        let (f, warnings) = parse_with_warnings(content, &ParseOptions::default()).unwrap();
+        let EntryType::Application(ref fields) = f.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(fields.exec.as_deref(), Some("run --prefix="));
+        assert_eq!(
+            warnings.iter().map(|w| w.position()).collect::<Vec<_>>(),
+            [Some((1, 0)), Some((3, 0))]
+        );
+        assert_eq!(warnings[0].code_name(), "trailing-whitespace");
+
+        let options = ParseOptions {
+            keep_trailing_whitespace: true,
+            ..Default::default()
+        };
+        let content = "[Desktop Entry]\nType=Application\nName=Test\nExec=run --prefix= \n";
+        let (f, warnings) = parse_with_warnings(content, &options).unwrap();
+        assert!(warnings.is_empty());
+        let EntryType::Application(ref fields) = f.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(fields.exec.as_deref(), Some("run --prefix= "));
+        assert!(f.to_desktop_string().contains("Exec=run --prefix=\\s\n"));
+    }
+
+    #[test]
+    fn test_skip_actions() {
+        let content = "[Desktop Entry]\nType=Application\nName=Browser\nActions=new;\n\n[Desktop Action new]\nName[de]=Neu\nbad key\n\n[X-Extra]\nFoo=bar\n";
+        assert!(parse(content).is_err());
+
+        let options = ParseOptions {
+            parse_actions: false,
+            ..Default::default()
+        };
+        let f = parse_with_options(content, &options).unwrap();
+        assert!(f.actions.is_empty());
+        let EntryType::Application(ref fields) = f.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(fields.actions, Some(vec!["new".to_string()]));
+        assert_eq!(f.other_groups[0].name, "X-Extra");
+    }
+
+    #[test]
+    fn test_unknown_groups() {
+        let content = "[Desktop Entry]\nType=Application\nName=App\nActions=a;b;\n\n[X-One]\nName=One\nHidden=true\n\n[Desktop Action a]\nName=A\n\n[X-Two]\nName=Two\nExec=two\n\n[Desktop Action b]\nName=B\n\n[X-Three Settings]\nName=Three\nNoDisplay=true\n";
+
+        for f in [parse(content).unwrap(), parse_unchecked(content)] {
+            assert_eq!(f.entry.name.default, "App");
+            assert_eq!(f.entry.hidden, None);
+            assert_eq!(f.entry.no_display, None);
+            assert!(f.entry.extras.is_empty());
+            assert_eq!(f.actions["a"].exec, None);
+            assert!(f.actions["a"].extras.is_empty());
+            assert_eq!(f.actions["b"].name.default, "B");
+
+            let groups = f
+                .other_groups
+                .iter()
+                .map(|g| (g.name.as_str(), g.get_raw("Name")))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                groups,
+                [
+                    ("X-One", Some("One")),
+                    ("X-Two", Some("Two")),
+                    ("X-Three Settings", Some("Three"))
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_unlisted_actions() {
+        let content = "[Desktop Entry]\nType=Application\nName=App\nActions=b;d;\n\n[Desktop Action a]\nName=A\nExec=app -a\n\n[Desktop Action b]\nName=B\n\n[Desktop Action c]\nExec=app -c\n\n[Desktop Action d]\nName=D\n\n[Desktop Action e]\nName[de]=E\n";
+
+        for f in [parse(content).unwrap(), parse_unchecked(content)] {
+            let mut listed = f.actions.keys().collect::<Vec<_>>();
+            listed.sort();
+            assert_eq!(listed, ["b", "d"]);
+            let mut unlisted = f.unlisted_actions.keys().collect::<Vec<_>>();
+            unlisted.sort();
+            assert_eq!(unlisted, ["a", "c", "e"]);
+            assert_eq!(f.unlisted_actions["a"].exec.as_deref(), Some("app -a"));
+            assert_eq!(f.unlisted_actions["c"].name.default, "");
+        }
+
+        // written back after the listed actions without being added to the Actions key
+        let written = parse(content).unwrap().to_desktop_string();
+        assert!(written.contains("Actions=b;d;\n"));
+        let reparsed = parse(&written).unwrap();
+        assert_eq!(reparsed.unlisted_actions.len(), 3);
+        assert!(written.find("[Desktop Action d]") < written.find("[Desktop Action a]"));
+    }
+
+    #[test]
+    fn test_missing_action_groups() {
+        let strict = ParseOptions {
+            require_action_groups: true,
+            ..Default::default()
+        };
+
+        let content = "[Desktop Entry]\nType=Application\nName=App\nActions=new-window;missing-action;private;\n\n[Desktop Action new-window]\nName=New\n\n[Desktop Action private]\nName=Private\n";
+        let f = parse(content).unwrap();
+        let mut ids = f.actions.keys().collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, ["new-window", "private"]);
+        assert_eq!(
+            parse_with_options(content, &strict).unwrap_err(),
+            ParseError::KeyError {
+                msg: "the action \"missing-action\" listed by Actions at line 3 has no [Desktop Action missing-action] group".into()
+            }
+        );
+
+        let content = "[Desktop Entry]\nType=Application\nActions=a;b;\nName=App\n";
+        assert!(parse(content).unwrap().actions.is_empty());
+        assert!(matches!(
+            parse_with_options(content, &strict),
+            Err(ParseError::KeyError { msg }) if msg.starts_with("the action \"a\" listed by Actions at line 2")
+        ));
+
+        let options = ParseOptions {
+            parse_actions: false,
+            ..strict
+        };
+        assert!(parse_with_options(content, &options).is_ok());
+    }
+
     #[test]
     #[should_panic]
     fn test_missing_required_fields() {
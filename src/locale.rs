@@ -0,0 +1,608 @@
+//! Resolution of localized values following the
+//! [Localized values for keys](https://specifications.freedesktop.org/desktop-entry-spec/latest/localized-keys.html)
+//! section of the specification.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+};
+
+use crate::{keys::Key, DesktopEntry, DesktopFile, EntryType, LocaleString, LocaleStringList};
+
+/// The parts of a locale of the form `lang_COUNTRY.ENCODING@MODIFIER`, with the case of the
+/// language and the modifier lowered and the country uppercased
+struct LocaleParts<'a> {
+    lang: String,
+    country: Option<String>,
+    encoding: Option<&'a str>,
+    modifier: Option<String>,
+}
+
+impl<'a> LocaleParts<'a> {
+    fn new(locale: &'a str) -> Self {
+        let (rest, modifier) = match locale.split_once('@') {
+            Some((rest, modifier)) => (rest, Some(modifier)),
+            None => (locale, None),
+        };
+        let (rest, encoding) = match rest.split_once('.') {
+            Some((rest, encoding)) => (rest, Some(encoding)),
+            None => (rest, None),
+        };
+        let (lang, country) = match rest.split_once('_') {
+            Some((lang, country)) => (lang, Some(country)),
+            None => (rest, None),
+        };
+
+        Self {
+            lang: lang.to_lowercase(),
+            country: country.map(str::to_uppercase),
+            encoding,
+            modifier: modifier.map(str::to_lowercase),
+        }
+    }
+}
+
+/// Returns the canonical casing of a locale: the language and the modifier lowercase and the
+/// country uppercase, so `pt_br` becomes `pt_BR`. The parser stores every locale this way, so
+/// variants that only differ by case are the same variant.
+pub fn canonicalize_locale(locale: &str) -> String {
+    let parts = LocaleParts::new(locale);
+    let mut res = parts.lang;
+    if let Some(country) = parts.country {
+        res.push('_');
+        res.push_str(&country);
+    }
+    if let Some(encoding) = parts.encoding {
+        res.push('.');
+        res.push_str(encoding);
+    }
+    if let Some(modifier) = parts.modifier {
+        res.push('@');
+        res.push_str(&modifier);
+    }
+    res
+}
+
+/// Returns the keys to look up for a locale of the form `lang_COUNTRY.ENCODING@MODIFIER`, most
+/// specific first and in canonical casing. The encoding is ignored, as the specification
+/// requires.
+pub fn locale_candidates(locale: &str) -> Vec<String> {
+    let LocaleParts {
+        lang,
+        country,
+        modifier,
+        ..
+    } = LocaleParts::new(locale);
+
+    let mut candidates = vec![];
+    if let (Some(country), Some(modifier)) = (&country, &modifier) {
+        candidates.push(format!("{lang}_{country}@{modifier}"));
+    }
+    if let Some(country) = country {
+        candidates.push(format!("{lang}_{country}"));
+    }
+    if let Some(modifier) = modifier {
+        candidates.push(format!("{lang}@{modifier}"));
+    }
+    candidates.push(lang);
+    candidates
+}
+
+/// Normalizes the locale of a variant by dropping its encoding, which the specification says
+/// to ignore, and canonicalizing its case, so `de_de.UTF-8@Euro` becomes `de_DE@euro`
+pub fn normalize_locale(locale: &str) -> String {
+    let (rest, modifier) = match locale.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (locale, None),
+    };
+    let rest = rest.split_once('.').map_or(rest, |(rest, _)| rest);
+    match modifier {
+        Some(modifier) => canonicalize_locale(&format!("{rest}@{modifier}")),
+        None => canonicalize_locale(rest),
+    }
+}
+
+/// Finds the first variant matching the locales in order of preference, returning the locale
+/// it was found under
+fn resolve<'a, T>(variants: &'a HashMap<String, T>, locales: &[&str]) -> Option<(&'a str, &'a T)> {
+    locales
+        .iter()
+        .flat_map(|locale| locale_candidates(locale))
+        .find_map(|candidate| variants.get_key_value(&candidate))
+        .map(|(locale, value)| (locale.as_str(), value))
+}
+
+/// Whether a variant can be picked when resolving for one of the locales, or is a more specific
+/// form of one of them, so both `de` and `de_DE` match `de`
+fn locale_matches(variant: &str, locales: &[&str]) -> bool {
+    let variant = locale_candidates(variant);
+    locales.iter().any(|locale| {
+        let candidates = locale_candidates(locale);
+        candidates.contains(&variant[0]) || variant.contains(&candidates[0])
+    })
+}
+
+/// Whether a list of locales keeps every variant, which an empty list and `*` do
+fn keeps_all(locales: &[&str]) -> bool {
+    locales.is_empty() || locales.contains(&"*")
+}
+
+/// Drops the variants that don't match the locales, returning how many were dropped
+fn retain<T>(variants: &mut HashMap<String, T>, locales: &[&str]) -> usize {
+    if keeps_all(locales) {
+        return 0;
+    }
+
+    let len = variants.len();
+    variants.retain(|variant, _| locale_matches(variant, locales));
+    variants.shrink_to_fit();
+    len - variants.len()
+}
+
+/// Drops the localized extension keys, like `X-GNOME-FullName[de]`, whose locale doesn't
+/// match the locales
+fn retain_extras(extras: &mut HashMap<String, String>, locales: &[&str]) -> usize {
+    if keeps_all(locales) {
+        return 0;
+    }
+
+    let len = extras.len();
+    extras.retain(|key, _| {
+        let locale = key
+            .split_once('[')
+            .and_then(|(_, locale)| locale.strip_suffix(']'));
+        locale.is_none_or(|locale| locale_matches(locale, locales))
+    });
+    extras.shrink_to_fit();
+    len - extras.len()
+}
+
+/// How [`LocaleString::merge`] treats the locales present on both sides
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// The variants of the other side replace the existing ones
+    #[default]
+    Override,
+    /// Only the locales missing from this side are taken from the other one
+    FillMissing,
+}
+
+/// Copies the variants of `other` into `variants` following the strategy. A variant counts as
+/// present even when its value is empty.
+fn merge<T: Clone>(
+    variants: &mut HashMap<String, T>,
+    other: &HashMap<String, T>,
+    strategy: MergeStrategy,
+) {
+    for (locale, value) in other {
+        match strategy {
+            MergeStrategy::Override => {
+                variants.insert(locale.clone(), value.clone());
+            }
+            MergeStrategy::FillMissing => {
+                variants
+                    .entry(locale.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+    }
+}
+
+/// The locale [`LocaleString::promote`] keeps the former default value under
+pub const FORMER_DEFAULT_LOCALE: &str = "en";
+
+/// Makes the variant best matching the locale the default value, keeping the former default
+/// as the [`FORMER_DEFAULT_LOCALE`] variant unless that variant exists. Returns whether a
+/// variant matched.
+fn promote<T: Clone + PartialEq>(
+    default: &mut T,
+    variants: &mut HashMap<String, T>,
+    locale: &str,
+) -> bool {
+    let Some((_, value)) = resolve(variants, &[locale]) else {
+        return false;
+    };
+    // promoting again changes nothing
+    if value == default {
+        return true;
+    }
+
+    let former = std::mem::replace(default, value.clone());
+    variants
+        .entry(FORMER_DEFAULT_LOCALE.to_string())
+        .or_insert(former);
+    true
+}
+
+/// A key that can have locale variants, either in the Desktop Entry group or in an action
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum LocalizedKey {
+    Entry(Key),
+    /// The Name key of the action with the given id
+    ActionName(String),
+}
+
+impl fmt::Display for LocalizedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Entry(key) => write!(f, "{key}"),
+            Self::ActionName(id) => write!(f, "Name of action {id}"),
+        }
+    }
+}
+
+impl LocaleString {
+    /// Merges the variants of another string into this one, such as freshly translated ones,
+    /// leaving the default value and the locales only present here untouched
+    pub fn merge(&mut self, other: &LocaleString, strategy: MergeStrategy) {
+        merge(&mut self.variants, &other.variants, strategy);
+    }
+
+    /// Makes the variant best matching the locale, found like [`LocaleString::resolve`] does,
+    /// the default value. The former default is kept as the [`FORMER_DEFAULT_LOCALE`] variant
+    /// unless that variant exists, and the promoted variant stays so lookups for its locale
+    /// don't change. Returns false, changing nothing, if no variant matches.
+    pub fn promote(&mut self, locale: &str) -> bool {
+        promote(&mut self.default, &mut self.variants, locale)
+    }
+
+    /// Drops the variants that no lookup for the locales can pick and that aren't more
+    /// specific forms of them, so `de` keeps `de` and `de_DE` while `de_AT` keeps `de_AT` and
+    /// `de`. The default value stays. An empty list or one containing `*` keeps everything.
+    /// Returns the number of variants dropped.
+    pub fn retain_locales(&mut self, locales: &[&str]) -> usize {
+        retain(&mut self.variants, locales)
+    }
+
+    /// Resolves the value for the locales in order of preference, falling back to the default
+    /// value. Also returns the locale the value was found under, None for the default value.
+    pub fn resolve(&self, locales: &[&str]) -> (&str, Option<&str>) {
+        match resolve(&self.variants, locales) {
+            Some((locale, value)) => (value, Some(locale)),
+            None => (&self.default, None),
+        }
+    }
+}
+
+impl LocaleStringList {
+    /// Merges the variants of another list into this one like [`LocaleString::merge`]. Each
+    /// variant is a whole list: a variant that is taken replaces the list of its locale rather
+    /// than adding items to it.
+    pub fn merge(&mut self, other: &LocaleStringList, strategy: MergeStrategy) {
+        merge(&mut self.variants, &other.variants, strategy);
+    }
+
+    /// Makes the variant best matching the locale the default list, like
+    /// [`LocaleString::promote`]
+    pub fn promote(&mut self, locale: &str) -> bool {
+        promote(&mut self.default, &mut self.variants, locale)
+    }
+
+    /// Drops the variants that don't match the locales, like [`LocaleString::retain_locales`]
+    pub fn retain_locales(&mut self, locales: &[&str]) -> usize {
+        retain(&mut self.variants, locales)
+    }
+
+    /// Resolves the list for the locales in order of preference, falling back to the default
+    /// list. Also returns the locale the list was found under, None for the default list.
+    pub fn resolve(&self, locales: &[&str]) -> (&[String], Option<&str>) {
+        match resolve(&self.variants, locales) {
+            Some((locale, value)) => (value, Some(locale)),
+            None => (&self.default, None),
+        }
+    }
+}
+
+/// An action with its name resolved for a list of locales
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalizedAction {
+    pub name: String,
+    /// The locale the name was found under, None for the default value
+    pub name_locale: Option<String>,
+}
+
+/// The localized keys of a file resolved for a list of locales
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalizedEntry {
+    pub name: String,
+    pub generic_name: Option<String>,
+    pub comment: Option<String>,
+    pub keywords: Vec<String>,
+    /// The actions by id, empty when localizing a [`DesktopEntry`] alone
+    pub actions: HashMap<String, LocalizedAction>,
+    /// The locale each key was found under. Keys that resolved to their default value are
+    /// absent.
+    pub resolved_locales: HashMap<Key, String>,
+}
+
+impl LocalizedEntry {
+    fn string(&mut self, key: Key, value: &LocaleString, locales: &[&str]) -> String {
+        let (value, locale) = value.resolve(locales);
+        if let Some(locale) = locale {
+            self.resolved_locales.insert(key, locale.to_string());
+        }
+        value.to_string()
+    }
+}
+
+impl DesktopEntry {
+    /// Makes the variants of the locale the default values of every localized key, see
+    /// [`LocaleString::promote`]. Returns the keys present without a variant for the locale,
+    /// which are left as they are.
+    pub fn set_default_locale(&mut self, locale: &str) -> Vec<LocalizedKey> {
+        let mut missing = vec![];
+
+        if !self.name.promote(locale) {
+            missing.push(LocalizedKey::Entry(Key::Name));
+        }
+        for (key, string) in [
+            (Key::GenericName, self.generic_name.as_mut()),
+            (Key::Comment, self.comment.as_mut()),
+        ] {
+            if string.is_some_and(|s| !s.promote(locale)) {
+                missing.push(LocalizedKey::Entry(key));
+            }
+        }
+        if let EntryType::Application(ref mut fields) = self.entry_type {
+            if fields.keywords.as_mut().is_some_and(|k| !k.promote(locale)) {
+                missing.push(LocalizedKey::Entry(Key::Keywords));
+            }
+        }
+
+        missing
+    }
+
+    /// Drops the variants of every localized key, including localized extension keys, that
+    /// don't match the locales, see [`LocaleString::retain_locales`]. Returns the number of
+    /// variants dropped.
+    pub fn retain_locales(&mut self, locales: &[&str]) -> usize {
+        let mut dropped = self.name.retain_locales(locales);
+        for string in [self.generic_name.as_mut(), self.comment.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            dropped += string.retain_locales(locales);
+        }
+        if let EntryType::Application(ref mut fields) = self.entry_type {
+            if let Some(ref mut keywords) = fields.keywords {
+                dropped += keywords.retain_locales(locales);
+            }
+        }
+        dropped + retain_extras(&mut self.extras, locales)
+    }
+
+    /// Resolves every localized key for the locales in order of preference, such as
+    /// `&["de_AT", "de"]`
+    pub fn localize(&self, locales: &[&str]) -> LocalizedEntry {
+        let mut res = LocalizedEntry::default();
+
+        res.name = res.string(Key::Name, &self.name, locales);
+        res.generic_name = self
+            .generic_name
+            .as_ref()
+            .map(|v| res.string(Key::GenericName, v, locales));
+        res.comment = self
+            .comment
+            .as_ref()
+            .map(|v| res.string(Key::Comment, v, locales));
+
+        if let EntryType::Application(ref fields) = self.entry_type {
+            if let Some(ref keywords) = fields.keywords {
+                let (value, locale) = keywords.resolve(locales);
+                if let Some(locale) = locale {
+                    res.resolved_locales
+                        .insert(Key::Keywords, locale.to_string());
+                }
+                res.keywords = value.to_vec();
+            }
+        }
+
+        res
+    }
+}
+
+impl DesktopFile {
+    /// Returns the localizable keys present in the file together with the locales of their
+    /// variants, as written
+    pub(crate) fn localized_keys(&self) -> Vec<(LocalizedKey, Vec<&str>)> {
+        fn locales<T>(variants: &HashMap<String, T>) -> Vec<&str> {
+            variants.keys().map(String::as_str).collect()
+        }
+
+        let entry = &self.entry;
+        let mut res = vec![(
+            LocalizedKey::Entry(Key::Name),
+            locales(&entry.name.variants),
+        )];
+        if let Some(ref v) = entry.generic_name {
+            res.push((LocalizedKey::Entry(Key::GenericName), locales(&v.variants)));
+        }
+        if let Some(ref v) = entry.comment {
+            res.push((LocalizedKey::Entry(Key::Comment), locales(&v.variants)));
+        }
+        if let EntryType::Application(ref fields) = entry.entry_type {
+            if let Some(ref v) = fields.keywords {
+                res.push((LocalizedKey::Entry(Key::Keywords), locales(&v.variants)));
+            }
+        }
+
+        let mut actions = self.actions.iter().collect::<Vec<_>>();
+        actions.sort_by_key(|(id, _)| *id);
+        for (id, action) in actions {
+            res.push((
+                LocalizedKey::ActionName(id.clone()),
+                locales(&action.name.variants),
+            ));
+        }
+        res
+    }
+
+    /// Returns every locale the file has a variant for, normalized with [`normalize_locale`]
+    pub fn available_locales(&self) -> BTreeSet<String> {
+        self.localized_keys()
+            .into_iter()
+            .flat_map(|(_, locales)| locales)
+            .map(normalize_locale)
+            .collect()
+    }
+
+    /// Returns the normalized locales of the variants of every localizable key present in the
+    /// file
+    pub fn available_locales_by_key(&self) -> BTreeMap<LocalizedKey, BTreeSet<String>> {
+        self.localized_keys()
+            .into_iter()
+            .map(|(key, locales)| (key, locales.into_iter().map(normalize_locale).collect()))
+            .collect()
+    }
+
+    /// Makes the variants of the locale the default values of every localized key of the
+    /// entry and of the action names, like [`DesktopEntry::set_default_locale`]
+    pub fn set_default_locale(&mut self, locale: &str) -> Vec<LocalizedKey> {
+        let mut missing = self.entry.set_default_locale(locale);
+
+        let mut actions = self.actions.iter_mut().collect::<Vec<_>>();
+        actions.sort_by(|a, b| a.0.cmp(b.0));
+        for (id, action) in actions {
+            if !action.name.promote(locale) {
+                missing.push(LocalizedKey::ActionName(id.clone()));
+            }
+        }
+        missing
+    }
+
+    /// Drops the variants that don't match the locales from the entry and the actions, listed
+    /// or not, like [`DesktopEntry::retain_locales`], to keep only the translations a launcher
+    /// displays. Returns the number of variants dropped.
+    pub fn retain_locales(&mut self, locales: &[&str]) -> usize {
+        let mut dropped = self.entry.retain_locales(locales);
+        for action in self
+            .actions
+            .values_mut()
+            .chain(self.unlisted_actions.values_mut())
+        {
+            dropped += action.name.retain_locales(locales);
+            dropped += retain_extras(&mut action.extras, locales);
+        }
+        dropped
+    }
+
+    /// Resolves every localized key of the entry and of the actions for the locales in order
+    /// of preference
+    pub fn localize(&self, locales: &[&str]) -> LocalizedEntry {
+        let mut res = self.entry.localize(locales);
+        res.actions = self
+            .actions
+            .iter()
+            .map(|(id, action)| {
+                let (name, locale) = action.name.resolve(locales);
+                let action = LocalizedAction {
+                    name: name.to_string(),
+                    name_locale: locale.map(|l| l.to_string()),
+                };
+                (id.clone(), action)
+            })
+            .collect();
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localize() {
+        assert_eq!(
+            locale_candidates("sr_yu.UTF-8@Latn"),
+            ["sr_YU@latn", "sr_YU", "sr@latn", "sr"]
+        );
+        assert_eq!(canonicalize_locale("PT_br.utf-8@Euro"), "pt_BR.utf-8@euro");
+
+        let file = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Editor\nName[de]=Bearbeiter\nName[de_AT]=Editor AT\nComment=Edit\nKeywords=text;\nKeywords[de]=Text;\nActions=new;\n\n[Desktop Action new]\nName=New\nName[de]=Neu\n",
+        )
+        .unwrap();
+
+        let localized = file.localize(&["de_AT.UTF-8", "de"]);
+        assert_eq!(localized.name, "Editor AT");
+        assert_eq!(localized.comment.as_deref(), Some("Edit"));
+        assert_eq!(localized.keywords, ["Text"]);
+        assert_eq!(localized.actions["new"].name, "Neu");
+        assert_eq!(localized.resolved_locales[&Key::Name], "de_AT");
+        assert_eq!(localized.resolved_locales[&Key::Keywords], "de");
+        assert!(!localized.resolved_locales.contains_key(&Key::Comment));
+
+        assert_eq!(file.entry.localize(&["fr"]).name, "Editor");
+
+        assert_eq!(normalize_locale("de_de.UTF-8@Euro"), "de_DE@euro");
+        assert_eq!(
+            file.available_locales().into_iter().collect::<Vec<_>>(),
+            ["de", "de_AT"]
+        );
+        let by_key = file.available_locales_by_key();
+        assert_eq!(by_key[&LocalizedKey::Entry(Key::Name)].len(), 2);
+        assert!(by_key[&LocalizedKey::Entry(Key::Comment)].is_empty());
+        assert!(by_key[&LocalizedKey::ActionName("new".into())].contains("de"));
+    }
+
+    #[test]
+    fn test_set_default_locale() {
+        let mut file = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Files\nName[de]=Dateien\nComment=Browse files\nKeywords=folder;\nKeywords[de]=Ordner;\nExec=files\nActions=new;\n\n[Desktop Action new]\nName=New Window\n",
+        )
+        .unwrap();
+
+        let missing = file.set_default_locale("de_DE.UTF-8");
+        assert_eq!(
+            missing,
+            [
+                LocalizedKey::Entry(Key::Comment),
+                LocalizedKey::ActionName("new".into())
+            ]
+        );
+        assert_eq!(file.entry.name.default, "Dateien");
+        assert_eq!(file.entry.name.variants["en"], "Files");
+        assert_eq!(file.entry.name.variants["de"], "Dateien");
+
+        let serialized = file.to_desktop_string();
+        assert!(
+            serialized.contains("Keywords=Ordner;\nKeywords[de]=Ordner;\nKeywords[en]=folder;\n")
+        );
+        let mut reparsed = crate::parse(&serialized).unwrap();
+        reparsed.set_default_locale("de_DE.UTF-8");
+        assert_eq!(reparsed.to_desktop_string(), serialized);
+    }
+
+    #[test]
+    fn test_retain_locales() {
+        let mut file = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Editor\nName[de]=Editor DE\nName[de_AT]=Editor AT\nName[de_CH]=Editor CH\nName[fr]=Éditeur\nName[sr@latin]=Urednik\nComment=Edit\nComment[fr]=Éditer\nKeywords=text;\nKeywords[de_DE]=Text;\nExec=editor\nX-GNOME-FullName[fr]=Éditeur GNOME\nActions=new;\n\n[Desktop Action new]\nName=New\nName[de]=Neu\nName[ja]=新規\n",
+        )
+        .unwrap();
+        let original = file.to_desktop_string();
+
+        assert_eq!(file.retain_locales(&[]), 0);
+        assert_eq!(file.retain_locales(&["de", "*"]), 0);
+        assert_eq!(file.to_desktop_string(), original);
+
+        let mut de_at = file.clone();
+        assert_eq!(de_at.retain_locales(&["de_AT"]), 7);
+        let mut locales = de_at.entry.name.variants.keys().collect::<Vec<_>>();
+        locales.sort();
+        assert_eq!(locales, ["de", "de_AT"]);
+
+        assert_eq!(file.retain_locales(&["de"]), 5);
+        let mut locales = file.entry.name.variants.keys().collect::<Vec<_>>();
+        locales.sort();
+        assert_eq!(locales, ["de", "de_AT", "de_CH"]);
+        assert_eq!(file.entry.comment.as_ref().unwrap().default, "Edit");
+        assert!(file.entry.extras.is_empty());
+        let fields = file.entry.as_application().unwrap();
+        assert_eq!(
+            fields.keywords.as_ref().unwrap().variants["de_DE"],
+            ["Text"]
+        );
+        assert_eq!(file.actions["new"].name.variants.len(), 1);
+    }
+}
@@ -0,0 +1,732 @@
+//! Construction of application menus according to the
+//! [Desktop Menu Specification](https://specifications.freedesktop.org/menu-spec/latest/).
+//!
+//! A `.menu` file is parsed into a [`Menu`], which is then resolved against parsed
+//! application entries and `.directory` entries into a tree of [`MenuNode`]s.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::{DesktopEntry, DesktopFile, EntryType};
+
+/// Nesting limit of MergeFile and MergeDir, which protects against cyclic merges.
+const MAX_MERGE_DEPTH: usize = 16;
+
+#[derive(Debug, Error)]
+pub enum MenuError {
+    #[error("Menu Error: invalid XML: {0}")]
+    Xml(String),
+    #[error("Menu Error: the root element must be <Menu>, found <{0}>")]
+    InvalidRoot(String),
+    #[error("Menu Error: a <Menu> is missing its <Name>")]
+    MissingName,
+    #[error("Menu Error: merging {path:?} failed: {source}")]
+    Merge { path: PathBuf, source: io::Error },
+}
+
+/// A matching rule of an `<Include>` or `<Exclude>` element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// Matches entries having the category
+    Category(String),
+    /// Matches the entry with the desktop file id
+    Filename(String),
+    /// Matches every entry
+    All,
+    /// Matches if all the rules match
+    And(Vec<Rule>),
+    /// Matches if any of the rules matches
+    Or(Vec<Rule>),
+    /// Matches if none of the rules matches
+    Not(Vec<Rule>),
+}
+
+impl Rule {
+    /// Returns whether the entry with the given id matches the rule
+    pub fn matches(&self, id: &str, entry: &DesktopEntry) -> bool {
+        match self {
+            Self::Category(category) => match entry.entry_type {
+                EntryType::Application(ref fields) => fields
+                    .categories
+                    .as_ref()
+                    .is_some_and(|c| c.iter().any(|c| c == category)),
+                _ => false,
+            },
+            Self::Filename(filename) => filename == id,
+            Self::All => true,
+            Self::And(rules) => rules.iter().all(|r| r.matches(id, entry)),
+            Self::Or(rules) => rules.iter().any(|r| r.matches(id, entry)),
+            Self::Not(rules) => !rules.iter().any(|r| r.matches(id, entry)),
+        }
+    }
+}
+
+/// An `<Include>` or `<Exclude>` element, applied in document order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// Adds the entries matching any of the rules
+    Include(Vec<Rule>),
+    /// Removes the entries matching any of the rules
+    Exclude(Vec<Rule>),
+}
+
+/// An element of a `<Layout>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutItem {
+    /// A submenu by name
+    Menuname(String),
+    /// An entry by desktop file id
+    Filename(String),
+    /// A separator
+    Separator,
+    /// The remaining submenus, sorted by name
+    MergeMenus,
+    /// The remaining entries, sorted by name
+    MergeFiles,
+    /// The remaining submenus and entries, sorted together by name
+    MergeAll,
+}
+
+/// The default layout of the specification: submenus first, then entries.
+const DEFAULT_LAYOUT: &[LayoutItem] = &[LayoutItem::MergeMenus, LayoutItem::MergeFiles];
+
+/// A `<Menu>` element of a `.menu` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Menu {
+    /// The name of the menu
+    pub name: String,
+    /// The `.directory` files providing the menu's metadata, the last one taking precedence
+    pub directories: Vec<String>,
+    /// Directories to take applications from
+    pub app_dirs: Vec<PathBuf>,
+    /// Directories to take `.directory` files from
+    pub directory_dirs: Vec<PathBuf>,
+    /// Whether `<DefaultAppDirs/>` was given
+    pub default_app_dirs: bool,
+    /// Whether `<DefaultDirectoryDirs/>` was given
+    pub default_directory_dirs: bool,
+    /// Include and Exclude elements in document order
+    pub selections: Vec<Selection>,
+    /// Whether the menu only takes entries no other menu took, the last element wins
+    pub only_unallocated: Option<bool>,
+    /// Whether the menu is deleted, the last element wins
+    pub deleted: Option<bool>,
+    /// The layout of the menu, if it has one
+    pub layout: Option<Vec<LayoutItem>>,
+    /// The submenus
+    pub submenus: Vec<Menu>,
+}
+
+impl Menu {
+    /// Parses the content of a `.menu` file. `<MergeFile>` and `<MergeDir>` are ignored since
+    /// there is no file to resolve them against, use [`Menu::load`] for them.
+    pub fn parse(xml: &str) -> Result<Self, MenuError> {
+        Self::parse_with_base(xml, None, 0)
+    }
+
+    /// Reads and parses a `.menu` file, merging the files referenced by `<MergeFile>` and
+    /// `<MergeDir>` relative to its location.
+    pub fn load(path: &Path) -> Result<Self, MenuError> {
+        Self::load_at_depth(path, 0)
+    }
+
+    fn load_at_depth(path: &Path, depth: usize) -> Result<Self, MenuError> {
+        let content = std::fs::read_to_string(path).map_err(|source| MenuError::Merge {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse_with_base(&content, path.parent(), depth)
+    }
+
+    fn parse_with_base(xml: &str, base: Option<&Path>, depth: usize) -> Result<Self, MenuError> {
+        let doc = parse_document(xml).map_err(|e| MenuError::Xml(e.to_string()))?;
+        let root = doc.root_element();
+        if root.tag_name().name() != "Menu" {
+            return Err(MenuError::InvalidRoot(root.tag_name().name().into()));
+        }
+
+        let mut menu = Menu::default();
+        fill_menu(&mut menu, root, base, depth)?;
+        if menu.name.is_empty() {
+            return Err(MenuError::MissingName);
+        }
+
+        menu.merge_duplicate_submenus();
+        Ok(menu)
+    }
+
+    /// Merges submenus sharing a name into the first of them, recursively
+    fn merge_duplicate_submenus(&mut self) {
+        let mut merged: Vec<Menu> = vec![];
+
+        for submenu in std::mem::take(&mut self.submenus) {
+            match merged.iter_mut().find(|m| m.name == submenu.name) {
+                Some(existing) => existing.append(submenu),
+                None => merged.push(submenu),
+            }
+        }
+
+        for submenu in merged.iter_mut() {
+            submenu.merge_duplicate_submenus();
+        }
+
+        self.submenus = merged;
+    }
+
+    /// Appends the content of another menu, its values taking precedence
+    fn append(&mut self, other: Menu) {
+        self.directories.extend(other.directories);
+        self.app_dirs.extend(other.app_dirs);
+        self.directory_dirs.extend(other.directory_dirs);
+        self.default_app_dirs |= other.default_app_dirs;
+        self.default_directory_dirs |= other.default_directory_dirs;
+        self.selections.extend(other.selections);
+        self.only_unallocated = other.only_unallocated.or(self.only_unallocated);
+        self.deleted = other.deleted.or(self.deleted);
+        self.layout = other.layout.or(self.layout.take());
+        self.submenus.extend(other.submenus);
+    }
+
+    /// Builds the menu tree from the parsed applications, keyed by desktop file id, and the
+    /// parsed `.directory` files, keyed by file name.
+    ///
+    /// Entries with NoDisplay or Hidden set are never shown, deleted menus and menus without
+    /// any entry are left out.
+    pub fn resolve(
+        &self,
+        applications: &HashMap<String, DesktopFile>,
+        directories: &HashMap<String, DesktopFile>,
+    ) -> MenuNode {
+        let mut ids = applications.keys().collect::<Vec<_>>();
+        ids.sort();
+
+        let visible = ids
+            .into_iter()
+            .filter(|id| {
+                let entry = &applications[*id].entry;
+                entry.no_display != Some(true) && entry.hidden != Some(true)
+            })
+            .map(|id| (id.as_str(), &applications[id].entry))
+            .collect::<Vec<_>>();
+
+        // first pass: every menu takes its entries, then the OnlyUnallocated menus take the
+        // entries no other menu took
+        let mut allocated = HashSet::new();
+        let mut node = self.select(&visible, &mut allocated, false);
+        self.select_unallocated(&mut node, &visible, &allocated);
+
+        node.finish(self, applications, directories)
+            .unwrap_or_else(|| MenuNode {
+                name: self.name.clone(),
+                ..Default::default()
+            })
+    }
+
+    fn select<'a>(
+        &self,
+        visible: &[(&'a str, &'a DesktopEntry)],
+        allocated: &mut HashSet<&'a str>,
+        parent_unallocated: bool,
+    ) -> MenuNode {
+        let unallocated = self.only_unallocated.unwrap_or(false) || parent_unallocated;
+        let mut entries = vec![];
+
+        if !unallocated {
+            entries = apply_selections(&self.selections, visible);
+            allocated.extend(entries.iter().copied());
+        }
+
+        MenuNode {
+            name: self.name.clone(),
+            directory: None,
+            entries: entries.into_iter().map(|id| id.to_string()).collect(),
+            children: self
+                .submenus
+                .iter()
+                .map(|m| m.select(visible, allocated, false))
+                .collect(),
+            layout: vec![],
+        }
+    }
+
+    fn select_unallocated(
+        &self,
+        node: &mut MenuNode,
+        visible: &[(&str, &DesktopEntry)],
+        allocated: &HashSet<&str>,
+    ) {
+        if self.only_unallocated.unwrap_or(false) {
+            let pool = visible
+                .iter()
+                .filter(|(id, _)| !allocated.contains(id))
+                .copied()
+                .collect::<Vec<_>>();
+            node.entries = apply_selections(&self.selections, &pool)
+                .into_iter()
+                .map(|id| id.to_string())
+                .collect();
+        }
+
+        for (submenu, child) in self.submenus.iter().zip(node.children.iter_mut()) {
+            submenu.select_unallocated(child, visible, allocated);
+        }
+    }
+}
+
+/// Applies Include and Exclude elements in order to the pool of entries
+fn apply_selections<'a>(
+    selections: &[Selection],
+    pool: &[(&'a str, &'a DesktopEntry)],
+) -> Vec<&'a str> {
+    let mut selected: Vec<&str> = vec![];
+
+    for selection in selections {
+        match selection {
+            Selection::Include(rules) => {
+                for (id, entry) in pool {
+                    if !selected.contains(id) && rules.iter().any(|r| r.matches(id, entry)) {
+                        selected.push(id);
+                    }
+                }
+            }
+            Selection::Exclude(rules) => {
+                selected.retain(|id| {
+                    let entry = pool.iter().find(|(i, _)| i == id).map(|(_, e)| *e);
+                    !entry.is_some_and(|entry| rules.iter().any(|r| r.matches(id, entry)))
+                });
+            }
+        }
+    }
+
+    selected
+}
+
+/// An item of a resolved menu, in display order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuItem {
+    /// An application, by desktop file id
+    Entry(String),
+    /// A submenu, by name
+    Menu(String),
+    /// A separator
+    Separator,
+}
+
+/// A resolved menu.
+#[derive(Debug, Clone, Default)]
+pub struct MenuNode {
+    /// The name of the menu as given in the `.menu` file
+    pub name: String,
+    /// The entry of the `.directory` file describing the menu, if one was found
+    pub directory: Option<DesktopEntry>,
+    /// The desktop file ids of the applications in the menu
+    pub entries: Vec<String>,
+    /// The submenus
+    pub children: Vec<MenuNode>,
+    /// The entries, submenus and separators in display order
+    pub layout: Vec<MenuItem>,
+}
+
+impl MenuNode {
+    /// Returns the name to display, taken from the `.directory` file if there is one
+    pub fn display_name(&self) -> &str {
+        match self.directory {
+            Some(ref directory) => &directory.name.default,
+            None => &self.name,
+        }
+    }
+
+    /// Resolves the directory and the layout, returning None if the menu is not shown
+    fn finish(
+        mut self,
+        menu: &Menu,
+        applications: &HashMap<String, DesktopFile>,
+        directories: &HashMap<String, DesktopFile>,
+    ) -> Option<Self> {
+        if menu.deleted.unwrap_or(false) {
+            return None;
+        }
+
+        self.directory = menu
+            .directories
+            .iter()
+            .rev()
+            .find_map(|name| directories.get(name))
+            .map(|file| file.entry.clone());
+
+        if self
+            .directory
+            .as_ref()
+            .is_some_and(|d| d.no_display == Some(true) || d.hidden == Some(true))
+        {
+            return None;
+        }
+
+        self.children = std::mem::take(&mut self.children)
+            .into_iter()
+            .zip(menu.submenus.iter())
+            .filter_map(|(child, submenu)| child.finish(submenu, applications, directories))
+            .collect();
+
+        if self.entries.is_empty() && self.children.is_empty() {
+            return None;
+        }
+
+        let entry_name = |id: &str| {
+            applications
+                .get(id)
+                .map(|f| f.entry.name.default.clone())
+                .unwrap_or_default()
+        };
+
+        let layout = menu.layout.as_deref().unwrap_or(DEFAULT_LAYOUT);
+        let mut used_menus = HashSet::new();
+        let mut used_entries = HashSet::new();
+
+        // explicitly named items are taken out of the merged remainder
+        for item in layout {
+            match item {
+                LayoutItem::Menuname(name) => {
+                    used_menus.insert(name.as_str());
+                }
+                LayoutItem::Filename(id) => {
+                    used_entries.insert(id.as_str());
+                }
+                _ => {}
+            }
+        }
+
+        let mut menus = self
+            .children
+            .iter()
+            .filter(|c| !used_menus.contains(c.name.as_str()))
+            .map(|c| {
+                (
+                    c.display_name().to_lowercase(),
+                    MenuItem::Menu(c.name.clone()),
+                )
+            })
+            .collect::<Vec<_>>();
+        menus.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut files = self
+            .entries
+            .iter()
+            .filter(|id| !used_entries.contains(id.as_str()))
+            .map(|id| (entry_name(id).to_lowercase(), MenuItem::Entry(id.clone())))
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut result = vec![];
+        for item in layout {
+            match item {
+                LayoutItem::Menuname(name) => {
+                    if self.children.iter().any(|c| &c.name == name) {
+                        result.push(MenuItem::Menu(name.clone()));
+                    }
+                }
+                LayoutItem::Filename(id) => {
+                    if self.entries.contains(id) {
+                        result.push(MenuItem::Entry(id.clone()));
+                    }
+                }
+                LayoutItem::Separator => result.push(MenuItem::Separator),
+                LayoutItem::MergeMenus => {
+                    result.extend(menus.drain(..).map(|(_, item)| item));
+                }
+                LayoutItem::MergeFiles => {
+                    result.extend(files.drain(..).map(|(_, item)| item));
+                }
+                LayoutItem::MergeAll => {
+                    let mut all = menus.drain(..).chain(files.drain(..)).collect::<Vec<_>>();
+                    all.sort_by(|a, b| a.0.cmp(&b.0));
+                    result.extend(all.into_iter().map(|(_, item)| item));
+                }
+            }
+        }
+
+        self.layout = result;
+        Some(self)
+    }
+}
+
+/// Reads every `.directory` file of the given directories, keyed by file name. Earlier
+/// directories take precedence, and files that can't be read or parsed are skipped.
+pub fn load_directories(dirs: &[PathBuf]) -> HashMap<String, DesktopFile> {
+    let mut result = HashMap::new();
+
+    for dir in dirs {
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            continue;
+        };
+
+        for path in read_dir.filter_map(|e| e.ok().map(|e| e.path())) {
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !name.ends_with(".directory") || result.contains_key(name) {
+                continue;
+            }
+
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if let Ok(file) = crate::parse(&content) {
+                result.insert(name.to_string(), file);
+            }
+        }
+    }
+
+    result
+}
+
+/// Parses an XML document, allowing the DOCTYPE declaration every `.menu` file starts with
+fn parse_document(xml: &str) -> Result<roxmltree::Document<'_>, roxmltree::Error> {
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    roxmltree::Document::parse_with_options(xml, options)
+}
+
+fn text_of(node: roxmltree::Node) -> String {
+    node.text().unwrap_or_default().trim().to_string()
+}
+
+fn resolve_path(text: &str, base: Option<&Path>) -> PathBuf {
+    let path = PathBuf::from(text);
+    match base {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path,
+    }
+}
+
+fn parse_rules(node: roxmltree::Node) -> Vec<Rule> {
+    node.children()
+        .filter(|c| c.is_element())
+        .filter_map(|c| match c.tag_name().name() {
+            "Category" => Some(Rule::Category(text_of(c))),
+            "Filename" => Some(Rule::Filename(text_of(c))),
+            "All" => Some(Rule::All),
+            "And" => Some(Rule::And(parse_rules(c))),
+            "Or" => Some(Rule::Or(parse_rules(c))),
+            "Not" => Some(Rule::Not(parse_rules(c))),
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_layout(node: roxmltree::Node) -> Vec<LayoutItem> {
+    node.children()
+        .filter(|c| c.is_element())
+        .filter_map(|c| match c.tag_name().name() {
+            "Menuname" => Some(LayoutItem::Menuname(text_of(c))),
+            "Filename" => Some(LayoutItem::Filename(text_of(c))),
+            "Separator" => Some(LayoutItem::Separator),
+            "Merge" => match c.attribute("type") {
+                Some("menus") => Some(LayoutItem::MergeMenus),
+                Some("files") => Some(LayoutItem::MergeFiles),
+                Some("all") => Some(LayoutItem::MergeAll),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn merge_file(
+    menu: &mut Menu,
+    path: &Path,
+    base: Option<&Path>,
+    depth: usize,
+) -> Result<(), MenuError> {
+    if depth >= MAX_MERGE_DEPTH {
+        return Ok(());
+    }
+
+    let path = resolve_path(&path.to_string_lossy(), base);
+    let content = std::fs::read_to_string(&path).map_err(|source| MenuError::Merge {
+        path: path.clone(),
+        source,
+    })?;
+    let doc = parse_document(&content).map_err(|e| MenuError::Xml(e.to_string()))?;
+
+    // the children of the merged root replace the merge element, except for its name
+    let mut merged = Menu::default();
+    fill_menu(&mut merged, doc.root_element(), path.parent(), depth + 1)?;
+    merged.name.clear();
+    menu.append(merged);
+    Ok(())
+}
+
+fn fill_menu(
+    menu: &mut Menu,
+    node: roxmltree::Node,
+    base: Option<&Path>,
+    depth: usize,
+) -> Result<(), MenuError> {
+    for child in node.children().filter(|c| c.is_element()) {
+        match child.tag_name().name() {
+            "Name" => menu.name = text_of(child),
+            "Directory" => menu.directories.push(text_of(child)),
+            "AppDir" => menu.app_dirs.push(resolve_path(&text_of(child), base)),
+            "DirectoryDir" => menu
+                .directory_dirs
+                .push(resolve_path(&text_of(child), base)),
+            "DefaultAppDirs" => menu.default_app_dirs = true,
+            "DefaultDirectoryDirs" => menu.default_directory_dirs = true,
+            "Include" => menu.selections.push(Selection::Include(parse_rules(child))),
+            "Exclude" => menu.selections.push(Selection::Exclude(parse_rules(child))),
+            "OnlyUnallocated" => menu.only_unallocated = Some(true),
+            "NotOnlyUnallocated" => menu.only_unallocated = Some(false),
+            "Deleted" => menu.deleted = Some(true),
+            "NotDeleted" => menu.deleted = Some(false),
+            "Layout" => menu.layout = Some(parse_layout(child)),
+            "Menu" => {
+                let mut submenu = Menu::default();
+                fill_menu(&mut submenu, child, base, depth)?;
+                if submenu.name.is_empty() {
+                    return Err(MenuError::MissingName);
+                }
+                menu.submenus.push(submenu);
+            }
+            "MergeFile" if base.is_some() => {
+                merge_file(menu, Path::new(&text_of(child)), base, depth)?;
+            }
+            "MergeDir" if base.is_some() => {
+                let dir = resolve_path(&text_of(child), base);
+                let Ok(read_dir) = std::fs::read_dir(&dir) else {
+                    continue;
+                };
+
+                let mut files = read_dir
+                    .filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| p.extension().is_some_and(|e| e == "menu"))
+                    .collect::<Vec<_>>();
+                files.sort();
+
+                for file in files {
+                    merge_file(menu, &file, base, depth)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const MENU: &str = r#"
+<!DOCTYPE Menu PUBLIC "-//freedesktop//DTD Menu 1.0//EN"
+ "http://www.freedesktop.org/standards/menu-spec/1.0/menu.dtd">
+<Menu>
+  <Name>Applications</Name>
+  <Directory>Applications.directory</Directory>
+  <DefaultAppDirs/>
+  <Menu>
+    <Name>Development</Name>
+    <Directory>Development.directory</Directory>
+    <Include>
+      <And>
+        <Category>Development</Category>
+        <Not><Category>Game</Category></Not>
+      </And>
+    </Include>
+    <Exclude><Filename>ide-hidden.desktop</Filename></Exclude>
+  </Menu>
+  <Menu>
+    <Name>Games</Name>
+    <Include><Category>Game</Category></Include>
+  </Menu>
+  <Menu>
+    <Name>Other</Name>
+    <OnlyUnallocated/>
+    <Include><All/></Include>
+  </Menu>
+  <Menu>
+    <Name>Empty</Name>
+    <Include><Category>Nothing</Category></Include>
+  </Menu>
+  <Menu>
+    <Name>Development</Name>
+    <Layout>
+      <Filename>z-editor.desktop</Filename>
+      <Separator/>
+      <Merge type="files"/>
+    </Layout>
+  </Menu>
+</Menu>
+"#;
+
+    fn app(name: &str, categories: &str) -> DesktopFile {
+        parse(&format!(
+            "[Desktop Entry]\nType=Application\nName={name}\nExec=x\nCategories={categories}"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_menu_tree() {
+        let menu = Menu::parse(MENU).unwrap();
+        assert_eq!(menu.submenus.len(), 4);
+
+        let applications = HashMap::from([
+            ("a-ide.desktop".to_string(), app("IDE", "Development;")),
+            (
+                "z-editor.desktop".to_string(),
+                app("Editor", "Development;"),
+            ),
+            (
+                "ide-hidden.desktop".to_string(),
+                app("Hidden", "Development;"),
+            ),
+            ("game.desktop".to_string(), app("Game", "Game;Development;")),
+            ("calc.desktop".to_string(), app("Calculator", "Utility;")),
+        ]);
+        let directories = HashMap::from([(
+            "Development.directory".to_string(),
+            parse("[Desktop Entry]\nType=Directory\nName=Programming").unwrap(),
+        )]);
+
+        let root = menu.resolve(&applications, &directories);
+        let names = root
+            .children
+            .iter()
+            .map(|c| c.display_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Programming", "Games", "Other"]);
+
+        let development = &root.children[0];
+        assert_eq!(
+            development.layout,
+            vec![
+                MenuItem::Entry("z-editor.desktop".into()),
+                MenuItem::Separator,
+                MenuItem::Entry("a-ide.desktop".into()),
+            ]
+        );
+        assert_eq!(root.children[1].entries, vec!["game.desktop"]);
+        assert_eq!(
+            root.children[2].entries,
+            vec!["calc.desktop", "ide-hidden.desktop"]
+        );
+        assert_eq!(
+            root.layout,
+            vec![
+                MenuItem::Menu("Games".into()),
+                MenuItem::Menu("Other".into()),
+                MenuItem::Menu("Development".into()),
+            ]
+        );
+    }
+}
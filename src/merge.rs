@@ -0,0 +1,266 @@
+//! Merging of layered desktop files, such as a user override in `$XDG_DATA_HOME` laid over
+//! the system entry, keeping track of which layer supplied each value.
+
+use std::collections::BTreeMap;
+
+use crate::{keyfile, locale::MergeStrategy, DesktopFile, EntryType, ParseError};
+
+/// Which layer of a merge supplied each value of the merged file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Provenance {
+    /// The labels of the layers, least important first
+    pub layers: Vec<String>,
+    /// The index in `layers` of the layer each value comes from, by group and by key as
+    /// written, like `Name[de]`
+    pub sources: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+impl Provenance {
+    /// Returns the label of the layer a key of a group comes from
+    pub fn source(&self, group: &str, key: &str) -> Option<&str> {
+        let layer = *self.sources.get(group)?.get(key)?;
+        Some(&self.layers[layer])
+    }
+
+    /// Returns the group and key of every value that comes from a layer above the first, which
+    /// are the values a settings UI would offer to reset to their default
+    pub fn customized_keys(&self) -> Vec<(&str, &str)> {
+        self.sources
+            .iter()
+            .flat_map(|(group, keys)| {
+                keys.iter()
+                    .filter(|(_, layer)| **layer != 0)
+                    .map(move |(key, _)| (group.as_str(), key.as_str()))
+            })
+            .collect()
+    }
+}
+
+/// A drop-in override: a partial desktop file holding only the keys it changes, such as
+/// `NoDisplay=true` or `Name[de]=…` in a `[Desktop Entry]` group. An empty assignment like
+/// `Comment=` unsets the key instead of setting it to an empty value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DesktopOverride {
+    pub keys: keyfile::KeyFile,
+}
+
+/// Parses a drop-in override. Unlike [`crate::parse`], required keys like Name and Type may be
+/// missing, and so may the Desktop Entry group.
+pub fn parse_partial(input: &str) -> Result<DesktopOverride, ParseError> {
+    keyfile::parse(input).map(|keys| DesktopOverride { keys })
+}
+
+impl DesktopFile {
+    /// Applies a drop-in override, replacing the keys it sets like [`DesktopFile::merged`]
+    /// does and removing the keys it unsets. The file is left unchanged if the result is not
+    /// a valid desktop file, such as when the override unsets Name.
+    pub fn apply_override(&mut self, partial: &DesktopOverride) -> Result<(), ParseError> {
+        let mut merged = keyfile::parse(&self.to_desktop_string())?;
+
+        for group in &partial.keys.groups {
+            let target = merged.group_mut(&group.name);
+            for entry in &group.entries {
+                if entry.value.is_empty() {
+                    target
+                        .entries
+                        .retain(|e| e.key != entry.key || e.locale != entry.locale);
+                } else {
+                    target.set_raw(&entry.key, entry.locale.as_deref(), &entry.value);
+                }
+            }
+        }
+
+        *self = crate::parse(&merged.to_string())?;
+        Ok(())
+    }
+
+    /// Lays `over` over this file, replacing the keys present in both, including the keys of
+    /// actions and of other groups
+    pub fn merged(&self, over: &DesktopFile) -> Result<DesktopFile, ParseError> {
+        Self::merged_with_provenance(&[("base", self), ("over", over)]).map(|(file, _)| file)
+    }
+
+    /// Merges the translations of another file into this one, such as a freshly translated
+    /// copy, using [`LocaleString::merge`](crate::LocaleString::merge) for Name, GenericName,
+    /// Comment, Keywords and the names of the actions both files have. Only locale variants
+    /// are taken, default values and every other key stay as they are.
+    pub fn merge_translations(&mut self, other: &DesktopFile, strategy: MergeStrategy) {
+        let (entry, from) = (&mut self.entry, &other.entry);
+        entry.name.merge(&from.name, strategy);
+        if let (Some(string), Some(from)) = (entry.generic_name.as_mut(), &from.generic_name) {
+            string.merge(from, strategy);
+        }
+        if let (Some(string), Some(from)) = (entry.comment.as_mut(), &from.comment) {
+            string.merge(from, strategy);
+        }
+        if let (EntryType::Application(fields), EntryType::Application(from)) =
+            (&mut entry.entry_type, &from.entry_type)
+        {
+            if let (Some(keywords), Some(from)) = (fields.keywords.as_mut(), &from.keywords) {
+                keywords.merge(from, strategy);
+            }
+        }
+
+        for (id, action) in self.actions.iter_mut() {
+            if let Some(from) = other.actions.get(id) {
+                action.name.merge(&from.name, strategy);
+            }
+        }
+    }
+
+    /// Lays labeled files over each other, least important first, such as
+    /// `[("/usr/share/applications/app.desktop", &system), ("user", &user)]`, and records
+    /// which layer supplied each value. A layer that repeats the value it is laid over doesn't
+    /// take it over, so only actual changes are attributed to higher layers.
+    pub fn merged_with_provenance(
+        layers: &[(&str, &DesktopFile)],
+    ) -> Result<(DesktopFile, Provenance), ParseError> {
+        let mut merged = keyfile::KeyFile::default();
+        let mut provenance = Provenance::default();
+
+        for (i, (label, file)) in layers.iter().enumerate() {
+            provenance.layers.push(label.to_string());
+            let layer = keyfile::parse(&file.to_desktop_string())?;
+
+            for group in &layer.groups {
+                let sources = provenance.sources.entry(group.name.clone()).or_default();
+                let target = merged.group_mut(&group.name);
+
+                for entry in &group.entries {
+                    let current = target
+                        .entries
+                        .iter()
+                        .find(|e| e.key == entry.key && e.locale == entry.locale);
+                    if current.is_some_and(|e| e.value == entry.value) {
+                        continue;
+                    }
+
+                    let key = match entry.locale {
+                        Some(ref locale) => format!("{}[{locale}]", entry.key),
+                        None => entry.key.clone(),
+                    };
+                    sources.insert(key, i);
+                    target.set_raw(&entry.key, entry.locale.as_deref(), &entry.value);
+                }
+            }
+        }
+
+        Ok((crate::parse(&merged.to_string())?, provenance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merged_with_provenance() {
+        let system = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Editor\nName[de]=Editor\nExec=editor\nActions=new;\n\n[Desktop Action new]\nName=New\nExec=editor --new\n",
+        )
+        .unwrap();
+        let user = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Editor\nName[de]=Texteditor\nExec=editor --dark\nNoDisplay=true\nActions=new;\n\n[Desktop Action new]\nName=New window\nExec=editor --new\n",
+        )
+        .unwrap();
+
+        let (merged, provenance) =
+            DesktopFile::merged_with_provenance(&[("system", &system), ("user", &user)]).unwrap();
+        assert_eq!(merged.entry.name.variants["de"], "Texteditor");
+        assert_eq!(merged.entry.no_display, Some(true));
+        assert_eq!(merged.actions["new"].name.default, "New window");
+        assert_eq!(
+            merged.to_desktop_string(),
+            system.merged(&user).unwrap().to_desktop_string()
+        );
+
+        assert_eq!(provenance.source("Desktop Entry", "Name"), Some("system"));
+        assert_eq!(provenance.source("Desktop Entry", "Exec"), Some("user"));
+        assert_eq!(provenance.source("Desktop Entry", "Comment"), None);
+        assert_eq!(
+            provenance.customized_keys(),
+            [
+                ("Desktop Action new", "Name"),
+                ("Desktop Entry", "Exec"),
+                ("Desktop Entry", "Name[de]"),
+                ("Desktop Entry", "NoDisplay"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_override() {
+        let mut file = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Editor\nComment=Edit text\nExec=editor\nActions=new;\n\n[Desktop Action new]\nName=New\nExec=editor --new\n",
+        )
+        .unwrap();
+        let partial = parse_partial(
+            "[Desktop Entry]\nNoDisplay=true\nName[de]=Texteditor\nComment=\n\n[Desktop Action new]\nExec=editor --new-window\n",
+        )
+        .unwrap();
+        assert!(crate::parse("[Desktop Entry]\nNoDisplay=true\n").is_err());
+
+        file.apply_override(&partial).unwrap();
+        assert_eq!(file.entry.name.default, "Editor");
+        assert_eq!(file.entry.name.variants["de"], "Texteditor");
+        assert_eq!(file.entry.no_display, Some(true));
+        assert!(file.entry.comment.is_none());
+        assert_eq!(
+            file.actions["new"].exec.as_deref(),
+            Some("editor --new-window")
+        );
+
+        let before = file.to_desktop_string();
+        let unset_name = parse_partial("[Desktop Entry]\nName=\n").unwrap();
+        assert!(file.apply_override(&unset_name).is_err());
+        assert_eq!(file.to_desktop_string(), before);
+    }
+
+    #[test]
+    fn test_merge_translations() {
+        let shipped = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Editor\nName[de]=Editor\nName[fr]=Éditeur\nKeywords=text;\nKeywords[de]=Text;Schreiben;\nExec=editor\nActions=new;\n\n[Desktop Action new]\nName=New\n",
+        )
+        .unwrap();
+        let translated = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=Text Editor\nName[de]=Texteditor\nName[es]=\nKeywords=text;\nKeywords[de]=Text;\nExec=editor\nActions=new;\n\n[Desktop Action new]\nName=New\nName[de]=Neu\n",
+        )
+        .unwrap();
+
+        let mut file = shipped.clone();
+        file.merge_translations(&translated, MergeStrategy::Override);
+        let name = &file.entry.name;
+        assert_eq!(name.default, "Editor");
+        assert_eq!(name.variants["de"], "Texteditor");
+        assert_eq!(name.variants["fr"], "Éditeur");
+        assert_eq!(name.variants["es"], "");
+        let EntryType::Application(ref fields) = file.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(fields.keywords.as_ref().unwrap().variants["de"], ["Text"]);
+        assert_eq!(file.actions["new"].name.variants["de"], "Neu");
+
+        let mut file = shipped.clone();
+        file.entry.name.variants.insert("es".into(), String::new());
+        let mut translated = translated.clone();
+        translated
+            .entry
+            .name
+            .variants
+            .insert("es".into(), "Editor de texto".into());
+        file.merge_translations(&translated, MergeStrategy::FillMissing);
+        let name = &file.entry.name;
+        assert_eq!(name.variants["de"], "Editor");
+        // an empty translation is present, so it is not filled in
+        assert_eq!(name.variants["es"], "");
+        let EntryType::Application(ref fields) = file.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(
+            fields.keywords.as_ref().unwrap().variants["de"],
+            ["Text", "Schreiben"]
+        );
+        assert_eq!(file.actions["new"].name.variants["de"], "Neu");
+    }
+}
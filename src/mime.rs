@@ -0,0 +1,310 @@
+//! Matching of files against the MimeType key of entries through the shared-mime-info
+//! database, which `update-mime-database` keeps in the `mime` directory of every data
+//! directory. Only the `globs2`, `aliases` and `subclasses` files are read, so types are
+//! guessed from file names and never from the content of the files.
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    util::{data_dirs, data_home},
+    DesktopEntry, EntryType,
+};
+
+/// The weight of globs written without one
+const DEFAULT_WEIGHT: u32 = 50;
+
+/// A file name pattern of the `globs2` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    pub weight: u32,
+    pub mime_type: String,
+    pub pattern: String,
+    /// Whether the pattern only matches file names with the same case, from the `cs` flag
+    pub case_sensitive: bool,
+}
+
+impl Glob {
+    /// Whether the pattern matches a file name
+    pub fn matches(&self, file_name: &str) -> bool {
+        if self.case_sensitive {
+            glob_match(&self.pattern, file_name)
+        } else {
+            glob_match(&self.pattern.to_lowercase(), &file_name.to_lowercase())
+        }
+    }
+}
+
+/// Matches a name against a pattern with `*`, `?` and `[...]` wildcards
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    match_from(&pattern, &name)
+}
+
+fn match_from(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|i| match_from(&pattern[1..], &name[i..])),
+        Some('?') => !name.is_empty() && match_from(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let Some(end) = pattern.iter().skip(1).position(|c| *c == ']') else {
+                return name.first() == Some(&'[') && match_from(&pattern[1..], &name[1..]);
+            };
+            let Some(ch) = name.first() else {
+                return false;
+            };
+
+            let class = &pattern[1..end + 1];
+            let (negated, class) = match class.first() {
+                Some('!') => (true, &class[1..]),
+                _ => (false, class),
+            };
+            let mut found = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    found |= (class[i]..=class[i + 2]).contains(ch);
+                    i += 3;
+                } else {
+                    found |= class[i] == *ch;
+                    i += 1;
+                }
+            }
+
+            found != negated && match_from(&pattern[end + 2..], &name[1..])
+        }
+        Some(c) => name.first() == Some(c) && match_from(&pattern[1..], &name[1..]),
+    }
+}
+
+/// The parts of the shared-mime-info database needed to guess types from file names
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MimeDatabase {
+    /// The globs of every directory, in the order they were added
+    pub globs: Vec<Glob>,
+    /// Map of aliases to the canonical type they stand for
+    pub aliases: HashMap<String, String>,
+    /// Map of types to their direct parent types
+    pub parents: HashMap<String, Vec<String>>,
+}
+
+impl MimeDatabase {
+    /// Loads the database of `$XDG_DATA_HOME` and `$XDG_DATA_DIRS`
+    pub fn load() -> Self {
+        let dirs = data_home()
+            .into_iter()
+            .chain(data_dirs())
+            .map(|dir| dir.join("mime"))
+            .collect::<Vec<_>>();
+        Self::load_from(&dirs)
+    }
+
+    /// Loads the database of the given `mime` directories, most important first. Missing
+    /// files are skipped.
+    pub fn load_from(dirs: &[PathBuf]) -> Self {
+        let mut database = Self::default();
+        let read = |dir: &Path, name: &str| std::fs::read_to_string(dir.join(name));
+
+        // less important directories first, so the more important ones override them
+        for dir in dirs.iter().rev() {
+            let _ = read(dir, "globs2").map(|s| database.add_globs2(&s));
+            let _ = read(dir, "aliases").map(|s| database.add_aliases(&s));
+            let _ = read(dir, "subclasses").map(|s| database.add_subclasses(&s));
+        }
+
+        database
+    }
+
+    /// Reads the database of a single `mime` directory, failing if one of its files is
+    /// missing
+    pub fn read(dir: &Path) -> io::Result<Self> {
+        let mut database = Self::default();
+        database.add_globs2(&std::fs::read_to_string(dir.join("globs2"))?);
+        database.add_aliases(&std::fs::read_to_string(dir.join("aliases"))?);
+        database.add_subclasses(&std::fs::read_to_string(dir.join("subclasses"))?);
+        Ok(database)
+    }
+
+    /// Adds the globs of a `globs2` file, whose lines are like `50:text/x-python:*.py`.
+    /// A `__NOGLOBS__` pattern removes the globs added before for its type.
+    pub fn add_globs2(&mut self, input: &str) {
+        for line in input.lines() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.splitn(4, ':');
+            let (Some(weight), Some(mime_type), Some(pattern)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+
+            if pattern == "__NOGLOBS__" {
+                self.globs.retain(|g| g.mime_type != mime_type);
+                continue;
+            }
+
+            let case_sensitive = fields
+                .next()
+                .is_some_and(|flags| flags.split(',').any(|f| f == "cs"));
+            self.globs.push(Glob {
+                weight: weight.parse().unwrap_or(DEFAULT_WEIGHT),
+                mime_type: mime_type.to_string(),
+                pattern: pattern.to_string(),
+                case_sensitive,
+            });
+        }
+    }
+
+    /// Adds the aliases of an `aliases` file, whose lines are written as `alias canonical`
+    pub fn add_aliases(&mut self, input: &str) {
+        for (alias, mime_type) in pairs(input) {
+            self.aliases
+                .insert(alias.to_string(), mime_type.to_string());
+        }
+    }
+
+    /// Adds the relations of a `subclasses` file, whose lines are written as `type parent`
+    pub fn add_subclasses(&mut self, input: &str) {
+        for (mime_type, parent) in pairs(input) {
+            let parents = self.parents.entry(mime_type.to_string()).or_default();
+            if !parents.iter().any(|p| p == parent) {
+                parents.push(parent.to_string());
+            }
+        }
+    }
+
+    /// Returns the canonical type an alias stands for, or the type itself
+    pub fn unalias<'a>(&'a self, mime_type: &'a str) -> &'a str {
+        self.aliases
+            .get(mime_type)
+            .map_or(mime_type, |t| t.as_str())
+    }
+
+    /// Guesses the type of a file from its name, taking the glob with the highest weight, then
+    /// the longest pattern, then a case-sensitive one as the specification says
+    pub fn type_for_path(&self, path: &Path) -> Option<&str> {
+        let file_name = path.file_name()?.to_str()?;
+
+        self.globs
+            .iter()
+            .filter(|g| g.matches(file_name))
+            .max_by_key(|g| (g.weight, g.pattern.len(), g.case_sensitive))
+            .map(|g| g.mime_type.as_str())
+    }
+
+    /// Whether a type is the same as another one or a subclass of it, following aliases and
+    /// the implicit relations of the specification: every `text/*` type is a subclass of
+    /// `text/plain`, and every type but `inode/*` ones is a subclass of
+    /// `application/octet-stream`
+    pub fn is_subclass(&self, mime_type: &str, parent: &str) -> bool {
+        let parent = self.unalias(parent);
+        let mut stack = vec![self.unalias(mime_type)];
+        let mut seen = vec![];
+
+        while let Some(current) = stack.pop() {
+            if current == parent {
+                return true;
+            }
+            if seen.contains(&current) {
+                continue;
+            }
+            seen.push(current);
+
+            if let Some(parents) = self.parents.get(current) {
+                stack.extend(parents.iter().map(|p| self.unalias(p)));
+            }
+        }
+
+        let mime_type = self.unalias(mime_type);
+        match parent {
+            "text/plain" => mime_type.starts_with("text/"),
+            "application/octet-stream" => !mime_type.starts_with("inode/"),
+            _ => false,
+        }
+    }
+
+    /// Whether an application declares a type in its MimeType key which the type of the file
+    /// is or is a subclass of
+    pub fn entry_handles_path(&self, entry: &DesktopEntry, path: &Path) -> bool {
+        let EntryType::Application(ref fields) = entry.entry_type else {
+            return false;
+        };
+        let Some(mime_type) = self.type_for_path(path) else {
+            return false;
+        };
+
+        fields
+            .mime_type
+            .iter()
+            .flatten()
+            .any(|declared| self.is_subclass(mime_type, declared))
+    }
+}
+
+/// Splits the lines of the `aliases` and `subclasses` files into their two types
+fn pairs(input: &str) -> impl Iterator<Item = (&str, &str)> {
+    input
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(' '))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> MimeDatabase {
+        let mut database = MimeDatabase::default();
+        database.add_globs2(
+            "# comment\n50:text/x-python3:*.py\n50:text/x-csrc:*.c\n50:text/x-c++src:*.C:cs\n50:text/plain:*.txt\n60:text/x-makefile:makefile\n50:image/png:*.png\n",
+        );
+        database.add_aliases("text/x-python text/x-python3\n");
+        database
+            .add_subclasses("text/x-python3 application/x-executable\ntext/x-csrc text/plain\n");
+        database
+    }
+
+    #[test]
+    fn test_entry_handles_path() {
+        let database = database();
+
+        assert_eq!(
+            database.type_for_path(Path::new("/src/foo.py")),
+            Some("text/x-python3")
+        );
+        assert_eq!(
+            database.type_for_path(Path::new("MAIN.c")),
+            Some("text/x-csrc")
+        );
+        assert_eq!(
+            database.type_for_path(Path::new("main.C")),
+            Some("text/x-c++src")
+        );
+        assert_eq!(
+            database.type_for_path(Path::new("Makefile")),
+            Some("text/x-makefile")
+        );
+        assert_eq!(database.type_for_path(Path::new("foo")), None);
+        assert!(database.is_subclass("text/x-python", "application/x-executable"));
+        assert!(database.is_subclass("image/png", "application/octet-stream"));
+        assert!(!database.is_subclass("image/png", "text/plain"));
+
+        let entry = |types: &str| {
+            crate::parse(&format!(
+                "[Desktop Entry]\nType=Application\nName=App\nExec=app %f\nMimeType={types}\n"
+            ))
+            .unwrap()
+            .entry
+        };
+        assert!(database.entry_handles_path(&entry("text/x-python;"), Path::new("foo.py")));
+        assert!(database.entry_handles_path(&entry("text/plain;"), Path::new("foo.py")));
+        assert!(database.entry_handles_path(&entry("text/plain;"), Path::new("main.c")));
+        assert!(!database.entry_handles_path(&entry("text/plain;"), Path::new("a.png")));
+    }
+}
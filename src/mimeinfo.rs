@@ -0,0 +1,183 @@
+//! Reading and writing of the `mimeinfo.cache` file that `update-desktop-database` maintains
+//! in every applications directory. The cache maps each MIME type to the desktop file ids
+//! of the applications declaring it in their MimeType key.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{util::write_atomic, DesktopFile, EntryType, ParseError};
+
+/// The name of the cache file inside an applications directory.
+pub const MIMEINFO_CACHE: &str = "mimeinfo.cache";
+
+/// The content of a `mimeinfo.cache` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MimeInfoCache {
+    /// Map of MIME types to the desktop file ids handling them, in the order of the file
+    pub types: BTreeMap<String, Vec<String>>,
+}
+
+impl MimeInfoCache {
+    /// Builds the cache from desktop file ids and their parsed files.
+    ///
+    /// Only applications are taken into account, and entries with Hidden=true are skipped
+    /// since they are considered deleted.
+    pub fn from_entries<'a>(entries: impl IntoIterator<Item = (&'a str, &'a DesktopFile)>) -> Self {
+        let mut types: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+        for (id, file) in entries {
+            if file.entry.hidden == Some(true) {
+                continue;
+            }
+
+            let EntryType::Application(ref fields) = file.entry.entry_type else {
+                continue;
+            };
+
+            for mime_type in fields.mime_type.iter().flatten() {
+                if mime_type.is_empty() {
+                    continue;
+                }
+
+                let ids = types.entry(mime_type.clone()).or_default();
+                if !ids.iter().any(|i| i == id) {
+                    ids.push(id.to_string());
+                }
+            }
+        }
+
+        Self { types }
+    }
+
+    /// Returns the ids of the desktop files handling the given MIME type
+    pub fn lookup(&self, mime_type: &str) -> &[String] {
+        match self.types.get(mime_type) {
+            Some(ids) => ids,
+            None => &[],
+        }
+    }
+
+    /// Parses the content of a `mimeinfo.cache` file
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut types = BTreeMap::new();
+        let mut in_cache_group = false;
+
+        for (row, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                in_cache_group = line == "[MIME Cache]";
+                continue;
+            }
+
+            let Some((mime_type, ids)) = line.split_once('=') else {
+                return Err(ParseError::Syntax {
+                    msg: "Expect \"=\" in a MIME type line".into(),
+                    row,
+                    col: 0,
+                    group: None,
+                });
+            };
+
+            if !in_cache_group {
+                continue;
+            }
+
+            types.insert(
+                mime_type.trim_end().to_string(),
+                ids.trim_start()
+                    .split(';')
+                    .filter(|id| !id.is_empty())
+                    .map(|id| id.to_string())
+                    .collect(),
+            );
+        }
+
+        Ok(Self { types })
+    }
+
+    /// Reads and parses a `mimeinfo.cache` file
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl std::fmt::Display for MimeInfoCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[MIME Cache]")?;
+        for (mime_type, ids) in &self.types {
+            write!(f, "{mime_type}=")?;
+            for id in ids {
+                write!(f, "{id};")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates the content of a `mimeinfo.cache` file from desktop file ids and their parsed
+/// files. MIME types are sorted, and every id is terminated by a semicolon.
+pub fn generate_mimeinfo_cache<'a>(
+    entries: impl IntoIterator<Item = (&'a str, &'a DesktopFile)>,
+) -> String {
+    MimeInfoCache::from_entries(entries).to_string()
+}
+
+/// Regenerates `mimeinfo.cache` in the given applications directory, replacing the old file
+/// atomically. Returns the path of the written file.
+pub fn write_mimeinfo_cache<'a>(
+    applications_dir: &Path,
+    entries: impl IntoIterator<Item = (&'a str, &'a DesktopFile)>,
+) -> io::Result<PathBuf> {
+    let path = applications_dir.join(MIMEINFO_CACHE);
+    write_atomic(&path, generate_mimeinfo_cache(entries).as_bytes())?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_generate_and_read_back() {
+        let editor = parse(
+            "[Desktop Entry]\nType=Application\nName=Editor\nExec=editor\nMimeType=text/plain;text/x-python;",
+        )
+        .unwrap();
+        let viewer = parse(
+            "[Desktop Entry]\nType=Application\nName=Viewer\nExec=viewer\nMimeType=text/plain;image/png;",
+        )
+        .unwrap();
+        let hidden = parse(
+            "[Desktop Entry]\nType=Application\nName=Hidden\nHidden=true\nMimeType=text/plain;",
+        )
+        .unwrap();
+
+        let content = generate_mimeinfo_cache([
+            ("editor.desktop", &editor),
+            ("viewer.desktop", &viewer),
+            ("hidden.desktop", &hidden),
+        ]);
+
+        assert_eq!(
+            content,
+            "[MIME Cache]\nimage/png=viewer.desktop;\ntext/plain=editor.desktop;viewer.desktop;\ntext/x-python=editor.desktop;\n"
+        );
+
+        let cache = MimeInfoCache::parse(&content).unwrap();
+        assert_eq!(
+            cache.lookup("text/plain"),
+            &["editor.desktop", "viewer.desktop"]
+        );
+        assert!(cache.lookup("text/html").is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+//! Heuristic detection of the tool that generated an entry, such as the ones Wine writes for
+//! every installed Windows program and Steam writes for every game. Each heuristic is a
+//! separate function, and [`DesktopEntry::generation_source`] reports which of them fired so
+//! callers can apply their own policy.
+
+use std::path::{Component, Path};
+
+use crate::{
+    exec::{ExecArg, ExecCommand},
+    DesktopEntry, EntryType,
+};
+
+/// The programs that run Windows executables through Wine
+const WINE_PROGRAMS: &[&str] = &[
+    "wine",
+    "wine64",
+    "wine-stable",
+    "wine-staging",
+    "wine-development",
+];
+
+/// The tool that generated an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationSource {
+    /// No heuristic fired
+    Native,
+    Wine,
+    /// A Steam game, which may run through Proton
+    Steam,
+    /// A PortableApps.com application run through Wine
+    PortableApp,
+    /// The entry has neither an Exec nor a URL to look at
+    Unknown,
+}
+
+/// A heuristic that fired for an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// Exec runs Wine, like `wine C:\\Program Files\\App\\app.exe`
+    WineExec,
+    /// Exec sets `WINEPREFIX` through `env`, as winemenubuilder writes it
+    WinePrefix,
+    /// The file is in the `wine` subdirectory of an applications directory
+    WineApplicationsDir,
+    /// Icon is named like the icons winemenubuilder extracts, such as `E0C4_notepad.0`
+    WineIcon,
+    /// Exec or the URL opens a `steam://rungameid/` URL
+    SteamUrl,
+    /// Exec runs the `proton` script of Steam
+    ProtonExec,
+    /// Exec runs a PortableApps.com launcher, like `AppPortable.exe` or a file under a
+    /// `PortableApps` directory
+    PortableAppsExec,
+}
+
+/// The detected source of an entry together with the heuristics that fired, in the order of
+/// [`Signal`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generation {
+    pub source: GenerationSource,
+    pub signals: Vec<Signal>,
+}
+
+/// Splits Exec into arguments. winemenubuilder writes `env WINEPREFIX="/home/me/.wine" wine`,
+/// which the quoting rules reject, so an invalid value is split on whitespace with its quotes
+/// dropped instead.
+fn exec_command(entry: &DesktopEntry) -> Option<ExecCommand> {
+    let EntryType::Application(ref fields) = entry.entry_type else {
+        return None;
+    };
+    let exec = fields.exec.as_deref()?;
+
+    ExecCommand::parse(exec).ok().or_else(|| {
+        let args = exec
+            .split_whitespace()
+            .map(|arg| ExecArg {
+                value: arg.replace('"', ""),
+                quoted: false,
+                col: arg.as_ptr() as usize - exec.as_ptr() as usize,
+            })
+            .collect::<Vec<_>>();
+        (!args.is_empty()).then_some(ExecCommand { args })
+    })
+}
+
+/// The last component of a path written with either kind of separator
+fn file_name(arg: &str) -> &str {
+    arg.rsplit(['/', '\\']).next().unwrap_or(arg)
+}
+
+/// Whether Exec runs one of the Wine programs, after a leading `env`
+pub fn exec_runs_wine(entry: &DesktopEntry) -> bool {
+    exec_command(entry)
+        .and_then(|c| {
+            c.effective_program()
+                .map(|p| WINE_PROGRAMS.contains(&file_name(p)))
+        })
+        .unwrap_or(false)
+}
+
+/// Whether Exec sets `WINEPREFIX`, like `env WINEPREFIX="/home/me/.wine" wine ...`
+pub fn exec_sets_wine_prefix(entry: &DesktopEntry) -> bool {
+    let Some(command) = exec_command(entry) else {
+        return false;
+    };
+    let end = command
+        .effective_program_index()
+        .unwrap_or(command.args.len());
+    command.args[..end]
+        .iter()
+        .any(|arg| arg.value.starts_with("WINEPREFIX="))
+}
+
+/// Whether a desktop file is inside the `wine` subdirectory of an applications directory,
+/// such as `~/.local/share/applications/wine/Programs/App.desktop`
+pub fn in_wine_applications_dir(path: &Path) -> bool {
+    let names = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    names.windows(2).any(|w| w == ["applications", "wine"])
+}
+
+/// Whether Icon is named like the icons winemenubuilder extracts from executables: four
+/// hexadecimal digits, an underscore, the name of the executable and the index of the icon,
+/// like `E0C4_notepad.0`
+pub fn has_wine_icon(entry: &DesktopEntry) -> bool {
+    let Some(ref icon) = entry.icon else {
+        return false;
+    };
+    let name = &icon.content;
+
+    let Some((hash, rest)) = name.split_once('_') else {
+        return false;
+    };
+    let Some((stem, index)) = rest.rsplit_once('.') else {
+        return false;
+    };
+    hash.len() == 4
+        && hash.chars().all(|c| c.is_ascii_hexdigit())
+        && !stem.is_empty()
+        && !index.is_empty()
+        && index.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Whether Exec or the URL of a link opens a Steam game, like `steam steam://rungameid/440`
+pub fn opens_steam_game(entry: &DesktopEntry) -> bool {
+    const PREFIX: &str = "steam://rungameid/";
+    match entry.entry_type {
+        EntryType::Link(ref fields) => fields.url.starts_with(PREFIX),
+        _ => {
+            exec_command(entry).is_some_and(|c| c.args.iter().any(|a| a.value.starts_with(PREFIX)))
+        }
+    }
+}
+
+/// Whether Exec runs the `proton` script Steam ships with every Proton version
+pub fn exec_runs_proton(entry: &DesktopEntry) -> bool {
+    exec_command(entry).is_some_and(|c| c.args.iter().any(|a| file_name(&a.value) == "proton"))
+}
+
+/// Whether Exec runs a PortableApps.com launcher, named like `AppPortable.exe` or inside a
+/// `PortableApps` directory
+pub fn exec_runs_portable_app(entry: &DesktopEntry) -> bool {
+    exec_command(entry).is_some_and(|c| {
+        c.args.iter().any(|arg| {
+            let value = arg.value.to_lowercase();
+            value.ends_with("portable.exe")
+                || value
+                    .split(['/', '\\'])
+                    .any(|component| component == "portableapps")
+        })
+    })
+}
+
+impl DesktopEntry {
+    /// Detects the tool that generated the entry from its keys alone, see
+    /// [`DesktopEntry::generation_source_at`]
+    pub fn generation_source(&self) -> Generation {
+        self.detect(None)
+    }
+
+    /// Detects the tool that generated the entry from its keys and the path of its file. Steam
+    /// signals take precedence, then PortableApps.com ones, then Wine ones.
+    pub fn generation_source_at(&self, path: &Path) -> Generation {
+        self.detect(Some(path))
+    }
+
+    fn detect(&self, path: Option<&Path>) -> Generation {
+        let checks = [
+            (Signal::WineExec, exec_runs_wine(self)),
+            (Signal::WinePrefix, exec_sets_wine_prefix(self)),
+            (
+                Signal::WineApplicationsDir,
+                path.is_some_and(in_wine_applications_dir),
+            ),
+            (Signal::WineIcon, has_wine_icon(self)),
+            (Signal::SteamUrl, opens_steam_game(self)),
+            (Signal::ProtonExec, exec_runs_proton(self)),
+            (Signal::PortableAppsExec, exec_runs_portable_app(self)),
+        ];
+        let signals = checks
+            .into_iter()
+            .filter_map(|(signal, fired)| fired.then_some(signal))
+            .collect::<Vec<_>>();
+
+        let fired = |candidates: &[Signal]| signals.iter().any(|s| candidates.contains(s));
+        let source = if fired(&[Signal::SteamUrl, Signal::ProtonExec]) {
+            GenerationSource::Steam
+        } else if fired(&[Signal::PortableAppsExec]) {
+            GenerationSource::PortableApp
+        } else if !signals.is_empty() {
+            GenerationSource::Wine
+        } else if exec_command(self).is_some() || matches!(self.entry_type, EntryType::Link(_)) {
+            GenerationSource::Native
+        } else {
+            GenerationSource::Unknown
+        };
+
+        Generation { source, signals }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(keys: &str) -> DesktopEntry {
+        crate::parse(&format!(
+            "[Desktop Entry]\nType=Application\nName=App\n{keys}\n"
+        ))
+        .unwrap()
+        .entry
+    }
+
+    #[test]
+    fn test_generation_source() {
+        let wine =
+            entry("Exec=env WINEPREFIX=\"/home/me/.wine\" wine notepad.exe\nIcon=E0C4_notepad.0");
+        assert!(exec_runs_wine(&wine));
+        assert!(exec_sets_wine_prefix(&wine));
+        assert!(has_wine_icon(&wine));
+        let path = Path::new("/home/me/.local/share/applications/wine/Programs/Notepad.desktop");
+        assert!(in_wine_applications_dir(path));
+        assert!(!in_wine_applications_dir(Path::new(
+            "/usr/share/applications/wine.desktop"
+        )));
+        assert_eq!(
+            wine.generation_source_at(path),
+            Generation {
+                source: GenerationSource::Wine,
+                signals: vec![
+                    Signal::WineExec,
+                    Signal::WinePrefix,
+                    Signal::WineApplicationsDir,
+                    Signal::WineIcon
+                ],
+            }
+        );
+
+        let steam = entry("Exec=steam steam://rungameid/440\nIcon=steam_icon_440");
+        assert_eq!(steam.generation_source().source, GenerationSource::Steam);
+        assert_eq!(steam.generation_source().signals, [Signal::SteamUrl]);
+
+        let portable = entry("Exec=wine /home/me/PortableApps/GIMPPortable/GIMPPortable.exe");
+        assert_eq!(
+            portable.generation_source().source,
+            GenerationSource::PortableApp
+        );
+
+        let native = entry("Exec=gedit %U\nIcon=org.gnome.gedit");
+        assert_eq!(native.generation_source().source, GenerationSource::Native);
+        assert!(native.generation_source().signals.is_empty());
+        let directory = crate::parse("[Desktop Entry]\nType=Directory\nName=Games\n")
+            .unwrap()
+            .entry;
+        assert_eq!(
+            directory.generation_source().source,
+            GenerationSource::Unknown
+        );
+    }
+}
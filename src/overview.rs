@@ -0,0 +1,186 @@
+//! A compact human-readable overview of a file for debugging and command line output, see
+//! [`DesktopFile::summary`]. The overview is meant to be read, not parsed: its layout may
+//! change between versions, unlike the desktop file format written by the serializer.
+
+use std::fmt;
+
+use crate::{DesktopFile, EntryType};
+
+/// The width lines are cut to, counted in characters
+const MAX_WIDTH: usize = 100;
+
+/// Cuts a line to [`MAX_WIDTH`], ending it with `…` when something was cut
+fn truncate(line: String) -> String {
+    if line.chars().count() <= MAX_WIDTH {
+        return line;
+    }
+    let mut res = line.chars().take(MAX_WIDTH - 1).collect::<String>();
+    res.push('…');
+    res
+}
+
+/// Writes `label: value`, indented under the header line
+fn push_line(out: &mut Vec<String>, label: &str, value: &str) {
+    out.push(truncate(format!("  {label}: {value}")));
+}
+
+/// Writes `label: a, b, c`, leaving out the items that don't fit and counting them instead
+fn push_list(out: &mut Vec<String>, label: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+
+    let mut line = format!("  {label}: ");
+    for (i, item) in items.iter().enumerate() {
+        let sep = if i == 0 { "" } else { ", " };
+        let more = format!(" (+{} more)", items.len() - i);
+        let fits = line.chars().count() + sep.len() + item.chars().count()
+            <= MAX_WIDTH - if i + 1 < items.len() { more.len() } else { 0 };
+        if !fits && i > 0 {
+            line.push_str(&more);
+            out.push(line);
+            return;
+        }
+        line.push_str(sep);
+        line.push_str(item);
+    }
+    out.push(truncate(line));
+}
+
+impl DesktopFile {
+    /// Renders a compact overview of the file: its type, name and generic name, the command or
+    /// URL, the icon, categories, MIME types, the keys hiding it and one line per action. The
+    /// localized keys are resolved for the locale like [`DesktopFile::localize`] does, or
+    /// take their default values for None. Absent keys are left out, and lines are cut to a
+    /// width of 100 characters.
+    pub fn summary(&self, locale: Option<&str>) -> String {
+        let localized = self.localize(locale.as_slice());
+        let entry = &self.entry;
+
+        let mut header = format!("{}: {}", entry.entry_type, localized.name);
+        if let Some(ref generic_name) = localized.generic_name {
+            header.push_str(&format!(" ({generic_name})"));
+        }
+        let mut out = vec![truncate(header)];
+
+        if let Some(ref comment) = localized.comment {
+            push_line(&mut out, "Comment", comment);
+        }
+        match entry.entry_type {
+            EntryType::Application(ref fields) => {
+                if let Some(ref exec) = fields.exec {
+                    push_line(&mut out, "Exec", exec);
+                }
+            }
+            EntryType::Link(ref fields) => push_line(&mut out, "URL", &fields.url),
+            _ => {}
+        }
+        if let Some(ref icon) = entry.icon {
+            push_line(&mut out, "Icon", &icon.content);
+        }
+
+        if let EntryType::Application(ref fields) = entry.entry_type {
+            push_list(
+                &mut out,
+                "Categories",
+                fields.categories.as_deref().unwrap_or_default(),
+            );
+            push_list(
+                &mut out,
+                "MIME types",
+                fields.mime_type.as_deref().unwrap_or_default(),
+            );
+        }
+
+        let mut flags = vec![];
+        if entry.no_display == Some(true) {
+            flags.push("NoDisplay".to_string());
+        }
+        if entry.hidden == Some(true) {
+            flags.push("Hidden".to_string());
+        }
+        if let Some(ref desktops) = entry.only_show_in {
+            flags.push(format!("OnlyShowIn={}", desktops.join(";")));
+        }
+        if let Some(ref desktops) = entry.not_show_in {
+            flags.push(format!("NotShowIn={}", desktops.join(";")));
+        }
+        if let EntryType::Application(ref fields) = entry.entry_type {
+            if fields.terminal == Some(true) {
+                flags.push("Terminal".to_string());
+            }
+        }
+        push_list(&mut out, "Flags", &flags);
+
+        // in the order of the Actions key, which the map of actions doesn't keep
+        let declared = entry
+            .as_application()
+            .and_then(|fields| fields.actions.as_deref())
+            .unwrap_or_default();
+        let actions = declared
+            .iter()
+            .filter_map(|id| localized.actions.get(id).map(|action| (id, action)))
+            .collect::<Vec<_>>();
+        if !actions.is_empty() {
+            out.push("  Actions:".into());
+            for (id, action) in actions {
+                out.push(truncate(format!("    {id}: {}", action.name)));
+            }
+        }
+
+        out.join("\n")
+    }
+}
+
+impl fmt::Display for DesktopFile {
+    /// Writes the overview of [`DesktopFile::summary`] with the default values of the localized
+    /// keys. Use [`DesktopFile::to_desktop_string`] for the file itself.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.summary(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mime_types = (0..40)
+            .map(|i| format!("application/x-type{i}"))
+            .collect::<Vec<_>>()
+            .join(";");
+        let file = crate::parse(&format!(
+            "[Desktop Entry]\nType=Application\nName=Browser\nName[de]=Webbrowser\nGenericName=Web Browser\nExec=browser %u\nIcon=browser\nCategories=Network;WebBrowser;\nMimeType={mime_types};\nNoDisplay=true\nOnlyShowIn=GNOME;KDE;\nActions=new-window;private;\n\n[Desktop Action private]\nName=Private Window\nName[de]=Privates Fenster\n\n[Desktop Action new-window]\nName=New Window\nName[de]=Neues Fenster\n"
+        ))
+        .unwrap();
+
+        let summary = file.to_string();
+        let lines = summary.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "Application: Browser (Web Browser)");
+        assert_eq!(lines[1], "  Exec: browser %u");
+        assert_eq!(lines[2], "  Icon: browser");
+        assert_eq!(lines[3], "  Categories: Network, WebBrowser");
+        assert!(lines[4].starts_with("  MIME types: application/x-type0, application/x-type1"));
+        assert!(lines[4].ends_with(" more)"));
+        assert!(lines[4].chars().count() <= MAX_WIDTH);
+        assert_eq!(lines[5], "  Flags: NoDisplay, OnlyShowIn=GNOME;KDE");
+        assert_eq!(
+            &lines[6..],
+            [
+                "  Actions:",
+                "    new-window: New Window",
+                "    private: Private Window"
+            ]
+        );
+        assert!(!summary.contains("None"));
+
+        let summary = file.summary(Some("de_DE.UTF-8"));
+        assert!(summary.starts_with("Application: Webbrowser (Web Browser)\n"));
+        assert!(summary.ends_with("    private: Privates Fenster"));
+
+        let link = crate::parse("[Desktop Entry]\nType=Link\nName=Docs\nURL=https://example.com\n")
+            .unwrap();
+        assert_eq!(link.summary(None), "Link: Docs\n  URL: https://example.com");
+    }
+}
@@ -1,253 +1,91 @@
-use crate::internal_structs::vec_to_map;
-use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
+use crate::internal_structs::{missing_default, vec_to_map};
+use std::collections::{HashMap, HashSet};
 
 use crate::{
+    comments::Comments,
+    events::{Event, Events},
+    handlers::{KeyHandlers, KeyHooks},
     internal_structs::{
         DesktopActionInternal, DesktopEntryInternal, Header, LocaleStringInternal,
         LocaleStringListInternal,
     },
-    structs::ParseError,
-    DesktopFile, IconString,
+    keyfile::{Group, KeyValue},
+    keys::Key,
+    locale::canonicalize_locale,
+    structs::{ParseError, ParseWarning},
+    template::TranslatableKey,
+    DesktopEntry, DesktopFile, IconString,
 };
 
+/// The group the following key value pairs belong to
 #[derive(Debug)]
-enum LineType {
-    Header,
-    ValPair,
-}
-
-#[derive(Debug)]
-enum EntryType {
-    Entry(Rc<RefCell<DesktopEntryInternal>>),
+enum Target {
+    Entry,
     Action(usize),
+    /// A group that is neither the entry nor an action, by index in the other groups
+    Other(usize),
 }
 
-#[derive(Debug)]
-struct Line<'a> {
-    content: Vec<Character<'a>>,
-    line_number: usize,
-}
-
-impl<'a> Line<'a> {
-    pub fn from_data(line: &'a str, line_number: usize) -> Self {
-        let content: Vec<Character<'a>> = line
-            .trim_end()
-            .char_indices()
-            .map(|(col_number, ch)| Character {
-                content: &line[col_number..col_number + ch.len_utf8()],
-                line_number,
-                col_number,
-            })
-            .filter(|ch| !(ch.col_number == 0 && ch.content == " "))
-            .collect();
-
-        Self {
-            content,
-            line_number,
-        }
-    }
-
-    pub fn line_type(&self) -> LineType {
-        if self.content[0].content == "[" {
-            LineType::Header
-        } else {
-            LineType::ValPair
-        }
-    }
-}
-impl<'a> fmt::Display for Line<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for ch in &self.content {
-            write!(f, "{}", ch.content)?;
-        }
-        Ok(())
-    }
-}
-
+/// Contains the parsed info of a key value line
 #[derive(Debug, Clone)]
-struct Character<'a> {
-    content: &'a str,
-    line_number: usize,
-    col_number: usize,
+pub(crate) struct LinePart {
+    pub(crate) key: Key,
+    pub(crate) locale: Option<String>,
+    pub(crate) value: String,
+    pub(crate) line_number: usize,
+    /// Whether trailing whitespace was cut off the value
+    pub(crate) trimmed: bool,
 }
 
-fn filter_lines(input: &str) -> Vec<Line> {
-    input
-        .split("\n")
-        .enumerate()
-        .filter(|element| !element.1.is_empty() && !element.1.trim().starts_with("#"))
-        .map(|(num, l)| Line::from_data(l, num))
-        .collect()
+/// A meaningful line of a key file
+#[derive(Debug, Clone)]
+pub(crate) enum Token {
+    Group { name: String, line_number: usize },
+    Pair(LinePart),
 }
 
-fn parse_header(input: &Line) -> Result<Header, ParseError> {
-    enum HeaderParseState {
-        Idle,
-        Content,
-    }
-
-    let mut state = HeaderParseState::Idle;
-    let mut result = String::new();
-
-    for (ind, ch) in input.content.iter().enumerate() {
-        match state {
-            HeaderParseState::Idle => match ch.content {
-                "[" => {
-                    state = HeaderParseState::Content;
-                }
-                _ => {
-                    return Err(ParseError::InternalError {
-                        msg: "line is mis-classified as a header".into(),
-                        row: ch.line_number,
-                        col: ch.col_number,
-                    });
-                }
-            },
-            HeaderParseState::Content => match ch.content {
-                "]" => {
-                    if ind != input.content.len() - 1 {
-                        return Err(ParseError::Syntax {
-                            msg: "nothing is expected after \"]\"".to_string(),
-                            row: ch.line_number,
-                            col: ch.col_number,
-                        });
-                    }
-                }
-                "[" => {
-                    return Err(ParseError::UnacceptableCharacter {
-                        ch: ch.content.to_string(),
-                        row: ch.line_number,
-                        col: ch.col_number,
-                        msg: format!("\"{}\" is not accepted in header", ch.content),
-                    });
-                }
-                _ => {
-                    if ch.content.chars().next().unwrap().is_control() {
-                        return Err(ParseError::UnacceptableCharacter {
-                            ch: ch.content.to_string(),
-                            row: ch.line_number,
-                            col: ch.col_number,
-                            msg: "none".to_string(),
-                        });
-                    }
-                    result.push_str(ch.content);
-                }
-            },
-        }
-    }
-
-    if result == "Desktop Entry" {
-        Ok(Header::DesktopEntry)
-    } else if let Some(remain) = result.strip_prefix("Desktop Action ") {
-        Ok(Header::DesktopAction {
-            name: remain.to_string(),
-        })
-    } else {
-        Ok(Header::Other { name: result })
-    }
+/// Turns the events of the input into owned tokens, skipping comments
+pub(crate) fn tokens(input: &str) -> impl Iterator<Item = Result<Token, ParseError>> + '_ {
+    let mut events = Events::new(input);
+    std::iter::from_fn(move || next_token(&mut events, &mut None))
 }
 
-/// Contains the parsed info of a key value line
-#[derive(Clone)]
-struct LinePart {
-    key: String,
-    locale: Option<String>,
-    value: String,
-    line_number: usize,
-}
-
-fn split_into_parts(line: &Line) -> Result<LinePart, ParseError> {
-    #[cfg(test)]
-    println!("This line is: {:?}", line.to_string());
-
-    enum State {
-        /// the initial key parser
-        Key,
-        /// the locale parser
-        KeyLocale,
-        /// the character that ends the locale spec
-        LocaleToValue,
-        /// the value
-        Value,
-    }
-
-    let mut result = LinePart {
-        key: "".into(),
-        locale: None,
-        value: "".into(),
-        line_number: line.line_number,
-    };
-
-    let mut state = State::Key;
-    let mut key_has_space = false;
-
-    for ch in line.content.iter() {
-        match state {
-            State::Key => match ch.content {
-                "[" => {
-                    state = State::KeyLocale;
-                    result.locale = Some("".into())
-                }
-
-                "=" => state = State::Value,
-
-                " " => key_has_space = true,
-
-                "A" | "B" | "C" | "D" | "E" | "F" | "G" | "H" | "I" | "J" | "K" | "L" | "M"
-                | "N" | "O" | "P" | "Q" | "R" | "S" | "T" | "U" | "V" | "W" | "X" | "Y" | "Z"
-                | "a" | "b" | "c" | "d" | "e" | "f" | "g" | "h" | "i" | "j" | "k" | "l" | "m"
-                | "n" | "o" | "p" | "q" | "r" | "s" | "t" | "u" | "v" | "w" | "x" | "y" | "z"
-                | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" | "0" | "-" => {
-                    if !key_has_space {
-                        result.key.push_str(ch.content)
-                    } else {
-                        return Err(ParseError::Syntax {
-                            msg: "Keys shouldn't have characters other than A-Za-z0-9-".into(),
-                            row: ch.line_number,
-                            col: ch.col_number,
-                        });
-                    }
-                }
-
-                _ => {
-                    return Err(ParseError::Syntax {
-                        msg: "Keys shouldn't have characters other than A-Za-z0-9-".into(),
-                        row: ch.line_number,
-                        col: ch.col_number,
-                    })
-                }
-            },
-
-            State::KeyLocale => match ch.content {
-                "]" => state = State::LocaleToValue,
-
-                _ => {
-                    if let Some(ref mut str) = result.locale {
-                        str.push_str(ch.content);
-                    }
-                }
-            },
-
-            State::LocaleToValue => match ch.content {
-                "=" => state = State::Value,
-
-                _ => {
-                    return Err(ParseError::Syntax {
-                        msg: "Expect \"=\" after \"=\"".into(),
-                        row: ch.line_number,
-                        col: ch.col_number,
-                    });
+/// Returns the token of the next event that isn't a comment, collecting the comments it
+/// passes into `comments` when that is set
+fn next_token(
+    events: &mut Events<'_>,
+    comments: &mut Option<Vec<String>>,
+) -> Option<Result<Token, ParseError>> {
+    loop {
+        let token = match events.next()? {
+            Err(e) => Err(e),
+            Ok(Event::Comment(comment)) => {
+                if let Some(comments) = comments {
+                    comments.push(comment.to_string());
                 }
-            },
-
-            State::Value => result.value.push_str(ch.content),
-        }
+                continue;
+            }
+            Ok(Event::GroupStart(name)) => Ok(Token::Group {
+                name: name.to_string(),
+                line_number: events.span().line,
+            }),
+            Ok(Event::KeyValue {
+                key,
+                locale,
+                value,
+                span,
+                trimmed,
+            }) => Ok(Token::Pair(LinePart {
+                key,
+                locale: locale.map(canonicalize_locale),
+                value: value.to_string(),
+                line_number: span.line,
+                trimmed,
+            })),
+        };
+
+        return Some(token);
     }
-
-    result.value = result.value.trim_start().to_string();
-    result.key = result.key.trim_end().to_string();
-
-    Ok(result)
 }
 
 fn set_locale_str(parts: LinePart, str: &mut LocaleStringInternal) -> Result<(), ParseError> {
@@ -257,21 +95,23 @@ fn set_locale_str(parts: LinePart, str: &mut LocaleStringInternal) -> Result<(),
         Some(locale) => {
             if str.variants.contains_key(&locale) {
                 return Err(ParseError::RepetitiveKey {
-                    key: parts.key,
+                    key: parts.key.to_string(),
                     row: parts.line_number,
                     col: 0,
+                    group: None,
                 });
             }
-            str.variants.insert(locale, parts.value);
+            str.variants.insert(locale, unescape_string(&parts.value));
         }
         None => {
             if str.default.is_none() {
-                str.default = Some(parts.value);
+                str.default = Some(unescape_string(&parts.value));
             } else {
                 return Err(ParseError::RepetitiveKey {
-                    key: parts.key,
+                    key: parts.key.to_string(),
                     row: parts.line_number,
                     col: 0,
+                    group: None,
                 });
             }
         }
@@ -308,6 +148,7 @@ fn set_bool(parts: LinePart, val: &mut bool) -> Result<(), ParseError> {
             msg: "Property's value needs to be bool".into(),
             row: parts.line_number,
             col: 0,
+            group: None,
         })?;
     Ok(())
 }
@@ -317,9 +158,10 @@ fn set_optional_bool(parts: LinePart, opt: &mut Option<bool>) -> Result<(), Pars
     match opt {
         Some(_) => {
             return Err(ParseError::RepetitiveKey {
-                key: parts.key,
+                key: parts.key.to_string(),
                 row: parts.line_number,
                 col: 0,
+                group: None,
             });
         }
         None => {
@@ -335,66 +177,174 @@ fn set_optional_bool(parts: LinePart, opt: &mut Option<bool>) -> Result<(), Pars
 fn set_optional_list(parts: LinePart, opt: &mut Option<Vec<String>>) -> Result<(), ParseError> {
     if !opt.is_none() {
         return Err(ParseError::RepetitiveKey {
-            key: parts.key,
+            key: parts.key.to_string(),
             row: parts.line_number,
             col: 0,
+            group: None,
         });
     }
 
-    *opt = Some({
-        let mut res = parts
-            .value
-            .split(";")
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>();
+    *opt = Some(parse_list(&parts.value));
+    Ok(())
+}
 
-        if let Some(val) = res.last() {
-            if val.is_empty() {
-                res.pop();
-            }
+/// Resolves the escape sequence following a backslash, returning None for unknown escapes
+pub(crate) fn unescape_char(ch: char) -> Option<char> {
+    match ch {
+        's' => Some(' '),
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '\\' => Some('\\'),
+        _ => None,
+    }
+}
+
+/// Resolves the escape sequences of a string value (`\s`, `\n`, `\t`, `\r` and `\\`).
+/// Unknown escapes are kept as they are.
+pub fn unescape_string(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            res.push(ch);
+            continue;
         }
 
-        res
-    });
-    Ok(())
+        match chars.next() {
+            Some(escaped) => match unescape_char(escaped) {
+                Some(unescaped) => res.push(unescaped),
+                None => {
+                    res.push('\\');
+                    res.push(escaped);
+                }
+            },
+            None => res.push('\\'),
+        }
+    }
+
+    res
+}
+
+/// Splits the value of a list key on unescaped semicolons, resolving the escape sequences of
+/// the spec (`\s`, `\n`, `\t`, `\r`, `\\` and `\;`). The trailing semicolon is optional.
+pub fn parse_list(value: &str) -> Vec<String> {
+    // without escapes the items are the pieces between the semicolons
+    if memchr::memchr(b'\\', value.as_bytes()).is_none() {
+        let mut res = value.split(';').map(String::from).collect::<Vec<_>>();
+        if res.last().is_some_and(|last| last.is_empty()) {
+            res.pop();
+        }
+        return res;
+    }
+
+    let mut res = vec![];
+    let mut current = String::new();
+    let mut chars = value.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => match chars.next() {
+                Some(';') => current.push(';'),
+                Some(escaped) => match unescape_char(escaped) {
+                    Some(unescaped) => current.push(unescaped),
+                    // unknown escapes are kept as they are
+                    None => {
+                        current.push('\\');
+                        current.push(escaped);
+                    }
+                },
+                None => current.push('\\'),
+            },
+            ';' => res.push(std::mem::take(&mut current)),
+            _ => current.push(ch),
+        }
+    }
+
+    if !current.is_empty() {
+        res.push(current);
+    }
+
+    res
 }
 
 fn set_optional_str(parts: LinePart, opt: &mut Option<String>) -> Result<(), ParseError> {
     if !opt.is_none() {
         return Err(ParseError::RepetitiveKey {
-            key: parts.key,
+            key: parts.key.to_string(),
             row: parts.line_number,
             col: 0,
+            group: None,
         });
     }
 
-    *opt = Some(parts.value);
+    *opt = Some(unescape_string(&parts.value));
     Ok(())
 }
 
 fn set_optional_icon_str(parts: LinePart, opt: &mut Option<IconString>) -> Result<(), ParseError> {
     if !opt.is_none() {
         return Err(ParseError::RepetitiveKey {
-            key: parts.key,
+            key: parts.key.to_string(),
             row: parts.line_number,
             col: 0,
+            group: None,
         });
     }
 
     *opt = Some(IconString {
-        content: parts.value,
+        content: unescape_string(&parts.value),
     });
     Ok(())
 }
 
-fn fill_entry_val(entry: &mut DesktopEntryInternal, parts: LinePart) -> Result<(), ParseError> {
-    match parts.key.as_str() {
-        "Type" => {
+fn set_extra(parts: LinePart, extras: &mut HashMap<String, String>) -> Result<(), ParseError> {
+    let key = match parts.locale {
+        Some(ref locale) => format!("{}[{}]", parts.key, locale),
+        None => parts.key.to_string(),
+    };
+
+    #[cfg(feature = "tracing")]
+    if let Key::Unknown(ref name) = parts.key {
+        tracing::debug!(key = %name, line = parts.line_number, "keeping unknown key as an extra");
+    }
+
+    if extras.contains_key(&key) {
+        return Err(ParseError::RepetitiveKey {
+            key,
+            row: parts.line_number,
+            col: 0,
+            group: None,
+        });
+    }
+
+    extras.insert(key, parts.value);
+    Ok(())
+}
+
+/// Runs the key handlers for a key that would be kept as an extra, returning whether one of
+/// them claimed it
+fn run_hooks(hooks: Option<KeyHooks>, parts: &LinePart) -> Result<bool, ParseError> {
+    match hooks {
+        Some(mut hooks) if !hooks.handlers.is_empty() => hooks.run(parts),
+        _ => Ok(false),
+    }
+}
+
+fn fill_entry_val(
+    entry: &mut DesktopEntryInternal,
+    parts: LinePart,
+    hooks: Option<KeyHooks>,
+) -> Result<(), ParseError> {
+    match parts.key {
+        Key::Type => {
             if entry.entry_type.is_some() {
                 return Err(ParseError::RepetitiveKey {
                     key: "Type".into(),
                     row: parts.line_number,
                     col: 0,
+                    group: None,
                 });
             }
 
@@ -402,36 +352,26 @@ fn fill_entry_val(entry: &mut DesktopEntryInternal, parts: LinePart) -> Result<(
                 parts.value.as_str(),
             ));
         }
-        "Version" => set_optional_str(parts, &mut entry.version)?,
-        "Name" => set_optional_locale_str(parts, &mut entry.name)?,
-        "GenericName" => set_optional_locale_str(parts, &mut entry.generic_name)?,
-        "NoDisplay" => set_optional_bool(parts, &mut entry.no_display)?,
-        "Comment" => set_optional_locale_str(parts, &mut entry.comment)?,
-        "Icon" => set_optional_icon_str(parts, &mut entry.icon)?,
-        "Hidden" => set_optional_bool(parts, &mut entry.hidden)?,
-        "OnlyShowIn" => set_optional_list(parts, &mut entry.only_show_in)?,
-        "NotShowIn" => set_optional_list(parts, &mut entry.not_show_in)?,
-        "DBusActivatable" => set_optional_bool(parts, &mut entry.dbus_activatable)?,
-        "TryExec" => set_optional_str(parts, &mut entry.try_exec)?,
-        "Exec" => set_optional_str(parts, &mut entry.exec)?,
-        "Path" => set_optional_str(parts, &mut entry.path)?,
-        "Terminal" => set_optional_bool(parts, &mut entry.terminal)?,
-        "Actions" => set_optional_list(parts, &mut entry.actions)?,
-        "MimeType" => set_optional_list(parts, &mut entry.mime_type)?,
-        "Categories" => set_optional_list(parts, &mut entry.categories)?,
-        "Implements" => set_optional_list(parts, &mut entry.implements)?,
-        "Keywords" => {
-            let mut split = parts
-                .value
-                .split(";")
-                .map(|str| str.to_string())
-                .collect::<Vec<String>>();
-
-            if let Some(val) = split.last() {
-                if val.is_empty() {
-                    split.pop();
-                }
-            }
+        Key::Version => set_optional_str(parts, &mut entry.version)?,
+        Key::Name => set_optional_locale_str(parts, &mut entry.name)?,
+        Key::GenericName => set_optional_locale_str(parts, &mut entry.generic_name)?,
+        Key::NoDisplay => set_optional_bool(parts, &mut entry.no_display)?,
+        Key::Comment => set_optional_locale_str(parts, &mut entry.comment)?,
+        Key::Icon => set_optional_icon_str(parts, &mut entry.icon)?,
+        Key::Hidden => set_optional_bool(parts, &mut entry.hidden)?,
+        Key::OnlyShowIn => set_optional_list(parts, &mut entry.only_show_in)?,
+        Key::NotShowIn => set_optional_list(parts, &mut entry.not_show_in)?,
+        Key::DBusActivatable => set_optional_bool(parts, &mut entry.dbus_activatable)?,
+        Key::TryExec => set_optional_str(parts, &mut entry.try_exec)?,
+        Key::Exec => set_optional_str(parts, &mut entry.exec)?,
+        Key::Path => set_optional_str(parts, &mut entry.path)?,
+        Key::Terminal => set_optional_bool(parts, &mut entry.terminal)?,
+        Key::Actions => set_optional_list(parts, &mut entry.actions)?,
+        Key::MimeType => set_optional_list(parts, &mut entry.mime_type)?,
+        Key::Categories => set_optional_list(parts, &mut entry.categories)?,
+        Key::Implements => set_optional_list(parts, &mut entry.implements)?,
+        Key::Keywords => {
+            let split = parse_list(&parts.value);
 
             match entry.keywords {
                 Some(ref mut kwds) => match parts.locale {
@@ -441,6 +381,7 @@ fn fill_entry_val(entry: &mut DesktopEntryInternal, parts: LinePart) -> Result<(
                                 key: "Keywords".into(),
                                 row: parts.line_number,
                                 col: 0,
+                                group: None,
                             });
                         }
 
@@ -452,6 +393,7 @@ fn fill_entry_val(entry: &mut DesktopEntryInternal, parts: LinePart) -> Result<(
                                 key: "Keywords".into(),
                                 row: parts.line_number,
                                 col: 0,
+                                group: None,
                             });
                         }
 
@@ -473,42 +415,173 @@ fn fill_entry_val(entry: &mut DesktopEntryInternal, parts: LinePart) -> Result<(
                 }
             }
         }
-        "StartupNotify" => set_optional_bool(parts, &mut entry.startup_notify)?,
-        "StartupWMClass" => set_optional_str(parts, &mut entry.startup_wm_class)?,
-        "URL" => set_optional_str(parts, &mut entry.url)?,
-        "PrefersNonDefaultGPU" => set_optional_bool(parts, &mut entry.prefers_non_default_gpu)?,
-        "SingleMainWindow" => set_optional_bool(parts, &mut entry.single_main_window)?,
-
-        _ => {}
+        Key::StartupNotify => set_optional_bool(parts, &mut entry.startup_notify)?,
+        Key::StartupWMClass => set_optional_str(parts, &mut entry.startup_wm_class)?,
+        Key::Url => set_optional_str(parts, &mut entry.url)?,
+        Key::PrefersNonDefaultGPU => set_optional_bool(parts, &mut entry.prefers_non_default_gpu)?,
+        Key::SingleMainWindow => set_optional_bool(parts, &mut entry.single_main_window)?,
+
+        _ if run_hooks(hooks, &parts)? => {}
+        _ => set_extra(parts, &mut entry.extras)?,
     }
 
     Ok(())
 }
 
-fn process_entry_val_pair(line: &Line, entry: &mut DesktopEntryInternal) -> Result<(), ParseError> {
-    let parts = split_into_parts(line)?;
+fn fill_action_val(
+    action: &mut DesktopActionInternal,
+    parts: LinePart,
+    hooks: Option<KeyHooks>,
+) -> Result<(), ParseError> {
+    match parts.key {
+        Key::Name => set_optional_locale_str(parts, &mut action.name)?,
+        Key::Exec => set_optional_str(parts, &mut action.exec)?,
+        Key::Icon => set_optional_icon_str(parts, &mut action.icon)?,
+        _ if run_hooks(hooks, &parts)? => {}
+        _ => set_extra(parts, &mut action.extras)?,
+    }
 
-    fill_entry_val(entry, parts)
+    Ok(())
 }
 
-fn fill_action_val(action: &mut DesktopActionInternal, parts: LinePart) -> Result<(), ParseError> {
-    match parts.key.as_str() {
-        "Name" => set_optional_locale_str(parts, &mut action.name)?,
-        "Exec" => set_optional_str(parts, &mut action.exec)?,
-        "Icon" => set_optional_icon_str(parts, &mut action.icon)?,
-        _ => {}
+/// Splits a key like `Name[de]` into the key and the locale, validating its syntax
+pub(crate) fn split_key(key: &str) -> Result<(Key, Option<String>), ParseError> {
+    if key.is_empty() {
+        return Err(ParseError::KeyError {
+            msg: "The key must not be empty".into(),
+        });
     }
 
-    Ok(())
+    let spec = format!("{key}=");
+    let mut events = Events::new(&spec);
+    match (events.next(), events.next()) {
+        (
+            Some(Ok(Event::KeyValue {
+                key: name,
+                locale,
+                value: "",
+                ..
+            })),
+            None,
+        ) if !name.as_str().is_empty() && !key.starts_with(' ') => {
+            Ok((name, locale.map(|l| l.to_string())))
+        }
+        (Some(Err(e)), _) => Err(e),
+        _ => Err(ParseError::KeyError {
+            msg: format!("{key:?} is not a valid key"),
+        }),
+    }
 }
 
-fn process_action_val_pair(
-    line: &Line,
-    action: &mut DesktopActionInternal,
+/// Sets a key of an entry, replacing its previous value. The value is interpreted the same
+/// way the parser interprets values read from a file.
+pub(crate) fn set_entry_key(
+    entry: &mut DesktopEntry,
+    key: &str,
+    value: &str,
 ) -> Result<(), ParseError> {
-    let parts = split_into_parts(line)?;
+    let (key, locale) = split_key(key)?;
+    let mut internal = DesktopEntryInternal::from(entry.clone());
+
+    internal.remove_key(key.as_str(), locale.as_deref());
+    // the bytes of the other keys stay valid
+    let mut raw_values = entry.raw_values.clone();
+    if locale.is_none() {
+        raw_values.forget(&key);
+    }
+    fill_entry_val(
+        &mut internal,
+        LinePart {
+            key,
+            locale,
+            value: value.to_string(),
+            line_number: 0,
+            trimmed: false,
+        },
+        None,
+    )
+    .map_err(|e| e.with_group("Desktop Entry"))?;
+
+    *entry = internal.try_into()?;
+    entry.raw_values = raw_values;
+    Ok(())
+}
+
+/// Builds an entry from flat key value pairs such as `Name[de]`, interpreting the values the
+/// same way the parser interprets values read from a file
+pub(crate) fn entry_from_key_map(
+    map: &HashMap<String, String>,
+) -> Result<DesktopEntry, ParseError> {
+    let mut pairs = map.iter().collect::<Vec<_>>();
+    pairs.sort();
+
+    let mut internal = DesktopEntryInternal::default();
+    for (key, value) in pairs {
+        let (key, locale) = split_key(key)?;
+        fill_entry_val(
+            &mut internal,
+            LinePart {
+                key,
+                locale,
+                value: value.to_string(),
+                line_number: 0,
+                trimmed: false,
+            },
+            None,
+        )?;
+    }
+
+    internal.try_into()
+}
+
+/// Removes a key of an entry, returning whether it was present. Fails if the entry would be
+/// missing a required key afterwards.
+pub(crate) fn remove_entry_key(entry: &mut DesktopEntry, key: &str) -> Result<bool, ParseError> {
+    let (key, locale) = split_key(key)?;
+    let mut internal = DesktopEntryInternal::from(entry.clone());
+
+    let removed = internal.remove_key(key.as_str(), locale.as_deref());
+    let mut raw_values = entry.raw_values.clone();
+    if locale.is_none() {
+        raw_values.forget(&key);
+    }
+    *entry = internal.try_into()?;
+    entry.raw_values = raw_values;
+    Ok(removed)
+}
 
-    fill_action_val(action, parts)
+/// A key value pair as it is written in the source, together with the group it belongs to
+#[derive(Debug, Clone)]
+pub(crate) struct RawPair {
+    /// The group of the pair, None if the pair appears before any header
+    pub header: Option<Header>,
+    pub key: String,
+    pub locale: Option<String>,
+    pub value: String,
+    pub line_number: usize,
+}
+
+/// Splits the input into key value pairs without interpreting them
+pub(crate) fn raw_pairs(input: &str) -> Result<Vec<RawPair>, ParseError> {
+    let mut header = None;
+    let mut result = vec![];
+
+    for token in tokens(input) {
+        match token? {
+            Token::Group { name, .. } => header = Some(Header::from_name(name)),
+            Token::Pair(parts) => {
+                result.push(RawPair {
+                    header: header.clone(),
+                    key: parts.key.to_string(),
+                    locale: parts.locale,
+                    value: parts.value,
+                    line_number: parts.line_number,
+                });
+            }
+        }
+    }
+
+    Ok(result)
 }
 
 /// Parses a desktop file's content into a structured DesktopFile.
@@ -531,104 +604,663 @@ fn process_action_val_pair(
 /// assert_eq!(desktop_file.entry.name.default, "Firefox");
 /// ```
 pub fn parse(input: &str) -> Result<DesktopFile, ParseError> {
-    let mut lines = filter_lines(input);
-    let result_entry = Rc::new(RefCell::new(DesktopEntryInternal::default()));
+    parse_with_options(input, &ParseOptions::default())
+}
+
+/// Options relaxing the rules of the parser for files that don't follow the specification,
+/// or leaving out parts of the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Skip groups preceding `[Desktop Entry]` instead of failing, collecting them into
+    /// [`DesktopFile::other_groups`]
+    pub skip_leading_groups: bool,
+    /// Let a later variant of a localized key replace an earlier one with the same locale,
+    /// such as `Name[DE]` after `Name[de]`, with a warning instead of failing
+    pub duplicate_locales_last_wins: bool,
+    /// Keep the whitespace at the end of values. The whitespace between `=` and the value is
+    /// always dropped, as the specification says, while trailing whitespace is dropped by
+    /// default with a warning whenever that changed a value.
+    pub keep_trailing_whitespace: bool,
+    /// Fail on group headers and keys indented with spaces or tabs, with an error at column 0.
+    /// By default any indentation is ignored, as real files often have it even though the
+    /// specification doesn't allow it.
+    pub reject_indentation: bool,
+    /// Parse the `[Desktop Action …]` groups, true by default. When false their lines are
+    /// skipped without being read, so [`DesktopFile::actions`] is empty while the Actions key
+    /// still lists the declared ids, and errors inside the skipped groups are not reported.
+    /// Serializing such a file writes an empty Actions key, as no action is present.
+    pub parse_actions: bool,
+    /// Keep the comments in [`DesktopFile::comments`], each block attached to the group or
+    /// the key that follows it, so the serializer writes them back in place
+    pub keep_comments: bool,
+    /// Accept an entry or action whose Name only has locale variants, promoting the `en`
+    /// variant to the default, else the `C` one, else the first in alphabetical order, with a
+    /// warning instead of failing
+    pub promote_name_variant: bool,
+    /// Merge the keys of a repeated `[Desktop Entry]` group into the first one with a warning
+    /// instead of failing. Keys repeated across the groups follow the same rules as within a
+    /// group, so only new keys and variants can be added.
+    pub merge_duplicate_entry_groups: bool,
+    /// Keep the bytes of the Exec, TryExec, Path and Icon values that are not valid UTF-8 in
+    /// [`DesktopEntry::raw_values`], when parsing with [`parse_bytes_with_options`]
+    pub keep_raw_bytes: bool,
+    /// Fail when the Actions key lists an id without a `[Desktop Action …]` group, with an
+    /// error naming the id and the line of the key. By default such ids are ignored, as the
+    /// specification says, and only the actions that exist are read. Has no effect when
+    /// [`ParseOptions::parse_actions`] is false.
+    pub require_action_groups: bool,
+    /// Handlers for extension keys, called with each key that would otherwise be kept in the
+    /// extras of its group, see [`KeyHandlers`]
+    pub key_handlers: KeyHandlers,
+}
 
-    let mut is_entry_found = false;
-    let mut is_first_entry = true;
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            skip_leading_groups: false,
+            duplicate_locales_last_wins: false,
+            keep_trailing_whitespace: false,
+            reject_indentation: false,
+            parse_actions: true,
+            keep_comments: false,
+            promote_name_variant: false,
+            merge_duplicate_entry_groups: false,
+            keep_raw_bytes: false,
+            require_action_groups: false,
+            key_handlers: KeyHandlers::default(),
+        }
+    }
+}
+
+/// Parses a desktop file that may not be valid UTF-8, replacing invalid sequences with
+/// U+FFFD in the typed fields
+pub fn parse_bytes(input: &[u8]) -> Result<DesktopFile, ParseError> {
+    parse_bytes_with_options(input, &ParseOptions::default())
+}
+
+/// Parses a desktop file that may not be valid UTF-8 like [`parse_bytes`], with options. With
+/// [`ParseOptions::keep_raw_bytes`] the values naming files also keep their original bytes.
+pub fn parse_bytes_with_options(
+    input: &[u8],
+    options: &ParseOptions,
+) -> Result<DesktopFile, ParseError> {
+    let mut file = parse_with_options(&String::from_utf8_lossy(input), options)?;
+    if options.keep_raw_bytes {
+        file.entry.raw_values = crate::bytes::raw_values(input);
+    }
+    Ok(file)
+}
+
+/// Parses a desktop file's content like [`parse`], with the rules relaxed by the options
+pub fn parse_with_options(input: &str, options: &ParseOptions) -> Result<DesktopFile, ParseError> {
+    parse_with_warnings(input, options).map(|(file, _)| file)
+}
+
+/// Parses a desktop file's content like [`parse_with_options`], also returning the warnings
+/// about the problems the options let through
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(len = input.len()))
+)]
+pub fn parse_with_warnings(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(DesktopFile, Vec<ParseWarning>), ParseError> {
+    parse_file(input, options, None)
+}
+
+/// Parses a file like [`parse_with_warnings`]. When `translatable` is set, keys written with a
+/// leading underscore like `_Name` are accepted as the key without it and collected there.
+pub(crate) fn parse_file(
+    input: &str,
+    options: &ParseOptions,
+    mut translatable: Option<&mut Vec<TranslatableKey>>,
+) -> Result<(DesktopFile, Vec<ParseWarning>), ParseError> {
+    if input
+        .strip_prefix('\u{FEFF}')
+        .unwrap_or(input)
+        .trim()
+        .is_empty()
+    {
+        return Err(ParseError::EmptyInput);
+    }
+
+    let mut warnings = vec![];
+    // the keys claimed by the key handlers, by group
+    let mut claimed = HashSet::new();
+    let mut entry = DesktopEntryInternal::default();
+    // the line of the [Desktop Entry] header
+    let mut entry_line = None;
 
     let mut result_actions: Vec<DesktopActionInternal> = vec![];
-    let mut current_target = EntryType::Entry(result_entry.clone());
+    // the header row of the group of each action
+    let mut action_rows: HashMap<String, usize> = HashMap::new();
+    let mut other_groups: Vec<Group> = vec![];
+    let mut current_target = Target::Entry;
+    #[cfg(feature = "tracing")]
+    let mut group_span: Option<tracing::span::EnteredSpan> = None;
+
+    let mut comments = Comments::default();
+    let mut pending = options.keep_comments.then(Vec::new);
+    let mut current_group = String::new();
+    let mut first_content = None;
+    // the first key before any group header, with its line
+    let mut stray_key: Option<(String, usize)> = None;
+    // the line of the Actions key
+    let mut actions_row = None;
+
+    let mut events = Events::new(input)
+        .keep_trailing_whitespace(options.keep_trailing_whitespace)
+        .reject_indentation(options.reject_indentation)
+        .template_keys(translatable.is_some());
+    while let Some(token) = next_token(&mut events, &mut pending) {
+        let mut token = token.map_err(|e| match current_group.as_str() {
+            "" => e,
+            group => e.with_group(group),
+        })?;
+        first_content.get_or_insert(events.span().line);
 
-    for line in lines.iter_mut() {
-        if line.content.len() == 0 {
-            continue;
+        if let Token::Group {
+            ref mut name,
+            line_number,
+        } = token
+        {
+            let normalized = Header::from_name(name.clone()).name();
+            if normalized != *name {
+                warnings.push(ParseWarning::PaddedGroupHeader {
+                    written: std::mem::replace(name, normalized.clone()),
+                    row: line_number,
+                    group: normalized,
+                });
+            }
         }
-        match current_target {
-            EntryType::Entry(ref entry) => match line.line_type() {
-                LineType::Header => {
-                    match parse_header(line)? {
-                        Header::DesktopEntry => {
-                            if is_entry_found {
-                                return Err(ParseError::RepetitiveEntry {
-                                    msg: "none".into(),
-                                    row: line.line_number,
-                                    col: 0,
-                                });
-                            } else {
-                                is_entry_found = true;
-                            }
 
-                            if !is_first_entry {
-                                return Err(ParseError::InternalError { msg: "it should be able to return error when entry is not in the first header".into(), row: line.line_number, col: 0 });
-                            } else {
-                                is_first_entry = false;
-                            }
-                        }
-                        Header::DesktopAction { name } => {
-                            if !is_entry_found {
-                                return Err(ParseError::InternalError { msg: "it should be able to return error when an action appears before an entry".into(), row: line.line_number, col: 0 });
-                            }
+        if let Some(lines) = pending.as_mut().filter(|p| !p.is_empty()) {
+            let lines = std::mem::take(lines);
+            match token {
+                Token::Group { ref name, .. } => comments.set_group(name, lines),
+                Token::Pair(ref parts) => {
+                    let key = match parts.locale {
+                        Some(ref locale) => format!("{}[{locale}]", parts.key),
+                        None => parts.key.to_string(),
+                    };
+                    comments.set_key(&current_group, &key, lines);
+                }
+            }
+        }
+        if let Token::Group { ref name, .. } = token {
+            current_group.clone_from(name);
+        }
+        if let (Token::Pair(ref parts), Some(keys)) = (&token, translatable.as_deref_mut()) {
+            if events.is_translatable() {
+                keys.push(TranslatableKey {
+                    group: current_group.clone(),
+                    key: parts.key.to_string(),
+                });
+            }
+        }
 
-                            if is_first_entry {
-                                return Err(ParseError::FormatError {
-                                    msg: "none".into(),
-                                    row: line.line_number,
-                                    col: 0,
-                                });
-                            }
+        #[cfg(feature = "tracing")]
+        if let Token::Group {
+            ref name,
+            line_number,
+        } = token
+        {
+            // the span of the previous group is exited before entering the next one
+            drop(group_span.take());
+            group_span =
+                Some(tracing::debug_span!("group", name = %name, line = line_number).entered());
+        }
 
-                            result_actions.push(DesktopActionInternal {
-                                ref_name: name,
-                                ..Default::default()
-                            });
+        // keys before the first header are only reported once a header shows the file has
+        // groups, a file without any stays a missing [Desktop Entry] group
+        if let (Token::Group { .. }, Some((key, row))) = (&token, &stray_key) {
+            return Err(ParseError::FormatError {
+                msg: format!("the key {key:?} appears before any group header"),
+                row: *row,
+                col: 0,
+            });
+        }
 
-                            current_target = EntryType::Action(result_actions.len() - 1);
+        match token {
+            Token::Group { name, line_number } => match Header::from_name(name) {
+                Header::DesktopEntry => {
+                    match entry_line {
+                        Some(first_row) if options.merge_duplicate_entry_groups => {
+                            warnings.push(ParseWarning::DuplicateEntryGroup {
+                                first_row,
+                                row: line_number,
+                            });
                         }
-                        _ => {}
-                    };
-                }
-                LineType::ValPair => {
-                    process_entry_val_pair(line, &mut entry.borrow_mut())?;
+                        Some(_) => {
+                            return Err(ParseError::RepetitiveEntry {
+                                msg: "There should only be one entry on top".into(),
+                                row: line_number,
+                                col: 0,
+                            });
+                        }
+                        None => entry_line = Some(line_number),
+                    }
+                    current_target = Target::Entry;
                 }
-            },
+                Header::DesktopAction { name } => {
+                    if entry_line.is_none() {
+                        return Err(ParseError::FormatError {
+                            msg: format!(
+                                "the action group {name:?} appears before [Desktop Entry]"
+                            ),
+                            row: line_number,
+                            col: 0,
+                        });
+                    }
+
+                    if !options.parse_actions {
+                        events.skip_group();
+                        continue;
+                    }
 
-            EntryType::Action(index) => match line.line_type() {
-                LineType::Header => match parse_header(line)? {
-                    Header::DesktopEntry => {
+                    if let Some(first_row) = action_rows.insert(name.clone(), line_number) {
                         return Err(ParseError::RepetitiveEntry {
-                            msg: "There should only be one entry on top".into(),
-                            row: line.line_number,
+                            msg: format!(
+                                "the action group {name:?} repeats the one at line {first_row}"
+                            ),
+                            row: line_number,
                             col: 0,
                         });
                     }
-                    Header::DesktopAction { name } => {
-                        result_actions.push(DesktopActionInternal {
-                            ref_name: name,
-                            ..Default::default()
+                    result_actions.push(DesktopActionInternal {
+                        ref_name: name,
+                        ..Default::default()
+                    });
+                    current_target = Target::Action(result_actions.len() - 1);
+                }
+                Header::Other { name } => {
+                    if entry_line.is_none() && !options.skip_leading_groups {
+                        return Err(ParseError::FormatError {
+                            msg: format!("the group {name:?} appears before [Desktop Entry]"),
+                            row: line_number,
+                            col: 0,
                         });
-                        current_target = EntryType::Action(result_actions.len() - 1)
                     }
-                    _ => {}
-                },
-                LineType::ValPair => {
-                    let target = &mut result_actions[index];
-                    process_action_val_pair(line, target)?;
+                    #[cfg(feature = "tracing")]
+                    if entry_line.is_none() {
+                        tracing::debug!(
+                            line = line_number,
+                            "skipping group before [Desktop Entry]"
+                        );
+                    }
+
+                    other_groups.push(Group {
+                        name,
+                        line_number,
+                        entries: vec![],
+                    });
+                    current_target = Target::Other(other_groups.len() - 1);
                 }
             },
+            Token::Pair(parts) if current_group.is_empty() => {
+                stray_key.get_or_insert((parts.key.to_string(), parts.line_number));
+            }
+            Token::Pair(parts) => {
+                if parts.trimmed {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(key = %parts.key, line = parts.line_number, "trimmed trailing whitespace");
+                    warnings.push(ParseWarning::TrailingWhitespace {
+                        key: parts.key.to_string(),
+                        row: parts.line_number,
+                        group: current_group.clone(),
+                    });
+                }
+
+                match current_target {
+                    Target::Entry => {
+                        if parts.key == Key::Actions {
+                            actions_row = Some(parts.line_number);
+                        }
+                        if let Some(ref locale) = parts.locale {
+                            if options.duplicate_locales_last_wins
+                                && entry.remove_key(parts.key.as_str(), Some(locale))
+                            {
+                                warnings.push(duplicate_locale_warning(
+                                    &parts,
+                                    locale,
+                                    &current_group,
+                                ));
+                            }
+                        }
+                        let hooks = KeyHooks {
+                            handlers: &options.key_handlers,
+                            group: &current_group,
+                            warnings: &mut warnings,
+                            claimed: &mut claimed,
+                        };
+                        fill_entry_val(&mut entry, parts, Some(hooks))
+                            .map_err(|e| e.with_group(&current_group))?
+                    }
+                    Target::Action(index) => {
+                        let action = &mut result_actions[index];
+                        if let Some(ref locale) = parts.locale {
+                            if options.duplicate_locales_last_wins
+                                && action.remove_variant(parts.key.as_str(), locale)
+                            {
+                                warnings.push(duplicate_locale_warning(
+                                    &parts,
+                                    locale,
+                                    &current_group,
+                                ));
+                            }
+                        }
+                        let hooks = KeyHooks {
+                            handlers: &options.key_handlers,
+                            group: &current_group,
+                            warnings: &mut warnings,
+                            claimed: &mut claimed,
+                        };
+                        fill_action_val(action, parts, Some(hooks))
+                            .map_err(|e| e.with_group(&current_group))?
+                    }
+                    Target::Other(index) => other_groups[index].entries.push(KeyValue {
+                        key: parts.key.to_string(),
+                        locale: parts.locale,
+                        value: parts.value,
+                        line_number: parts.line_number,
+                    }),
+                }
+            }
+        }
+    }
+
+    if entry_line.is_none() {
+        return Err(ParseError::MissingDesktopEntryGroup { row: first_content });
+    }
+
+    if let Some(ref mut name) = entry.name {
+        fill_default_name(name, "Desktop Entry", options, &mut warnings)?;
+    }
+    let declared = entry
+        .actions
+        .iter()
+        .flatten()
+        .map(String::as_str)
+        .collect::<HashSet<_>>();
+    for action in &mut result_actions {
+        let declared = declared.contains(action.ref_name.as_str());
+        if let (true, Some(name)) = (declared, action.name.as_mut()) {
+            let group = format!("Desktop Action {}", action.ref_name);
+            fill_default_name(name, &group, options, &mut warnings)?;
+        }
+    }
+
+    let (listed, unlisted): (Vec<_>, Vec<_>) = result_actions
+        .into_iter()
+        .partition(|action| declared.contains(action.ref_name.as_str()));
+    let actions = vec_to_map(listed)?;
+    if let (true, true, Some(row)) = (
+        options.require_action_groups,
+        options.parse_actions,
+        actions_row,
+    ) {
+        let missing = entry
+            .actions
+            .iter()
+            .flatten()
+            .find(|id| !actions.contains_key(*id));
+        if let Some(id) = missing {
+            return Err(ParseError::KeyError {
+                msg: format!(
+                    "the action {id:?} listed by Actions at line {row} has no [Desktop Action {id}] group"
+                ),
+            });
+        }
+    }
+    let unlisted_actions = unlisted
+        .into_iter()
+        .map(DesktopActionInternal::into_unchecked)
+        .collect();
+
+    comments.trailing = pending.unwrap_or_default();
+    let file = DesktopFile {
+        entry: entry.try_into().map_err(|e| match e {
+            ParseError::MissingUrl { row: None } => ParseError::MissingUrl { row: entry_line },
+            e => e,
+        })?,
+        actions,
+        unlisted_actions,
+        other_groups,
+        comments,
+    };
+    Ok((file, warnings))
+}
+
+/// Gives a Name written only with locale variants a default, when the options allow it, or
+/// fails naming the variants that exist
+fn fill_default_name(
+    name: &mut LocaleStringInternal,
+    group: &str,
+    options: &ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<(), ParseError> {
+    if name.default.is_some() {
+        return Ok(());
+    }
+
+    if !options.promote_name_variant {
+        return Err(missing_default("Name", group, &name.variants));
+    }
+
+    let mut locales = name.variants.keys().collect::<Vec<_>>();
+    locales.sort();
+
+    // the variants are keyed by canonical locales, which spell C in lowercase
+    let locale = ["en", "C"]
+        .map(canonicalize_locale)
+        .into_iter()
+        .find(|l| name.variants.contains_key(l))
+        .or(locales.first().map(|l| l.to_string()))
+        .unwrap_or_default();
+    name.default = name.variants.get(&locale).cloned();
+    warnings.push(ParseWarning::PromotedName {
+        locale,
+        group: group.to_string(),
+    });
+    Ok(())
+}
+
+/// Sets a localized string for [`parse_unchecked`], replacing an earlier value
+fn overwrite_locale_str(opt: &mut Option<LocaleStringInternal>, locale: Option<&str>, value: &str) {
+    let str = opt.get_or_insert_with(Default::default);
+    match locale {
+        Some(locale) => {
+            str.variants
+                .insert(locale.to_string(), unescape_string(value));
         }
+        None => str.default = Some(unescape_string(value)),
     }
+}
 
-    let mut entry = result_entry.take();
-    let actions = match entry.actions {
-        Some(ref mut d) => vec_to_map(result_actions, d)?,
-        None => HashMap::new(),
+/// Sets a key of the entry for [`parse_unchecked`]: the value replaces an earlier one and a
+/// value that fails to parse is skipped
+fn overwrite_entry_val(
+    entry: &mut DesktopEntryInternal,
+    key: Key,
+    locale: Option<&str>,
+    value: &str,
+) {
+    let string = || Some(unescape_string(value));
+    let list = || Some(parse_list(value));
+    let bool = |field: &mut Option<bool>| {
+        if let Ok(value) = value.parse() {
+            *field = Some(value);
+        }
     };
 
-    Ok(DesktopFile {
-        entry: entry.try_into()?,
+    match (key, locale) {
+        (Key::Name, _) => overwrite_locale_str(&mut entry.name, locale, value),
+        (Key::GenericName, _) => overwrite_locale_str(&mut entry.generic_name, locale, value),
+        (Key::Comment, _) => overwrite_locale_str(&mut entry.comment, locale, value),
+        (Key::Keywords, _) => {
+            let keywords = entry.keywords.get_or_insert_with(Default::default);
+            match locale {
+                Some(locale) => {
+                    keywords
+                        .variants
+                        .insert(locale.to_string(), parse_list(value));
+                }
+                None => keywords.default = list(),
+            }
+        }
+        (key, Some(locale)) => {
+            entry
+                .extras
+                .insert(format!("{key}[{locale}]"), value.to_string());
+        }
+        (Key::Type, None) => {
+            entry.entry_type = Some(crate::internal_structs::EntryTypeInternal::from(value))
+        }
+        (Key::Version, None) => entry.version = string(),
+        (Key::NoDisplay, None) => bool(&mut entry.no_display),
+        (Key::Icon, None) => {
+            entry.icon = Some(IconString {
+                content: unescape_string(value),
+            })
+        }
+        (Key::Hidden, None) => bool(&mut entry.hidden),
+        (Key::OnlyShowIn, None) => entry.only_show_in = list(),
+        (Key::NotShowIn, None) => entry.not_show_in = list(),
+        (Key::DBusActivatable, None) => bool(&mut entry.dbus_activatable),
+        (Key::TryExec, None) => entry.try_exec = string(),
+        (Key::Exec, None) => entry.exec = string(),
+        (Key::Path, None) => entry.path = string(),
+        (Key::Terminal, None) => bool(&mut entry.terminal),
+        (Key::Actions, None) => entry.actions = list(),
+        (Key::MimeType, None) => entry.mime_type = list(),
+        (Key::Categories, None) => entry.categories = list(),
+        (Key::Implements, None) => entry.implements = list(),
+        (Key::StartupNotify, None) => bool(&mut entry.startup_notify),
+        (Key::StartupWMClass, None) => entry.startup_wm_class = string(),
+        (Key::Url, None) => entry.url = string(),
+        (Key::PrefersNonDefaultGPU, None) => bool(&mut entry.prefers_non_default_gpu),
+        (Key::SingleMainWindow, None) => bool(&mut entry.single_main_window),
+        (key, None) => {
+            entry.extras.insert(key.to_string(), value.to_string());
+        }
+    }
+}
+
+/// Sets a key of an action for [`parse_unchecked`], replacing an earlier value
+fn overwrite_action_val(
+    action: &mut DesktopActionInternal,
+    key: Key,
+    locale: Option<&str>,
+    value: &str,
+) {
+    match (key, locale) {
+        (Key::Name, _) => overwrite_locale_str(&mut action.name, locale, value),
+        (Key::Exec, None) => action.exec = Some(unescape_string(value)),
+        (Key::Icon, None) => {
+            action.icon = Some(IconString {
+                content: unescape_string(value),
+            })
+        }
+        (key, Some(locale)) => {
+            action
+                .extras
+                .insert(format!("{key}[{locale}]"), value.to_string());
+        }
+        (key, None) => {
+            action.extras.insert(key.to_string(), value.to_string());
+        }
+    }
+}
+
+/// Parses a desktop file that is known to be well formed, such as one this crate serialized,
+/// skipping the checks of [`parse`]: repeated keys and groups replace the earlier ones, lines
+/// that fail to tokenize and values that fail to parse are skipped, missing required keys are
+/// left empty, and locales are taken as written instead of being canonicalized. An entry
+/// without a Type gets [`EntryType::Unknown`](crate::EntryType).
+///
+/// On malformed input the result is best effort, but this never panics.
+pub fn parse_unchecked(input: &str) -> DesktopFile {
+    let mut entry = DesktopEntryInternal::default();
+    let mut actions: Vec<DesktopActionInternal> = vec![];
+    let mut other_groups: Vec<Group> = vec![];
+    let mut current_target = Target::Entry;
+
+    // the values are set straight from the borrowed lines, without the duplicate checks of
+    // parse, and the locales are taken as written since the serializer writes them canonical
+    let mut events = Events::new(input);
+    while let Some(event) = events.next() {
+        match event {
+            Ok(Event::GroupStart(name)) => match Header::from_name(name.to_string()) {
+                Header::DesktopEntry => current_target = Target::Entry,
+                Header::DesktopAction { name } => {
+                    actions.push(DesktopActionInternal {
+                        ref_name: name,
+                        ..Default::default()
+                    });
+                    current_target = Target::Action(actions.len() - 1);
+                }
+                Header::Other { name } => {
+                    other_groups.push(Group {
+                        name,
+                        line_number: events.span().line,
+                        entries: vec![],
+                    });
+                    current_target = Target::Other(other_groups.len() - 1);
+                }
+            },
+            Ok(Event::KeyValue {
+                key,
+                locale,
+                value,
+                span,
+                ..
+            }) => match current_target {
+                Target::Entry => overwrite_entry_val(&mut entry, key, locale, value),
+                Target::Action(index) => {
+                    overwrite_action_val(&mut actions[index], key, locale, value)
+                }
+                Target::Other(index) => other_groups[index].entries.push(KeyValue {
+                    key: key.to_string(),
+                    locale: locale.map(str::to_string),
+                    value: value.to_string(),
+                    line_number: span.line,
+                }),
+            },
+            Ok(Event::Comment(_)) | Err(_) => {}
+        }
+    }
+
+    let listed = entry.actions.clone().unwrap_or_default();
+    let (actions, unlisted_actions) = actions
+        .into_iter()
+        .map(DesktopActionInternal::into_unchecked)
+        .partition(|(id, _)| listed.contains(id));
+
+    DesktopFile {
+        entry: entry.into_unchecked(),
         actions,
-    })
+        unlisted_actions,
+        other_groups,
+        comments: Comments::default(),
+    }
+}
+
+fn duplicate_locale_warning(parts: &LinePart, locale: &str, group: &str) -> ParseWarning {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        key = %parts.key,
+        locale,
+        line = parts.line_number,
+        "replacing the earlier variant with the same locale"
+    );
+
+    ParseWarning::DuplicateLocale {
+        key: parts.key.to_string(),
+        locale: locale.to_string(),
+        row: parts.line_number,
+        group: group.to_string(),
+    }
 }
 
 #[cfg(test)]
@@ -637,15 +1269,36 @@ mod tests {
 
     #[test]
     fn filter_lines_test() {
-        let res = filter_lines("aaa你好 \n\n\n aaaa\n           #sadas")
-            .iter()
-            .map(|l| l.to_string())
+        let res = tokens("[aaa你好] \n\n\n aaaa=1\n           #sadas")
+            .map(|t| match t.unwrap() {
+                Token::Group { name, .. } => name,
+                Token::Pair(parts) => parts.key.to_string(),
+            })
             .collect::<Vec<_>>();
 
-        println!("{:?}", res);
         assert_eq!(vec!["aaa你好", "aaaa"], res);
     }
 
+    #[test]
+    fn test_whitespace_lines() {
+        let file = parse(
+            "[Desktop Entry]\nType=Application\nName=A\nActions=x;\n   \n\t\n[Desktop Action x]\nName=X\n",
+        )
+        .unwrap();
+        assert_eq!(file.actions["x"].name.default, "X");
+
+        let file = parse("[Desktop Entry]\nType=Application\nName=A\n \t ").unwrap();
+        assert_eq!(file.entry.name.default, "A");
+        assert_eq!(
+            parse_unchecked("[Desktop Entry]\nName=A\n\t")
+                .entry
+                .name
+                .default,
+            "A"
+        );
+        assert!(matches!(parse("   \n\t\n"), Err(ParseError::EmptyInput)));
+    }
+
     #[test]
     fn test_clense() {
         let content = r#"
@@ -653,9 +1306,61 @@ Name = a
 Type = Application
         "#;
 
-        let l = filter_lines(content);
-        let parts = split_into_parts(&l[0]).unwrap();
-        assert_eq!(parts.key, "Name".to_string());
+        let Token::Pair(parts) = tokens(content).next().unwrap().unwrap() else {
+            panic!("expected a key value pair");
+        };
+        assert_eq!(parts.key, Key::Name);
         assert_eq!(parts.value, "a".to_string());
     }
+
+    #[test]
+    fn test_string_escapes() {
+        let file =
+            parse("[Desktop Entry]\nType=Application\nName=\\sA\\tB\\\\C\\x\nExec=foo").unwrap();
+        assert_eq!(file.entry.name.default, " A\tB\\C\\x");
+        assert_eq!(
+            file.to_desktop_string(),
+            "[Desktop Entry]\nType=Application\nName=\\sA\\tB\\\\C\\\\x\nExec=foo\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_unchecked() {
+        let content = "[Desktop Entry]\nType=Application\nName[de]=Alt\nName=Old\nName=New\nTerminal=maybe\nbad key\nExec=app\nActions=new;\n\n[Desktop Action new]\nName=New Window\n\n[Desktop Action gone]\nName=Gone\n";
+        let file = parse_unchecked(content);
+        assert_eq!(file.entry.name.default, "New");
+        assert_eq!(file.entry.name.get_variant("de"), "Alt");
+        assert_eq!(file.actions["new"].name.default, "New Window");
+        assert_eq!(file.actions.len(), 1);
+
+        let file = parse_unchecked(
+            "[Desktop Entry]\nType=Application\nName=A\nTerminal=true\nTerminal=maybe\nX-Foo=1\nX-Foo=2\nExec=a\nExec=b\n",
+        );
+        assert_eq!(file.entry.extras["X-Foo"], "2");
+        let crate::EntryType::Application(ref fields) = file.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(fields.terminal, Some(true));
+        assert_eq!(fields.exec.as_deref(), Some("b"));
+
+        let file = parse_unchecked("[Desktop Entry]\nName[de]=Nur\n");
+        assert!(matches!(file.entry.entry_type, crate::EntryType::Unknown(ref t) if t.is_empty()));
+        assert_eq!(file.entry.name.default, "");
+
+        let browser = include_str!("../benches/data/browser.desktop");
+        let (unchecked, checked) = (parse_unchecked(browser), parse(browser).unwrap());
+        assert_eq!(unchecked.entry.name.variants, checked.entry.name.variants);
+        assert_eq!(unchecked.actions.len(), checked.actions.len());
+        for (id, action) in &checked.actions {
+            assert_eq!(unchecked.actions[id].name.variants, action.name.variants);
+            assert_eq!(unchecked.actions[id].exec, action.exec);
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_parse_unchecked_never_panics(input in "(\\[[^\\n]*\\]?\n|[A-Za-z\\[\\]@_. -]*=?[^\\n]*\n|\\PC*){0,12}") {
+            let _ = parse_unchecked(&input);
+        }
+    }
 }
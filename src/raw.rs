@@ -0,0 +1,174 @@
+//! Untyped access to single values of any group, for the keys the typed model doesn't cover.
+
+use crate::{
+    keyfile::Group,
+    keys::Key,
+    locale::canonicalize_locale,
+    parser::{split_key, unescape_string},
+    serializer::escape_string,
+    DesktopFile, IconString, ParseError,
+};
+
+/// Splits a key like `Name[de]` into the key and its canonical locale
+fn split(key: &str) -> Result<(Key, Option<String>), ParseError> {
+    let (key, locale) = split_key(key)?;
+    Ok((key, locale.as_deref().map(canonicalize_locale)))
+}
+
+/// Joins a key and a locale into a key like `Name[de]`
+fn join(key: &Key, locale: Option<&str>) -> String {
+    match locale {
+        Some(locale) => format!("{key}[{locale}]"),
+        None => key.to_string(),
+    }
+}
+
+/// Checks that a group name can be written as a header
+fn check_group(group: &str) -> Result<(), ParseError> {
+    if group.is_empty() || group.contains(['[', ']']) || group.contains(char::is_control) {
+        return Err(ParseError::KeyError {
+            msg: format!("{group:?} is not a valid group name"),
+        });
+    }
+    Ok(())
+}
+
+impl DesktopFile {
+    /// Returns the value of a key of any group, such as `X-GNOME-Autostart-Delay` of
+    /// `Desktop Entry` or `Name[de]` of `Desktop Action new`.
+    ///
+    /// Values are plain strings, their escape sequences resolved, so this returns what
+    /// [`DesktopFile::set_raw`] was given. Lists come back in their written form, like `a;b;`.
+    pub fn get_raw(&self, group: &str, key: &str) -> Option<String> {
+        let (key, locale) = split(key).ok()?;
+        let full_key = join(&key, locale.as_deref());
+
+        let value = if group == "Desktop Entry" {
+            self.entry.to_key_map().remove(&full_key)?
+        } else if let Some(id) = group.strip_prefix("Desktop Action ") {
+            self.actions.get(id)?.to_key_map().remove(&full_key)?
+        } else {
+            let group = self.other_groups.iter().rev().find(|g| g.name == group)?;
+            group
+                .entries
+                .iter()
+                .rev()
+                .find(|e| e.key == key.as_str() && e.locale == locale)?
+                .value
+                .clone()
+        };
+
+        Some(unescape_string(&value))
+    }
+
+    /// Sets a key of any group, escaping the value. Other groups are created when missing,
+    /// while actions must be added with [`DesktopFile::add_action`] first.
+    ///
+    /// Standard keys of the Desktop Entry and of actions update the typed fields, with the
+    /// value interpreted the way the parser does, so an invalid value like `Terminal=maybe`
+    /// is rejected and the file is left unchanged.
+    pub fn set_raw(&mut self, group: &str, key: &str, value: &str) -> Result<(), ParseError> {
+        check_group(group)?;
+        let (key, locale) = split(key)?;
+        let full_key = join(&key, locale.as_deref());
+        let escaped = escape_string(value);
+
+        if group == "Desktop Entry" {
+            return self.entry.set_key(&full_key, &escaped);
+        }
+
+        if let Some(id) = group.strip_prefix("Desktop Action ") {
+            let action = self
+                .actions
+                .get_mut(id)
+                .ok_or_else(|| ParseError::KeyError {
+                    msg: format!("the action {id:?} doesn't exist"),
+                })?;
+
+            match (key, locale) {
+                (Key::Name, Some(locale)) => {
+                    action.name.variants.insert(locale, value.to_string());
+                }
+                (Key::Name, None) => action.name.default = value.to_string(),
+                (Key::Exec, None) => action.exec = Some(value.to_string()),
+                (Key::Icon, None) => {
+                    action.icon = Some(IconString {
+                        content: value.to_string(),
+                    })
+                }
+                _ => {
+                    action.extras.insert(full_key, escaped);
+                }
+            }
+            return Ok(());
+        }
+
+        let index = match self.other_groups.iter().position(|g| g.name == group) {
+            Some(index) => index,
+            None => {
+                self.other_groups.push(Group {
+                    name: group.to_string(),
+                    line_number: 0,
+                    entries: vec![],
+                });
+                self.other_groups.len() - 1
+            }
+        };
+        self.other_groups[index].set_raw(key.as_str(), locale.as_deref(), &escaped);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, EntryType};
+
+    #[test]
+    fn test_raw() {
+        let mut file = parse(
+            "[Desktop Entry]\nType=Application\nName=App\nExec=app\nX-GNOME-Autostart-Delay=5\nActions=new;\n\n[Desktop Action new]\nName=New\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            file.get_raw("Desktop Entry", "X-GNOME-Autostart-Delay")
+                .as_deref(),
+            Some("5")
+        );
+        assert_eq!(
+            file.get_raw("Desktop Entry", "Exec").as_deref(),
+            Some("app")
+        );
+        assert_eq!(file.get_raw("Desktop Entry", "Comment"), None);
+
+        file.set_raw("Desktop Entry", "Name[DE]", " Anwendung")
+            .unwrap();
+        assert_eq!(file.entry.name.variants["de"], " Anwendung");
+        assert_eq!(
+            file.get_raw("Desktop Entry", "Name[de]").as_deref(),
+            Some(" Anwendung")
+        );
+        assert!(file.set_raw("Desktop Entry", "Terminal", "maybe").is_err());
+        assert!(file.set_raw("Desktop Entry", "bad key", "1").is_err());
+        file.set_raw("Desktop Entry", "Terminal", "true").unwrap();
+        let EntryType::Application(ref fields) = file.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(fields.terminal, Some(true));
+
+        file.set_raw("Desktop Action new", "Name[fr]", "Nouveau")
+            .unwrap();
+        assert_eq!(
+            file.get_raw("Desktop Action new", "Name[fr]").as_deref(),
+            Some("Nouveau")
+        );
+        assert!(file.set_raw("Desktop Action old", "Name", "Old").is_err());
+
+        file.set_raw("X-My Group", "Foo", "a\tb").unwrap();
+        assert!(file.set_raw("X-[Bad]", "Foo", "bar").is_err());
+        assert_eq!(file.get_raw("X-My Group", "Foo").as_deref(), Some("a\tb"));
+        assert!(file
+            .to_desktop_string()
+            .ends_with("\n[X-My Group]\nFoo=a\\tb\n"));
+    }
+}
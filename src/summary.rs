@@ -0,0 +1,231 @@
+//! A fast parse of the keys launchers need at startup, reading the Desktop Entry group only.
+
+use crate::{
+    events::{Event, Events},
+    keys::Key,
+    locale::{canonicalize_locale, locale_candidates},
+    parser::{parse_list, unescape_string},
+    DesktopFile, EntryType, IconString, LocaleString, ParseError,
+};
+
+/// The keys of a file a launcher needs to list it
+#[derive(Debug, Clone, Default)]
+pub struct EntrySummary {
+    /// The Type key as written. Converting a [`DesktopFile`] of an unknown type gives
+    /// `Unknown`, since the parser doesn't keep the actual name.
+    pub entry_type: String,
+    /// The Name key, with the variants of the requested locales only
+    pub name: LocaleString,
+    pub icon: Option<IconString>,
+    pub exec: Option<String>,
+    pub no_display: Option<bool>,
+    pub categories: Option<Vec<String>>,
+}
+
+impl From<&DesktopFile> for EntrySummary {
+    /// Takes the keys of a fully parsed file, with every variant of Name
+    fn from(file: &DesktopFile) -> Self {
+        let entry = &file.entry;
+        let (exec, categories) = match entry.entry_type {
+            EntryType::Application(ref fields) => (fields.exec.clone(), fields.categories.clone()),
+            _ => (None, None),
+        };
+
+        Self {
+            entry_type: entry.entry_type.to_string(),
+            name: entry.name.clone(),
+            icon: entry.icon.clone(),
+            exec,
+            no_display: entry.no_display,
+            categories,
+        }
+    }
+}
+
+impl From<DesktopFile> for EntrySummary {
+    fn from(file: DesktopFile) -> Self {
+        Self::from(&file)
+    }
+}
+
+/// The summary being read, with the required keys still optional
+#[derive(Default)]
+struct Partial {
+    entry_type: Option<String>,
+    name: Option<String>,
+    name_variants: Vec<(String, String)>,
+    icon: Option<IconString>,
+    exec: Option<String>,
+    no_display: Option<bool>,
+    categories: Option<Vec<String>>,
+}
+
+impl Partial {
+    fn is_complete(&self, wanted: &[String]) -> bool {
+        self.entry_type.is_some()
+            && self.name.is_some()
+            && self.name_variants.len() == wanted.len()
+            && self.icon.is_some()
+            && self.exec.is_some()
+            && self.no_display.is_some()
+            && self.categories.is_some()
+    }
+}
+
+/// Reads the Type, Name, Icon, Exec, NoDisplay and Categories keys of a file, keeping only
+/// the Name variants that can match the locales, given in order of preference like for
+/// [`LocaleString::resolve`].
+///
+/// Reading stops at the end of the Desktop Entry group, or as soon as every key and variant
+/// was found, so the actions and the rest of the file are neither read nor validated. When a
+/// key is repeated the first value wins.
+pub fn parse_summary(input: &str, locales: &[&str]) -> Result<EntrySummary, ParseError> {
+    if input
+        .strip_prefix('\u{FEFF}')
+        .unwrap_or(input)
+        .trim()
+        .is_empty()
+    {
+        return Err(ParseError::EmptyInput);
+    }
+
+    let mut wanted = locales
+        .iter()
+        .flat_map(|locale| locale_candidates(locale))
+        .collect::<Vec<_>>();
+    wanted.sort();
+    wanted.dedup();
+
+    let mut res = Partial::default();
+    let mut is_entry_found = false;
+    let mut first_content = None;
+    // the first key before any group header, with its line
+    let mut stray_key: Option<(String, usize)> = None;
+    let mut events = Events::new(input);
+
+    while let Some(event) = events.next() {
+        let event = event?;
+        if !matches!(event, Event::Comment(_)) {
+            first_content.get_or_insert(events.span().line);
+        }
+
+        let (key, locale, value) = match event {
+            Event::Comment(_) => continue,
+            Event::GroupStart(_) if stray_key.is_some() => {
+                let (key, row) = stray_key.unwrap();
+                return Err(ParseError::FormatError {
+                    msg: format!("the key {key:?} appears before any group header"),
+                    row,
+                    col: 0,
+                });
+            }
+            Event::KeyValue { key, .. } if !is_entry_found => {
+                stray_key.get_or_insert((key.to_string(), events.span().line));
+                continue;
+            }
+            Event::GroupStart(name) if !is_entry_found && name.trim() == "Desktop Entry" => {
+                is_entry_found = true;
+                continue;
+            }
+            Event::GroupStart(name) if !is_entry_found => {
+                return Err(ParseError::FormatError {
+                    msg: format!("the group {name:?} appears before [Desktop Entry]"),
+                    row: events.span().line,
+                    col: 0,
+                });
+            }
+            Event::GroupStart(_) => break,
+            Event::KeyValue {
+                key, locale, value, ..
+            } => (key, locale, value),
+        };
+
+        match (key, locale) {
+            (Key::Name, Some(locale)) => {
+                let locale = canonicalize_locale(locale);
+                if wanted.contains(&locale) && !res.name_variants.iter().any(|(l, _)| *l == locale)
+                {
+                    res.name_variants.push((locale, unescape_string(value)));
+                }
+            }
+            (_, Some(_)) => {}
+            (Key::Type, None) => {
+                res.entry_type.get_or_insert_with(|| value.to_string());
+            }
+            (Key::Name, None) => {
+                res.name.get_or_insert_with(|| unescape_string(value));
+            }
+            (Key::Icon, None) => {
+                res.icon.get_or_insert_with(|| IconString {
+                    content: unescape_string(value),
+                });
+            }
+            (Key::Exec, None) => {
+                res.exec.get_or_insert_with(|| unescape_string(value));
+            }
+            (Key::NoDisplay, None) if res.no_display.is_none() => {
+                res.no_display = Some(value.parse().map_err(|_| ParseError::Syntax {
+                    msg: "Property's value needs to be bool".into(),
+                    row: events.span().line,
+                    col: 0,
+                    group: None,
+                })?);
+            }
+            (Key::Categories, None) => {
+                res.categories.get_or_insert_with(|| parse_list(value));
+            }
+            _ => {}
+        }
+
+        if res.is_complete(&wanted) {
+            break;
+        }
+    }
+
+    if !is_entry_found {
+        return Err(ParseError::MissingDesktopEntryGroup { row: first_content });
+    }
+
+    Ok(EntrySummary {
+        entry_type: res.entry_type.ok_or_else(|| ParseError::KeyError {
+            msg: "Entry Type must be specified".into(),
+        })?,
+        name: LocaleString {
+            default: res.name.ok_or_else(|| ParseError::KeyError {
+                msg: "Entry name must be specified".into(),
+            })?,
+            variants: res.name_variants.into_iter().collect(),
+        },
+        icon: res.icon,
+        exec: res.exec,
+        no_display: res.no_display,
+        categories: res.categories,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let content = "[Desktop Entry]\nType=Application\nName=Files\nName[de]=Dateien\nName[fr]=Fichiers\nComment=Browse\nExec=files %U\nIcon=files\nCategories=System;\nActions=new;\n\n[Desktop Action new]\nName=New\nbad key=1\n";
+
+        let summary = parse_summary(content, &["de_DE"]).unwrap();
+        assert_eq!(summary.entry_type, "Application");
+        assert_eq!(summary.name.resolve(&["de_DE"]).0, "Dateien");
+        assert_eq!(summary.name.variants.len(), 1);
+        assert_eq!(summary.exec.as_deref(), Some("files %U"));
+        assert_eq!(summary.icon.unwrap().content, "files");
+        assert_eq!(summary.no_display, None);
+        assert_eq!(summary.categories, Some(vec!["System".to_string()]));
+
+        let full = crate::parse(&content.replace("bad key=1\n", "")).unwrap();
+        let summary = EntrySummary::from(&full);
+        assert_eq!(summary.exec.as_deref(), Some("files %U"));
+        assert_eq!(summary.name.variants.len(), 2);
+
+        assert!(parse_summary("[Desktop Entry]\nName=Foo\n", &[]).is_err());
+        assert!(parse_summary("[Other]\n[Desktop Entry]\n", &[]).is_err());
+    }
+}
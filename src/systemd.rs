@@ -0,0 +1,488 @@
+//! Translation of desktop entries into systemd user services, as used by session managers
+//! that start autostart entries through systemd.
+
+use std::{
+    fmt::Write,
+    io,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+};
+
+use thiserror::Error;
+
+use crate::{
+    exec::{ExecCommand, ExecError},
+    DesktopEntry, EntryType,
+};
+
+/// A reason why an entry can't be represented as a service
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SystemdReason {
+    #[error("the entry is not an application")]
+    NotApplication,
+    #[error("the entry is hidden")]
+    Hidden,
+    #[error("the entry runs in a terminal")]
+    Terminal,
+    #[error("the entry has no Exec key")]
+    NoExec,
+    #[error("{0}")]
+    InvalidExec(ExecError),
+    #[error("the Exec key uses the field code {0} that can't be expanded without arguments")]
+    FieldCode(String),
+}
+
+fn join_reasons(reasons: &[SystemdReason]) -> String {
+    reasons
+        .iter()
+        .map(|r| r.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Systemd Error: the entry can't be represented as a service: {}", join_reasons(.reasons))]
+pub struct SystemdError {
+    /// Every reason found, not just the first one
+    pub reasons: Vec<SystemdReason>,
+}
+
+/// How to handle the field codes for files and URLs (`%f`, `%F`, `%u` and `%U`), which have
+/// no value when the service is started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldCodePolicy {
+    /// Drop the field codes from the command line
+    #[default]
+    Strip,
+    /// Fail with [`SystemdReason::FieldCode`]
+    Reject,
+}
+
+/// Options for [`DesktopEntry::to_systemd_service`]
+#[derive(Debug, Clone)]
+pub struct SystemdServiceOptions {
+    /// The locale used for Description and the `%c` field code, None for the default values
+    pub locale: Option<String>,
+    pub field_codes: FieldCodePolicy,
+    /// The slice the service is placed in
+    pub slice: String,
+    /// The target the service is bound to, started after and wanted by
+    pub target: String,
+}
+
+impl Default for SystemdServiceOptions {
+    fn default() -> Self {
+        Self {
+            locale: None,
+            field_codes: FieldCodePolicy::default(),
+            slice: "app-graphical.slice".into(),
+            target: "graphical-session.target".into(),
+        }
+    }
+}
+
+/// Escapes the specifiers and variables systemd would expand in a unit file value
+fn escape_unit_value(value: &str) -> String {
+    value.replace('%', "%%").replace('$', "$$")
+}
+
+/// Quotes an ExecStart argument the way systemd splits command lines
+fn quote_exec_start_arg(arg: &str) -> String {
+    let escaped = escape_unit_value(arg);
+    if !escaped.is_empty()
+        && !escaped.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ';'))
+    {
+        return escaped;
+    }
+
+    let mut result = String::from("\"");
+    for ch in escaped.chars() {
+        match ch {
+            '"' | '\\' => {
+                result.push('\\');
+                result.push(ch);
+            }
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            _ => result.push(ch),
+        }
+    }
+    result.push('"');
+    result
+}
+
+impl DesktopEntry {
+    /// Expands the field codes of the Exec arguments for a start without files or URLs
+    fn service_args(
+        &self,
+        command: &ExecCommand,
+        options: &SystemdServiceOptions,
+        reasons: &mut Vec<SystemdReason>,
+    ) -> Vec<String> {
+        let name = match options.locale {
+            Some(ref locale) => self.name.get_variant(locale),
+            None => &self.name.default,
+        };
+
+        let mut args = vec![];
+        for arg in &command.args {
+            if arg.value == "%i" {
+                if let Some(ref icon) = self.icon {
+                    args.push("--icon".to_string());
+                    args.push(icon.content.clone());
+                }
+                continue;
+            }
+
+            let mut expanded = String::new();
+            let mut had_field_code = false;
+            let mut chars = arg.value.chars();
+            while let Some(ch) = chars.next() {
+                if ch != '%' {
+                    expanded.push(ch);
+                    continue;
+                }
+
+                match chars.next() {
+                    Some('%') => expanded.push('%'),
+                    Some('c') => expanded.push_str(name),
+                    Some(code @ ('f' | 'F' | 'u' | 'U')) => {
+                        had_field_code = true;
+                        if options.field_codes == FieldCodePolicy::Reject {
+                            reasons.push(SystemdReason::FieldCode(format!("%{code}")));
+                        }
+                    }
+                    // deprecated codes, %i inside an argument and %k have no value here
+                    Some(_) | None => had_field_code = true,
+                }
+            }
+
+            if !(had_field_code && expanded.is_empty()) {
+                args.push(expanded);
+            }
+        }
+
+        args
+    }
+
+    /// Generates a systemd user service that starts the application, for starting autostart
+    /// entries through systemd.
+    ///
+    /// OnlyShowIn and NotShowIn become `ConditionEnvironment=` lines on XDG_CURRENT_DESKTOP,
+    /// which systemd compares to the whole variable, so they only match single desktop
+    /// values. Entries that can't be represented fail with every reason found.
+    pub fn to_systemd_service(
+        &self,
+        options: &SystemdServiceOptions,
+    ) -> Result<String, SystemdError> {
+        let mut reasons = vec![];
+
+        let EntryType::Application(ref fields) = self.entry_type else {
+            return Err(SystemdError {
+                reasons: vec![SystemdReason::NotApplication],
+            });
+        };
+
+        if self.hidden == Some(true) {
+            reasons.push(SystemdReason::Hidden);
+        }
+        if fields.terminal == Some(true) {
+            reasons.push(SystemdReason::Terminal);
+        }
+
+        let args = match fields.exec.as_deref().map(ExecCommand::parse) {
+            None => {
+                reasons.push(SystemdReason::NoExec);
+                vec![]
+            }
+            Some(Err(e)) => {
+                reasons.push(SystemdReason::InvalidExec(e));
+                vec![]
+            }
+            Some(Ok(command)) => match command.validate_field_codes() {
+                Ok(()) => self.service_args(&command, options, &mut reasons),
+                Err(e) => {
+                    reasons.push(SystemdReason::InvalidExec(e));
+                    vec![]
+                }
+            },
+        };
+
+        if !reasons.is_empty() {
+            return Err(SystemdError { reasons });
+        }
+
+        let localized = |s: &crate::LocaleString| match options.locale {
+            Some(ref locale) => s.get_variant(locale).to_string(),
+            None => s.default.clone(),
+        };
+        let mut description = localized(&self.name);
+        if let Some(ref comment) = self.comment {
+            description.push_str(" - ");
+            description.push_str(&localized(comment));
+        }
+
+        let mut unit = String::new();
+        let _ = writeln!(unit, "[Unit]");
+        let _ = writeln!(
+            unit,
+            "Description={}",
+            escape_unit_value(&description.replace('\n', " "))
+        );
+        let _ = writeln!(unit, "PartOf={}", options.target);
+        let _ = writeln!(unit, "After={}", options.target);
+        for desktop in self.only_show_in.iter().flatten() {
+            let _ = writeln!(unit, "ConditionEnvironment=|XDG_CURRENT_DESKTOP={desktop}");
+        }
+        for desktop in self.not_show_in.iter().flatten() {
+            let _ = writeln!(unit, "ConditionEnvironment=!XDG_CURRENT_DESKTOP={desktop}");
+        }
+
+        let _ = writeln!(unit, "\n[Service]");
+        let _ = writeln!(unit, "Type=exec");
+        let _ = writeln!(
+            unit,
+            "ExecStart={}",
+            args.iter()
+                .map(|a| quote_exec_start_arg(a))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        if let Some(ref path) = fields.path {
+            let _ = writeln!(unit, "WorkingDirectory={}", escape_unit_value(path));
+        }
+        let _ = writeln!(unit, "Slice={}", options.slice);
+        let _ = writeln!(unit, "Restart=no");
+
+        let _ = writeln!(unit, "\n[Install]");
+        let _ = writeln!(unit, "WantedBy={}", options.target);
+
+        Ok(unit)
+    }
+}
+
+/// Escapes a string for use in a unit name, as `systemd-escape` does
+fn escape_unit_name(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    for (i, byte) in value.bytes().enumerate() {
+        match byte {
+            b'.' if i == 0 => res.push_str("\\x2e"),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'_' | b'.' => res.push(byte as char),
+            _ => {
+                let _ = write!(res, "\\x{byte:02x}");
+            }
+        }
+    }
+    res
+}
+
+/// Returns the name of the scope of a launched application, following the systemd naming
+/// conventions for desktop environments: `app[-<launcher>]-<ApplicationID>-<RANDOM>.scope`,
+/// with the desktop file ID without its `.desktop` suffix as the application ID
+pub fn scope_unit_name(desktop_id: &str, launcher: Option<&str>, random: &str) -> String {
+    let app_id = desktop_id.strip_suffix(".desktop").unwrap_or(desktop_id);
+    let mut name = String::from("app-");
+    if let Some(launcher) = launcher {
+        name.push_str(&escape_unit_name(launcher));
+        name.push('-');
+    }
+    let _ = write!(
+        name,
+        "{}-{}.scope",
+        escape_unit_name(app_id),
+        escape_unit_name(random)
+    );
+    name
+}
+
+/// Options for [`spawn_in_scope`]
+#[derive(Debug, Clone)]
+pub struct ScopeOptions {
+    /// The launcher, such as `gnome`, included in the unit name
+    pub launcher: Option<String>,
+    /// Environment variables added to the environment of the application, such as the ones
+    /// of [`LaunchOptions::env`](crate::launch::LaunchOptions::env) or the variables selecting
+    /// the GPU
+    pub env: Vec<(String, String)>,
+    /// The systemd-run program
+    pub systemd_run: PathBuf,
+}
+
+impl Default for ScopeOptions {
+    fn default() -> Self {
+        Self {
+            launcher: None,
+            env: vec![],
+            systemd_run: "systemd-run".into(),
+        }
+    }
+}
+
+/// How [`spawn_in_scope`] started the application
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScopeLaunch {
+    /// In a transient scope with the given unit name
+    Scope { unit: String },
+    /// Directly, because systemd is not available for the given reason
+    Direct { reason: String },
+}
+
+/// Returns the systemd-run command starting `argv` in a new transient scope of the user
+/// manager, along with the name of the scope. The scope runs the application as a child of
+/// systemd-run, so the environment set on the command is the environment of the application.
+pub fn scope_command(
+    argv: &[String],
+    desktop_id: &str,
+    options: &ScopeOptions,
+) -> (Command, String) {
+    let random = format!(
+        "{:016x}",
+        std::hash::BuildHasher::hash_one(&std::collections::hash_map::RandomState::new(), 0)
+    );
+    let unit = scope_unit_name(desktop_id, options.launcher.as_deref(), &random);
+
+    let mut command = Command::new(&options.systemd_run);
+    command
+        .args(["--user", "--scope", "--collect", "--quiet"])
+        .arg(format!("--unit={unit}"))
+        .arg("--")
+        .args(argv)
+        .envs(options.env.iter().map(|(k, v)| (k, v)));
+    (command, unit)
+}
+
+/// Returns why systemd can't start scopes for the current user, None if it can
+fn scope_unavailable() -> Option<String> {
+    if !Path::new("/run/systemd/system").exists() {
+        return Some("the system was not booted with systemd".into());
+    }
+    let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR") else {
+        return Some("XDG_RUNTIME_DIR is not set".into());
+    };
+    let socket = Path::new(&runtime_dir).join("systemd/private");
+    (!socket.exists()).then(|| "the systemd user manager is not running".into())
+}
+
+/// Starts an already expanded command line in its own transient systemd scope, so that the
+/// session can apply resource control to it and clean it up. When systemd or systemd-run is
+/// not available, the command is started directly instead, and the reason is reported.
+pub fn spawn_in_scope(
+    argv: &[String],
+    desktop_id: &str,
+    options: &ScopeOptions,
+) -> io::Result<(Child, ScopeLaunch)> {
+    let direct = |reason: String| {
+        let (program, args) = argv
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the command is empty"))?;
+        let child = Command::new(program)
+            .args(args)
+            .envs(options.env.iter().map(|(k, v)| (k, v)))
+            .spawn()?;
+        Ok((child, ScopeLaunch::Direct { reason }))
+    };
+
+    if let Some(reason) = scope_unavailable() {
+        return direct(reason);
+    }
+
+    let (mut command, unit) = scope_command(argv, desktop_id, options);
+    match command.spawn() {
+        Ok(child) => Ok((child, ScopeLaunch::Scope { unit })),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            direct(format!("{:?} was not found", options.systemd_run))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_to_systemd_service() {
+        let file = parse(
+            r#"[Desktop Entry]
+Type=Application
+Name=Notes
+Comment=Take notes
+Icon=notes
+Exec=notes --name %c %i --at=50%% %U
+Path=/home/user
+OnlyShowIn=GNOME;KDE;"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            file.entry
+                .to_systemd_service(&SystemdServiceOptions::default())
+                .unwrap(),
+            r#"[Unit]
+Description=Notes - Take notes
+PartOf=graphical-session.target
+After=graphical-session.target
+ConditionEnvironment=|XDG_CURRENT_DESKTOP=GNOME
+ConditionEnvironment=|XDG_CURRENT_DESKTOP=KDE
+
+[Service]
+Type=exec
+ExecStart=notes --name Notes --icon notes --at=50%%
+WorkingDirectory=/home/user
+Slice=app-graphical.slice
+Restart=no
+
+[Install]
+WantedBy=graphical-session.target
+"#
+        );
+
+        let file = parse(
+            "[Desktop Entry]\nType=Application\nName=Term\nTerminal=true\nDBusActivatable=true",
+        )
+        .unwrap();
+        let err = file
+            .entry
+            .to_systemd_service(&SystemdServiceOptions::default())
+            .unwrap_err();
+        assert_eq!(
+            err.reasons,
+            vec![SystemdReason::Terminal, SystemdReason::NoExec]
+        );
+    }
+
+    #[test]
+    fn test_scope_command() {
+        assert_eq!(
+            scope_unit_name("org.gnome.Text-Editor.desktop", Some("gnome"), "1f"),
+            "app-gnome-org.gnome.Text\\x2dEditor-1f.scope"
+        );
+
+        let options = ScopeOptions {
+            env: vec![("DESKTOP_STARTUP_ID".into(), "id".into())],
+            ..Default::default()
+        };
+        let (command, unit) =
+            scope_command(&["editor".into(), "a b".into()], "editor.desktop", &options);
+        assert!(unit.starts_with("app-editor-") && unit.ends_with(".scope"));
+        let args = command
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            args,
+            [
+                "--user",
+                "--scope",
+                "--collect",
+                "--quiet",
+                &format!("--unit={unit}"),
+                "--",
+                "editor",
+                "a b"
+            ]
+        );
+        assert_eq!(command.get_envs().count(), 1);
+    }
+}
@@ -0,0 +1,194 @@
+//! `.desktop.in` templates, where the keys to translate are marked with a leading underscore
+//! like `_Name=Text Editor`, and the merging of their translations into a desktop file as
+//! intltool and `msgfmt --desktop` do.
+
+use std::collections::HashMap;
+
+use crate::{parser::parse_file, DesktopFile, ParseError, ParseOptions};
+
+/// Translations by locale, each mapping a message, the value of a key as written in the
+/// template, to its translation
+pub type Translations = HashMap<String, HashMap<String, String>>;
+
+/// A key marked for translation in a template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranslatableKey {
+    /// The name of the group, such as `Desktop Entry`
+    pub group: String,
+    pub key: String,
+}
+
+/// A parsed template: the file with the keys as if written without the underscore, and the
+/// keys that had one
+#[derive(Debug, Clone, Default)]
+pub struct DesktopTemplate {
+    pub file: DesktopFile,
+    /// The keys marked for translation, in the order of the template
+    pub translatable: Vec<TranslatableKey>,
+}
+
+/// Parses a `.desktop.in` template, accepting keys marked for translation with a leading
+/// underscore. Everything else follows the rules of [`crate::parse_with_options`].
+pub fn parse_template(input: &str, options: &ParseOptions) -> Result<DesktopTemplate, ParseError> {
+    let mut translatable = vec![];
+    let (file, _) = parse_file(input, options, Some(&mut translatable))?;
+    Ok(DesktopTemplate { file, translatable })
+}
+
+/// A line of a serialized file, as far as the rewriting of templates needs to know
+enum Line<'a> {
+    Header(&'a str),
+    Pair {
+        key: &'a str,
+        locale: Option<&'a str>,
+        value: &'a str,
+    },
+    Other,
+}
+
+impl<'a> Line<'a> {
+    /// Splits a line of the serializer's output, where keys are never padded
+    fn parse(line: &'a str) -> Self {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            return Self::Header(name);
+        }
+        let Some((key, value)) = line.split_once('=').filter(|_| !line.starts_with('#')) else {
+            return Self::Other;
+        };
+
+        match key.split_once('[') {
+            Some((key, locale)) => Self::Pair {
+                key,
+                locale: locale.strip_suffix(']'),
+                value,
+            },
+            None => Self::Pair {
+                key,
+                locale: None,
+                value,
+            },
+        }
+    }
+}
+
+impl DesktopTemplate {
+    /// Whether a key of a group is marked for translation
+    pub fn is_translatable(&self, group: &str, key: &str) -> bool {
+        self.translatable
+            .iter()
+            .any(|k| k.group == group && k.key == key)
+    }
+
+    /// Rewrites the serialized file line by line, giving `rewrite` the translatable pairs
+    fn rewrite(&self, mut rewrite: impl FnMut(&str, Option<&str>, &str, &mut String)) -> String {
+        let mut output = String::new();
+        let mut group = "";
+
+        let serialized = self.file.to_desktop_string();
+        for line in serialized.lines() {
+            match Line::parse(line) {
+                Line::Header(name) => group = name,
+                Line::Pair { key, locale, value } if self.is_translatable(group, key) => {
+                    rewrite(key, locale, value, &mut output);
+                    continue;
+                }
+                _ => {}
+            }
+            output.push_str(line);
+            output.push('\n');
+        }
+
+        output
+    }
+
+    /// Serializes the template form, with the translatable keys marked by an underscore and
+    /// without their locale variants
+    pub fn to_template_string(&self) -> String {
+        self.rewrite(|key, locale, value, output| {
+            if locale.is_none() {
+                output.push_str(&format!("_{key}={value}\n"));
+            }
+        })
+    }
+
+    /// Serializes the merged form, adding a variant of each translatable key for every locale
+    /// that translates its value. Values are looked up and the translations written as they
+    /// appear in the file, escape sequences included. Untranslated and empty messages are
+    /// skipped, and a translation replaces a variant already present for its locale.
+    pub fn to_merged_string(&self, translations: &Translations) -> String {
+        let mut locales = translations.keys().collect::<Vec<_>>();
+        locales.sort();
+
+        self.rewrite(|key, locale, value, output| match locale {
+            None => {
+                output.push_str(&format!("{key}={value}\n"));
+                for locale in &locales {
+                    if let Some(translation) =
+                        translations[*locale].get(value).filter(|t| !t.is_empty())
+                    {
+                        output.push_str(&format!("{key}[{locale}]={translation}\n"));
+                    }
+                }
+            }
+            Some(locale) if !translations.contains_key(locale) => {
+                output.push_str(&format!("{key}[{locale}]={value}\n"));
+            }
+            Some(_) => {}
+        })
+    }
+
+    /// Merges the translations into the file, see [`DesktopTemplate::to_merged_string`]
+    pub fn merge(&self, translations: &Translations) -> Result<DesktopFile, ParseError> {
+        crate::parse(&self.to_merged_string(translations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = "[Desktop Entry]\n_Name=Text Editor\n_Comment=Edit text files\nType=Application\nExec=editor %U\n_Keywords=text;plain;\nActions=new;\n\n[Desktop Action new]\n_Name=New Window\nExec=editor --new\n";
+
+    #[test]
+    fn test_template() {
+        let template = parse_template(TEMPLATE, &ParseOptions::default()).unwrap();
+        assert_eq!(template.file.entry.name.default, "Text Editor");
+        assert!(template.is_translatable("Desktop Entry", "Keywords"));
+        assert!(template.is_translatable("Desktop Action new", "Name"));
+        assert!(!template.is_translatable("Desktop Entry", "Exec"));
+        assert!(crate::parse(TEMPLATE).is_err());
+
+        let round_trip =
+            parse_template(&template.to_template_string(), &ParseOptions::default()).unwrap();
+        assert_eq!(round_trip.translatable, template.translatable);
+
+        let mut translations = Translations::new();
+        translations.insert(
+            "de".into(),
+            HashMap::from([
+                ("Text Editor".into(), "Texteditor".into()),
+                ("text;plain;".into(), "Text;einfach;".into()),
+                ("New Window".into(), "Neues Fenster".into()),
+                ("Edit text files".into(), String::new()),
+            ]),
+        );
+        let merged = template.merge(&translations).unwrap();
+        assert_eq!(merged.entry.name.get_variant("de"), "Texteditor");
+        assert!(merged.entry.comment.as_ref().unwrap().variants.is_empty());
+        assert_eq!(
+            merged
+                .entry
+                .as_application()
+                .unwrap()
+                .keywords
+                .as_ref()
+                .unwrap()
+                .get_variant("de"),
+            ["Text", "einfach"]
+        );
+        assert_eq!(
+            merged.actions["new"].name.get_variant("de"),
+            "Neues Fenster"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Checks whether a desktop file may be launched, the way file managers do before running a
+//! desktop file that was opened directly, such as one double-clicked in `~/Downloads`.
+//!
+//! Desktop files in the applications directories are trusted by their location. Anywhere
+//! else they need to be marked as trusted with the executable bit and, for file managers
+//! using GIO, the `metadata::trusted` attribute.
+
+use std::{
+    io,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use crate::util::{data_dirs, data_home};
+
+/// The extended attribute holding the GIO `metadata::trusted` attribute
+pub const TRUSTED_XATTR: &str = "user.metadata::trusted";
+
+/// Whether a desktop file is trusted, and why
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustStatus {
+    /// The file is inside one of the trusted directories
+    TrustedByLocation,
+    /// The file is marked as trusted by the user
+    TrustedByMark,
+    Untrusted,
+}
+
+/// How trust is checked and marked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustOptions {
+    /// Directories whose files are trusted by their location
+    pub trusted_dirs: Vec<PathBuf>,
+    /// Whether the `metadata::trusted` attribute is required in addition to the executable
+    /// bit, and set by [`mark_trusted_with`]
+    pub use_xattr: bool,
+}
+
+impl Default for TrustOptions {
+    /// Trusts the applications directories of `$XDG_DATA_HOME` and `$XDG_DATA_DIRS`, and only
+    /// requires the executable bit
+    fn default() -> Self {
+        Self {
+            trusted_dirs: data_home()
+                .into_iter()
+                .chain(data_dirs())
+                .map(|d| d.join("applications"))
+                .collect(),
+            use_xattr: false,
+        }
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+fn has_trusted_xattr(path: &Path) -> bool {
+    matches!(
+        xattr::get(path, TRUSTED_XATTR),
+        Ok(Some(value)) if value == b"true" || value == b"yes"
+    )
+}
+
+/// Checks whether a desktop file is trusted with the default options
+pub fn is_trusted(path: &Path) -> TrustStatus {
+    is_trusted_with(path, &TrustOptions::default())
+}
+
+/// Checks whether a desktop file is trusted. Symlinks are resolved first, so a link placed in
+/// a trusted directory doesn't make its target trusted by location.
+pub fn is_trusted_with(path: &Path, options: &TrustOptions) -> TrustStatus {
+    let Ok(path) = path.canonicalize() else {
+        return TrustStatus::Untrusted;
+    };
+
+    let in_trusted_dir = options
+        .trusted_dirs
+        .iter()
+        .filter_map(|d| d.canonicalize().ok())
+        .any(|d| path.starts_with(d));
+    if in_trusted_dir {
+        return TrustStatus::TrustedByLocation;
+    }
+
+    if is_executable(&path) && (!options.use_xattr || has_trusted_xattr(&path)) {
+        TrustStatus::TrustedByMark
+    } else {
+        TrustStatus::Untrusted
+    }
+}
+
+/// Marks a desktop file as trusted with the default options
+pub fn mark_trusted(path: &Path) -> io::Result<()> {
+    mark_trusted_with(path, &TrustOptions::default())
+}
+
+/// Marks a desktop file as trusted by setting the executable bit for everyone who can read
+/// it, and the `metadata::trusted` attribute if the options use it
+pub fn mark_trusted_with(path: &Path, options: &TrustOptions) -> io::Result<()> {
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    let mode = permissions.mode();
+    // r bits are 0o444, the matching x bits 0o111
+    permissions.set_mode(mode | ((mode & 0o444) >> 2));
+    std::fs::set_permissions(path, permissions)?;
+
+    if options.use_xattr {
+        xattr::set(path, TRUSTED_XATTR, b"true")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trust() {
+        let base =
+            std::env::temp_dir().join(format!("desktop-file-parser-trust-{}", std::process::id()));
+        let applications = base.join("applications");
+        let downloads = base.join("downloads");
+        std::fs::create_dir_all(&applications).unwrap();
+        std::fs::create_dir_all(&downloads).unwrap();
+
+        let installed = applications.join("foo.desktop");
+        let downloaded = downloads.join("foo.desktop");
+        std::fs::write(&installed, "[Desktop Entry]").unwrap();
+        std::fs::write(&downloaded, "[Desktop Entry]").unwrap();
+        std::fs::set_permissions(&downloaded, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let options = TrustOptions {
+            trusted_dirs: vec![applications],
+            use_xattr: false,
+        };
+        assert_eq!(
+            is_trusted_with(&installed, &options),
+            TrustStatus::TrustedByLocation
+        );
+        assert_eq!(
+            is_trusted_with(&downloaded, &options),
+            TrustStatus::Untrusted
+        );
+
+        mark_trusted_with(&downloaded, &options).unwrap();
+        assert_eq!(
+            std::fs::metadata(&downloaded).unwrap().permissions().mode() & 0o777,
+            0o755
+        );
+        assert_eq!(
+            is_trusted_with(&downloaded, &options),
+            TrustStatus::TrustedByMark
+        );
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,518 @@
+//! Mechanical rewriting of old entries to the conventions of a version of the specification,
+//! for bulk migrations, and the inverse: writing entries for desktops implementing an older
+//! version.
+
+use std::collections::HashMap;
+
+use crate::{
+    exec::ExecCommand,
+    keyfile,
+    keys::{Key, SpecVersion, ValueType},
+    locale::normalize_locale,
+    DesktopAction, DesktopFile, EntryType, IconString, LocaleString, ParseError,
+};
+
+/// Field codes the specification deprecated, which launchers expand to nothing
+const DEPRECATED_FIELD_CODES: [&str; 6] = ["%d", "%D", "%n", "%N", "%v", "%m"];
+
+/// A change applied by an upgrade, with the values before and after in their unescaped form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpgradeChange {
+    /// The group of the key, such as `Desktop Entry`
+    pub group: String,
+    /// The key as written, like `Name[de]`
+    pub key: String,
+    /// The value before the change, None for an added key
+    pub before: Option<String>,
+    /// The value after the change, None for a removed key
+    pub after: Option<String>,
+}
+
+/// What an upgrade changed and what it left for a human to fix
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpgradeReport {
+    /// The applied changes, in the order they were applied
+    pub changes: Vec<UpgradeChange>,
+    /// Problems that can't be fixed automatically
+    pub manual: Vec<String>,
+}
+
+impl UpgradeReport {
+    fn change(&mut self, group: &str, key: String, before: Option<String>, after: Option<String>) {
+        self.changes.push(UpgradeChange {
+            group: group.to_string(),
+            key,
+            before,
+            after,
+        });
+    }
+}
+
+/// Drops the encoding of the locales of the variants, like `de_DE.UTF-8` to `de_DE`
+fn strip_encodings<T>(
+    group: &str,
+    key: &Key,
+    variants: &mut HashMap<String, T>,
+    render: fn(&T) -> String,
+    report: &mut UpgradeReport,
+) {
+    let mut locales = variants.keys().cloned().collect::<Vec<_>>();
+    locales.sort();
+
+    for locale in locales {
+        let normalized = normalize_locale(&locale);
+        if normalized == locale {
+            continue;
+        }
+        if variants.contains_key(&normalized) {
+            report.manual.push(format!(
+                "{key}[{locale}] of {group} conflicts with {key}[{normalized}]"
+            ));
+            continue;
+        }
+
+        let value = variants.remove(&locale).unwrap();
+        let written = render(&value);
+        report.change(
+            group,
+            format!("{key}[{locale}]"),
+            Some(written.clone()),
+            None,
+        );
+        report.change(group, format!("{key}[{normalized}]"), None, Some(written));
+        variants.insert(normalized, value);
+    }
+}
+
+/// Removes the deprecated field codes of an Exec value, None if it has none
+fn strip_deprecated_codes(exec: &str) -> Option<String> {
+    let mut cmd = ExecCommand::parse(exec).ok()?;
+    let before = cmd.args.len();
+    cmd.args
+        .retain(|arg| arg.quoted || !DEPRECATED_FIELD_CODES.contains(&arg.value.as_str()));
+    (cmd.args.len() != before && !cmd.args.is_empty()).then(|| cmd.to_exec_string())
+}
+
+impl DesktopFile {
+    /// Rewrites the file to the conventions of a version of the specification. This drops the
+    /// Encoding key, removes the deprecated field codes (`%d`, `%D`, `%n`, `%N`, `%v` and `%m`)
+    /// from Exec values, drops the encoding of the locales of variants, turns Unity quicklists
+    /// (`X-Ayatana-Desktop-Shortcuts` and their `[... Shortcut Group]` groups) into actions
+    /// when upgrading to 1.1 or later, and raises the Version key to `version`.
+    ///
+    /// Legacy 0/1 booleans are rejected by the parser, use [`upgrade_source`] to convert them.
+    /// A `Legacy-Mixed` encoding is left in place and reported as needing manual attention.
+    pub fn upgrade_to(&mut self, version: SpecVersion) -> UpgradeReport {
+        const GROUP: &str = "Desktop Entry";
+        let mut report = UpgradeReport::default();
+
+        match self.entry.extras.get("Encoding").map(String::as_str) {
+            Some("Legacy-Mixed") => report.manual.push(
+                "the Legacy-Mixed encoding needs the values to be converted to UTF-8 by hand"
+                    .into(),
+            ),
+            Some(_) => {
+                let before = self.entry.extras.remove("Encoding");
+                report.change(GROUP, "Encoding".into(), before, None);
+            }
+            None => {}
+        }
+
+        let mut execs = vec![];
+        if let EntryType::Application(ref mut fields) = self.entry.entry_type {
+            execs.push((GROUP.to_string(), &mut fields.exec));
+        }
+        let mut ids = self.actions.keys().cloned().collect::<Vec<_>>();
+        ids.sort();
+        let mut actions = self.actions.iter_mut().collect::<Vec<_>>();
+        actions.sort_by(|a, b| a.0.cmp(b.0));
+        for (id, action) in actions {
+            execs.push((format!("Desktop Action {id}"), &mut action.exec));
+        }
+        for (group, exec) in execs {
+            let Some(stripped) = exec.as_deref().and_then(strip_deprecated_codes) else {
+                continue;
+            };
+            let before = exec.replace(stripped.clone());
+            report.change(&group, "Exec".into(), before, Some(stripped));
+        }
+
+        let entry = &mut self.entry;
+        for (key, string) in [
+            (Key::Name, Some(&mut entry.name)),
+            (Key::GenericName, entry.generic_name.as_mut()),
+            (Key::Comment, entry.comment.as_mut()),
+        ] {
+            if let Some(string) = string {
+                strip_encodings(
+                    GROUP,
+                    &key,
+                    &mut string.variants,
+                    String::clone,
+                    &mut report,
+                );
+            }
+        }
+        if let EntryType::Application(ref mut fields) = entry.entry_type {
+            if let Some(ref mut keywords) = fields.keywords {
+                let render = |list: &Vec<String>| list.iter().map(|k| format!("{k};")).collect();
+                strip_encodings(
+                    GROUP,
+                    &Key::Keywords,
+                    &mut keywords.variants,
+                    render,
+                    &mut report,
+                );
+            }
+        }
+        for id in &ids {
+            let group = format!("Desktop Action {id}");
+            let name = &mut self.actions.get_mut(id).unwrap().name;
+            strip_encodings(
+                &group,
+                &Key::Name,
+                &mut name.variants,
+                String::clone,
+                &mut report,
+            );
+        }
+
+        if version >= SpecVersion::V1_1 {
+            self.migrate_quicklists(&mut report);
+        }
+
+        let is_older = match self.entry.version.as_deref() {
+            Some(current) => current
+                .split('.')
+                .map(|n| n.parse::<u32>().unwrap_or(0))
+                .lt(version.as_str().split('.').map(|n| n.parse().unwrap_or(0))),
+            None => true,
+        };
+        if is_older {
+            let before = self.entry.version.replace(version.as_str().into());
+            report.change(
+                GROUP,
+                "Version".into(),
+                before,
+                Some(version.as_str().into()),
+            );
+        }
+
+        report
+    }
+
+    /// Turns the Unity quicklist groups listed in `X-Ayatana-Desktop-Shortcuts` into actions
+    fn migrate_quicklists(&mut self, report: &mut UpgradeReport) {
+        const SHORTCUTS: &str = "X-Ayatana-Desktop-Shortcuts";
+        let Some(shortcuts) = self.entry.extras.get(SHORTCUTS).cloned() else {
+            return;
+        };
+
+        let mut remaining = vec![];
+        for id in crate::parser::parse_list(&shortcuts) {
+            let group_name = format!("{id} Shortcut Group");
+            let Some(index) = self.other_groups.iter().position(|g| g.name == group_name) else {
+                report
+                    .manual
+                    .push(format!("the quicklist group [{group_name}] is missing"));
+                remaining.push(id);
+                continue;
+            };
+
+            let group = &self.other_groups[index];
+            let Some(name) = group.get_locale_string("Name") else {
+                report
+                    .manual
+                    .push(format!("the quicklist group [{group_name}] has no Name"));
+                remaining.push(id);
+                continue;
+            };
+            let action = DesktopAction {
+                name: LocaleString {
+                    default: name.default,
+                    variants: name.variants,
+                },
+                exec: group.get_string("Exec"),
+                icon: group
+                    .get_string("Icon")
+                    .map(|content| IconString { content }),
+                extras: HashMap::new(),
+            };
+
+            let exec = action.exec.clone();
+            if let Err(e) = self.add_action(&id, action) {
+                report.manual.push(format!(
+                    "the quicklist group [{group_name}] can't become an action: {e}"
+                ));
+                remaining.push(id);
+                continue;
+            }
+
+            self.other_groups.remove(index);
+            let group = format!("Desktop Action {id}");
+            report.change(
+                &group,
+                "Name".into(),
+                None,
+                Some(self.actions[&id].name.default.clone()),
+            );
+            if exec.is_some() {
+                report.change(&group, "Exec".into(), None, exec);
+            }
+        }
+
+        let before = Some(shortcuts);
+        if remaining.is_empty() {
+            self.entry.extras.remove(SHORTCUTS);
+            report.change("Desktop Entry", SHORTCUTS.into(), before, None);
+        } else {
+            let after = remaining
+                .iter()
+                .map(|id| format!("{id};"))
+                .collect::<String>();
+            if before.as_deref() != Some(after.as_str()) {
+                self.entry.extras.insert(SHORTCUTS.into(), after.clone());
+                report.change("Desktop Entry", SHORTCUTS.into(), before, Some(after));
+            }
+        }
+    }
+}
+
+/// How to write a file for an older version of the specification
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatOptions {
+    /// Keep the keys the version doesn't know instead of dropping them, for the keys that are
+    /// harmless to ignore. They are still listed in the report.
+    pub keep_unknown: bool,
+    /// Write the version in the Version key
+    pub downgrade_version: bool,
+}
+
+/// A key that an older version of the specification doesn't know
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatKey {
+    /// The group of the key, such as `Desktop Entry`
+    pub group: String,
+    /// The key as written, like `Keywords[de]`
+    pub key: String,
+    /// The version that introduced the key
+    pub since: SpecVersion,
+}
+
+/// What writing a file for an older version of the specification left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatReport {
+    /// The keys that were dropped
+    pub dropped: Vec<CompatKey>,
+    /// The keys the version doesn't know that were kept with [`CompatOptions::keep_unknown`]
+    pub kept: Vec<CompatKey>,
+    /// The action groups that were dropped, as versions before 1.1 have no actions
+    pub dropped_groups: Vec<String>,
+}
+
+impl DesktopFile {
+    /// Serializes the file for desktops implementing an older version of the specification,
+    /// leaving out the keys introduced after it according to [`KeyInfo::since`], and the
+    /// action groups for versions before 1.1. Other groups and extension keys are written as
+    /// they are.
+    ///
+    /// [`KeyInfo::since`]: crate::keys::KeyInfo::since
+    pub fn serialize_for(
+        &self,
+        version: SpecVersion,
+        options: &CompatOptions,
+    ) -> (String, CompatReport) {
+        let mut report = CompatReport::default();
+        let Ok(mut file) = keyfile::parse(&self.to_desktop_string()) else {
+            return (self.to_desktop_string(), report);
+        };
+
+        let is_entry_group =
+            |name: &str| name == "Desktop Entry" || name.starts_with("Desktop Action ");
+        if version < SpecVersion::V1_1 && !options.keep_unknown {
+            file.groups.retain(|group| {
+                let is_action = group.name.starts_with("Desktop Action ");
+                if is_action {
+                    report.dropped_groups.push(group.name.clone());
+                }
+                !is_action
+            });
+        }
+
+        for group in file.groups.iter_mut().filter(|g| is_entry_group(&g.name)) {
+            group.entries.retain(|entry| {
+                let Some(since) = Key::from(entry.key.as_str()).info().map(|info| info.since)
+                else {
+                    return true;
+                };
+                if since <= version {
+                    return true;
+                }
+
+                let key = CompatKey {
+                    group: group.name.clone(),
+                    key: match entry.locale {
+                        Some(ref locale) => format!("{}[{locale}]", entry.key),
+                        None => entry.key.clone(),
+                    },
+                    since,
+                };
+                if options.keep_unknown {
+                    report.kept.push(key);
+                } else {
+                    report.dropped.push(key);
+                }
+                options.keep_unknown
+            });
+        }
+
+        if options.downgrade_version {
+            file.group_mut("Desktop Entry")
+                .set_raw("Version", None, version.as_str());
+        }
+
+        (file.to_string(), report)
+    }
+}
+
+/// Parses a desktop file and upgrades it with [`DesktopFile::upgrade_to`], converting legacy
+/// `0` and `1` booleans to `false` and `true` first so the parser accepts them
+pub fn upgrade_source(
+    input: &str,
+    version: SpecVersion,
+) -> Result<(DesktopFile, UpgradeReport), ParseError> {
+    let mut report = UpgradeReport::default();
+    let mut group = String::new();
+    let mut converted = String::with_capacity(input.len());
+
+    for line in input.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let trimmed = content.trim();
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            group = name.to_string();
+        } else if let Some((key, value)) = content.split_once('=') {
+            let (key, value) = (key.trim(), value.trim());
+            let is_bool = Key::from(key).value_type() == Some(ValueType::Boolean);
+            let fixed = match value {
+                "0" => "false",
+                "1" => "true",
+                _ => "",
+            };
+            if group == "Desktop Entry" && is_bool && !fixed.is_empty() {
+                report.change(&group, key.into(), Some(value.into()), Some(fixed.into()));
+                converted.push_str(&format!("{key}={fixed}"));
+                converted.push_str(&line[content.len()..]);
+                continue;
+            }
+        }
+        converted.push_str(line);
+    }
+
+    let mut file = crate::parse(&converted)?;
+    let upgraded = file.upgrade_to(version);
+    report.changes.extend(upgraded.changes);
+    report.manual.extend(upgraded.manual);
+    Ok((file, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade() {
+        let (file, report) = upgrade_source(
+            "[Desktop Entry]\nVersion=1.0\nEncoding=UTF-8\nType=Application\nName=Editor\nName[de_DE.UTF-8]=Texteditor\nExec=editor %m %U\nTerminal=0\nX-Ayatana-Desktop-Shortcuts=NewWindow;\n\n[NewWindow Shortcut Group]\nName=New Window\nExec=editor --new\nTargetEnvironment=Unity\n",
+            SpecVersion::V1_5,
+        )
+        .unwrap();
+
+        let changed = report
+            .changes
+            .iter()
+            .map(|c| format!("{} {}", c.group, c.key))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changed,
+            [
+                "Desktop Entry Terminal",
+                "Desktop Entry Encoding",
+                "Desktop Entry Exec",
+                "Desktop Entry Name[de_DE.UTF-8]",
+                "Desktop Entry Name[de_DE]",
+                "Desktop Action NewWindow Name",
+                "Desktop Action NewWindow Exec",
+                "Desktop Entry X-Ayatana-Desktop-Shortcuts",
+                "Desktop Entry Version",
+            ]
+        );
+        assert!(report.manual.is_empty());
+
+        let EntryType::Application(ref fields) = file.entry.entry_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(fields.exec.as_deref(), Some("editor %U"));
+        assert_eq!(fields.terminal, Some(false));
+        assert_eq!(
+            fields.actions.as_deref(),
+            Some(&["NewWindow".to_string()][..])
+        );
+        assert_eq!(file.entry.name.variants["de_DE"], "Texteditor");
+        assert_eq!(file.entry.version.as_deref(), Some("1.5"));
+        assert!(file.other_groups.is_empty());
+        assert!(!file.entry.extras.contains_key("Encoding"));
+
+        let mut file =
+            crate::parse("[Desktop Entry]\nVersion=1.5\nEncoding=Legacy-Mixed\nType=Link\nName=Link\nURL=https://example.com\n")
+                .unwrap();
+        let report = file.upgrade_to(SpecVersion::V1_5);
+        assert!(report.changes.is_empty());
+        assert_eq!(report.manual.len(), 1);
+    }
+
+    #[test]
+    fn test_serialize_for() {
+        let file = crate::parse(
+            "[Desktop Entry]\nVersion=1.5\nType=Application\nName=Editor\nExec=editor\nKeywords=text;\nSingleMainWindow=true\nX-Vendor=1\nActions=new;\n\n[Desktop Action new]\nName=New\nExec=editor --new\n",
+        )
+        .unwrap();
+
+        let options = CompatOptions {
+            keep_unknown: false,
+            downgrade_version: true,
+        };
+        let (output, report) = file.serialize_for(SpecVersion::V1_0, &options);
+        assert_eq!(
+            output,
+            "[Desktop Entry]\nType=Application\nVersion=1.0\nName=Editor\nExec=editor\nX-Vendor=1\n"
+        );
+        let dropped = report
+            .dropped
+            .iter()
+            .map(|k| (k.key.as_str(), k.since))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dropped,
+            [
+                ("Actions", SpecVersion::V1_1),
+                ("Keywords", SpecVersion::V1_1),
+                ("SingleMainWindow", SpecVersion::V1_5),
+            ]
+        );
+        assert_eq!(report.dropped_groups, ["Desktop Action new"]);
+
+        let (output, report) = file.serialize_for(SpecVersion::V1_4, &CompatOptions::default());
+        assert!(!output.contains("SingleMainWindow"));
+        assert!(output.contains("Version=1.5"));
+        assert_eq!(report.dropped.len(), 1);
+
+        let options = CompatOptions {
+            keep_unknown: true,
+            ..Default::default()
+        };
+        let (output, report) = file.serialize_for(SpecVersion::V1_0, &options);
+        assert_eq!(output, file.to_desktop_string());
+        assert!(report.dropped.is_empty());
+        assert_eq!(report.kept.len(), 3);
+    }
+}
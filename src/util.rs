@@ -0,0 +1,84 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Writes a file by writing a temporary file next to it and renaming it over the target,
+/// so readers never see a partially written file.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp_path = dir.join(tmp_name);
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+/// Reads a path from an environment variable, ignoring empty and relative values as the
+/// base directory specification requires
+fn env_path(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+}
+
+/// Reads a colon separated list of paths from an environment variable, falling back to the
+/// given defaults when it is unset or empty
+fn env_paths(var: &str, defaults: &[&str]) -> Vec<PathBuf> {
+    let paths = std::env::var_os(var)
+        .map(|v| {
+            std::env::split_paths(&v)
+                .filter(|p| p.is_absolute())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    if paths.is_empty() {
+        defaults.iter().map(PathBuf::from).collect()
+    } else {
+        paths
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env_path("HOME")
+}
+
+/// `$XDG_CONFIG_HOME`, defaulting to `~/.config`
+pub(crate) fn config_home() -> Option<PathBuf> {
+    env_path("XDG_CONFIG_HOME").or_else(|| home_dir().map(|h| h.join(".config")))
+}
+
+/// `$XDG_CONFIG_DIRS`, defaulting to `/etc/xdg`
+pub(crate) fn config_dirs() -> Vec<PathBuf> {
+    env_paths("XDG_CONFIG_DIRS", &["/etc/xdg"])
+}
+
+/// `$XDG_DATA_HOME`, defaulting to `~/.local/share`
+pub(crate) fn data_home() -> Option<PathBuf> {
+    env_path("XDG_DATA_HOME").or_else(|| home_dir().map(|h| h.join(".local/share")))
+}
+
+/// `$XDG_DATA_DIRS`, defaulting to `/usr/local/share:/usr/share`
+pub(crate) fn data_dirs() -> Vec<PathBuf> {
+    env_paths("XDG_DATA_DIRS", &["/usr/local/share", "/usr/share"])
+}
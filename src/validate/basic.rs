@@ -0,0 +1,331 @@
+//! Rules that only look at the content of the file.
+
+use super::{Finding, RuleContext, Severity};
+use crate::exec::ExecCommand;
+use crate::keys::Key;
+use crate::{EntryType, LocaleString};
+
+/// Id of the rule reporting keys that are neither standard nor extensions (`X-`).
+pub const UNKNOWN_KEY: &str = "unknown-key";
+/// Id of the rule reporting list values that are not terminated by a semicolon.
+pub const TRAILING_SEMICOLON: &str = "trailing-semicolon";
+/// Id of the rule reporting Exec values with invalid quoting or field codes.
+pub const EXEC_FIELD_CODES: &str = "exec-field-codes";
+/// Id of the rule reporting desktop environments listed in both OnlyShowIn and NotShowIn.
+pub const SHOW_IN_CONFLICT: &str = "show-in-conflict";
+/// Id of the rule reporting names that are empty or only whitespace.
+pub const EMPTY_NAME: &str = "empty-name";
+/// Id of the rule reporting GenericName or Comment values that repeat another of the three.
+pub const REDUNDANT_NAME: &str = "redundant-name";
+/// Id of the rule reporting Keywords that repeat the Name or GenericName.
+pub const REDUNDANT_KEYWORD: &str = "redundant-keyword";
+
+/// Returns the action id if the group is an action group
+fn action_of(group: &str) -> Option<&str> {
+    group.strip_prefix("Desktop Action ")
+}
+
+pub(super) fn check_unknown_keys(ctx: &RuleContext) -> Vec<Finding> {
+    ctx.raw
+        .iter()
+        .filter_map(|pair| {
+            let group = pair.group.as_deref()?;
+            let key = Key::from(pair.key.as_str());
+            let known = match (action_of(group), key.info()) {
+                (_, None) => matches!(key, Key::Extension(_)),
+                (Some(_), Some(info)) => info.in_action(),
+                (None, Some(_)) if group == "Desktop Entry" => true,
+                (None, Some(_)) => return None,
+            };
+
+            if known {
+                return None;
+            }
+
+            Some(Finding {
+                rule: UNKNOWN_KEY,
+                severity: Severity::Warning,
+                action: action_of(group).map(|a| a.to_string()),
+                key: Some(pair.key.clone()),
+                line: Some(pair.line),
+                message: format!(
+                    "{:?} is not a key of the {group} group, extension keys must start with \"X-\"",
+                    pair.key
+                ),
+                suggestion: None,
+            })
+        })
+        .collect()
+}
+
+pub(super) fn check_trailing_semicolons(ctx: &RuleContext) -> Vec<Finding> {
+    ctx.raw
+        .iter()
+        .filter(|pair| {
+            pair.group.as_deref() == Some("Desktop Entry")
+                && Key::from(pair.key.as_str())
+                    .info()
+                    .is_some_and(|info| info.is_list())
+                && !pair.value.is_empty()
+                && !pair.value.ends_with(';')
+        })
+        .map(|pair| Finding {
+            rule: TRAILING_SEMICOLON,
+            severity: Severity::Warning,
+            action: None,
+            key: Some(pair.key.clone()),
+            line: Some(pair.line),
+            message: format!(
+                "the list value {:?} should be terminated by a semicolon",
+                pair.value
+            ),
+            suggestion: None,
+        })
+        .collect()
+}
+
+pub(super) fn check_exec_field_codes(ctx: &RuleContext) -> Vec<Finding> {
+    let mut execs = vec![];
+
+    if let EntryType::Application(ref fields) = ctx.file.entry.entry_type {
+        if let Some(ref exec) = fields.exec {
+            execs.push((None, exec));
+        }
+    }
+
+    let mut actions = ctx.file.actions.iter().collect::<Vec<_>>();
+    actions.sort_by(|a, b| a.0.cmp(b.0));
+    for (id, action) in actions {
+        if let Some(ref exec) = action.exec {
+            execs.push((Some(id), exec));
+        }
+    }
+
+    execs
+        .into_iter()
+        .filter_map(|(action, exec)| {
+            let error = match ExecCommand::parse(exec) {
+                Ok(cmd) => cmd.validate_field_codes().err()?,
+                Err(e) => e,
+            };
+
+            Some(Finding {
+                rule: EXEC_FIELD_CODES,
+                severity: Severity::Error,
+                action: action.cloned(),
+                key: Some("Exec".into()),
+                line: None,
+                message: error.to_string(),
+                suggestion: None,
+            })
+        })
+        .collect()
+}
+
+pub(super) fn check_show_in_conflict(ctx: &RuleContext) -> Vec<Finding> {
+    let entry = &ctx.file.entry;
+    let (Some(only), Some(not)) = (&entry.only_show_in, &entry.not_show_in) else {
+        return vec![];
+    };
+
+    only.iter()
+        .filter(|desktop| not.contains(desktop))
+        .map(|desktop| Finding {
+            rule: SHOW_IN_CONFLICT,
+            severity: Severity::Error,
+            action: None,
+            key: Some("NotShowIn".into()),
+            line: None,
+            message: format!("{desktop:?} is listed in both OnlyShowIn and NotShowIn"),
+            suggestion: None,
+        })
+        .collect()
+}
+
+pub(super) fn check_empty_names(ctx: &RuleContext) -> Vec<Finding> {
+    let mut names = vec![(None, &ctx.file.entry.name)];
+    let mut actions = ctx.file.actions.iter().collect::<Vec<_>>();
+    actions.sort_by(|a, b| a.0.cmp(b.0));
+    names.extend(actions.into_iter().map(|(id, a)| (Some(id), &a.name)));
+
+    let line_of = |action: Option<&String>, locale: Option<&str>| {
+        let group = match action {
+            Some(id) => format!("Desktop Action {id}"),
+            None => "Desktop Entry".into(),
+        };
+        ctx.raw
+            .iter()
+            .rev()
+            .find(|pair| {
+                pair.group.as_ref() == Some(&group)
+                    && pair.key == "Name"
+                    && pair.locale.as_deref() == locale
+            })
+            .map(|pair| pair.line)
+    };
+
+    let mut findings = vec![];
+    for (action, name) in names {
+        let LocaleString { default, variants } = name;
+        if default.trim().is_empty() {
+            findings.push(Finding {
+                rule: EMPTY_NAME,
+                severity: Severity::Error,
+                action: action.cloned(),
+                key: Some("Name".into()),
+                line: line_of(action, None),
+                message: "the name is empty and would show up as a blank item".into(),
+                suggestion: None,
+            });
+        }
+
+        let mut variants = variants.iter().collect::<Vec<_>>();
+        variants.sort();
+        for (locale, value) in variants {
+            if value.trim().is_empty() {
+                findings.push(Finding {
+                    rule: EMPTY_NAME,
+                    severity: Severity::Warning,
+                    action: action.cloned(),
+                    key: Some(format!("Name[{locale}]")),
+                    line: line_of(action, Some(locale)),
+                    message: format!(
+                        "the name is empty and would show up as a blank item for the {locale} locale"
+                    ),
+                    suggestion: None,
+                });
+            }
+        }
+    }
+
+    findings
+}
+
+pub(super) fn check_redundant_names(ctx: &RuleContext) -> Vec<Finding> {
+    let entry = &ctx.file.entry;
+    let strings = [
+        (Key::Name, Some(&entry.name)),
+        (Key::GenericName, entry.generic_name.as_ref()),
+        (Key::Comment, entry.comment.as_ref()),
+    ];
+
+    let mut locales = strings
+        .iter()
+        .flat_map(|(_, s)| s.iter().flat_map(|s| s.variants.keys()))
+        .map(|locale| Some(locale.as_str()))
+        .collect::<Vec<_>>();
+    locales.sort();
+    locales.dedup();
+    locales.insert(0, None);
+
+    let mut findings = vec![];
+    for locale in locales {
+        let resolved = strings
+            .iter()
+            .filter_map(|(key, s)| {
+                let (value, found) = (*s)?.resolve(locale.as_slice());
+                Some((key, value.trim().to_lowercase(), found))
+            })
+            .collect::<Vec<_>>();
+
+        for (i, (first, first_value, first_locale)) in resolved.iter().enumerate() {
+            for (second, second_value, second_locale) in &resolved[i + 1..] {
+                // pairs of default values are reported for the default locale only
+                let is_localized = first_locale.is_some() || second_locale.is_some();
+                if first_value != second_value || locale.is_some() != is_localized {
+                    continue;
+                }
+
+                let key = match second_locale {
+                    Some(l) => format!("{second}[{l}]"),
+                    None => second.to_string(),
+                };
+                let message = match locale {
+                    Some(l) => format!("{second} repeats {first} for the {l} locale"),
+                    None => format!("{second} repeats {first}"),
+                };
+                findings.push(Finding {
+                    rule: REDUNDANT_NAME,
+                    severity: Severity::Warning,
+                    action: None,
+                    key: Some(key),
+                    line: None,
+                    message,
+                    suggestion: None,
+                });
+            }
+        }
+    }
+
+    findings
+}
+
+/// Reports the keywords of each locale equal to the Name or GenericName of the same locale,
+/// compared trimmed and case-insensitively, as warnings. A keyword equal to a single word of
+/// a multi-word name is only a hint, since it is often intentional.
+pub(super) fn check_redundant_keywords(ctx: &RuleContext) -> Vec<Finding> {
+    let entry = &ctx.file.entry;
+    let Some(keywords) = entry.as_application().and_then(|f| f.keywords.as_ref()) else {
+        return vec![];
+    };
+    let names = [
+        (Key::Name, Some(&entry.name)),
+        (Key::GenericName, entry.generic_name.as_ref()),
+    ];
+
+    let mut locales = keywords
+        .variants
+        .keys()
+        .map(|locale| Some(locale.as_str()))
+        .collect::<Vec<_>>();
+    locales.sort();
+    locales.insert(0, None);
+
+    let mut findings = vec![];
+    for locale in locales {
+        let list = match locale {
+            Some(l) => &keywords.variants[l],
+            None => &keywords.default,
+        };
+        let key = match locale {
+            Some(l) => format!("Keywords[{l}]"),
+            None => "Keywords".into(),
+        };
+        let for_locale = locale.map_or(String::new(), |l| format!(" for the {l} locale"));
+
+        for (name_key, name) in &names {
+            let Some(name) = name else {
+                continue;
+            };
+            let name = name.resolve(locale.as_slice()).0.trim().to_lowercase();
+            let words = name.split_whitespace().collect::<Vec<_>>();
+
+            for (i, keyword) in list.iter().enumerate() {
+                let keyword = keyword.trim().to_lowercase();
+                let severity = if keyword == name {
+                    Severity::Warning
+                } else if words.len() > 1 && words.contains(&keyword.as_str()) {
+                    Severity::Hint
+                } else {
+                    continue;
+                };
+
+                let repeats = match severity {
+                    Severity::Hint => format!("a word of {name_key}"),
+                    _ => name_key.to_string(),
+                };
+                findings.push(Finding {
+                    rule: REDUNDANT_KEYWORD,
+                    severity,
+                    action: None,
+                    key: Some(key.clone()),
+                    line: None,
+                    message: format!("keyword {i} {keyword:?} repeats {repeats}{for_locale}"),
+                    suggestion: None,
+                });
+            }
+        }
+    }
+
+    findings
+}
@@ -0,0 +1,116 @@
+//! Rules about D-Bus names: the desktop file id of D-Bus activatable entries and the
+//! interfaces listed in Implements.
+
+use std::path::Path;
+
+use super::{Finding, RuleContext, Severity};
+use crate::EntryType;
+
+/// Id of the rule reporting desktop file ids that are not valid D-Bus well-known names.
+pub const DBUS_NAME: &str = "dbus-name";
+/// Id of the rule reporting Implements values that are not valid D-Bus interface names.
+pub const IMPLEMENTS_INTERFACE: &str = "implements-interface";
+
+/// The longest name D-Bus accepts
+const MAX_NAME_LEN: usize = 255;
+
+/// Checks a name against the D-Bus grammar of well-known bus names, which allows hyphens, or
+/// of interface names, which don't. Returns why the name is invalid.
+pub(super) fn dbus_name_error(name: &str, allow_hyphen: bool) -> Option<String> {
+    if name.len() > MAX_NAME_LEN {
+        return Some(format!("is longer than {MAX_NAME_LEN} bytes"));
+    }
+
+    let elements = name.split('.').collect::<Vec<_>>();
+    if elements.len() < 2 {
+        return Some("needs at least two elements separated by dots".into());
+    }
+
+    for element in elements {
+        let Some(first) = element.chars().next() else {
+            return Some("has an empty element".into());
+        };
+        if first.is_ascii_digit() {
+            return Some(format!("has the element {element:?} starting with a digit"));
+        }
+        if let Some(ch) = element
+            .chars()
+            .find(|&c| !(c.is_ascii_alphanumeric() || c == '_' || (allow_hyphen && c == '-')))
+        {
+            return Some(format!("has the character {ch:?}, which is not allowed"));
+        }
+    }
+
+    None
+}
+
+/// Reports a desktop file id that is not a valid D-Bus well-known name, as an error when the
+/// entry is D-Bus activatable since activation then never works, and as a warning otherwise
+pub(super) fn check_dbus_name(ctx: &RuleContext, path: &Path) -> Option<Finding> {
+    let file_name = path.file_name()?.to_str()?;
+    let id = file_name.strip_suffix(".desktop").unwrap_or(file_name);
+    let reason = dbus_name_error(id, true)?;
+
+    let activatable = ctx.file.entry.dbus_activatable == Some(true);
+    Some(Finding {
+        rule: DBUS_NAME,
+        severity: match activatable {
+            true => Severity::Error,
+            false => Severity::Warning,
+        },
+        action: None,
+        key: activatable.then(|| "DBusActivatable".into()),
+        line: None,
+        message: match activatable {
+            true => format!(
+                "the desktop file id {id:?} {reason}, so D-Bus activation of the entry never works"
+            ),
+            false => format!(
+                "the desktop file id {id:?} {reason}; ids are expected to be D-Bus well-known \
+                 names such as org.example.App"
+            ),
+        },
+        suggestion: None,
+    })
+}
+
+pub(super) fn check_implements(ctx: &RuleContext) -> Vec<Finding> {
+    let EntryType::Application(ref fields) = ctx.file.entry.entry_type else {
+        return vec![];
+    };
+
+    fields
+        .implements
+        .iter()
+        .flatten()
+        .filter_map(|interface| {
+            let reason = dbus_name_error(interface, false)?;
+            Some(Finding {
+                rule: IMPLEMENTS_INTERFACE,
+                severity: Severity::Error,
+                action: None,
+                key: Some("Implements".into()),
+                line: None,
+                message: format!("the interface {interface:?} {reason}"),
+                suggestion: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dbus_name_error() {
+        assert_eq!(dbus_name_error("org.gnome.TextEditor", false), None);
+        assert_eq!(dbus_name_error("org.example.my-app", true), None);
+        assert!(dbus_name_error("org.example.my-app", false).is_some());
+        assert!(dbus_name_error("firefox", true).is_some());
+        assert!(dbus_name_error("org..App", true).is_some());
+        assert!(dbus_name_error("org.7zip.App", true).is_some());
+        assert!(dbus_name_error("my launcher.app", true).is_some());
+        assert!(dbus_name_error(&format!("org.{}", "a".repeat(252)), true).is_some());
+    }
+}
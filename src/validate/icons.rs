@@ -0,0 +1,235 @@
+//! The [`ICON_RESOLVABLE`] rule, which needs the `resolve-icons` feature.
+
+use std::path::{Path, PathBuf};
+
+use super::{Finding, Severity, ICON_RESOLVABLE};
+use crate::{DesktopFile, IconString};
+
+/// Configuration of the icon lookup performed by the [`ICON_RESOLVABLE`] rule.
+#[derive(Debug, Clone)]
+pub struct IconLookupConfig {
+    /// The icon theme to search before falling back to hicolor
+    pub theme: String,
+    /// The nominal icon size to look up
+    pub size: u16,
+    /// The scale to look up
+    pub scale: u16,
+    /// Base directories containing icon themes, such as `/usr/share/icons`.
+    /// If None, the system lookup of the freedesktop-icons crate is used.
+    pub search_paths: Option<Vec<PathBuf>>,
+}
+
+impl Default for IconLookupConfig {
+    fn default() -> Self {
+        Self {
+            theme: "hicolor".into(),
+            size: 48,
+            scale: 1,
+            search_paths: None,
+        }
+    }
+}
+
+impl IconLookupConfig {
+    /// Describes the lookup that is attempted for a themed icon name
+    fn describe(&self) -> String {
+        let themes = if self.theme == "hicolor" {
+            "theme \"hicolor\"".to_string()
+        } else {
+            format!("theme \"{}\" (or \"hicolor\")", self.theme)
+        };
+
+        format!("{themes} at size {}@{}", self.size, self.scale)
+    }
+
+    /// Looks up an icon name in the configured search paths
+    fn lookup_in(&self, name: &str, search_paths: &[PathBuf]) -> Option<PathBuf> {
+        const EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+
+        let sized = if self.scale > 1 {
+            format!("{0}x{0}@{1}", self.size, self.scale)
+        } else {
+            format!("{0}x{0}", self.size)
+        };
+
+        let mut themes = vec![self.theme.as_str()];
+        if self.theme != "hicolor" {
+            themes.push("hicolor");
+        }
+
+        for theme in themes {
+            for base in search_paths {
+                for size_dir in [sized.as_str(), "scalable"] {
+                    let dir = base.join(theme).join(size_dir);
+                    let Ok(contexts) = std::fs::read_dir(&dir) else {
+                        continue;
+                    };
+
+                    let mut contexts = contexts
+                        .filter_map(|c| c.ok().map(|c| c.path()))
+                        .collect::<Vec<_>>();
+                    contexts.sort();
+
+                    for context in contexts {
+                        for ext in EXTENSIONS {
+                            let candidate = context.join(format!("{name}.{ext}"));
+                            if candidate.is_file() {
+                                return Some(candidate);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // unthemed icons live directly in the base directories, like /usr/share/pixmaps
+        search_paths.iter().find_map(|base| {
+            EXTENSIONS
+                .iter()
+                .map(|ext| base.join(format!("{name}.{ext}")))
+                .find(|candidate| candidate.is_file())
+        })
+    }
+
+    /// Looks up an icon name, returning the path if one was found
+    fn lookup(&self, name: &str) -> Option<PathBuf> {
+        match self.search_paths {
+            Some(ref paths) => self.lookup_in(name, paths),
+            None => freedesktop_icons::lookup(name)
+                .with_theme(&self.theme)
+                .with_size(self.size)
+                .with_scale(self.scale)
+                .find(),
+        }
+    }
+}
+
+/// Checks that the entry's Icon and the Icon of every action resolve to a readable file.
+///
+/// Absolute paths must exist and be readable, icon names must be found in the configured
+/// theme or in hicolor. This rule touches the filesystem, so it is not part of any default
+/// rule set.
+pub fn check_icons(file: &DesktopFile, config: &IconLookupConfig) -> Vec<Finding> {
+    let mut findings = vec![];
+
+    if let Some(ref icon) = file.entry.icon {
+        if let Some(message) = check_icon(icon, config) {
+            findings.push(Finding {
+                rule: ICON_RESOLVABLE,
+                severity: Severity::Warning,
+                action: None,
+                key: Some("Icon".into()),
+                line: None,
+                message,
+                suggestion: None,
+            });
+        }
+    }
+
+    let mut actions = file.actions.iter().collect::<Vec<_>>();
+    actions.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (id, action) in actions {
+        if let Some(ref icon) = action.icon {
+            if let Some(message) = check_icon(icon, config) {
+                findings.push(Finding {
+                    rule: ICON_RESOLVABLE,
+                    severity: Severity::Warning,
+                    action: Some(id.clone()),
+                    key: Some("Icon".into()),
+                    line: None,
+                    message,
+                    suggestion: None,
+                });
+            }
+        }
+    }
+
+    findings
+}
+
+/// Returns a message describing why the icon does not resolve, None if it does
+fn check_icon(icon: &IconString, config: &IconLookupConfig) -> Option<String> {
+    let path = Path::new(&icon.content);
+
+    if icon.content.is_empty() {
+        return Some("Icon is empty".into());
+    }
+
+    if path.is_absolute() {
+        return if !path.is_file() {
+            Some(format!(
+                "Icon path {:?} does not exist or is not a file",
+                icon.content
+            ))
+        } else if std::fs::File::open(path).is_err() {
+            Some(format!("Icon path {:?} is not readable", icon.content))
+        } else {
+            None
+        };
+    }
+
+    match config.lookup(&icon.content) {
+        Some(_) => None,
+        None => Some(format!(
+            "Icon name {:?} was not found in {}",
+            icon.content,
+            config.describe()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn temp_icon_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "desktop-file-parser-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let apps = dir.join("hicolor").join("48x48").join("apps");
+        std::fs::create_dir_all(&apps).unwrap();
+        std::fs::write(apps.join("present.png"), b"").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_icon_rule_finds_missing_names() {
+        let dir = temp_icon_dir("icon-rule");
+        let content = r#"
+[Desktop Entry]
+Name=Test
+Type=Application
+Icon=present
+Actions=a;b;
+
+[Desktop Action a]
+Name=A
+Icon=missing
+
+[Desktop Action b]
+Name=B
+Icon=/nonexistent/icon.png
+"#;
+        let file = parse(content).unwrap();
+        let config = IconLookupConfig {
+            theme: "Adwaita".into(),
+            search_paths: Some(vec![dir.clone()]),
+            ..Default::default()
+        };
+
+        let findings = check_icons(&file, &config);
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].action.as_deref(), Some("a"));
+        assert!(findings[0]
+            .message
+            .contains("theme \"Adwaita\" (or \"hicolor\") at size 48@1"));
+        assert_eq!(findings[1].action.as_deref(), Some("b"));
+        assert!(findings[1].message.contains("does not exist"));
+    }
+}
@@ -0,0 +1,47 @@
+//! Rules about where a file is installed, which only run for files validated with their path.
+
+use std::path::{Path, PathBuf};
+
+use super::{Finding, Severity};
+use crate::DesktopFile;
+
+/// Id of the rule reporting Hidden=true entries installed in a system data directory.
+pub const HIDDEN_SYSTEM_ENTRY: &str = "hidden-system-entry";
+
+/// Reports a Hidden=true entry whose path is under one of the system data directories, or
+/// under `$XDG_DATA_DIRS` if None
+pub(super) fn check_hidden_system_entry(
+    file: &DesktopFile,
+    path: &Path,
+    system_dirs: Option<&[PathBuf]>,
+) -> Option<Finding> {
+    if file.entry.hidden != Some(true) {
+        return None;
+    }
+
+    let defaults;
+    let system_dirs = match system_dirs {
+        Some(dirs) => dirs,
+        None => {
+            defaults = crate::util::data_dirs();
+            &defaults
+        }
+    };
+    let dir = system_dirs.iter().find(|dir| path.starts_with(dir))?;
+
+    Some(Finding {
+        rule: HIDDEN_SYSTEM_ENTRY,
+        severity: Severity::Warning,
+        action: None,
+        key: Some("Hidden".into()),
+        line: None,
+        message: format!(
+            "the entry is hidden but installed in the system directory {dir:?}. Hidden=true marks \
+             an entry as deleted and is meant for a file with the same desktop file ID in a \
+             directory of higher precedence, such as ~/.local/share, to hide the system entry; \
+             shipped in {dir:?} it masks entries of the same ID in directories of lower \
+             precedence"
+        ),
+        suggestion: None,
+    })
+}
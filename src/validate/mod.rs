@@ -0,0 +1,716 @@
+//! Validation rules that check a parsed [`DesktopFile`] for problems the parser itself
+//! does not reject, such as icons that cannot be found on the system.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::{parser::raw_pairs, DesktopFile, ParseError};
+
+mod basic;
+mod dbus;
+#[cfg(feature = "resolve-icons")]
+mod icons;
+mod location;
+mod program;
+
+pub use basic::{
+    EMPTY_NAME, EXEC_FIELD_CODES, REDUNDANT_KEYWORD, REDUNDANT_NAME, SHOW_IN_CONFLICT,
+    TRAILING_SEMICOLON, UNKNOWN_KEY,
+};
+pub use dbus::{DBUS_NAME, IMPLEMENTS_INTERFACE};
+#[cfg(feature = "resolve-icons")]
+pub use icons::{check_icons, IconLookupConfig};
+pub use location::HIDDEN_SYSTEM_ENTRY;
+pub use program::{
+    check_exec, check_exec_paths, ExecLookupConfig, ExecPathPolicy, EXEC_PATH_POLICY,
+    EXEC_PROGRAM_EXISTS,
+};
+
+/// Id of the rule checking that every Icon resolves to a file. The rule needs the
+/// `resolve-icons` feature, while its id is always defined for [`BUILTIN_RULES`].
+pub const ICON_RESOLVABLE: &str = "icon-resolvable";
+
+/// How serious a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Severity {
+    /// A suggestion that does not indicate a problem by itself
+    Hint,
+    /// Something that is likely wrong but does not break the entry
+    Warning,
+    /// Something that breaks the entry for its users
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Self::Hint => "hint",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        };
+        write!(f, "{str}")
+    }
+}
+
+/// A single problem reported by a validation rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// The id of the rule that produced this finding
+    pub rule: &'static str,
+    /// How serious the finding is
+    pub severity: Severity,
+    /// The identifier of the action the finding is about, None for the main entry
+    pub action: Option<String>,
+    /// The key the finding is about, if any
+    pub key: Option<String>,
+    /// The line the finding is about, if known
+    pub line: Option<usize>,
+    /// Human readable description of the problem
+    pub message: String,
+    /// A replacement value of the key that fixes the problem, when one can be derived
+    pub suggestion: Option<String>,
+}
+
+impl Finding {
+    /// Returns the stable code of the rule of the finding, None for custom rules
+    pub fn code(&self) -> Option<&'static str> {
+        BUILTIN_RULES
+            .iter()
+            .find(|rule| rule.id == self.rule)
+            .map(|rule| rule.code)
+    }
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.code() {
+            Some(code) => write!(f, "{}[{code} {}]", self.severity, self.rule)?,
+            None => write!(f, "{}[{}]", self.severity, self.rule)?,
+        }
+        match (&self.action, &self.key) {
+            (Some(action), Some(key)) => write!(f, " Desktop Action {action}: {key}")?,
+            (Some(action), None) => write!(f, " Desktop Action {action}")?,
+            (None, Some(key)) => write!(f, " {key}")?,
+            (None, None) => {}
+        }
+        if let Some(line) = self.line {
+            write!(f, " (line {line})")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// The findings of a validation run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// All findings, in the order the rules produced them
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    /// Returns true if no finding has the severity Error
+    pub fn is_valid(&self) -> bool {
+        !self.findings.iter().any(|f| f.severity == Severity::Error)
+    }
+
+    /// Returns the findings with the given severity
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(move |f| f.severity == severity)
+    }
+}
+
+/// A key value pair as written in the source of the validated file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawEntry {
+    /// The name of the group the pair is in, such as `Desktop Entry`, None if the pair
+    /// appears before any group header
+    pub group: Option<String>,
+    /// The key without the locale
+    pub key: String,
+    /// The locale of the key, if any
+    pub locale: Option<String>,
+    /// The value as written in the file
+    pub value: String,
+    /// The line of the pair
+    pub line: usize,
+}
+
+/// Everything a rule gets to look at.
+#[derive(Debug, Clone, Copy)]
+pub struct RuleContext<'a> {
+    /// The parsed file
+    pub file: &'a DesktopFile,
+    /// The key value pairs of the source, empty if the file was not validated from source
+    pub raw: &'a [RawEntry],
+    /// The path of the file, if it was validated with [`Validator::validate_source_at`]
+    pub path: Option<&'a Path>,
+}
+
+/// Description of a built-in rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleInfo {
+    /// The stable id of the rule
+    pub id: &'static str,
+    /// The stable code of the rule, such as `L001`. Codes are never reused or reassigned.
+    pub code: &'static str,
+    /// The severity of the findings of the rule, unless overridden
+    pub severity: Severity,
+    /// Whether the rule runs unless explicitly enabled. Rules touching the filesystem are off
+    /// by default.
+    pub default_enabled: bool,
+}
+
+/// All the built-in rules.
+pub const BUILTIN_RULES: &[RuleInfo] = &[
+    RuleInfo {
+        id: UNKNOWN_KEY,
+        code: "L001",
+        severity: Severity::Warning,
+        default_enabled: true,
+    },
+    RuleInfo {
+        id: TRAILING_SEMICOLON,
+        code: "L002",
+        severity: Severity::Warning,
+        default_enabled: true,
+    },
+    RuleInfo {
+        id: EXEC_FIELD_CODES,
+        code: "L003",
+        severity: Severity::Error,
+        default_enabled: true,
+    },
+    RuleInfo {
+        id: SHOW_IN_CONFLICT,
+        code: "L004",
+        severity: Severity::Error,
+        default_enabled: true,
+    },
+    RuleInfo {
+        id: EXEC_PROGRAM_EXISTS,
+        code: "L005",
+        severity: Severity::Error,
+        default_enabled: false,
+    },
+    RuleInfo {
+        id: ICON_RESOLVABLE,
+        code: "L006",
+        severity: Severity::Warning,
+        default_enabled: false,
+    },
+    RuleInfo {
+        id: EMPTY_NAME,
+        code: "L007",
+        severity: Severity::Error,
+        default_enabled: true,
+    },
+    RuleInfo {
+        id: REDUNDANT_NAME,
+        code: "L008",
+        severity: Severity::Warning,
+        default_enabled: true,
+    },
+    RuleInfo {
+        id: HIDDEN_SYSTEM_ENTRY,
+        code: "L009",
+        severity: Severity::Warning,
+        default_enabled: false,
+    },
+    RuleInfo {
+        id: DBUS_NAME,
+        code: "L010",
+        severity: Severity::Error,
+        default_enabled: true,
+    },
+    RuleInfo {
+        id: IMPLEMENTS_INTERFACE,
+        code: "L011",
+        severity: Severity::Error,
+        default_enabled: true,
+    },
+    RuleInfo {
+        id: EXEC_PATH_POLICY,
+        code: "L012",
+        severity: Severity::Error,
+        default_enabled: false,
+    },
+    RuleInfo {
+        id: REDUNDANT_KEYWORD,
+        code: "L013",
+        severity: Severity::Warning,
+        default_enabled: true,
+    },
+];
+
+type CustomRule = Box<dyn Fn(&RuleContext) -> Vec<Finding>>;
+
+/// A configurable set of validation rules.
+///
+/// # Examples
+/// ```
+/// use freedesktop_file_parser::validate::{Severity, Validator};
+///
+/// let validator = Validator::new()
+///     .disable("trailing-semicolon")
+///     .severity("unknown-key", Severity::Error);
+///
+/// let report = validator
+///     .validate_source("[Desktop Entry]\nType=Application\nName=Foo\nFoo=bar")
+///     .unwrap();
+/// assert!(!report.is_valid());
+/// ```
+#[derive(Default)]
+pub struct Validator {
+    enabled: HashSet<String>,
+    disabled: HashSet<String>,
+    severities: HashMap<String, Severity>,
+    custom: Vec<CustomRule>,
+    exec_lookup: ExecLookupConfig,
+    exec_path_policy: ExecPathPolicy,
+    #[cfg(feature = "resolve-icons")]
+    icon_lookup: IconLookupConfig,
+    system_dirs: Option<Vec<PathBuf>>,
+}
+
+impl Validator {
+    /// Creates a validator running the built-in rules that are enabled by default
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables a rule, which is needed for the rules that are off by default
+    pub fn enable(mut self, id: &str) -> Self {
+        self.disabled.remove(id);
+        self.enabled.insert(id.into());
+        self
+    }
+
+    /// Disables a rule
+    pub fn disable(mut self, id: &str) -> Self {
+        self.enabled.remove(id);
+        self.disabled.insert(id.into());
+        self
+    }
+
+    /// Changes the severity of every finding produced by a rule, including custom rules
+    pub fn severity(mut self, id: &str, severity: Severity) -> Self {
+        self.severities.insert(id.into(), severity);
+        self
+    }
+
+    /// Adds a custom rule. The findings it returns go through the same severity overrides
+    /// and can be disabled by their rule id like the built-in rules.
+    pub fn custom<F>(mut self, rule: F) -> Self
+    where
+        F: Fn(&RuleContext) -> Vec<Finding> + 'static,
+    {
+        self.custom.push(Box::new(rule));
+        self
+    }
+
+    /// Sets the program lookup of the [`EXEC_PROGRAM_EXISTS`] rule and enables it. The
+    /// lookup is also used by the [`EXEC_PATH_POLICY`] rule.
+    pub fn exec_lookup(mut self, config: ExecLookupConfig) -> Self {
+        self.exec_lookup = config;
+        self.enable(EXEC_PROGRAM_EXISTS)
+    }
+
+    /// Sets the policy of the [`EXEC_PATH_POLICY`] rule and enables it. Absolute paths are
+    /// suggested from the search path of [`Validator::exec_lookup`], `$PATH` by default.
+    pub fn exec_path_policy(mut self, policy: ExecPathPolicy) -> Self {
+        self.exec_path_policy = policy;
+        self.enable(EXEC_PATH_POLICY)
+    }
+
+    /// Sets the icon lookup of the [`ICON_RESOLVABLE`] rule and enables it
+    #[cfg(feature = "resolve-icons")]
+    pub fn icon_lookup(mut self, config: IconLookupConfig) -> Self {
+        self.icon_lookup = config;
+        self.enable(ICON_RESOLVABLE)
+    }
+
+    /// Sets the system data directories of the [`HIDDEN_SYSTEM_ENTRY`] rule, `$XDG_DATA_DIRS`
+    /// by default, and enables it
+    pub fn system_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.system_dirs = Some(dirs);
+        self.enable(HIDDEN_SYSTEM_ENTRY)
+    }
+
+    /// Returns whether a rule runs with this configuration
+    pub fn is_enabled(&self, id: &str) -> bool {
+        if self.disabled.contains(id) {
+            return false;
+        }
+
+        self.enabled.contains(id)
+            || BUILTIN_RULES
+                .iter()
+                .any(|rule| rule.id == id && rule.default_enabled)
+    }
+
+    /// Validates a parsed file. Rules that need the source, such as [`UNKNOWN_KEY`], find
+    /// nothing this way; use [`Validator::validate_source`] for them.
+    pub fn validate(&self, file: &DesktopFile) -> ValidationReport {
+        self.run(&RuleContext {
+            file,
+            raw: &[],
+            path: None,
+        })
+    }
+
+    /// Parses and validates the content of a desktop file
+    pub fn validate_source(&self, input: &str) -> Result<ValidationReport, ParseError> {
+        self.validate_source_with_path(input, None)
+    }
+
+    /// Parses and validates the content of the desktop file at `path`, which also runs the
+    /// rules about where the file is installed, such as [`HIDDEN_SYSTEM_ENTRY`]
+    pub fn validate_source_at(
+        &self,
+        input: &str,
+        path: &Path,
+    ) -> Result<ValidationReport, ParseError> {
+        self.validate_source_with_path(input, Some(path))
+    }
+
+    fn validate_source_with_path(
+        &self,
+        input: &str,
+        path: Option<&Path>,
+    ) -> Result<ValidationReport, ParseError> {
+        let file = crate::parse(input)?;
+        let raw = raw_pairs(input)?
+            .into_iter()
+            .map(|pair| RawEntry {
+                group: pair.header.map(|h| h.name()),
+                key: pair.key,
+                locale: pair.locale,
+                value: pair.value,
+                line: pair.line_number,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(self.run(&RuleContext {
+            file: &file,
+            raw: &raw,
+            path,
+        }))
+    }
+
+    fn run(&self, ctx: &RuleContext) -> ValidationReport {
+        let mut findings = vec![];
+
+        if self.is_enabled(UNKNOWN_KEY) {
+            findings.extend(basic::check_unknown_keys(ctx));
+        }
+        if self.is_enabled(TRAILING_SEMICOLON) {
+            findings.extend(basic::check_trailing_semicolons(ctx));
+        }
+        if self.is_enabled(EXEC_FIELD_CODES) {
+            findings.extend(basic::check_exec_field_codes(ctx));
+        }
+        if self.is_enabled(SHOW_IN_CONFLICT) {
+            findings.extend(basic::check_show_in_conflict(ctx));
+        }
+        if self.is_enabled(EMPTY_NAME) {
+            findings.extend(basic::check_empty_names(ctx));
+        }
+        if self.is_enabled(REDUNDANT_NAME) {
+            findings.extend(basic::check_redundant_names(ctx));
+        }
+        if self.is_enabled(REDUNDANT_KEYWORD) {
+            findings.extend(basic::check_redundant_keywords(ctx));
+        }
+        if self.is_enabled(IMPLEMENTS_INTERFACE) {
+            findings.extend(dbus::check_implements(ctx));
+        }
+        if self.is_enabled(EXEC_PROGRAM_EXISTS) {
+            findings.extend(check_exec(ctx.file, &self.exec_lookup));
+        }
+        if self.is_enabled(EXEC_PATH_POLICY) {
+            findings.extend(check_exec_paths(
+                ctx.file,
+                self.exec_path_policy,
+                &self.exec_lookup,
+            ));
+        }
+        #[cfg(feature = "resolve-icons")]
+        if self.is_enabled(ICON_RESOLVABLE) {
+            findings.extend(check_icons(ctx.file, &self.icon_lookup));
+        }
+        if let (true, Some(path)) = (self.is_enabled(HIDDEN_SYSTEM_ENTRY), ctx.path) {
+            findings.extend(location::check_hidden_system_entry(
+                ctx.file,
+                path,
+                self.system_dirs.as_deref(),
+            ));
+        }
+        if let (true, Some(path)) = (self.is_enabled(DBUS_NAME), ctx.path) {
+            findings.extend(dbus::check_dbus_name(ctx, path));
+        }
+
+        for rule in &self.custom {
+            findings.extend(
+                rule(ctx)
+                    .into_iter()
+                    .filter(|finding| !self.disabled.contains(finding.rule)),
+            );
+        }
+
+        for finding in findings.iter_mut() {
+            if let Some(severity) = self.severities.get(finding.rule) {
+                finding.severity = *severity;
+            }
+        }
+
+        ValidationReport { findings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = r#"
+[Desktop Entry]
+Name=Test
+Type=Application
+Exec=test %x
+Categories=Utility
+OnlyShowIn=GNOME;
+NotShowIn=GNOME;KDE;
+X-Vendor-Key=fine
+Unknown=bad
+"#;
+
+    #[test]
+    fn test_default_rules() {
+        let report = Validator::new().validate_source(CONTENT).unwrap();
+        let rules = report.findings.iter().map(|f| f.rule).collect::<Vec<_>>();
+
+        assert_eq!(
+            rules,
+            vec![
+                UNKNOWN_KEY,
+                TRAILING_SEMICOLON,
+                EXEC_FIELD_CODES,
+                SHOW_IN_CONFLICT
+            ]
+        );
+        assert_eq!(report.findings[0].line, Some(9));
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn test_rule_codes() {
+        let codes = BUILTIN_RULES
+            .iter()
+            .map(|rule| (rule.id, rule.code))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            [
+                ("unknown-key", "L001"),
+                ("trailing-semicolon", "L002"),
+                ("exec-field-codes", "L003"),
+                ("show-in-conflict", "L004"),
+                ("exec-program-exists", "L005"),
+                ("icon-resolvable", "L006"),
+                ("empty-name", "L007"),
+                ("redundant-name", "L008"),
+                ("hidden-system-entry", "L009"),
+                ("dbus-name", "L010"),
+                ("implements-interface", "L011"),
+                ("exec-path-policy", "L012"),
+                ("redundant-keyword", "L013"),
+            ]
+        );
+
+        let report = Validator::new().validate_source(CONTENT).unwrap();
+        assert!(report.findings[0]
+            .to_string()
+            .starts_with("warning[L001 unknown-key] Unknown (line 9)"));
+    }
+
+    #[test]
+    fn test_redundant_names() {
+        let report = Validator::new()
+            .validate_source(
+                "[Desktop Entry]\nType=Application\nName=Editor\nGenericName=editor \nComment=Edit text\nName[de]=Editor\nComment[de]=Text bearbeiten\nComment[fr]=Éditeur\nName[fr]=éditeur\nGenericName[fr]=Éditeur de texte\n",
+            )
+            .unwrap();
+        let findings = report
+            .findings
+            .iter()
+            .map(|f| (f.key.as_deref(), f.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            findings,
+            [
+                (Some("GenericName"), "GenericName repeats Name"),
+                (
+                    Some("GenericName"),
+                    "GenericName repeats Name for the de locale"
+                ),
+                (
+                    Some("Comment[fr]"),
+                    "Comment repeats Name for the fr locale"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redundant_keywords() {
+        let report = Validator::new()
+            .validate_source(
+                "[Desktop Entry]\nType=Application\nName=Text Editor\nName[de]=Texteditor\nGenericName=Editor\nExec=edit\nKeywords=text editor ;edit;Text;\nKeywords[de]=texteditor;Editor;Notizen;\n",
+            )
+            .unwrap();
+        let findings = report
+            .findings
+            .iter()
+            .map(|f| (f.severity, f.key.as_deref().unwrap(), f.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            findings,
+            [
+                (
+                    Severity::Warning,
+                    "Keywords",
+                    "keyword 0 \"text editor\" repeats Name"
+                ),
+                (
+                    Severity::Hint,
+                    "Keywords",
+                    "keyword 2 \"text\" repeats a word of Name"
+                ),
+                (
+                    Severity::Warning,
+                    "Keywords[de]",
+                    "keyword 0 \"texteditor\" repeats Name for the de locale"
+                ),
+                (
+                    Severity::Warning,
+                    "Keywords[de]",
+                    "keyword 1 \"editor\" repeats GenericName for the de locale"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hidden_system_entry() {
+        let source = "[Desktop Entry]\nType=Application\nName=Test\nHidden=true\n";
+        let validator = Validator::new()
+            .system_dirs(vec!["/usr/share".into()])
+            .disable(DBUS_NAME);
+        let rules = |path: &str| {
+            validator
+                .validate_source_at(source, Path::new(path))
+                .unwrap()
+                .findings
+                .iter()
+                .map(|f| f.rule)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            rules("/usr/share/applications/test.desktop"),
+            [HIDDEN_SYSTEM_ENTRY]
+        );
+        assert!(rules("/home/me/.local/share/applications/test.desktop").is_empty());
+        assert!(validator
+            .validate_source(source)
+            .unwrap()
+            .findings
+            .is_empty());
+        assert!(Validator::new()
+            .disable(DBUS_NAME)
+            .validate_source_at(source, Path::new("/usr/share/applications/test.desktop"))
+            .unwrap()
+            .findings
+            .is_empty());
+    }
+
+    #[test]
+    fn test_dbus_names() {
+        let findings = |source: &str, path: &str| {
+            Validator::new()
+                .validate_source_at(source, Path::new(path))
+                .unwrap()
+                .findings
+                .into_iter()
+                .map(|f| (f.rule, f.severity))
+                .collect::<Vec<_>>()
+        };
+        let activatable = "[Desktop Entry]\nType=Application\nName=Test\nExec=test\nDBusActivatable=true\nImplements=org.example.Plugin;org.example.bad-name;\n";
+        let plain = "[Desktop Entry]\nType=Application\nName=Test\nExec=test\n";
+
+        assert_eq!(
+            findings(activatable, "/usr/share/applications/my launcher.desktop"),
+            [
+                (IMPLEMENTS_INTERFACE, Severity::Error),
+                (DBUS_NAME, Severity::Error)
+            ]
+        );
+        assert_eq!(
+            findings(
+                activatable,
+                "/usr/share/applications/org.example.App.desktop"
+            ),
+            [(IMPLEMENTS_INTERFACE, Severity::Error)]
+        );
+        assert_eq!(
+            findings(plain, "/usr/share/applications/test.desktop"),
+            [(DBUS_NAME, Severity::Warning)]
+        );
+        assert!(Validator::new()
+            .validate_source(plain)
+            .unwrap()
+            .findings
+            .is_empty());
+    }
+
+    #[test]
+    fn test_configured_rules() {
+        let report = Validator::new()
+            .disable(TRAILING_SEMICOLON)
+            .disable(EXEC_FIELD_CODES)
+            .severity(SHOW_IN_CONFLICT, Severity::Hint)
+            .severity("my-rule", Severity::Error)
+            .custom(|ctx| {
+                vec![Finding {
+                    rule: "my-rule",
+                    severity: Severity::Hint,
+                    action: None,
+                    key: Some("Name".into()),
+                    line: None,
+                    message: format!("{} is not allowed", ctx.file.entry.name.default),
+                    suggestion: None,
+                }]
+            })
+            .validate_source(CONTENT)
+            .unwrap();
+
+        let rules = report
+            .findings
+            .iter()
+            .map(|f| (f.rule, f.severity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rules,
+            vec![
+                (UNKNOWN_KEY, Severity::Warning),
+                (SHOW_IN_CONFLICT, Severity::Hint),
+                ("my-rule", Severity::Error)
+            ]
+        );
+    }
+}
@@ -0,0 +1,403 @@
+//! The [`EXEC_PROGRAM_EXISTS`] and [`EXEC_PATH_POLICY`] rules.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use super::{Finding, Severity};
+use crate::exec::ExecCommand;
+use crate::{DesktopFile, EntryType};
+
+/// Id of the rule checking that the program of every Exec exists and is executable.
+pub const EXEC_PROGRAM_EXISTS: &str = "exec-program-exists";
+
+/// Id of the rule checking that the program of every Exec is written the way the
+/// [`ExecPathPolicy`] requires.
+pub const EXEC_PATH_POLICY: &str = "exec-path-policy";
+
+/// How the program of Exec values has to be written, for the [`EXEC_PATH_POLICY`] rule
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExecPathPolicy {
+    /// The program is an absolute path, so it doesn't depend on the search path
+    RequireAbsolute,
+    /// The program is a bare name looked up in the search path, so installs are relocatable
+    RequireBare,
+    /// The program can be written either way
+    #[default]
+    Any,
+}
+
+/// Configuration of the program lookup performed by the [`EXEC_PROGRAM_EXISTS`] and
+/// [`EXEC_PATH_POLICY`] rules.
+#[derive(Debug, Clone, Default)]
+pub struct ExecLookupConfig {
+    /// The search path used instead of the `PATH` environment variable
+    pub path: Option<OsString>,
+}
+
+impl ExecLookupConfig {
+    /// Returns the directories programs are looked up in
+    fn search_dirs(&self) -> Vec<PathBuf> {
+        match self.path {
+            Some(ref path) => std::env::split_paths(path).collect(),
+            None => std::env::var_os("PATH")
+                .map(|path| std::env::split_paths(&path).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Resolves a program, returning the resolved path if any and every candidate checked
+    fn resolve(&self, program: &str) -> (Option<PathBuf>, Vec<PathBuf>) {
+        let path = Path::new(program);
+
+        if path.is_absolute() {
+            let found = is_executable(path).then(|| path.to_path_buf());
+            return (found, vec![path.to_path_buf()]);
+        }
+
+        let mut candidates = vec![];
+        for dir in self.search_dirs() {
+            let candidate = dir.join(program);
+            if is_executable(&candidate) {
+                return (Some(candidate.clone()), {
+                    candidates.push(candidate);
+                    candidates
+                });
+            }
+            candidates.push(candidate);
+        }
+
+        (None, candidates)
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Returns a finding if the program of the Exec value can't be resolved
+fn check_exec_value(
+    exec: &str,
+    key: &str,
+    action: Option<&String>,
+    config: &ExecLookupConfig,
+) -> (Option<PathBuf>, Option<Finding>) {
+    let finding = |severity, message| Finding {
+        rule: EXEC_PROGRAM_EXISTS,
+        severity,
+        action: action.cloned(),
+        key: Some(key.into()),
+        line: None,
+        message,
+        suggestion: None,
+    };
+
+    let program = match ExecCommand::parse(exec) {
+        Ok(cmd) => match cmd.effective_program() {
+            Some(program) => program.to_string(),
+            None => {
+                return (
+                    None,
+                    Some(finding(
+                        Severity::Error,
+                        format!("{key} {exec:?} does not name a program after env"),
+                    )),
+                )
+            }
+        },
+        Err(e) => {
+            return (
+                None,
+                Some(finding(
+                    Severity::Error,
+                    format!("{key} {exec:?} could not be split into arguments: {e}"),
+                )),
+            )
+        }
+    };
+
+    let (resolved, candidates) = config.resolve(&program);
+    match resolved {
+        Some(path) => (Some(path), None),
+        None => {
+            let checked = candidates
+                .iter()
+                .map(|c| c.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(", ");
+            (
+                None,
+                Some(finding(
+                    Severity::Error,
+                    format!(
+                        "program {program:?} is missing or not executable (checked: {checked})"
+                    ),
+                )),
+            )
+        }
+    }
+}
+
+/// Checks that the program of the entry's Exec and TryExec, and of every action's Exec,
+/// exists and is executable. The program is looked up in the search path unless it is an
+/// absolute path, skipping a leading `env` with its assignments.
+///
+/// Also hints when TryExec and Exec point at different programs. This rule touches the
+/// filesystem, so it is not part of any default rule set.
+pub fn check_exec(file: &DesktopFile, config: &ExecLookupConfig) -> Vec<Finding> {
+    let mut findings = vec![];
+
+    if let EntryType::Application(ref fields) = file.entry.entry_type {
+        let mut exec_program = None;
+        let mut try_exec_program = None;
+
+        if let Some(ref exec) = fields.exec {
+            let (resolved, finding) = check_exec_value(exec, "Exec", None, config);
+            exec_program = resolved;
+            findings.extend(finding);
+        }
+
+        if let Some(ref try_exec) = fields.try_exec {
+            // TryExec is a single path rather than a command line
+            let (resolved, candidates) = config.resolve(try_exec);
+            match resolved {
+                Some(path) => try_exec_program = Some(path),
+                None => findings.push(Finding {
+                    rule: EXEC_PROGRAM_EXISTS,
+                    severity: Severity::Warning,
+                    action: None,
+                    key: Some("TryExec".into()),
+                    line: None,
+                    message: format!(
+                        "program {try_exec:?} is missing or not executable, the entry will be hidden (checked: {})",
+                        candidates
+                            .iter()
+                            .map(|c| c.to_string_lossy())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    suggestion: None,
+                }),
+            }
+        }
+
+        if let (Some(exec), Some(try_exec)) = (exec_program, try_exec_program) {
+            if exec != try_exec {
+                findings.push(Finding {
+                    rule: EXEC_PROGRAM_EXISTS,
+                    severity: Severity::Hint,
+                    action: None,
+                    key: Some("TryExec".into()),
+                    line: None,
+                    message: format!(
+                        "TryExec resolves to {:?} but Exec runs {:?}",
+                        try_exec.to_string_lossy(),
+                        exec.to_string_lossy()
+                    ),
+                    suggestion: None,
+                });
+            }
+        }
+    }
+
+    let mut actions = file.actions.iter().collect::<Vec<_>>();
+    actions.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (id, action) in actions {
+        if let Some(ref exec) = action.exec {
+            let (_, finding) = check_exec_value(exec, "Exec", Some(id), config);
+            findings.extend(finding);
+        }
+    }
+
+    findings
+}
+
+/// Returns a finding if the program of the Exec value is not written as the policy requires,
+/// suggesting the value with the program rewritten when the replacement is known
+fn check_exec_path(
+    exec: &str,
+    action: Option<&String>,
+    policy: ExecPathPolicy,
+    config: &ExecLookupConfig,
+) -> Option<Finding> {
+    let mut cmd = ExecCommand::parse(exec).ok()?;
+    let index = cmd.effective_program_index()?;
+    let program = cmd.args[index].value.clone();
+    let is_absolute = Path::new(&program).is_absolute();
+
+    let (message, replacement) = match policy {
+        ExecPathPolicy::RequireAbsolute if !is_absolute => (
+            format!(
+                "program {program:?} is looked up in the search path, an absolute path is required"
+            ),
+            config
+                .resolve(&program)
+                .0
+                .map(|p| p.to_string_lossy().to_string()),
+        ),
+        ExecPathPolicy::RequireBare if is_absolute || program.contains('/') => (
+            format!("program {program:?} is written as a path, a bare name is required"),
+            Path::new(&program)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string()),
+        ),
+        _ => return None,
+    };
+
+    let suggestion = replacement.map(|replacement| {
+        cmd.args[index].value = replacement;
+        cmd.to_exec_string()
+    });
+    Some(Finding {
+        rule: EXEC_PATH_POLICY,
+        severity: Severity::Error,
+        action: action.cloned(),
+        key: Some("Exec".into()),
+        line: None,
+        message,
+        suggestion,
+    })
+}
+
+/// Checks that the program of the entry's Exec and of every action's Exec is written as the
+/// policy requires, skipping a leading `env` with its assignments. Programs that need an
+/// absolute path get one suggested when they are found in the search path.
+pub fn check_exec_paths(
+    file: &DesktopFile,
+    policy: ExecPathPolicy,
+    config: &ExecLookupConfig,
+) -> Vec<Finding> {
+    let mut findings = vec![];
+
+    if let Some(exec) = file
+        .entry
+        .entry_type
+        .application()
+        .and_then(|fields| fields.exec.as_ref())
+    {
+        findings.extend(check_exec_path(exec, None, policy, config));
+    }
+
+    let mut actions = file.actions.iter().collect::<Vec<_>>();
+    actions.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (id, action) in actions {
+        if let Some(ref exec) = action.exec {
+            findings.extend(check_exec_path(exec, Some(id), policy, config));
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_exec_rule_checks_programs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!(
+            "desktop-file-parser-exec-rule-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, mode) in [("tool", 0o755), ("other", 0o755), ("plain", 0o644)] {
+            let path = dir.join(name);
+            std::fs::write(&path, b"").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        }
+
+        let content = r#"
+[Desktop Entry]
+Name=Test
+Type=Application
+TryExec=other
+Exec=env FOO=bar tool %U
+Actions=a;b;
+
+[Desktop Action a]
+Name=A
+Exec=plain --flag
+
+[Desktop Action b]
+Name=B
+Exec=missing
+"#;
+        let file = parse(content).unwrap();
+        let config = ExecLookupConfig {
+            path: Some(dir.clone().into_os_string()),
+        };
+
+        let findings = check_exec(&file, &config);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings[0].severity, Severity::Hint);
+        assert_eq!(findings[1].action.as_deref(), Some("a"));
+        assert!(findings[1]
+            .message
+            .contains(&dir.join("plain").to_string_lossy().to_string()));
+        assert_eq!(findings[2].action.as_deref(), Some("b"));
+        assert_eq!(findings[2].severity, Severity::Error);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_exec_path_policy() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!(
+            "desktop-file-parser-exec-path-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tool = dir.join("tool");
+        std::fs::write(&tool, b"").unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let file = parse(
+            "[Desktop Entry]\nName=Test\nType=Application\nExec=env FOO=bar \"tool\" --x %U\nActions=a;b;\n\n[Desktop Action a]\nName=A\nExec=/opt/app/bin/app --new\n\n[Desktop Action b]\nName=B\nExec=missing\n",
+        )
+        .unwrap();
+        let config = ExecLookupConfig {
+            path: Some(dir.clone().into_os_string()),
+        };
+
+        let absolute = check_exec_paths(&file, ExecPathPolicy::RequireAbsolute, &config);
+        let bare = check_exec_paths(&file, ExecPathPolicy::RequireBare, &config);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let suggestions = absolute
+            .iter()
+            .map(|f| (f.action.as_deref(), f.suggestion.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            suggestions,
+            [
+                (
+                    None,
+                    Some(format!("env FOO=bar \"{}\" --x %U", tool.to_string_lossy()))
+                ),
+                (Some("b"), None),
+            ]
+        );
+        assert_eq!(bare.len(), 1);
+        assert_eq!(bare[0].action.as_deref(), Some("a"));
+        assert_eq!(bare[0].suggestion.as_deref(), Some("app --new"));
+        assert!(check_exec_paths(&file, ExecPathPolicy::Any, &config).is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+//! Typed access to the keys of an entry by [`Key`], for tools that handle every key the same
+//! way, such as an editor form or a diff viewer.
+
+use thiserror::Error;
+
+use crate::{
+    keys::{Key, ValueType},
+    ApplicationFields, DesktopEntry, EntryType, IconString, LocaleString, LocaleStringList,
+};
+
+/// The value of a key, with one variant per [`ValueType`]
+#[derive(Debug, Clone)]
+pub enum Value {
+    String(String),
+    LocaleString(LocaleString),
+    IconString(IconString),
+    Bool(bool),
+    StringList(Vec<String>),
+    LocaleStringList(LocaleStringList),
+}
+
+impl Value {
+    /// Returns the type of the value
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Self::String(_) => ValueType::String,
+            Self::LocaleString(_) => ValueType::LocaleString,
+            Self::IconString(_) => ValueType::IconString,
+            Self::Bool(_) => ValueType::Boolean,
+            Self::StringList(_) => ValueType::StringList,
+            Self::LocaleStringList(_) => ValueType::LocaleStringList,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TypeError {
+    #[error("Type Error: the key {key:?} expects a value of type {expected:?}, found {found:?}")]
+    WrongType {
+        key: String,
+        expected: ValueType,
+        found: ValueType,
+    },
+    #[error("Type Error: the key {key:?} can't be set on an entry of type {entry_type}")]
+    NotApplicable { key: String, entry_type: String },
+}
+
+fn application_fields<'a>(
+    entry_type: &'a mut EntryType,
+    key: &Key,
+) -> Result<&'a mut ApplicationFields, TypeError> {
+    match entry_type {
+        EntryType::Application(fields) => Ok(fields),
+        other => Err(TypeError::NotApplicable {
+            key: key.to_string(),
+            entry_type: other.to_string(),
+        }),
+    }
+}
+
+impl DesktopEntry {
+    /// Returns the value of a key, or None if the key is absent or doesn't apply to the type
+    /// of the entry. Extension and unknown keys are returned as raw strings.
+    pub fn get(&self, key: Key) -> Option<Value> {
+        let app = match self.entry_type {
+            EntryType::Application(ref fields) => Some(fields),
+            _ => None,
+        };
+
+        match key {
+            Key::Type => Some(Value::String(self.entry_type.to_string())),
+            Key::Version => self.version.clone().map(Value::String),
+            Key::Name => Some(Value::LocaleString(self.name.clone())),
+            Key::GenericName => self.generic_name.clone().map(Value::LocaleString),
+            Key::NoDisplay => self.no_display.map(Value::Bool),
+            Key::Comment => self.comment.clone().map(Value::LocaleString),
+            Key::Icon => self.icon.clone().map(Value::IconString),
+            Key::Hidden => self.hidden.map(Value::Bool),
+            Key::OnlyShowIn => self.only_show_in.clone().map(Value::StringList),
+            Key::NotShowIn => self.not_show_in.clone().map(Value::StringList),
+            Key::DBusActivatable => self.dbus_activatable.map(Value::Bool),
+            Key::TryExec => app?.try_exec.clone().map(Value::String),
+            Key::Exec => app?.exec.clone().map(Value::String),
+            Key::Path => app?.path.clone().map(Value::String),
+            Key::Terminal => app?.terminal.map(Value::Bool),
+            Key::Actions => app?.actions.clone().map(Value::StringList),
+            Key::MimeType => app?.mime_type.clone().map(Value::StringList),
+            Key::Categories => app?.categories.clone().map(Value::StringList),
+            Key::Implements => app?.implements.clone().map(Value::StringList),
+            Key::Keywords => app?.keywords.clone().map(Value::LocaleStringList),
+            Key::StartupNotify => app?.startup_notify.map(Value::Bool),
+            Key::StartupWMClass => app?.startup_wm_class.clone().map(Value::String),
+            Key::Url => match self.entry_type {
+                EntryType::Link(ref fields) => Some(Value::String(fields.url.clone())),
+                _ => None,
+            },
+            Key::PrefersNonDefaultGPU => app?.prefers_non_default_gpu.map(Value::Bool),
+            Key::SingleMainWindow => app?.single_main_window.map(Value::Bool),
+            Key::Extension(name) | Key::Unknown(name) => {
+                self.extras.get(&name).cloned().map(Value::String)
+            }
+        }
+    }
+
+    /// Sets the value of a key. The value must have the type the specification gives the key,
+    /// and the key must apply to the type of the entry. Setting Type to a different type
+    /// resets the fields specific to the previous type. Extension and unknown keys take raw
+    /// strings.
+    pub fn set(&mut self, key: Key, value: Value) -> Result<(), TypeError> {
+        match (&key, value) {
+            (Key::Type, Value::String(s)) => {
+                if self.entry_type.to_string() != s {
+                    self.entry_type = EntryType::from(s.as_str());
+                    for key in [Key::Exec, Key::TryExec, Key::Path] {
+                        self.raw_values.forget(&key);
+                    }
+                }
+            }
+            (Key::Version, Value::String(s)) => self.version = Some(s),
+            (Key::Name, Value::LocaleString(s)) => self.name = s,
+            (Key::GenericName, Value::LocaleString(s)) => self.generic_name = Some(s),
+            (Key::NoDisplay, Value::Bool(b)) => self.no_display = Some(b),
+            (Key::Comment, Value::LocaleString(s)) => self.comment = Some(s),
+            (Key::Icon, Value::IconString(s)) => self.icon = Some(s),
+            (Key::Hidden, Value::Bool(b)) => self.hidden = Some(b),
+            (Key::OnlyShowIn, Value::StringList(l)) => self.only_show_in = Some(l),
+            (Key::NotShowIn, Value::StringList(l)) => self.not_show_in = Some(l),
+            (Key::DBusActivatable, Value::Bool(b)) => self.dbus_activatable = Some(b),
+            (Key::TryExec, Value::String(s)) => {
+                application_fields(&mut self.entry_type, &key)?.try_exec = Some(s)
+            }
+            (Key::Exec, Value::String(s)) => {
+                application_fields(&mut self.entry_type, &key)?.exec = Some(s)
+            }
+            (Key::Path, Value::String(s)) => {
+                application_fields(&mut self.entry_type, &key)?.path = Some(s)
+            }
+            (Key::Terminal, Value::Bool(b)) => {
+                application_fields(&mut self.entry_type, &key)?.terminal = Some(b)
+            }
+            (Key::Actions, Value::StringList(l)) => {
+                application_fields(&mut self.entry_type, &key)?.actions = Some(l)
+            }
+            (Key::MimeType, Value::StringList(l)) => {
+                application_fields(&mut self.entry_type, &key)?.mime_type = Some(l)
+            }
+            (Key::Categories, Value::StringList(l)) => {
+                application_fields(&mut self.entry_type, &key)?.categories = Some(l)
+            }
+            (Key::Implements, Value::StringList(l)) => {
+                application_fields(&mut self.entry_type, &key)?.implements = Some(l)
+            }
+            (Key::Keywords, Value::LocaleStringList(l)) => {
+                application_fields(&mut self.entry_type, &key)?.keywords = Some(l)
+            }
+            (Key::StartupNotify, Value::Bool(b)) => {
+                application_fields(&mut self.entry_type, &key)?.startup_notify = Some(b)
+            }
+            (Key::StartupWMClass, Value::String(s)) => {
+                application_fields(&mut self.entry_type, &key)?.startup_wm_class = Some(s)
+            }
+            (Key::Url, Value::String(s)) => match self.entry_type {
+                EntryType::Link(ref mut fields) => fields.url = s,
+                ref other => {
+                    return Err(TypeError::NotApplicable {
+                        key: key.to_string(),
+                        entry_type: other.to_string(),
+                    })
+                }
+            },
+            (Key::PrefersNonDefaultGPU, Value::Bool(b)) => {
+                application_fields(&mut self.entry_type, &key)?.prefers_non_default_gpu = Some(b)
+            }
+            (Key::SingleMainWindow, Value::Bool(b)) => {
+                application_fields(&mut self.entry_type, &key)?.single_main_window = Some(b)
+            }
+            (Key::Extension(name) | Key::Unknown(name), Value::String(s)) => {
+                self.extras.insert(name.clone(), s);
+            }
+            (_, value) => {
+                return Err(TypeError::WrongType {
+                    key: key.to_string(),
+                    expected: key.value_type().unwrap_or(ValueType::String),
+                    found: value.value_type(),
+                })
+            }
+        }
+
+        self.raw_values.forget(&key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_set() {
+        let mut entry = crate::parse("[Desktop Entry]\nType=Application\nName=Foo\nX-Foo=bar\n")
+            .unwrap()
+            .entry;
+
+        assert!(matches!(entry.get(Key::Name), Some(Value::LocaleString(s)) if s.default == "Foo"));
+        assert!(entry.get(Key::Comment).is_none());
+        assert!(matches!(entry.get(Key::from("X-Foo")), Some(Value::String(s)) if s == "bar"));
+
+        entry.set(Key::Terminal, Value::Bool(true)).unwrap();
+        assert!(matches!(entry.get(Key::Terminal), Some(Value::Bool(true))));
+
+        assert_eq!(
+            entry.set(Key::Terminal, Value::String("true".into())),
+            Err(TypeError::WrongType {
+                key: "Terminal".into(),
+                expected: ValueType::Boolean,
+                found: ValueType::String,
+            })
+        );
+        assert!(matches!(
+            entry.set(Key::Url, Value::String("https://example.com".into())),
+            Err(TypeError::NotApplicable { .. })
+        ));
+
+        entry.set(Key::Type, Value::String("Link".into())).unwrap();
+        entry
+            .set(Key::Url, Value::String("https://example.com".into()))
+            .unwrap();
+        assert!(entry.get(Key::Terminal).is_none());
+    }
+}
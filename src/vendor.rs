@@ -0,0 +1,94 @@
+//! Typed access to the descriptive keys vendors and software centers add to desktop files.
+//! The keys stay in the extras of the entry as written.
+
+use crate::{parser::unescape_string, DesktopEntry, LocaleString};
+
+/// The descriptive vendor keys of a desktop entry
+#[derive(Debug, Clone, Default)]
+pub struct VendorMeta {
+    /// The full branded name, from `X-GNOME-FullName` or else `X-MATE-FullName`
+    pub full_name: Option<LocaleString>,
+    /// The name of the distribution package, from `X-AppInstall-Package`
+    pub package: Option<String>,
+    /// The popularity contest score, from `X-AppInstall-Popcon`
+    pub popcon: Option<u64>,
+    /// The archive section of the package, from `X-AppInstall-Section`
+    pub section: Option<String>,
+}
+
+impl DesktopEntry {
+    /// Assembles a localized string from an extension key and its `[locale]` variants, or
+    /// returns None if neither is present
+    fn extra_locale_string(&self, key: &str) -> Option<LocaleString> {
+        let mut string = LocaleString::default();
+        let mut found = false;
+
+        for (name, value) in &self.extras {
+            let Some(rest) = name.strip_prefix(key) else {
+                continue;
+            };
+
+            if rest.is_empty() {
+                string.default = unescape_string(value);
+            } else if let Some(locale) = rest.strip_prefix('[').and_then(|r| r.strip_suffix(']')) {
+                string
+                    .variants
+                    .insert(locale.to_string(), unescape_string(value));
+            } else {
+                continue;
+            }
+            found = true;
+        }
+
+        found.then_some(string)
+    }
+
+    /// Returns the descriptive vendor keys of the entry, or None if it has none of them
+    pub fn vendor_meta(&self) -> Option<VendorMeta> {
+        let string = |key: &str| self.extras.get(key).map(|v| unescape_string(v));
+
+        let meta = VendorMeta {
+            full_name: self
+                .extra_locale_string("X-GNOME-FullName")
+                .or_else(|| self.extra_locale_string("X-MATE-FullName")),
+            package: string("X-AppInstall-Package"),
+            popcon: self
+                .extras
+                .get("X-AppInstall-Popcon")
+                .and_then(|v| v.trim().parse().ok()),
+            section: string("X-AppInstall-Section"),
+        };
+
+        let is_empty = meta.full_name.is_none()
+            && meta.package.is_none()
+            && meta.popcon.is_none()
+            && meta.section.is_none();
+        (!is_empty).then_some(meta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    #[test]
+    fn test_vendor_meta() {
+        let file = parse(
+            "[Desktop Entry]\nType=Application\nName=Files\nExec=nautilus\nX-GNOME-FullName=GNOME Files\nX-GNOME-FullName[de]=GNOME Dateien\nX-GNOME-FullNameExtra=x\nX-AppInstall-Package=nautilus\nX-AppInstall-Popcon=4242\nX-AppInstall-Section=main\n",
+        )
+        .unwrap();
+        let meta = file.entry.vendor_meta().unwrap();
+
+        let full_name = meta.full_name.unwrap();
+        assert_eq!(full_name.default, "GNOME Files");
+        assert_eq!(full_name.get_variant("de"), "GNOME Dateien");
+        assert_eq!(full_name.variants.len(), 1);
+        assert_eq!(meta.package.as_deref(), Some("nautilus"));
+        assert_eq!(meta.popcon, Some(4242));
+        assert_eq!(meta.section.as_deref(), Some("main"));
+        assert_eq!(file.entry.extras["X-GNOME-FullName[de]"], "GNOME Dateien");
+
+        let file = parse("[Desktop Entry]\nType=Application\nName=App\nExec=app\n").unwrap();
+        assert!(file.entry.vendor_meta().is_none());
+    }
+}